@@ -0,0 +1,314 @@
+//! A growable byte buffer with a guaranteed minimum alignment.
+
+use core::{
+    borrow::{Borrow, BorrowMut},
+    fmt,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    ptr::{self, NonNull},
+    slice,
+};
+use std::{alloc, io};
+
+/// A vector of bytes that is always aligned to at least a minimum alignment.
+///
+/// Unlike a `Vec<u8>`, the start of the buffer is guaranteed to be aligned, so
+/// archived values can be accessed directly out of it. The minimum alignment
+/// defaults to 16 bytes, the same alignment as [`Aligned`](crate::Aligned).
+///
+/// ## Examples
+/// ```
+/// use rkyv::AlignedVec;
+///
+/// let mut bytes = AlignedVec::with_align(64);
+/// bytes.extend_from_slice(&[1, 2, 3, 4]);
+/// assert_eq!(bytes.as_ptr() as usize % 64, 0);
+/// assert_eq!(bytes.as_slice(), &[1, 2, 3, 4]);
+/// ```
+pub struct AlignedVec {
+    ptr: NonNull<u8>,
+    cap: usize,
+    len: usize,
+    align: usize,
+}
+
+impl AlignedVec {
+    /// The default minimum alignment of an `AlignedVec`.
+    pub const DEFAULT_ALIGNMENT: usize = 16;
+
+    /// Creates a new, empty `AlignedVec` with the default alignment.
+    ///
+    /// The vector will not allocate until bytes are added to it.
+    #[inline]
+    pub fn new() -> Self {
+        Self::with_align(Self::DEFAULT_ALIGNMENT)
+    }
+
+    /// Creates a new, empty `AlignedVec` with the given minimum alignment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    #[inline]
+    pub fn with_align(align: usize) -> Self {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        Self {
+            // A dangling pointer is always aligned to the requested alignment
+            ptr: unsafe { NonNull::new_unchecked(align as *mut u8) },
+            cap: 0,
+            len: 0,
+            align,
+        }
+    }
+
+    /// Creates a new, empty `AlignedVec` with the default alignment and at
+    /// least the given capacity.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_align(capacity, Self::DEFAULT_ALIGNMENT)
+    }
+
+    /// Creates a new, empty `AlignedVec` with the given minimum alignment and
+    /// at least the given capacity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    #[inline]
+    pub fn with_capacity_and_align(capacity: usize, align: usize) -> Self {
+        let mut result = Self::with_align(align);
+        result.reserve(capacity);
+        result
+    }
+
+    fn layout(&self, capacity: usize) -> alloc::Layout {
+        alloc::Layout::from_size_align(capacity, self.align).expect("capacity overflow")
+    }
+
+    fn grow_to(&mut self, new_cap: usize) {
+        let new_layout = self.layout(new_cap);
+        let ptr = unsafe {
+            if self.cap == 0 {
+                alloc::alloc(new_layout)
+            } else {
+                alloc::realloc(self.ptr.as_ptr(), self.layout(self.cap), new_cap)
+            }
+        };
+        self.ptr = match NonNull::new(ptr) {
+            Some(ptr) => ptr,
+            None => alloc::handle_alloc_error(new_layout),
+        };
+        self.cap = new_cap;
+    }
+
+    /// Returns the minimum alignment of the vector.
+    #[inline]
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// Returns the number of bytes in the vector.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the vector contains no bytes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes the vector can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Reserves capacity for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        if required > self.cap {
+            let new_cap = usize::max(required, self.cap * 2);
+            self.grow_to(usize::max(new_cap, self.align));
+        }
+    }
+
+    /// Clears the vector, removing all bytes but keeping its capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Appends a byte to the end of the vector.
+    #[inline]
+    pub fn push(&mut self, value: u8) {
+        self.reserve(1);
+        unsafe {
+            self.ptr.as_ptr().add(self.len).write(value);
+        }
+        self.len += 1;
+    }
+
+    /// Appends all the bytes in the given slice to the end of the vector.
+    pub fn extend_from_slice(&mut self, other: &[u8]) {
+        self.reserve(other.len());
+        unsafe {
+            ptr::copy_nonoverlapping(other.as_ptr(), self.ptr.as_ptr().add(self.len), other.len());
+        }
+        self.len += other.len();
+    }
+
+    /// Resizes the vector to the given length, filling any new bytes with
+    /// `value`.
+    pub fn resize(&mut self, new_len: usize, value: u8) {
+        if new_len > self.len {
+            let additional = new_len - self.len;
+            self.reserve(additional);
+            unsafe {
+                ptr::write_bytes(self.ptr.as_ptr().add(self.len), value, additional);
+            }
+        }
+        self.len = new_len;
+    }
+
+    /// Returns a raw pointer to the start of the vector's buffer.
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns an unsafe mutable pointer to the start of the vector's buffer.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    /// Extracts a slice containing the entire vector.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Extracts a mutable slice containing the entire vector.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Extracts a pinned mutable slice containing the entire vector.
+    ///
+    /// This is suitable for use with
+    /// [`archived_value_mut`](crate::archived_value_mut).
+    #[inline]
+    pub fn as_pin_mut_slice(&mut self) -> Pin<&mut [u8]> {
+        Pin::new(self.as_mut_slice())
+    }
+
+    /// Copies the bytes of the vector into a `Vec<u8>`. The returned vector
+    /// does not have any alignment guarantees.
+    #[inline]
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.as_slice().to_vec()
+    }
+}
+
+impl Drop for AlignedVec {
+    fn drop(&mut self) {
+        if self.cap != 0 {
+            unsafe {
+                alloc::dealloc(self.ptr.as_ptr(), self.layout(self.cap));
+            }
+        }
+    }
+}
+
+impl Clone for AlignedVec {
+    fn clone(&self) -> Self {
+        let mut result = Self::with_capacity_and_align(self.len, self.align);
+        result.extend_from_slice(self.as_slice());
+        result
+    }
+}
+
+impl Default for AlignedVec {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for AlignedVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl PartialEq for AlignedVec {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for AlignedVec {}
+
+impl Deref for AlignedVec {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for AlignedVec {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+impl AsRef<[u8]> for AlignedVec {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsMut<[u8]> for AlignedVec {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl Borrow<[u8]> for AlignedVec {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl BorrowMut<[u8]> for AlignedVec {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl io::Write for AlignedVec {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// AlignedVec owns its buffer just like a Vec<u8>
+unsafe impl Send for AlignedVec {}
+unsafe impl Sync for AlignedVec {}
@@ -0,0 +1,36 @@
+//! Diff implementations for hashmaps and hashsets.
+
+use super::{ArchivedHashMap, ArchivedHashSet};
+use crate::diff::{ArchiveDiff, Differ, PathSegment};
+use core::{fmt, hash::Hash};
+
+impl<K: Hash + Eq + fmt::Debug, V: ArchiveDiff + fmt::Debug> ArchiveDiff for ArchivedHashMap<K, V> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        for (key, old) in self.iter() {
+            match other.get(key) {
+                Some(new) => differ.diff(PathSegment::Key(key), old, new),
+                None => differ.removed(PathSegment::Key(key), old),
+            }
+        }
+        for (key, new) in other.iter() {
+            if !self.contains_key(key) {
+                differ.added(PathSegment::Key(key), new);
+            }
+        }
+    }
+}
+
+impl<K: Hash + Eq + fmt::Debug> ArchiveDiff for ArchivedHashSet<K> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        for key in self.iter() {
+            if !other.contains(key) {
+                differ.removed(PathSegment::Key(key), key);
+            }
+        }
+        for key in other.iter() {
+            if !self.contains(key) {
+                differ.added(PathSegment::Key(key), key);
+            }
+        }
+    }
+}
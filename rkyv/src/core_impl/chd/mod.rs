@@ -1,18 +1,30 @@
-//! [`Archive`] implementation for hashmaps.
+//! Archived hash maps and hash sets.
 //!
 //! During archiving, hashmaps are built into minimal perfect hashmaps using
 //! [compress, hash and displace](http://cmph.sourceforge.net/papers/esa09.pdf).
-
+//!
+//! Archived hash maps only need `core` to look up and iterate over their
+//! entries, and only need a [`ScratchSpace`] to be serialized. The
+//! implementations for `HashMap` and `HashSet` are in
+//! [`std_impl::chd`](crate::std_impl::chd).
+
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "schema")]
+pub mod schema;
 #[cfg(feature = "validation")]
 pub mod validation;
 
 use crate::{
-    offset_of, ser::Serializer, Archive, Archived, ArchivedUsize, Deserialize, Fallible, RawRelPtr,
-    Serialize,
+    offset_of,
+    ser::{ScratchSpace, ScratchVec, Serializer},
+    ArchivedU32, ArchivedUsize, FixedUsize, OffsetError, RawRelPtr, Serialize,
 };
 use core::{
     borrow::Borrow,
     cmp::Reverse,
+    convert::TryFrom,
+    fmt,
     hash::{Hash, Hasher},
     iter::FusedIterator,
     marker::PhantomData,
@@ -21,7 +33,24 @@ use core::{
     pin::Pin,
     slice,
 };
-use std::collections::{HashMap, HashSet};
+
+/// A type that can be used to look up keys of type `K` in an archived hash map.
+///
+/// This is implemented for every type that `K` can be borrowed as. With the
+/// `archive_le` or `archive_be` features, it's also implemented for native
+/// primitives so that maps with fixed-endian keys can be queried with native
+/// keys. Equivalent values must hash the same.
+pub trait Equivalent<K: ?Sized> {
+    /// Returns whether this value is equal to the given key.
+    fn equivalent(&self, key: &K) -> bool;
+}
+
+impl<Q: Eq + ?Sized, K: Borrow<Q> + ?Sized> Equivalent<K> for Q {
+    #[inline]
+    fn equivalent(&self, key: &K) -> bool {
+        self == key.borrow()
+    }
+}
 
 #[cfg_attr(feature = "strict", repr(C))]
 struct Entry<K, V> {
@@ -42,7 +71,7 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
     /// Gets the number of items in the hash map.
     #[inline]
     pub fn len(&self) -> usize {
-        self.len as usize
+        from_archived!(self.len) as usize
     }
 
     fn make_hasher() -> seahash::SeaHasher {
@@ -61,7 +90,7 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
     }
 
     unsafe fn displace(&self, index: usize) -> u32 {
-        *self.displace.as_ptr().cast::<u32>().add(index)
+        from_archived!(*self.displace.as_ptr().cast::<ArchivedU32>().add(index))
     }
 
     unsafe fn entry(&self, index: usize) -> &Entry<K, V> {
@@ -75,12 +104,15 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
     #[inline]
     fn index<Q: ?Sized>(&self, k: &Q) -> Option<usize>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
+        if self.is_empty() {
+            return None;
+        }
+
         let mut hasher = self.hasher();
         k.hash(&mut hasher);
-        let displace_index = hasher.finish() % self.len as u64;
+        let displace_index = hasher.finish() % self.len() as u64;
         let displace = unsafe { self.displace(displace_index as usize) };
 
         let index = if displace == u32::MAX {
@@ -91,11 +123,11 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
             let mut hasher = self.hasher();
             displace.hash(&mut hasher);
             k.hash(&mut hasher);
-            hasher.finish() % self.len as u64
+            hasher.finish() % self.len() as u64
         };
 
         let entry = unsafe { self.entry(index as usize) };
-        if entry.key.borrow() == k {
+        if k.equivalent(&entry.key) {
             Some(index as usize)
         } else {
             None
@@ -106,8 +138,7 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
     #[inline]
     pub fn get_key_value<Q: ?Sized>(&self, k: &Q) -> Option<(&K, &V)>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         self.index(k).map(move |index| {
             let entry = unsafe { self.entry(index) };
@@ -119,8 +150,7 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
     #[inline]
     pub fn get_key_value_pin<Q: ?Sized>(self: Pin<&mut Self>, k: &Q) -> Option<(&K, Pin<&mut V>)>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         unsafe {
             let hash_map = self.get_unchecked_mut();
@@ -135,8 +165,7 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
     #[inline]
     pub fn contains_key<Q: ?Sized>(&self, k: &Q) -> bool
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         self.index(k).is_some()
     }
@@ -145,8 +174,7 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
     #[inline]
     pub fn get<Q: ?Sized>(&self, k: &Q) -> Option<&V>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         self.index(k)
             .map(|index| unsafe { &self.entry(index).value })
@@ -156,8 +184,7 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
     #[inline]
     pub fn get_pin<Q: ?Sized>(self: Pin<&mut Self>, k: &Q) -> Option<Pin<&mut V>>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         unsafe {
             let hash_map = self.get_unchecked_mut();
@@ -226,122 +253,147 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
         }
     }
 
+    /// Serializes an iterator of key-value pairs as a hash map.
+    ///
+    /// `len` must be the number of pairs the iterator yields, and the keys
+    /// must be unique. The temporary memory needed to build the hash map is
+    /// taken from the serializer's scratch space, so this works without an
+    /// allocator.
     #[inline]
-    fn serialize_from_iter<
+    pub fn serialize_from_iter<
         'a,
         KU: 'a + Serialize<S, Archived = K> + Hash + Eq,
         VU: 'a + Serialize<S, Archived = V>,
-        S: Serializer + ?Sized,
+        S: ScratchSpace + Serializer + ?Sized,
     >(
         iter: impl Iterator<Item = (&'a KU, &'a VU)>,
         len: usize,
         serializer: &mut S,
     ) -> Result<ArchivedHashMapResolver, S::Error> {
-        let mut bucket_size = vec![0u32; len];
-        let mut displaces = Vec::with_capacity(len);
-
-        for (key, value) in iter {
-            let mut hasher = Self::make_hasher();
-            key.hash(&mut hasher);
-            let displace = (hasher.finish() % len as u64) as u32;
-            displaces.push((displace, (key, value)));
-            bucket_size[displace as usize] += 1;
-        }
+        unsafe {
+            let mut bucket_size = ScratchVec::new(serializer, len)?;
+            for _ in 0..len {
+                bucket_size.push(0u32);
+            }
+            let mut displaces = ScratchVec::new(serializer, len)?;
+
+            for (key, value) in iter {
+                let mut hasher = Self::make_hasher();
+                key.hash(&mut hasher);
+                let displace = (hasher.finish() % len as u64) as u32;
+                displaces.push((displace, (key, value)));
+                bucket_size[displace as usize] += 1;
+            }
+
+            displaces.sort_unstable_by_key(|&(displace, _)| {
+                (Reverse(bucket_size[displace as usize]), displace)
+            });
+
+            let mut entries = ScratchVec::new(serializer, len)?;
+            for _ in 0..len {
+                entries.push(None);
+            }
+            let mut displacements = ScratchVec::new(serializer, len)?;
+            for _ in 0..len {
+                displacements.push(to_archived!(u32::MAX));
+            }
 
-        displaces.sort_by_key(|&(displace, _)| (Reverse(bucket_size[displace as usize]), displace));
-
-        let mut entries = Vec::with_capacity(len);
-        entries.resize_with(len, || None);
-        let mut displacements = vec![u32::MAX; len];
-
-        let mut first_empty = 0;
-        let mut assignments = Vec::with_capacity(8);
-
-        let mut start = 0;
-        while start < displaces.len() {
-            let displace = displaces[start].0;
-            let bucket_size = bucket_size[displace as usize] as usize;
-            let end = start + bucket_size;
-            let bucket = &displaces[start..end];
-            start = end;
-
-            if bucket_size > 1 {
-                'find_seed: for seed in 0x80_00_00_00..=0xFF_FF_FF_FF {
-                    let mut base_hasher = Self::make_hasher();
-                    seed.hash(&mut base_hasher);
-
-                    assignments.clear();
-
-                    for &(_, (key, _)) in bucket.iter() {
-                        let mut hasher = base_hasher;
-                        key.hash(&mut hasher);
-                        let index = (hasher.finish() % len as u64) as u32;
-                        if entries[index as usize].is_some() || assignments.contains(&index) {
-                            continue 'find_seed;
-                        } else {
-                            assignments.push(index);
+            let mut first_empty = 0;
+            let max_bucket_size = bucket_size.iter().copied().max().unwrap_or(0) as usize;
+            let mut assignments = ScratchVec::new(serializer, max_bucket_size)?;
+
+            let mut start = 0;
+            while start < displaces.len() {
+                let displace = displaces[start].0;
+                let bucket_size = bucket_size[displace as usize] as usize;
+                let end = start + bucket_size;
+                let bucket = &displaces[start..end];
+                start = end;
+
+                if bucket_size > 1 {
+                    'find_seed: for seed in 0x80_00_00_00..=0xFF_FF_FF_FF {
+                        let mut base_hasher = Self::make_hasher();
+                        seed.hash(&mut base_hasher);
+
+                        assignments.clear();
+
+                        for &(_, (key, _)) in bucket.iter() {
+                            let mut hasher = base_hasher;
+                            key.hash(&mut hasher);
+                            let index = (hasher.finish() % len as u64) as u32;
+                            if entries[index as usize].is_some() || assignments.contains(&index) {
+                                continue 'find_seed;
+                            } else {
+                                assignments.push(index);
+                            }
                         }
-                    }
 
-                    for i in 0..bucket_size {
-                        entries[assignments[i] as usize] = Some(bucket[i].1);
+                        for i in 0..bucket_size {
+                            entries[assignments[i] as usize] = Some(bucket[i].1);
+                        }
+                        displacements[displace as usize] = to_archived!(seed);
+                        break;
                     }
-                    displacements[displace as usize] = seed;
-                    break;
+                } else {
+                    let offset = entries[first_empty..]
+                        .iter()
+                        .position(|value| value.is_none())
+                        .unwrap();
+                    first_empty += offset;
+                    entries[first_empty] = Some(bucket[0].1);
+                    displacements[displace as usize] = to_archived!(first_empty as u32);
+                    first_empty += 1;
                 }
-            } else {
-                let offset = entries[first_empty..]
-                    .iter()
-                    .position(|value| value.is_none())
-                    .unwrap();
-                first_empty += offset;
-                entries[first_empty] = Some(bucket[0].1);
-                displacements[displace as usize] = first_empty as u32;
-                first_empty += 1;
             }
-        }
 
-        // Archive entries
-        let mut resolvers = entries
-            .iter()
-            .map(|e| {
+            // Archive entries
+            let mut resolvers = ScratchVec::new(serializer, len)?;
+            for e in entries.iter() {
                 let (key, value) = e.unwrap();
-                Ok((key.serialize(serializer)?, value.serialize(serializer)?))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+                resolvers.push((key.serialize(serializer)?, value.serialize(serializer)?));
+            }
 
-        // Write blocks
-        let displace_pos = serializer.align_for::<u32>()?;
-        let displacements_slice = unsafe {
-            slice::from_raw_parts(
+            // Write blocks
+            let displace_pos = serializer.align_for::<ArchivedU32>()?;
+            let displacements_slice = slice::from_raw_parts(
                 displacements.as_ptr().cast::<u8>(),
-                displacements.len() * size_of::<u32>(),
-            )
-        };
-        serializer.write(displacements_slice)?;
-
-        let entries_pos = serializer.align_for::<Entry<K, V>>()?;
-        for ((key, value), (key_resolver, value_resolver)) in
-            entries.iter().map(|r| r.unwrap()).zip(resolvers.drain(..))
-        {
-            let entry_pos = serializer.pos();
-            let entry = Entry {
-                key: key.resolve(entry_pos + offset_of!(Entry<K, V>, key), key_resolver),
-                value: value.resolve(entry_pos + offset_of!(Entry<K, V>, value), value_resolver),
-            };
-            let entry_slice = unsafe {
-                slice::from_raw_parts(
+                displacements.len() * size_of::<ArchivedU32>(),
+            );
+            serializer.write(displacements_slice)?;
+
+            let entries_pos = serializer.align_for::<Entry<K, V>>()?;
+            for ((key, value), (key_resolver, value_resolver)) in
+                entries.iter().map(|r| r.unwrap()).zip(resolvers.drain())
+            {
+                let entry_pos = serializer.pos();
+                let entry = Entry {
+                    key: key
+                        .resolve(entry_pos + offset_of!(Entry<K, V>, key), key_resolver)
+                        .map_err(|e| serializer.offset_error(e))?,
+                    value: value
+                        .resolve(entry_pos + offset_of!(Entry<K, V>, value), value_resolver)
+                        .map_err(|e| serializer.offset_error(e))?,
+                };
+                let entry_slice = slice::from_raw_parts(
                     (&entry as *const Entry<K, V>).cast::<u8>(),
                     size_of::<Entry<K, V>>(),
-                )
-            };
-            serializer.write(entry_slice)?;
-        }
+                );
+                serializer.write(entry_slice)?;
+            }
 
-        Ok(ArchivedHashMapResolver {
-            displace_pos,
-            entries_pos,
-        })
+            // Free scratch space in the reverse order it was allocated
+            resolvers.free(serializer)?;
+            assignments.free(serializer)?;
+            displacements.free(serializer)?;
+            entries.free(serializer)?;
+            displaces.free(serializer)?;
+            bucket_size.free(serializer)?;
+
+            Ok(ArchivedHashMapResolver {
+                displace_pos,
+                entries_pos,
+            })
+        }
     }
 }
 
@@ -566,62 +618,33 @@ pub struct ArchivedHashMapResolver {
 }
 
 impl ArchivedHashMapResolver {
-    fn resolve_from_len<K, V>(self, pos: usize, len: usize) -> ArchivedHashMap<K, V> {
+    /// Resolves an archived hash map with `len` entries at the given position.
+    pub fn resolve_from_len<K, V>(
+        self,
+        pos: usize,
+        len: usize,
+    ) -> Result<ArchivedHashMap<K, V>, OffsetError> {
+        let len = FixedUsize::try_from(len).map_err(|_| OffsetError::UsizeOutOfRange(len))?;
         unsafe {
-            ArchivedHashMap {
-                len: len as ArchivedUsize,
-                displace: RawRelPtr::new(
+            Ok(ArchivedHashMap {
+                len: to_archived!(len),
+                displace: RawRelPtr::try_new(
                     pos + offset_of!(ArchivedHashMap<K, V>, displace),
                     self.displace_pos,
-                ),
-                entries: RawRelPtr::new(
+                )?,
+                entries: RawRelPtr::try_new(
                     pos + offset_of!(ArchivedHashMap<K, V>, entries),
                     self.entries_pos,
-                ),
+                )?,
                 _phantom: PhantomData,
-            }
+            })
         }
     }
 }
 
-impl<K: Archive + Hash + Eq, V: Archive> Archive for HashMap<K, V>
-where
-    K::Archived: Hash + Eq,
-{
-    type Archived = ArchivedHashMap<K::Archived, V::Archived>;
-    type Resolver = ArchivedHashMapResolver;
-
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
-        resolver.resolve_from_len(pos, self.len())
-    }
-}
-
-impl<K: Serialize<S> + Hash + Eq, V: Serialize<S>, S: Serializer + ?Sized> Serialize<S>
-    for HashMap<K, V>
-where
-    K::Archived: Hash + Eq,
-{
-    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        Ok(ArchivedHashMap::serialize_from_iter(
-            self.iter(),
-            self.len(),
-            serializer,
-        )?)
-    }
-}
-
-impl<K: Archive + Hash + Eq, V: Archive, D: Fallible + ?Sized> Deserialize<HashMap<K, V>, D>
-    for Archived<HashMap<K, V>>
-where
-    K::Archived: Deserialize<K, D> + Hash + Eq,
-    V::Archived: Deserialize<V, D>,
-{
-    fn deserialize(&self, deserializer: &mut D) -> Result<HashMap<K, V>, D::Error> {
-        let mut result = HashMap::new();
-        for (k, v) in self.iter() {
-            result.insert(k.deserialize(deserializer)?, v.deserialize(deserializer)?);
-        }
-        Ok(result)
+impl<K: Hash + Eq + fmt::Debug, V: fmt::Debug> fmt::Debug for ArchivedHashMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
     }
 }
 
@@ -638,28 +661,7 @@ impl<K: Hash + Eq, V: PartialEq> PartialEq for ArchivedHashMap<K, V> {
 
 impl<K: Hash + Eq, V: Eq> Eq for ArchivedHashMap<K, V> {}
 
-impl<K: Hash + Eq + Borrow<AK>, V, AK: Hash + Eq, AV: PartialEq<V>> PartialEq<HashMap<K, V>>
-    for ArchivedHashMap<AK, AV>
-{
-    fn eq(&self, other: &HashMap<K, V>) -> bool {
-        if self.len() != other.len() {
-            false
-        } else {
-            self.iter()
-                .all(|(key, value)| other.get(key).map_or(false, |v| *value == *v))
-        }
-    }
-}
-
-impl<K: Hash + Eq + Borrow<AK>, V, AK: Hash + Eq, AV: PartialEq<V>>
-    PartialEq<ArchivedHashMap<AK, AV>> for HashMap<K, V>
-{
-    fn eq(&self, other: &ArchivedHashMap<AK, AV>) -> bool {
-        other.eq(self)
-    }
-}
-
-impl<K: Eq + Hash + Borrow<Q>, Q: Eq + Hash + ?Sized, V> Index<&'_ Q> for ArchivedHashMap<K, V> {
+impl<K: Eq + Hash, Q: Hash + Equivalent<K> + ?Sized, V> Index<&'_ Q> for ArchivedHashMap<K, V> {
     type Output = V;
 
     fn index(&self, key: &Q) -> &V {
@@ -684,8 +686,7 @@ impl<K: Hash + Eq> ArchivedHashSet<K> {
     #[inline]
     pub fn get<Q: ?Sized>(&self, k: &Q) -> Option<&K>
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         self.0.get_key_value(k).map(|(k, _)| k)
     }
@@ -694,8 +695,7 @@ impl<K: Hash + Eq> ArchivedHashSet<K> {
     #[inline]
     pub fn contains<Q: ?Sized>(&self, k: &Q) -> bool
     where
-        K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: Hash + Equivalent<K>,
     {
         self.0.contains_key(k)
     }
@@ -717,48 +717,43 @@ impl<K: Hash + Eq> ArchivedHashSet<K> {
     pub fn iter(&self) -> Keys<K, ()> {
         self.0.keys()
     }
-}
 
-/// The resolver for archived hash sets.
-pub struct ArchivedHashSetResolver(ArchivedHashMapResolver);
-
-impl<K: Archive + Hash + Eq> Archive for HashSet<K>
-where
-    K::Archived: Hash + Eq,
-{
-    type Archived = ArchivedHashSet<K::Archived>;
-    type Resolver = ArchivedHashSetResolver;
-
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
-        ArchivedHashSet(resolver.0.resolve_from_len(pos, self.len()))
+    /// Serializes an iterator of keys as a hash set.
+    ///
+    /// `len` must be the number of keys the iterator yields, and the keys must
+    /// be unique.
+    #[inline]
+    pub fn serialize_from_iter<
+        'a,
+        KU: 'a + Serialize<S, Archived = K> + Hash + Eq,
+        S: ScratchSpace + Serializer + ?Sized,
+    >(
+        iter: impl Iterator<Item = &'a KU>,
+        len: usize,
+        serializer: &mut S,
+    ) -> Result<ArchivedHashSetResolver, S::Error> {
+        Ok(ArchivedHashSetResolver(
+            ArchivedHashMap::serialize_from_iter(iter.map(|x| (x, &())), len, serializer)?,
+        ))
     }
 }
 
-impl<K: Serialize<S> + Hash + Eq, S: Serializer + ?Sized> Serialize<S> for HashSet<K>
-where
-    K::Archived: Hash + Eq,
-{
-    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        Ok(ArchivedHashSetResolver(
-            ArchivedHashMap::serialize_from_iter(
-                self.iter().map(|x| (x, &())),
-                self.len(),
-                serializer,
-            )?,
-        ))
+impl<K: Hash + Eq + fmt::Debug> fmt::Debug for ArchivedHashSet<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
     }
 }
 
-impl<K: Archive + Hash + Eq, D: Fallible + ?Sized> Deserialize<HashSet<K>, D>
-    for Archived<HashSet<K>>
-where
-    K::Archived: Deserialize<K, D> + Hash + Eq,
-{
-    fn deserialize(&self, deserializer: &mut D) -> Result<HashSet<K>, D::Error> {
-        let mut result = HashSet::new();
-        for k in self.iter() {
-            result.insert(k.deserialize(deserializer)?);
-        }
-        Ok(result)
+/// The resolver for archived hash sets.
+pub struct ArchivedHashSetResolver(ArchivedHashMapResolver);
+
+impl ArchivedHashSetResolver {
+    /// Resolves an archived hash set with `len` keys at the given position.
+    pub fn resolve_from_len<K: Hash + Eq>(
+        self,
+        pos: usize,
+        len: usize,
+    ) -> Result<ArchivedHashSet<K>, OffsetError> {
+        Ok(ArchivedHashSet(self.0.resolve_from_len(pos, len)?))
     }
 }
@@ -0,0 +1,38 @@
+//! Schema implementations for HashMap and HashSet.
+
+use crate::{
+    core_impl::chd::{ArchivedHashMap, ArchivedHashSet, Entry},
+    offset_of,
+    schema::{FieldSchema, Schema, SchemaGraph, TypeKind, TypeSchema},
+    ArchivedU32, ArchivedUsize, RawRelPtr,
+};
+use core::hash::Hash;
+
+impl<K: Schema, V: Schema> Schema for Entry<K, V> {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![
+            FieldSchema::new::<K>(graph, "key", offset_of!(Self, key)),
+            FieldSchema::new::<V>(graph, "value", offset_of!(Self, value)),
+        ]))
+    }
+}
+
+impl<K: Schema, V: Schema> Schema for ArchivedHashMap<K, V> {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::HashMap {
+            len: FieldSchema::new::<ArchivedUsize>(graph, "len", offset_of!(Self, len)),
+            displace: FieldSchema::new::<RawRelPtr>(graph, "displace", offset_of!(Self, displace)),
+            displacement: graph.register::<ArchivedU32>(),
+            entries: FieldSchema::new::<RawRelPtr>(graph, "entries", offset_of!(Self, entries)),
+            entry: graph.register::<Entry<K, V>>(),
+        })
+    }
+}
+
+impl<K: Hash + Eq + Schema> Schema for ArchivedHashSet<K> {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![FieldSchema::new::<
+            ArchivedHashMap<K, ()>,
+        >(graph, "0", 0)]))
+    }
+}
@@ -1,18 +1,18 @@
 //! Validation implementations for HashMap and HashSet.
 
 use crate::{
+    core_impl::chd::{ArchivedHashMap, ArchivedHashSet, Entry},
     offset_of,
-    std_impl::chd::{ArchivedHashMap, ArchivedHashSet, Entry},
     validation::{ArchiveBoundsContext, ArchiveMemoryContext},
-    Fallible, RawRelPtr,
+    ArchivedU32, ArchivedUsize, Fallible, RawRelPtr,
 };
-use bytecheck::{CheckBytes, SliceCheckError, Unreachable};
+use bytecheck::{CheckBytes, Error, SliceCheckError};
 use core::{
-    alloc::Layout,
+    alloc::{Layout, LayoutErr},
+    convert::Infallible,
     fmt,
     hash::{Hash, Hasher},
 };
-use std::{alloc::LayoutErr, error::Error};
 
 /// Errors that can occur while checking an archived hash map entry.
 #[derive(Debug)]
@@ -32,7 +32,8 @@ impl<K: fmt::Display, V: fmt::Display> fmt::Display for ArchivedHashMapEntryErro
     }
 }
 
-impl<K: fmt::Debug + fmt::Display, V: fmt::Debug + fmt::Display> Error
+#[cfg(feature = "std")]
+impl<K: fmt::Debug + fmt::Display, V: fmt::Debug + fmt::Display> std::error::Error
     for ArchivedHashMapEntryError<K, V>
 {
 }
@@ -61,7 +62,7 @@ pub enum HashMapError<K, V, C> {
     /// An error occured while checking the layouts of displacements or entries
     LayoutError(LayoutErr),
     /// An error occured while checking the displacements
-    CheckDisplaceError(SliceCheckError<Unreachable>),
+    CheckDisplaceError(SliceCheckError<Infallible>),
     /// An error occured while checking the entries
     CheckEntryError(SliceCheckError<ArchivedHashMapEntryError<K, V>>),
     /// A displacement value was invalid
@@ -94,21 +95,22 @@ impl<K: fmt::Display, V: fmt::Display, E: fmt::Display> fmt::Display for HashMap
     }
 }
 
-impl<K: Error + 'static, V: Error + 'static, C: Error + 'static> Error for HashMapError<K, V, C> {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
+#[cfg(feature = "std")]
+impl<K: Error, V: Error, C: Error> std::error::Error for HashMapError<K, V, C> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            HashMapError::LayoutError(e) => Some(e as &dyn Error),
-            HashMapError::CheckDisplaceError(e) => Some(e as &dyn Error),
-            HashMapError::CheckEntryError(e) => Some(e as &dyn Error),
+            HashMapError::LayoutError(e) => Some(e as &dyn std::error::Error),
+            HashMapError::CheckDisplaceError(e) => Some(e as &dyn std::error::Error),
+            HashMapError::CheckEntryError(e) => Some(e as &dyn std::error::Error),
             HashMapError::InvalidDisplacement { .. } => None,
             HashMapError::InvalidKeyPosition { .. } => None,
-            HashMapError::ContextError(e) => Some(e as &dyn Error),
+            HashMapError::ContextError(e) => Some(e as &dyn std::error::Error),
         }
     }
 }
 
-impl<K, V, C> From<Unreachable> for HashMapError<K, V, C> {
-    fn from(_: Unreachable) -> Self {
+impl<K, V, C> From<Infallible> for HashMapError<K, V, C> {
+    fn from(_: Infallible) -> Self {
         unreachable!();
     }
 }
@@ -119,8 +121,8 @@ impl<K, V, C> From<LayoutErr> for HashMapError<K, V, C> {
     }
 }
 
-impl<K, V, C> From<SliceCheckError<Unreachable>> for HashMapError<K, V, C> {
-    fn from(e: SliceCheckError<Unreachable>) -> Self {
+impl<K, V, C> From<SliceCheckError<Infallible>> for HashMapError<K, V, C> {
+    fn from(e: SliceCheckError<Infallible>) -> Self {
         Self::CheckDisplaceError(e)
     }
 }
@@ -147,10 +149,10 @@ where
     ) -> Result<&'a Self, Self::Error> {
         let bytes = value.cast::<u8>();
 
-        let len = *u32::check_bytes(
+        let len = from_archived!(*ArchivedUsize::check_bytes(
             bytes.add(offset_of!(ArchivedHashMap<K, V>, len)).cast(),
             context,
-        )?;
+        )?) as usize;
 
         let displace_rel_ptr = RawRelPtr::manual_check_bytes(
             bytes
@@ -161,15 +163,16 @@ where
         let displace_data_ptr = context
             .check_rel_ptr(displace_rel_ptr.base(), displace_rel_ptr.offset())
             .map_err(HashMapError::ContextError)?;
-        Layout::array::<u32>(len as usize)?;
-        let displace_ptr = ptr_meta::from_raw_parts(displace_data_ptr.cast(), len as usize);
+        Layout::array::<ArchivedU32>(len)?;
+        let displace_ptr = ptr_meta::from_raw_parts(displace_data_ptr.cast(), len);
         context
             .claim_owned_ptr(displace_ptr)
             .map_err(HashMapError::ContextError)?;
-        let displace = <[u32]>::check_bytes(displace_ptr, context)?;
+        let displace = <[ArchivedU32]>::check_bytes(displace_ptr, context)?;
 
         for (i, &d) in displace.iter().enumerate() {
-            if d >= len && d < 0x80_00_00_00 {
+            let d = from_archived!(d);
+            if d as usize >= len && d < 0x80_00_00_00 {
                 return Err(HashMapError::InvalidDisplacement { index: i, value: d });
             }
         }
@@ -181,20 +184,20 @@ where
         let entries_data_ptr = context
             .check_rel_ptr(entries_rel_ptr.base(), entries_rel_ptr.offset())
             .map_err(HashMapError::ContextError)?;
-        Layout::array::<Entry<K, V>>(len as usize)?;
-        let entries_ptr = ptr_meta::from_raw_parts(entries_data_ptr.cast(), len as usize);
+        Layout::array::<Entry<K, V>>(len)?;
+        let entries_ptr = ptr_meta::from_raw_parts(entries_data_ptr.cast(), len);
         context
             .claim_owned_ptr(entries_ptr)
             .map_err(HashMapError::ContextError)?;
         let entries = <[Entry<K, V>]>::check_bytes(entries_ptr, context)?;
 
-        for i in 0..len as usize {
+        for i in 0..len {
             let entry = &entries[i];
 
             let mut hasher = ArchivedHashMap::<K, V>::make_hasher();
             entry.key.hash(&mut hasher);
             let displace_index = hasher.finish() % len as u64;
-            let displace = displace[displace_index as usize];
+            let displace = from_archived!(displace[displace_index as usize]);
 
             let index = if displace == u32::MAX {
                 return Err(HashMapError::InvalidKeyPosition { index: i as usize });
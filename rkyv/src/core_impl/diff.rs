@@ -0,0 +1,104 @@
+//! Diff implementations for core types.
+
+use crate::{
+    core_impl::ArchivedOption,
+    diff::{bits_eq, ArchiveDiff, Differ, PathSegment},
+};
+use core::{
+    fmt,
+    marker::PhantomData,
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16,
+        NonZeroU32, NonZeroU64, NonZeroU8,
+    },
+};
+
+macro_rules! impl_primitive {
+    ($type:ty) => {
+        impl ArchiveDiff for $type {
+            fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+                if !bits_eq(self, other) {
+                    differ.changed(self, other);
+                }
+            }
+        }
+    };
+}
+
+impl_primitive!(bool);
+impl_primitive!(i8);
+impl_primitive!(i16);
+impl_primitive!(i32);
+impl_primitive!(i64);
+impl_primitive!(i128);
+impl_primitive!(u8);
+impl_primitive!(u16);
+impl_primitive!(u32);
+impl_primitive!(u64);
+impl_primitive!(u128);
+impl_primitive!(f32);
+impl_primitive!(f64);
+impl_primitive!(char);
+impl_primitive!(());
+impl_primitive!(NonZeroI8);
+impl_primitive!(NonZeroI16);
+impl_primitive!(NonZeroI32);
+impl_primitive!(NonZeroI64);
+impl_primitive!(NonZeroI128);
+impl_primitive!(NonZeroU8);
+impl_primitive!(NonZeroU16);
+impl_primitive!(NonZeroU32);
+impl_primitive!(NonZeroU64);
+impl_primitive!(NonZeroU128);
+
+impl<T: ?Sized> ArchiveDiff for PhantomData<T> {
+    fn diff<'a>(&'a self, _: &'a Self, _: &mut Differ<'a>) {}
+}
+
+impl<T: ArchiveDiff + fmt::Debug> ArchiveDiff for [T] {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        for (i, (old, new)) in self.iter().zip(other.iter()).enumerate() {
+            differ.diff(PathSegment::Index(i), old, new);
+        }
+        for (i, old) in self.iter().enumerate().skip(other.len()) {
+            differ.removed(PathSegment::Index(i), old);
+        }
+        for (i, new) in other.iter().enumerate().skip(self.len()) {
+            differ.added(PathSegment::Index(i), new);
+        }
+    }
+}
+
+#[cfg(not(feature = "const_generics"))]
+macro_rules! impl_array {
+    () => ();
+    ($len:literal, $($rest:literal,)*) => {
+        impl<T: ArchiveDiff + fmt::Debug> ArchiveDiff for [T; $len] {
+            fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+                self[..].diff(&other[..], differ);
+            }
+        }
+
+        impl_array! { $($rest,)* }
+    };
+}
+
+#[cfg(not(feature = "const_generics"))]
+impl_array! { 31, 30, 29, 28, 27, 26, 25, 24, 23, 22, 21, 20, 19, 18, 17, 16, 15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, }
+
+#[cfg(feature = "const_generics")]
+impl<T: ArchiveDiff + fmt::Debug, const N: usize> ArchiveDiff for [T; N] {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        self[..].diff(&other[..], differ);
+    }
+}
+
+impl<T: ArchiveDiff + fmt::Debug> ArchiveDiff for ArchivedOption<T> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        match (self, other) {
+            (ArchivedOption::Some(old), ArchivedOption::Some(new)) => old.diff(new, differ),
+            (ArchivedOption::None, ArchivedOption::None) => (),
+            _ => differ.changed(self, other),
+        }
+    }
+}
@@ -1,9 +1,15 @@
 //! [`Archive`] implementations for core types.
 
+#[cfg(feature = "alloc")]
+use crate::ser::{ScratchSpace, ScratchVec};
 use crate::{
     de::Deserializer, offset_of, ser::Serializer, Archive, ArchiveCopy, ArchivePointee,
-    ArchiveUnsized, Archived, ArchivedIsize, ArchivedMetadata, ArchivedUsize, Deserialize,
-    DeserializeUnsized, Fallible, Serialize, SerializeUnsized,
+    ArchiveUnsized, Archived, ArchivedChar, ArchivedF32, ArchivedF64, ArchivedI128, ArchivedI16,
+    ArchivedI32, ArchivedI64, ArchivedIsize, ArchivedMetadata, ArchivedNonZeroI128,
+    ArchivedNonZeroI16, ArchivedNonZeroI32, ArchivedNonZeroI64, ArchivedNonZeroU128,
+    ArchivedNonZeroU16, ArchivedNonZeroU32, ArchivedNonZeroU64, ArchivedU128, ArchivedU16,
+    ArchivedU32, ArchivedU64, ArchivedUsize, Deserialize, DeserializeUnsized, Fallible, FixedIsize,
+    FixedUsize, OffsetError, Serialize, SerializeUnsized,
 };
 #[cfg(rkyv_atomic)]
 use core::sync::atomic::{
@@ -13,6 +19,7 @@ use core::sync::atomic::{
 use core::sync::atomic::{AtomicI64, AtomicU64};
 use core::{
     alloc, cmp,
+    convert::TryFrom,
     hash::{Hash, Hasher},
     marker::PhantomData,
     num::{
@@ -23,7 +30,13 @@ use core::{
 };
 use ptr_meta::Pointee;
 
+pub mod chd;
+#[cfg(feature = "std")]
+pub mod diff;
+pub mod niche;
 pub mod range;
+#[cfg(feature = "schema")]
+pub mod schema;
 #[cfg(feature = "validation")]
 pub mod validation;
 
@@ -38,7 +51,13 @@ impl<T: Archive> ArchiveUnsized for T {
 
     type MetadataResolver = ();
 
-    fn resolve_metadata(&self, _: usize, _: Self::MetadataResolver) -> ArchivedMetadata<Self> {}
+    fn resolve_metadata(
+        &self,
+        _: usize,
+        _: Self::MetadataResolver,
+    ) -> Result<ArchivedMetadata<Self>, OffsetError> {
+        Ok(())
+    }
 }
 
 impl<T: Serialize<S>, S: Serializer + ?Sized> SerializeUnsized<S> for T {
@@ -71,8 +90,8 @@ impl<T: ?Sized> Archive for PhantomData<T> {
     type Archived = PhantomData<T>;
     type Resolver = ();
 
-    fn resolve(&self, _: usize, _: Self::Resolver) -> Self::Archived {
-        PhantomData
+    fn resolve(&self, _: usize, _: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        Ok(PhantomData)
     }
 }
 
@@ -99,8 +118,8 @@ macro_rules! impl_primitive {
             type Archived = Self;
             type Resolver = ();
 
-            fn resolve(&self, _: usize, _: Self::Resolver) -> Self::Archived {
-                *self
+            fn resolve(&self, _: usize, _: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+                Ok(*self)
             }
         }
 
@@ -126,35 +145,67 @@ macro_rules! impl_primitive {
 impl_primitive!(());
 impl_primitive!(bool);
 impl_primitive!(i8);
-impl_primitive!(i16);
-impl_primitive!(i32);
-impl_primitive!(i64);
-impl_primitive!(i128);
 impl_primitive!(u8);
-impl_primitive!(u16);
-impl_primitive!(u32);
-impl_primitive!(u64);
-impl_primitive!(u128);
-impl_primitive!(f32);
-impl_primitive!(f64);
-impl_primitive!(char);
 impl_primitive!(NonZeroI8);
-impl_primitive!(NonZeroI16);
-impl_primitive!(NonZeroI32);
-impl_primitive!(NonZeroI64);
-impl_primitive!(NonZeroI128);
 impl_primitive!(NonZeroU8);
-impl_primitive!(NonZeroU16);
-impl_primitive!(NonZeroU32);
-impl_primitive!(NonZeroU64);
-impl_primitive!(NonZeroU128);
+
+// Multibyte primitives may be archived with a fixed endianness, in which case
+// their archived forms are wrappers that have to be converted.
+macro_rules! impl_multibyte_primitive {
+    ($type:ty, $archived:ty) => {
+        impl Archive for $type {
+            type Archived = $archived;
+            type Resolver = ();
+
+            fn resolve(&self, _: usize, _: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+                Ok(to_archived!(*self))
+            }
+        }
+
+        impl<S: Fallible + ?Sized> Serialize<S> for $type {
+            fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+                Ok(())
+            }
+        }
+
+        #[cfg(not(any(feature = "archive_le", feature = "archive_be")))]
+        unsafe impl ArchiveCopy for $type {}
+
+        impl<D: Fallible + ?Sized> Deserialize<$type, D> for $archived {
+            fn deserialize(&self, _: &mut D) -> Result<$type, D::Error> {
+                Ok(from_archived!(*self))
+            }
+        }
+    };
+}
+
+impl_multibyte_primitive!(i16, ArchivedI16);
+impl_multibyte_primitive!(i32, ArchivedI32);
+impl_multibyte_primitive!(i64, ArchivedI64);
+impl_multibyte_primitive!(i128, ArchivedI128);
+impl_multibyte_primitive!(u16, ArchivedU16);
+impl_multibyte_primitive!(u32, ArchivedU32);
+impl_multibyte_primitive!(u64, ArchivedU64);
+impl_multibyte_primitive!(u128, ArchivedU128);
+impl_multibyte_primitive!(f32, ArchivedF32);
+impl_multibyte_primitive!(f64, ArchivedF64);
+impl_multibyte_primitive!(char, ArchivedChar);
+impl_multibyte_primitive!(NonZeroI16, ArchivedNonZeroI16);
+impl_multibyte_primitive!(NonZeroI32, ArchivedNonZeroI32);
+impl_multibyte_primitive!(NonZeroI64, ArchivedNonZeroI64);
+impl_multibyte_primitive!(NonZeroI128, ArchivedNonZeroI128);
+impl_multibyte_primitive!(NonZeroU16, ArchivedNonZeroU16);
+impl_multibyte_primitive!(NonZeroU32, ArchivedNonZeroU32);
+impl_multibyte_primitive!(NonZeroU64, ArchivedNonZeroU64);
+impl_multibyte_primitive!(NonZeroU128, ArchivedNonZeroU128);
 
 impl Archive for usize {
     type Archived = ArchivedUsize;
     type Resolver = ();
 
-    fn resolve(&self, _: usize, _: Self::Resolver) -> Self::Archived {
-        *self as ArchivedUsize
+    fn resolve(&self, _: usize, _: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        let value = FixedUsize::try_from(*self).map_err(|_| OffsetError::UsizeOutOfRange(*self))?;
+        Ok(to_archived!(value))
     }
 }
 
@@ -166,7 +217,7 @@ impl<S: Fallible + ?Sized> Serialize<S> for usize {
 
 impl<D: Fallible + ?Sized> Deserialize<usize, D> for ArchivedUsize {
     fn deserialize(&self, _: &mut D) -> Result<usize, D::Error> {
-        Ok(*self as usize)
+        Ok(from_archived!(*self) as usize)
     }
 }
 
@@ -174,8 +225,9 @@ impl Archive for isize {
     type Archived = ArchivedIsize;
     type Resolver = ();
 
-    fn resolve(&self, _: usize, _: Self::Resolver) -> Self::Archived {
-        *self as ArchivedIsize
+    fn resolve(&self, _: usize, _: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        let value = FixedIsize::try_from(*self).map_err(|_| OffsetError::IsizeOutOfRange(*self))?;
+        Ok(to_archived!(value))
     }
 }
 
@@ -187,7 +239,7 @@ impl<S: Fallible + ?Sized> Serialize<S> for isize {
 
 impl<D: Fallible + ?Sized> Deserialize<isize, D> for ArchivedIsize {
     fn deserialize(&self, _: &mut D) -> Result<isize, D::Error> {
-        Ok(*self as isize)
+        Ok(from_archived!(*self) as isize)
     }
 }
 
@@ -201,8 +253,12 @@ macro_rules! impl_atomic {
             type Archived = Self;
             type Resolver = AtomicResolver;
 
-            fn resolve(&self, _pos: usize, _resolver: AtomicResolver) -> $type {
-                <$type>::new(self.load(atomic::Ordering::Relaxed))
+            fn resolve(
+                &self,
+                _pos: usize,
+                _resolver: AtomicResolver,
+            ) -> Result<$type, OffsetError> {
+                Ok(<$type>::new(self.load(atomic::Ordering::Relaxed)))
             }
         }
 
@@ -225,19 +281,66 @@ impl_atomic!(AtomicBool);
 #[cfg(rkyv_atomic)]
 impl_atomic!(AtomicI8);
 #[cfg(rkyv_atomic)]
-impl_atomic!(AtomicI16);
+impl_atomic!(AtomicU8);
+
+// Multibyte atomics can't be archived with a fixed endianness, so they are
+// archived as their fixed-endian primitives instead.
+#[cfg(all(rkyv_atomic, any(feature = "archive_le", feature = "archive_be")))]
+macro_rules! impl_endian_atomic {
+    ($type:ty, $prim:ty) => {
+        impl Archive for $type {
+            type Archived = Archived<$prim>;
+            type Resolver = AtomicResolver;
+
+            fn resolve(
+                &self,
+                _pos: usize,
+                _resolver: AtomicResolver,
+            ) -> Result<Self::Archived, OffsetError> {
+                Ok(to_archived!(self.load(atomic::Ordering::Relaxed)))
+            }
+        }
+
+        impl<S: Fallible + ?Sized> Serialize<S> for $type {
+            fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+                Ok(AtomicResolver)
+            }
+        }
+
+        impl<D: Fallible + ?Sized> Deserialize<$type, D> for Archived<$prim> {
+            fn deserialize(&self, _: &mut D) -> Result<$type, D::Error> {
+                Ok(<$type>::new(from_archived!(*self)))
+            }
+        }
+    };
+}
+
+#[cfg(not(any(feature = "archive_le", feature = "archive_be")))]
+macro_rules! impl_multibyte_atomic {
+    ($type:ty, $prim:ty) => {
+        impl_atomic!($type);
+    };
+}
+
+#[cfg(any(feature = "archive_le", feature = "archive_be"))]
+macro_rules! impl_multibyte_atomic {
+    ($type:ty, $prim:ty) => {
+        impl_endian_atomic!($type, $prim);
+    };
+}
+
 #[cfg(rkyv_atomic)]
-impl_atomic!(AtomicI32);
-#[cfg(rkyv_atomic_64)]
-impl_atomic!(AtomicI64);
+impl_multibyte_atomic!(AtomicI16, i16);
 #[cfg(rkyv_atomic)]
-impl_atomic!(AtomicU8);
+impl_multibyte_atomic!(AtomicI32, i32);
+#[cfg(rkyv_atomic_64)]
+impl_multibyte_atomic!(AtomicI64, i64);
 #[cfg(rkyv_atomic)]
-impl_atomic!(AtomicU16);
+impl_multibyte_atomic!(AtomicU16, u16);
 #[cfg(rkyv_atomic)]
-impl_atomic!(AtomicU32);
+impl_multibyte_atomic!(AtomicU32, u32);
 #[cfg(rkyv_atomic_64)]
-impl_atomic!(AtomicU64);
+impl_multibyte_atomic!(AtomicU64, u64);
 
 #[cfg(not(feature = "strict"))]
 macro_rules! peel_tuple {
@@ -254,10 +357,10 @@ macro_rules! impl_tuple {
             type Archived = ($($type::Archived,)+);
             type Resolver = ($($type::Resolver,)+);
 
-            fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
+            fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
                 #[allow(clippy::unneeded_wildcard_pattern)]
-                let rev = ($(self.$index.resolve(pos + memoffset::offset_of_tuple!(Self::Archived, $index), resolver.$index),)+);
-                ($(rev.$index,)+)
+                let rev = ($(self.$index.resolve(pos + memoffset::offset_of_tuple!(Self::Archived, $index), resolver.$index)?,)+);
+                Ok(($(rev.$index,)+))
             }
         }
 
@@ -295,7 +398,7 @@ macro_rules! impl_array {
             type Archived = [T::Archived; $len];
             type Resolver = [T::Resolver; $len];
 
-            fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
+            fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
                 let mut resolvers = core::mem::MaybeUninit::new(resolver);
                 let resolvers_ptr = resolvers.as_mut_ptr().cast::<T::Resolver>();
                 let mut result = core::mem::MaybeUninit::<Self::Archived>::uninit();
@@ -303,11 +406,11 @@ macro_rules! impl_array {
                 #[allow(clippy::reversed_empty_ranges)]
                 for i in 0..$len {
                     unsafe {
-                        result_ptr.add(i).write(self[i].resolve(pos + i * core::mem::size_of::<T>(), resolvers_ptr.add(i).read()));
+                        result_ptr.add(i).write(self[i].resolve(pos + i * core::mem::size_of::<T>(), resolvers_ptr.add(i).read())?);
                     }
                 }
                 unsafe {
-                    result.assume_init()
+                    Ok(result.assume_init())
                 }
             }
         }
@@ -358,7 +461,7 @@ impl<T: Archive, const N: usize> Archive for [T; N] {
     type Archived = [T::Archived; N];
     type Resolver = [T::Resolver; N];
 
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
         let mut resolvers = core::mem::MaybeUninit::new(resolver);
         let resolvers_ptr = resolvers.as_mut_ptr().cast::<T::Resolver>();
         let mut result = core::mem::MaybeUninit::<Self::Archived>::uninit();
@@ -368,10 +471,10 @@ impl<T: Archive, const N: usize> Archive for [T; N] {
                 result_ptr.add(i).write(self[i].resolve(
                     pos + i * core::mem::size_of::<T::Archived>(),
                     resolvers_ptr.add(i).read(),
-                ));
+                )?);
             }
         }
-        unsafe { result.assume_init() }
+        unsafe { Ok(result.assume_init()) }
     }
 }
 
@@ -411,8 +514,14 @@ impl<T: Archive> ArchiveUnsized for [T] {
 
     type MetadataResolver = ();
 
-    fn resolve_metadata(&self, _: usize, _: Self::MetadataResolver) -> ArchivedMetadata<Self> {
-        ptr_meta::metadata(self) as ArchivedUsize
+    fn resolve_metadata(
+        &self,
+        _: usize,
+        _: Self::MetadataResolver,
+    ) -> Result<ArchivedMetadata<Self>, OffsetError> {
+        let len = ptr_meta::metadata(self);
+        let len = FixedUsize::try_from(len).map_err(|_| OffsetError::UsizeOutOfRange(len))?;
+        Ok(to_archived!(len))
     }
 }
 
@@ -420,11 +529,11 @@ impl<T> ArchivePointee for [T] {
     type ArchivedMetadata = ArchivedUsize;
 
     fn pointer_metadata(archived: &Self::ArchivedMetadata) -> <Self as Pointee>::Metadata {
-        *archived as usize
+        from_archived!(*archived) as usize
     }
 }
 
-#[cfg(not(feature = "std"))]
+#[cfg(not(feature = "alloc"))]
 impl<T: ArchiveCopy, S: Serializer + ?Sized> SerializeUnsized<S> for [T] {
     fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
         if !self.is_empty() {
@@ -447,21 +556,22 @@ impl<T: ArchiveCopy, S: Serializer + ?Sized> SerializeUnsized<S> for [T] {
     }
 }
 
-#[cfg(feature = "std")]
-impl<T: Serialize<S>, S: Serializer + ?Sized> SerializeUnsized<S> for [T] {
+#[cfg(feature = "alloc")]
+impl<T: Serialize<S>, S: ScratchSpace + Serializer + ?Sized> SerializeUnsized<S> for [T] {
     fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
         if !self.is_empty() {
-            let mut resolvers = Vec::with_capacity(self.len());
-            for value in self {
-                resolvers.push(value.serialize(serializer)?);
-            }
-            let result = serializer.align_for::<T::Archived>()?;
             unsafe {
-                for (i, resolver) in resolvers.drain(..).enumerate() {
+                let mut resolvers = ScratchVec::new(serializer, self.len())?;
+                for value in self {
+                    resolvers.push(value.serialize(serializer)?);
+                }
+                let result = serializer.align_for::<T::Archived>()?;
+                for (i, resolver) in resolvers.drain().enumerate() {
                     serializer.resolve_aligned(&self[i], resolver)?;
                 }
+                resolvers.free(serializer)?;
+                Ok(result)
             }
-            Ok(result)
         } else {
             Ok(0)
         }
@@ -472,7 +582,7 @@ impl<T: Serialize<S>, S: Serializer + ?Sized> SerializeUnsized<S> for [T] {
     }
 }
 
-#[cfg(not(feature = "std"))]
+#[cfg(not(feature = "alloc"))]
 impl<T: ArchiveCopy, D: Deserializer + ?Sized> DeserializeUnsized<[T], D>
     for <[T] as ArchiveUnsized>::Archived
 where
@@ -491,7 +601,7 @@ where
     }
 }
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 impl<T: Archive, D: Deserializer + ?Sized> DeserializeUnsized<[T], D>
     for <[T] as ArchiveUnsized>::Archived
 where
@@ -517,8 +627,12 @@ impl ArchiveUnsized for str {
 
     type MetadataResolver = ();
 
-    fn resolve_metadata(&self, _: usize, _: Self::MetadataResolver) -> ArchivedMetadata<Self> {
-        ptr_meta::metadata(self) as ArchivedUsize
+    fn resolve_metadata(
+        &self,
+        pos: usize,
+        resolver: Self::MetadataResolver,
+    ) -> Result<ArchivedMetadata<Self>, OffsetError> {
+        self.as_bytes().resolve_metadata(pos, resolver)
     }
 }
 
@@ -558,6 +672,10 @@ impl<D: Deserializer + ?Sized> DeserializeUnsized<str, D> for <str as ArchiveUns
 ///
 /// It functions identically to [`Option`] but has a different internal
 /// representation to allow for archiving.
+///
+/// `ArchivedOption` always stores a tag before its value. Options of nonzero
+/// integers and owned pointers can be archived without the tag by adding
+/// `#[with(Niche)]` to the field, see [`Niche`](crate::with::Niche).
 #[derive(Debug)]
 #[repr(u8)]
 pub enum ArchivedOption<T> {
@@ -632,14 +750,18 @@ impl<T: Archive> Archive for Option<T> {
     type Archived = ArchivedOption<T::Archived>;
     type Resolver = Option<T::Resolver>;
 
-    fn resolve(&self, pos: usize, resolver: Option<T::Resolver>) -> Self::Archived {
-        match resolver {
+    fn resolve(
+        &self,
+        pos: usize,
+        resolver: Option<T::Resolver>,
+    ) -> Result<Self::Archived, OffsetError> {
+        Ok(match resolver {
             None => ArchivedOption::None,
             Some(resolver) => ArchivedOption::Some(self.as_ref().unwrap().resolve(
                 pos + offset_of!(ArchivedOptionVariantSome<T::Archived>, 1),
                 resolver,
-            )),
-        }
+            )?),
+        })
     }
 }
 
@@ -708,3 +830,22 @@ impl<T: PartialEq<U>, U> PartialEq<ArchivedOption<T>> for Option<U> {
         other.eq(self)
     }
 }
+
+impl<T, U: PartialOrd<T>> PartialOrd<Option<T>> for ArchivedOption<U> {
+    fn partial_cmp(&self, other: &Option<T>) -> Option<cmp::Ordering> {
+        match (self, other) {
+            (ArchivedOption::Some(self_value), Some(other_value)) => {
+                self_value.partial_cmp(other_value)
+            }
+            (ArchivedOption::Some(_), None) => Some(cmp::Ordering::Greater),
+            (ArchivedOption::None, Some(_)) => Some(cmp::Ordering::Less),
+            (ArchivedOption::None, None) => Some(cmp::Ordering::Equal),
+        }
+    }
+}
+
+impl<T: PartialOrd<U>, U> PartialOrd<ArchivedOption<T>> for Option<U> {
+    fn partial_cmp(&self, other: &ArchivedOption<T>) -> Option<cmp::Ordering> {
+        other.partial_cmp(self).map(cmp::Ordering::reverse)
+    }
+}
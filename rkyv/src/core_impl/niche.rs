@@ -0,0 +1,150 @@
+//! Niche-optimized archived options for nonzero integers.
+//!
+//! These types use zero to represent `None`, so they are the same size as the
+//! integers they wrap instead of needing an additional tag like an
+//! [`ArchivedOption`](super::ArchivedOption).
+
+use crate::{
+    with::{ArchiveWith, DeserializeWith, Niche, SerializeWith},
+    Archived, Fallible, OffsetError,
+};
+use core::{
+    fmt,
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16,
+        NonZeroU32, NonZeroU64, NonZeroU8,
+    },
+};
+
+// Single-byte primitives are never wrapped, so they don't need to be converted
+macro_rules! single_byte {
+    ($expr:expr) => {
+        $expr
+    };
+}
+
+macro_rules! impl_nonzero_niche {
+    ($name:ident, $nz:ty, $int:ty) => {
+        impl_nonzero_niche!($name, $nz, $int, from_archived, to_archived);
+    };
+    ($name:ident, $nz:ty, $int:ty, $from:ident, $to:ident) => {
+        #[doc = concat!("An archived `Option<", stringify!($nz), ">` that uses zero to represent `None`.")]
+        #[derive(Clone, Copy, Eq, Hash, PartialEq)]
+        #[repr(transparent)]
+        pub struct $name(Archived<$int>);
+
+        impl $name {
+            /// Returns `true` if the option is a `None` value.
+            pub fn is_none(&self) -> bool {
+                $from!(self.0) == 0
+            }
+
+            /// Returns `true` if the option is a `Some` value.
+            pub fn is_some(&self) -> bool {
+                !self.is_none()
+            }
+
+            #[doc = concat!("Converts to an `Option<&Archived<", stringify!($nz), ">>`.")]
+            pub fn as_ref(&self) -> Option<&Archived<$nz>> {
+                if self.is_none() {
+                    None
+                } else {
+                    // The archived nonzero integer has the same layout as the
+                    // archived integer, and we just checked that it's nonzero
+                    Some(unsafe { &*(&self.0 as *const Archived<$int>).cast() })
+                }
+            }
+
+            #[doc = concat!("Converts to an `Option<&mut Archived<", stringify!($nz), ">>`.")]
+            pub fn as_mut(&mut self) -> Option<&mut Archived<$nz>> {
+                if self.is_none() {
+                    None
+                } else {
+                    Some(unsafe { &mut *(&mut self.0 as *mut Archived<$int>).cast() })
+                }
+            }
+
+            /// Returns the contained value as an option.
+            pub fn get(&self) -> Option<$nz> {
+                self.as_ref().map(|value| $from!(*value))
+            }
+
+            #[doc = concat!("Resolves an archived option from an `Option<", stringify!($nz), ">`.")]
+            pub fn resolve_from_option(field: Option<$nz>) -> Self {
+                Self($to!(field.map_or(0, <$nz>::get)))
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.get().fmt(f)
+            }
+        }
+
+        impl From<Option<$nz>> for $name {
+            fn from(value: Option<$nz>) -> Self {
+                Self::resolve_from_option(value)
+            }
+        }
+
+        impl PartialEq<Option<$nz>> for $name {
+            fn eq(&self, other: &Option<$nz>) -> bool {
+                self.get() == *other
+            }
+        }
+
+        impl PartialEq<$name> for Option<$nz> {
+            fn eq(&self, other: &$name) -> bool {
+                other.eq(self)
+            }
+        }
+
+        impl ArchiveWith<Option<$nz>> for Niche {
+            type Archived = $name;
+            type Resolver = ();
+
+            fn resolve_with(
+                field: &Option<$nz>,
+                _: usize,
+                _: Self::Resolver,
+            ) -> Result<Self::Archived, OffsetError> {
+                Ok($name::resolve_from_option(*field))
+            }
+        }
+
+        impl<S: Fallible + ?Sized> SerializeWith<Option<$nz>, S> for Niche {
+            fn serialize_with(_: &Option<$nz>, _: &mut S) -> Result<Self::Resolver, S::Error> {
+                Ok(())
+            }
+        }
+
+        impl<D: Fallible + ?Sized> DeserializeWith<$name, Option<$nz>, D> for Niche {
+            fn deserialize_with(field: &$name, _: &mut D) -> Result<Option<$nz>, D::Error> {
+                Ok(field.get())
+            }
+        }
+    };
+}
+
+impl_nonzero_niche!(
+    ArchivedOptionNonZeroI8,
+    NonZeroI8,
+    i8,
+    single_byte,
+    single_byte
+);
+impl_nonzero_niche!(ArchivedOptionNonZeroI16, NonZeroI16, i16);
+impl_nonzero_niche!(ArchivedOptionNonZeroI32, NonZeroI32, i32);
+impl_nonzero_niche!(ArchivedOptionNonZeroI64, NonZeroI64, i64);
+impl_nonzero_niche!(ArchivedOptionNonZeroI128, NonZeroI128, i128);
+impl_nonzero_niche!(
+    ArchivedOptionNonZeroU8,
+    NonZeroU8,
+    u8,
+    single_byte,
+    single_byte
+);
+impl_nonzero_niche!(ArchivedOptionNonZeroU16, NonZeroU16, u16);
+impl_nonzero_niche!(ArchivedOptionNonZeroU32, NonZeroU32, u32);
+impl_nonzero_niche!(ArchivedOptionNonZeroU64, NonZeroU64, u64);
+impl_nonzero_niche!(ArchivedOptionNonZeroU128, NonZeroU128, u128);
@@ -1,6 +1,8 @@
 //! [`Archive`] implementations for ranges.
 
-use crate::{offset_of, Archive, ArchiveCopy, Archived, Deserialize, Fallible, Serialize};
+use crate::{
+    offset_of, Archive, ArchiveCopy, Archived, Deserialize, Fallible, OffsetError, Serialize,
+};
 use core::{
     cmp, fmt,
     ops::{Bound, Range, RangeBounds, RangeFull, RangeInclusive},
@@ -10,8 +12,8 @@ impl Archive for RangeFull {
     type Archived = Self;
     type Resolver = ();
 
-    fn resolve(&self, _: usize, _: Self::Resolver) -> Self::Archived {
-        RangeFull
+    fn resolve(&self, _: usize, _: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        Ok(RangeFull)
     }
 }
 
@@ -86,15 +88,15 @@ impl<T: Archive> Archive for Range<T> {
     type Archived = ArchivedRange<T::Archived>;
     type Resolver = Range<T::Resolver>;
 
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
-        ArchivedRange {
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        Ok(ArchivedRange {
             start: self
                 .start
-                .resolve(pos + offset_of!(Self::Archived, start), resolver.start),
+                .resolve(pos + offset_of!(Self::Archived, start), resolver.start)?,
             end: self
                 .end
-                .resolve(pos + offset_of!(Self::Archived, end), resolver.end),
-        }
+                .resolve(pos + offset_of!(Self::Archived, end), resolver.end)?,
+        })
     }
 }
 
@@ -176,15 +178,15 @@ impl<T: Archive> Archive for RangeInclusive<T> {
     type Archived = ArchivedRangeInclusive<T::Archived>;
     type Resolver = Range<T::Resolver>;
 
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
-        ArchivedRangeInclusive {
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        Ok(ArchivedRangeInclusive {
             start: self
                 .start()
-                .resolve(pos + offset_of!(Self::Archived, start), resolver.start),
+                .resolve(pos + offset_of!(Self::Archived, start), resolver.start)?,
             end: self
                 .end()
-                .resolve(pos + offset_of!(Self::Archived, end), resolver.end),
-        }
+                .resolve(pos + offset_of!(Self::Archived, end), resolver.end)?,
+        })
     }
 }
 
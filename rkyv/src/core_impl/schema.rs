@@ -0,0 +1,199 @@
+//! Schema implementations for core types.
+
+use crate::{
+    core_impl::{
+        niche::{
+            ArchivedOptionNonZeroI128, ArchivedOptionNonZeroI16, ArchivedOptionNonZeroI32,
+            ArchivedOptionNonZeroI64, ArchivedOptionNonZeroI8, ArchivedOptionNonZeroU128,
+            ArchivedOptionNonZeroU16, ArchivedOptionNonZeroU32, ArchivedOptionNonZeroU64,
+            ArchivedOptionNonZeroU8,
+        },
+        range::{ArchivedRange, ArchivedRangeInclusive},
+        ArchivedOption, ArchivedOptionTag, ArchivedOptionVariantSome,
+    },
+    offset_of,
+    schema::{Endian, FieldSchema, Schema, SchemaGraph, TypeKind, TypeSchema, VariantSchema},
+    Archived,
+};
+use core::{
+    marker::PhantomData,
+    mem,
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU128, NonZeroU16,
+        NonZeroU32, NonZeroU64, NonZeroU8,
+    },
+    ops::RangeFull,
+};
+
+macro_rules! impl_primitive {
+    ($type:ty) => {
+        impl Schema for $type {
+            fn describe(_: &mut SchemaGraph) -> TypeSchema {
+                TypeSchema::sized::<Self>(TypeKind::Primitive {
+                    name: stringify!($type),
+                    endian: Endian::NATIVE,
+                })
+            }
+        }
+    };
+}
+
+impl_primitive!(bool);
+impl_primitive!(i8);
+impl_primitive!(i16);
+impl_primitive!(i32);
+impl_primitive!(i64);
+impl_primitive!(i128);
+impl_primitive!(u8);
+impl_primitive!(u16);
+impl_primitive!(u32);
+impl_primitive!(u64);
+impl_primitive!(u128);
+impl_primitive!(f32);
+impl_primitive!(f64);
+impl_primitive!(char);
+impl_primitive!(NonZeroI8);
+impl_primitive!(NonZeroI16);
+impl_primitive!(NonZeroI32);
+impl_primitive!(NonZeroI64);
+impl_primitive!(NonZeroI128);
+impl_primitive!(NonZeroU8);
+impl_primitive!(NonZeroU16);
+impl_primitive!(NonZeroU32);
+impl_primitive!(NonZeroU64);
+impl_primitive!(NonZeroU128);
+
+impl Schema for () {
+    fn describe(_: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(Vec::new()))
+    }
+}
+
+impl<T: ?Sized> Schema for PhantomData<T> {
+    fn describe(_: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(Vec::new()))
+    }
+}
+
+impl Schema for RangeFull {
+    fn describe(_: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(Vec::new()))
+    }
+}
+
+#[cfg(not(feature = "const_generics"))]
+macro_rules! impl_array {
+    () => ();
+    ($len:literal, $($rest:literal,)*) => {
+        impl<T: Schema> Schema for [T; $len] {
+            fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+                TypeSchema::sized::<Self>(TypeKind::Array {
+                    element: graph.register::<T>(),
+                    len: $len,
+                })
+            }
+        }
+
+        impl_array! { $($rest,)* }
+    };
+}
+
+#[cfg(not(feature = "const_generics"))]
+impl_array! { 31, 30, 29, 28, 27, 26, 25, 24, 23, 22, 21, 20, 19, 18, 17, 16, 15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, }
+
+#[cfg(feature = "const_generics")]
+impl<T: Schema, const N: usize> Schema for [T; N] {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Array {
+            element: graph.register::<T>(),
+            len: N,
+        })
+    }
+}
+
+impl<T: Schema> Schema for [T] {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema {
+            size: None,
+            align: mem::align_of::<T>(),
+            kind: TypeKind::Slice {
+                element: graph.register::<T>(),
+            },
+        }
+    }
+}
+
+impl Schema for str {
+    fn describe(_: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema {
+            size: None,
+            align: 1,
+            kind: TypeKind::Str,
+        }
+    }
+}
+
+impl<T: Schema> Schema for ArchivedOption<T> {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Enum {
+            tag: graph.register::<u8>(),
+            variants: vec![
+                VariantSchema {
+                    name: "None".to_string(),
+                    tag: ArchivedOptionTag::None as i128,
+                    fields: Vec::new(),
+                },
+                VariantSchema {
+                    name: "Some".to_string(),
+                    tag: ArchivedOptionTag::Some as i128,
+                    fields: vec![FieldSchema::new::<T>(
+                        graph,
+                        "0",
+                        offset_of!(ArchivedOptionVariantSome<T>, 1),
+                    )],
+                },
+            ],
+        })
+    }
+}
+
+impl<T: Schema> Schema for ArchivedRange<T> {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![
+            FieldSchema::new::<T>(graph, "start", offset_of!(Self, start)),
+            FieldSchema::new::<T>(graph, "end", offset_of!(Self, end)),
+        ]))
+    }
+}
+
+impl<T: Schema> Schema for ArchivedRangeInclusive<T> {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![
+            FieldSchema::new::<T>(graph, "start", offset_of!(Self, start)),
+            FieldSchema::new::<T>(graph, "end", offset_of!(Self, end)),
+        ]))
+    }
+}
+
+macro_rules! impl_nonzero_niche {
+    ($name:ident, $int:ty) => {
+        impl Schema for $name {
+            fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+                TypeSchema::sized::<Self>(TypeKind::Niche {
+                    inner: graph.register::<Archived<$int>>(),
+                })
+            }
+        }
+    };
+}
+
+impl_nonzero_niche!(ArchivedOptionNonZeroI8, i8);
+impl_nonzero_niche!(ArchivedOptionNonZeroI16, i16);
+impl_nonzero_niche!(ArchivedOptionNonZeroI32, i32);
+impl_nonzero_niche!(ArchivedOptionNonZeroI64, i64);
+impl_nonzero_niche!(ArchivedOptionNonZeroI128, i128);
+impl_nonzero_niche!(ArchivedOptionNonZeroU8, u8);
+impl_nonzero_niche!(ArchivedOptionNonZeroU16, u16);
+impl_nonzero_niche!(ArchivedOptionNonZeroU32, u32);
+impl_nonzero_niche!(ArchivedOptionNonZeroU64, u64);
+impl_nonzero_niche!(ArchivedOptionNonZeroU128, u128);
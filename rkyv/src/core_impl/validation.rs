@@ -2,13 +2,21 @@
 
 use crate::{
     core_impl::{
+        niche::{
+            ArchivedOptionNonZeroI128, ArchivedOptionNonZeroI16, ArchivedOptionNonZeroI32,
+            ArchivedOptionNonZeroI64, ArchivedOptionNonZeroI8, ArchivedOptionNonZeroU128,
+            ArchivedOptionNonZeroU16, ArchivedOptionNonZeroU32, ArchivedOptionNonZeroU64,
+            ArchivedOptionNonZeroU8,
+        },
         range::{ArchivedRange, ArchivedRangeInclusive},
         ArchivedOption, ArchivedOptionTag, ArchivedOptionVariantSome,
     },
-    offset_of,
+    offset_of, Archived,
 };
-use bytecheck::{CheckBytes, StructCheckError, Unreachable};
-use core::fmt;
+use alloc::boxed::Box;
+use bytecheck::{CheckBytes, StructCheckError};
+use core::{convert::Infallible, fmt};
+#[cfg(feature = "std")]
 use std::error::Error;
 
 /// Errors that can occur while checking an [`ArchivedOption`].
@@ -31,6 +39,7 @@ impl<T: fmt::Display> fmt::Display for ArchivedOptionError<T> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<T: Error + 'static> Error for ArchivedOptionError<T> {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
@@ -40,8 +49,8 @@ impl<T: Error + 'static> Error for ArchivedOptionError<T> {
     }
 }
 
-impl<T> From<Unreachable> for ArchivedOptionError<T> {
-    fn from(_: Unreachable) -> Self {
+impl<T> From<Infallible> for ArchivedOptionError<T> {
+    fn from(_: Infallible) -> Self {
         unreachable!();
     }
 }
@@ -132,3 +141,36 @@ impl<C: ?Sized, T: CheckBytes<C>> CheckBytes<C> for ArchivedRangeInclusive<T> {
         Ok(&*value)
     }
 }
+
+macro_rules! impl_nonzero_niche_check_bytes {
+    ($($name:ident: $int:ty,)*) => {
+        $(
+            impl<C: ?Sized> CheckBytes<C> for $name {
+                type Error = <Archived<$int> as CheckBytes<C>>::Error;
+
+                unsafe fn check_bytes<'a>(
+                    value: *const Self,
+                    context: &mut C,
+                ) -> Result<&'a Self, Self::Error> {
+                    // Zero represents `None` and every other value is a valid
+                    // nonzero integer
+                    Archived::<$int>::check_bytes(value.cast(), context)?;
+                    Ok(&*value)
+                }
+            }
+        )*
+    };
+}
+
+impl_nonzero_niche_check_bytes! {
+    ArchivedOptionNonZeroI8: i8,
+    ArchivedOptionNonZeroI16: i16,
+    ArchivedOptionNonZeroI32: i32,
+    ArchivedOptionNonZeroI64: i64,
+    ArchivedOptionNonZeroI128: i128,
+    ArchivedOptionNonZeroU8: u8,
+    ArchivedOptionNonZeroU16: u16,
+    ArchivedOptionNonZeroU32: u32,
+    ArchivedOptionNonZeroU64: u64,
+    ArchivedOptionNonZeroU128: u128,
+}
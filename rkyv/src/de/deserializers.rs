@@ -1,18 +1,18 @@
 //! Deserializers that can be used standalone and provide basic capabilities.
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 use crate::{de::Deserializer, Fallible};
+#[cfg(feature = "alloc")]
+use core::{alloc, fmt};
 #[cfg(feature = "std")]
-use core::alloc;
-#[cfg(feature = "std")]
-use std::{error::Error, fmt};
+use std::error::Error;
 
 /// Errors that may be returned by [`AllocDeserializer`].
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 #[derive(Debug)]
 pub enum AllocDeserializerError {}
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 impl fmt::Display for AllocDeserializerError {
     fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
         unreachable!();
@@ -23,17 +23,17 @@ impl fmt::Display for AllocDeserializerError {
 impl Error for AllocDeserializerError {}
 
 /// A deserializer that provides access to the global alloc function.
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 pub struct AllocDeserializer;
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 impl Fallible for AllocDeserializer {
     type Error = AllocDeserializerError;
 }
 
-#[cfg(feature = "std")]
+#[cfg(feature = "alloc")]
 impl Deserializer for AllocDeserializer {
     unsafe fn alloc(&mut self, layout: alloc::Layout) -> Result<*mut u8, Self::Error> {
-        Ok(std::alloc::alloc(layout))
+        Ok(::alloc::alloc::alloc(layout))
     }
 }
@@ -0,0 +1,204 @@
+//! Structural diffs between archived values.
+//!
+//! [`ArchiveDiff`] walks two archived values of the same type and reports
+//! where they differ without deserializing either of them. Each [`Change`] has
+//! the path to the part of the value that changed along with its old and new
+//! values.
+//!
+//! `ArchiveDiff` can be derived for archived types by adding `#[archive(diff)]`
+//! to a type that derives `Archive`.
+//!
+//! ## Example
+//!
+//! ```
+//! use rkyv::{
+//!     archived_value,
+//!     diff::diff,
+//!     ser::{serializers::WriteSerializer, Serializer},
+//!     Archive, Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! #[archive(diff)]
+//! struct Config {
+//!     name: String,
+//!     ports: Vec<u16>,
+//! }
+//!
+//! let mut serializer = WriteSerializer::new(Vec::new());
+//! let old_pos = serializer
+//!     .serialize_value(&Config {
+//!         name: "server".to_string(),
+//!         ports: vec![80, 443],
+//!     })
+//!     .unwrap();
+//! let new_pos = serializer
+//!     .serialize_value(&Config {
+//!         name: "server".to_string(),
+//!         ports: vec![80, 8443, 9000],
+//!     })
+//!     .unwrap();
+//! let buf = serializer.into_inner();
+//!
+//! let old = unsafe { archived_value::<Config>(buf.as_ref(), old_pos) };
+//! let new = unsafe { archived_value::<Config>(buf.as_ref(), new_pos) };
+//! let changes = diff(old, new)
+//!     .iter()
+//!     .map(|change| change.to_string())
+//!     .collect::<Vec<_>>();
+//! assert_eq!(changes, ["ports[1]: 443 -> 8443", "ports[2]: (none) -> 9000"]);
+//! ```
+
+use core::{fmt, mem, slice};
+
+/// An archived type that can report how two of its values differ.
+pub trait ArchiveDiff {
+    /// Reports the differences between this value and another to the given
+    /// differ.
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>);
+}
+
+/// A step along the path from an archived value to one of its parts.
+#[derive(Clone, Copy)]
+pub enum PathSegment<'a> {
+    /// A field of a struct or enum variant
+    Field(&'static str),
+    /// An element of a sequence
+    Index(usize),
+    /// The value for a key of a map
+    Key(&'a dyn fmt::Debug),
+}
+
+impl fmt::Debug for PathSegment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Field(name) => f.debug_tuple("Field").field(name).finish(),
+            PathSegment::Index(index) => f.debug_tuple("Index").field(index).finish(),
+            PathSegment::Key(key) => f.debug_tuple("Key").field(key).finish(),
+        }
+    }
+}
+
+/// A difference between two archived values.
+#[derive(Clone)]
+pub struct Change<'a> {
+    /// The path to the part of the values that changed
+    pub path: Vec<PathSegment<'a>>,
+    /// The old value, or `None` if it was added
+    pub old: Option<&'a dyn fmt::Debug>,
+    /// The new value, or `None` if it was removed
+    pub new: Option<&'a dyn fmt::Debug>,
+}
+
+impl fmt::Debug for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Change")
+            .field("path", &self.path)
+            .field("old", &self.old)
+            .field("new", &self.new)
+            .finish()
+    }
+}
+
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "(root)")?;
+        }
+        for (i, segment) in self.path.iter().enumerate() {
+            match segment {
+                PathSegment::Field(name) if i == 0 => write!(f, "{}", name)?,
+                PathSegment::Field(name) => write!(f, ".{}", name)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+                PathSegment::Key(key) => write!(f, "[{:?}]", key)?,
+            }
+        }
+        match self.old {
+            Some(old) => write!(f, ": {:?} -> ", old)?,
+            None => write!(f, ": (none) -> ")?,
+        }
+        match self.new {
+            Some(new) => write!(f, "{:?}", new),
+            None => write!(f, "(none)"),
+        }
+    }
+}
+
+/// Collects the changes between two archived values as they're walked.
+#[derive(Default)]
+pub struct Differ<'a> {
+    path: Vec<PathSegment<'a>>,
+    changes: Vec<Change<'a>>,
+}
+
+impl<'a> Differ<'a> {
+    /// Creates a new differ with no changes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports the differences between two values at the given path segment,
+    /// relative to the current path.
+    pub fn diff<T: ArchiveDiff + ?Sized>(
+        &mut self,
+        segment: PathSegment<'a>,
+        old: &'a T,
+        new: &'a T,
+    ) {
+        self.path.push(segment);
+        old.diff(new, self);
+        self.path.pop();
+    }
+
+    /// Reports that the value at the current path changed.
+    pub fn changed(&mut self, old: &'a dyn fmt::Debug, new: &'a dyn fmt::Debug) {
+        self.push_change(None, Some(old), Some(new));
+    }
+
+    /// Reports that a value was added at the given path segment.
+    pub fn added(&mut self, segment: PathSegment<'a>, new: &'a dyn fmt::Debug) {
+        self.push_change(Some(segment), None, Some(new));
+    }
+
+    /// Reports that a value was removed from the given path segment.
+    pub fn removed(&mut self, segment: PathSegment<'a>, old: &'a dyn fmt::Debug) {
+        self.push_change(Some(segment), Some(old), None);
+    }
+
+    fn push_change(
+        &mut self,
+        segment: Option<PathSegment<'a>>,
+        old: Option<&'a dyn fmt::Debug>,
+        new: Option<&'a dyn fmt::Debug>,
+    ) {
+        let mut path = self.path.clone();
+        path.extend(segment);
+        self.changes.push(Change { path, old, new });
+    }
+
+    /// Returns the changes that have been reported, in the order they were
+    /// found.
+    pub fn into_changes(self) -> Vec<Change<'a>> {
+        self.changes
+    }
+}
+
+/// Returns the changes from one archived value to another.
+pub fn diff<'a, T: ArchiveDiff + ?Sized>(old: &'a T, new: &'a T) -> Vec<Change<'a>> {
+    let mut differ = Differ::new();
+    old.diff(new, &mut differ);
+    differ.into_changes()
+}
+
+/// Compares the bytes of two primitive values.
+///
+/// Primitives are compared bitwise so that values like NaN are equal to
+/// themselves. Every byte of a primitive is initialized.
+#[inline]
+pub(crate) fn bits_eq<T: Copy>(a: &T, b: &T) -> bool {
+    let size = mem::size_of::<T>();
+    unsafe {
+        slice::from_raw_parts((a as *const T).cast::<u8>(), size)
+            == slice::from_raw_parts((b as *const T).cast::<u8>(), size)
+    }
+}
@@ -0,0 +1,20 @@
+//! Diff implementations for fixed-endian primitives.
+
+use super::{BigEndian, LittleEndian, Primitive};
+use crate::diff::{bits_eq, ArchiveDiff, Differ};
+use core::fmt;
+
+macro_rules! impl_diff {
+    ($name:ident) => {
+        impl<T: Primitive + fmt::Debug> ArchiveDiff for $name<T> {
+            fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+                if !bits_eq(&self.value().to_bits(), &other.value().to_bits()) {
+                    differ.changed(self, other);
+                }
+            }
+        }
+    };
+}
+
+impl_diff!(LittleEndian);
+impl_diff!(BigEndian);
@@ -0,0 +1,394 @@
+//! Fixed-endian wrappers for archived primitives.
+//!
+//! With the `archive_le` or `archive_be` features enabled, multibyte primitives
+//! are archived as [`LittleEndian`] or [`BigEndian`] wrappers instead of as
+//! themselves. This makes archives readable on hosts with a different native
+//! endianness than the one that wrote them. The wrappers convert to and from
+//! native values on access, so they can mostly be used like the primitives they
+//! wrap.
+
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(feature = "validation")]
+pub mod validation;
+
+use crate::core_impl::chd::Equivalent;
+#[cfg(feature = "alloc")]
+use crate::std_impl::btree_map::Comparable;
+use core::{
+    cmp, fmt,
+    hash::{Hash, Hasher},
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroU128, NonZeroU16, NonZeroU32,
+        NonZeroU64,
+    },
+};
+
+/// A primitive that can be stored with a fixed endianness.
+///
+/// # Safety
+///
+/// Every byte-swapped value returned from `to_bits` must also be a valid
+/// `Bits`, and `from_bits` must return a valid primitive for every value
+/// returned from `to_bits`.
+pub unsafe trait Primitive: Copy {
+    /// The type that the bits of the primitive are stored as.
+    type Bits: Copy;
+
+    /// Converts the primitive into its native-endian bits.
+    fn to_bits(self) -> Self::Bits;
+
+    /// Converts native-endian bits back into the primitive.
+    ///
+    /// # Safety
+    ///
+    /// `bits` must have been returned from `to_bits`.
+    unsafe fn from_bits(bits: Self::Bits) -> Self;
+
+    /// Converts native-endian bits to little-endian bits and back.
+    fn swap_le(bits: Self::Bits) -> Self::Bits;
+
+    /// Converts native-endian bits to big-endian bits and back.
+    fn swap_be(bits: Self::Bits) -> Self::Bits;
+}
+
+macro_rules! impl_integer {
+    ($type:ty) => {
+        unsafe impl Primitive for $type {
+            type Bits = $type;
+
+            #[inline]
+            fn to_bits(self) -> Self::Bits {
+                self
+            }
+
+            #[inline]
+            unsafe fn from_bits(bits: Self::Bits) -> Self {
+                bits
+            }
+
+            #[inline]
+            fn swap_le(bits: Self::Bits) -> Self::Bits {
+                bits.to_le()
+            }
+
+            #[inline]
+            fn swap_be(bits: Self::Bits) -> Self::Bits {
+                bits.to_be()
+            }
+        }
+    };
+}
+
+impl_integer!(i16);
+impl_integer!(i32);
+impl_integer!(i64);
+impl_integer!(i128);
+impl_integer!(u16);
+impl_integer!(u32);
+impl_integer!(u64);
+impl_integer!(u128);
+
+macro_rules! impl_float {
+    ($type:ty, $bits:ty) => {
+        unsafe impl Primitive for $type {
+            type Bits = $bits;
+
+            #[inline]
+            fn to_bits(self) -> Self::Bits {
+                <$type>::to_bits(self)
+            }
+
+            #[inline]
+            unsafe fn from_bits(bits: Self::Bits) -> Self {
+                <$type>::from_bits(bits)
+            }
+
+            #[inline]
+            fn swap_le(bits: Self::Bits) -> Self::Bits {
+                bits.to_le()
+            }
+
+            #[inline]
+            fn swap_be(bits: Self::Bits) -> Self::Bits {
+                bits.to_be()
+            }
+        }
+    };
+}
+
+impl_float!(f32, u32);
+impl_float!(f64, u64);
+
+unsafe impl Primitive for char {
+    type Bits = u32;
+
+    #[inline]
+    fn to_bits(self) -> Self::Bits {
+        self as u32
+    }
+
+    #[inline]
+    unsafe fn from_bits(bits: Self::Bits) -> Self {
+        core::char::from_u32_unchecked(bits)
+    }
+
+    #[inline]
+    fn swap_le(bits: Self::Bits) -> Self::Bits {
+        bits.to_le()
+    }
+
+    #[inline]
+    fn swap_be(bits: Self::Bits) -> Self::Bits {
+        bits.to_be()
+    }
+}
+
+// Byte swapping a nonzero value always yields another nonzero value, so the
+// bits can keep their nonzero type and niche.
+macro_rules! impl_nonzero {
+    ($type:ty) => {
+        unsafe impl Primitive for $type {
+            type Bits = $type;
+
+            #[inline]
+            fn to_bits(self) -> Self::Bits {
+                self
+            }
+
+            #[inline]
+            unsafe fn from_bits(bits: Self::Bits) -> Self {
+                bits
+            }
+
+            #[inline]
+            fn swap_le(bits: Self::Bits) -> Self::Bits {
+                unsafe { <$type>::new_unchecked(bits.get().to_le()) }
+            }
+
+            #[inline]
+            fn swap_be(bits: Self::Bits) -> Self::Bits {
+                unsafe { <$type>::new_unchecked(bits.get().to_be()) }
+            }
+        }
+    };
+}
+
+impl_nonzero!(NonZeroI16);
+impl_nonzero!(NonZeroI32);
+impl_nonzero!(NonZeroI64);
+impl_nonzero!(NonZeroI128);
+impl_nonzero!(NonZeroU16);
+impl_nonzero!(NonZeroU32);
+impl_nonzero!(NonZeroU64);
+impl_nonzero!(NonZeroU128);
+
+macro_rules! define_endian {
+    ($(#[$attr:meta])* $name:ident, $swap:ident, $target_endian:literal) => {
+        $(#[$attr])*
+        #[repr(transparent)]
+        pub struct $name<T: Primitive> {
+            bits: T::Bits,
+        }
+
+        impl<T: Primitive> $name<T> {
+            /// Whether the stored bytes are in the reverse order of the native
+            /// bytes.
+            #[cfg(feature = "validation")]
+            pub(crate) const SWAPPED: bool = !cfg!(target_endian = $target_endian);
+
+            /// Creates a new fixed-endian value from a native value.
+            #[inline]
+            pub fn new(value: T) -> Self {
+                Self {
+                    bits: T::$swap(value.to_bits()),
+                }
+            }
+
+            /// Returns the native value.
+            #[inline]
+            pub fn value(&self) -> T {
+                unsafe { T::from_bits(T::$swap(self.bits)) }
+            }
+
+            /// Replaces the stored value with the given native value.
+            #[inline]
+            pub fn set(&mut self, value: T) {
+                *self = Self::new(value);
+            }
+        }
+
+        impl<T: Primitive> Clone for $name<T> {
+            #[inline]
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<T: Primitive> Copy for $name<T> {}
+
+        impl<T: Primitive + Default> Default for $name<T> {
+            #[inline]
+            fn default() -> Self {
+                Self::new(T::default())
+            }
+        }
+
+        impl<T: Primitive> From<T> for $name<T> {
+            #[inline]
+            fn from(value: T) -> Self {
+                Self::new(value)
+            }
+        }
+
+        impl<T: Primitive + fmt::Debug> fmt::Debug for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.value().fmt(f)
+            }
+        }
+
+        impl<T: Primitive + fmt::Display> fmt::Display for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.value().fmt(f)
+            }
+        }
+
+        impl<T: Primitive + Eq> Eq for $name<T> {}
+
+        // Archived hash maps are built by hashing the unarchived keys, so this
+        // must hash the same way as the native value.
+        impl<T: Primitive + Hash> Hash for $name<T> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.value().hash(state)
+            }
+        }
+
+        impl<T: Primitive + Ord> Ord for $name<T> {
+            fn cmp(&self, other: &Self) -> cmp::Ordering {
+                self.value().cmp(&other.value())
+            }
+        }
+
+        impl<T: Primitive + PartialEq> PartialEq for $name<T> {
+            fn eq(&self, other: &Self) -> bool {
+                self.value().eq(&other.value())
+            }
+        }
+
+        impl<T: Primitive + PartialOrd> PartialOrd for $name<T> {
+            fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+                self.value().partial_cmp(&other.value())
+            }
+        }
+
+        impl<T: Primitive + PartialEq> PartialEq<T> for $name<T> {
+            fn eq(&self, other: &T) -> bool {
+                self.value().eq(other)
+            }
+        }
+
+        impl<T: Primitive + PartialOrd> PartialOrd<T> for $name<T> {
+            fn partial_cmp(&self, other: &T) -> Option<cmp::Ordering> {
+                self.value().partial_cmp(other)
+            }
+        }
+    };
+}
+
+define_endian!(
+    /// A primitive that is always stored in little-endian byte order.
+    LittleEndian,
+    swap_le,
+    "little"
+);
+
+define_endian!(
+    /// A primitive that is always stored in big-endian byte order.
+    BigEndian,
+    swap_be,
+    "big"
+);
+
+macro_rules! impl_native_eq {
+    ($($type:ty,)*) => {
+        $(
+            impl PartialEq<LittleEndian<$type>> for $type {
+                fn eq(&self, other: &LittleEndian<$type>) -> bool {
+                    other.eq(self)
+                }
+            }
+
+            impl PartialEq<BigEndian<$type>> for $type {
+                fn eq(&self, other: &BigEndian<$type>) -> bool {
+                    other.eq(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_native_eq! {
+    i16, i32, i64, i128, u16, u32, u64, u128, f32, f64, char,
+    NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128,
+}
+
+// Fixed-endian keys hash the same as their native values, so archived hash
+// maps with fixed-endian keys can be queried with native keys.
+macro_rules! impl_equivalent {
+    ($($type:ty,)*) => {
+        $(
+            impl Equivalent<LittleEndian<$type>> for $type {
+                #[inline]
+                fn equivalent(&self, key: &LittleEndian<$type>) -> bool {
+                    key.value() == *self
+                }
+            }
+
+            impl Equivalent<BigEndian<$type>> for $type {
+                #[inline]
+                fn equivalent(&self, key: &BigEndian<$type>) -> bool {
+                    key.value() == *self
+                }
+            }
+        )*
+    };
+}
+
+impl_equivalent! {
+    i16, i32, i64, i128, u16, u32, u64, u128, char,
+    NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128,
+}
+
+// Fixed-endian keys are ordered the same as their native values, so archived
+// B-tree maps with fixed-endian keys can be queried with native keys.
+#[cfg(feature = "alloc")]
+macro_rules! impl_comparable {
+    ($($type:ty,)*) => {
+        $(
+            impl Comparable<LittleEndian<$type>> for $type {
+                #[inline]
+                fn compare(&self, key: &LittleEndian<$type>) -> cmp::Ordering {
+                    self.cmp(&key.value())
+                }
+            }
+
+            impl Comparable<BigEndian<$type>> for $type {
+                #[inline]
+                fn compare(&self, key: &BigEndian<$type>) -> cmp::Ordering {
+                    self.cmp(&key.value())
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl_comparable! {
+    i16, i32, i64, i128, u16, u32, u64, u128, char,
+    NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128,
+}
@@ -0,0 +1,21 @@
+//! Schema implementations for fixed-endian primitives.
+
+use super::{BigEndian, LittleEndian, Primitive};
+use crate::schema::{Endian, Schema, SchemaGraph, TypeKind, TypeSchema};
+
+macro_rules! impl_schema {
+    ($name:ident, $endian:expr) => {
+        impl<T: Primitive + Schema> Schema for $name<T> {
+            fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+                let mut kind = T::describe(graph).kind;
+                if let TypeKind::Primitive { ref mut endian, .. } = kind {
+                    *endian = $endian;
+                }
+                TypeSchema::sized::<Self>(kind)
+            }
+        }
+    };
+}
+
+impl_schema!(LittleEndian, Endian::Little);
+impl_schema!(BigEndian, Endian::Big);
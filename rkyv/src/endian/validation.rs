@@ -0,0 +1,33 @@
+//! Validation implementations for fixed-endian primitives.
+
+use super::{BigEndian, LittleEndian, Primitive};
+use crate::Aligned;
+use bytecheck::CheckBytes;
+use core::{mem, ptr};
+
+macro_rules! impl_check_bytes {
+    ($name:ident) => {
+        impl<T: Primitive + CheckBytes<C>, C: ?Sized> CheckBytes<C> for $name<T> {
+            type Error = T::Error;
+
+            unsafe fn check_bytes<'a>(
+                value: *const Self,
+                context: &mut C,
+            ) -> Result<&'a Self, Self::Error> {
+                // Primitives are at most 16 bytes, so the native bytes can be
+                // reconstructed in an aligned buffer and checked there.
+                let size = mem::size_of::<T>();
+                let mut native = Aligned([0u8; 16]);
+                ptr::copy_nonoverlapping(value.cast::<u8>(), native.0.as_mut_ptr(), size);
+                if Self::SWAPPED {
+                    native.0[..size].reverse();
+                }
+                T::check_bytes(native.0.as_ptr().cast::<T>(), context)?;
+                Ok(&*value)
+            }
+        }
+    };
+}
+
+impl_check_bytes!(LittleEndian);
+impl_check_bytes!(BigEndian);
@@ -0,0 +1,176 @@
+//! Archived structs that can gain fields over time.
+//!
+//! Structs derived with `#[archive(evolvable)]` can add new trailing fields by
+//! marking them with `#[archive(since = N)]`, where `N` is the version that
+//! added them. The fields of each version are archived out of line after the
+//! fields of the previous version and linked to them with an
+//! [`ArchivedEvolution`]. A version that wasn't written is stored as a null
+//! pointer, so readers see which fields are present no matter which version of
+//! the struct wrote the archive:
+//!
+//! - Newer readers get `None` from the accessors of fields that an older writer
+//!   didn't know about, and deserialize them with their default value.
+//! - Older readers stop at the last version they know, which is linked to the
+//!   rest of the fields with an [`ArchivedFutureEvolution`].
+//!
+//! The archived layout of each version never changes once it's been released,
+//! so fields can only ever be added in a new version at the end of the struct.
+//!
+//! ## Example
+//!
+//! ```
+//! use rkyv::{
+//!     archived_value,
+//!     from_archived,
+//!     ser::{Serializer, serializers::WriteSerializer},
+//!     Archive,
+//!     Deserialize,
+//!     Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize, Deserialize)]
+//! #[archive(evolvable)]
+//! struct Config {
+//!     name: String,
+//!     #[archive(since = 1)]
+//!     retries: u32,
+//! }
+//!
+//! let value = Config {
+//!     name: "server".to_string(),
+//!     retries: 3,
+//! };
+//!
+//! let mut serializer = WriteSerializer::new(Vec::new());
+//! let pos = serializer.serialize_value(&value)
+//!     .expect("failed to archive config");
+//! let buf = serializer.into_inner();
+//!
+//! let archived = unsafe { archived_value::<Config>(buf.as_ref(), pos) };
+//! assert_eq!(archived.name, "server");
+//! assert_eq!(archived.retries().map(|r| from_archived!(*r)), Some(3));
+//! ```
+
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(feature = "validation")]
+pub mod validation;
+
+use crate::{ser::Serializer, OffsetError, RawRelPtr};
+use core::{fmt, marker::PhantomData, mem, pin::Pin, slice};
+
+/// A relative pointer to the fields added by the next version of an evolvable
+/// struct.
+///
+/// The pointer is null if the archive was written by a version of the struct
+/// that didn't have those fields.
+#[repr(transparent)]
+pub struct ArchivedEvolution<T> {
+    ptr: RawRelPtr,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> ArchivedEvolution<T> {
+    /// Resolves an archived evolution that points to the fields written at the
+    /// given position.
+    pub fn resolve_from_pos(pos: usize, next_pos: usize) -> Result<Self, OffsetError> {
+        Ok(Self {
+            ptr: RawRelPtr::try_new(pos, next_pos)?,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Creates an archived evolution that doesn't have the fields of the next
+    /// version.
+    pub fn absent() -> Self {
+        Self {
+            ptr: RawRelPtr::null(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns whether the fields of the next version were written.
+    pub fn is_present(&self) -> bool {
+        !self.ptr.is_null()
+    }
+
+    /// Gets the fields of the next version, if they were written.
+    pub fn get(&self) -> Option<&T> {
+        if self.is_present() {
+            unsafe { Some(&*self.ptr.as_ptr().cast::<T>()) }
+        } else {
+            None
+        }
+    }
+
+    /// Gets the fields of the next version as a pinned mutable reference, if
+    /// they were written.
+    pub fn get_pin(self: Pin<&mut Self>) -> Option<Pin<&mut T>> {
+        if self.is_present() {
+            unsafe {
+                let ptr = self.get_unchecked_mut().ptr.as_mut_ptr().cast::<T>();
+                Some(Pin::new_unchecked(&mut *ptr))
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Writes the fields of the next version and returns the position they were
+    /// written at.
+    ///
+    /// The fields are created by calling `resolve` with their position, which
+    /// is aligned for `T`.
+    pub fn serialize_next<S: Serializer + ?Sized>(
+        serializer: &mut S,
+        resolve: impl FnOnce(usize) -> Result<T, OffsetError>,
+    ) -> Result<usize, S::Error> {
+        let pos = serializer.align_for::<T>()?;
+        let fields = resolve(pos).map_err(|e| serializer.offset_error(e))?;
+        let data = (&fields as *const T).cast::<u8>();
+        let len = mem::size_of::<T>();
+        serializer.write(unsafe { slice::from_raw_parts(data, len) })?;
+        Ok(pos)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedEvolution<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+/// A relative pointer to the fields of versions of an evolvable struct that are
+/// newer than the current one.
+///
+/// This links the last known version of an evolvable struct to any fields
+/// added after it. It has the same layout as an [`ArchivedEvolution`], which
+/// replaces it when a new version is added.
+#[repr(transparent)]
+pub struct ArchivedFutureEvolution {
+    ptr: RawRelPtr,
+}
+
+impl ArchivedFutureEvolution {
+    /// Creates an archived future evolution. The current version never writes
+    /// any newer fields, so it's always absent.
+    pub fn absent() -> Self {
+        Self {
+            ptr: RawRelPtr::null(),
+        }
+    }
+
+    /// Returns whether the archive was written by a newer version with more
+    /// fields.
+    pub fn is_present(&self) -> bool {
+        !self.ptr.is_null()
+    }
+}
+
+impl fmt::Debug for ArchivedFutureEvolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArchivedFutureEvolution")
+            .field("is_present", &self.is_present())
+            .finish()
+    }
+}
@@ -0,0 +1,26 @@
+//! Schema implementations for evolution pointers.
+
+use super::{ArchivedEvolution, ArchivedFutureEvolution};
+use crate::{
+    schema::{Schema, SchemaGraph, TypeKind, TypeSchema},
+    RawRelPtr, RelPtr,
+};
+
+impl<T> Schema for ArchivedEvolution<T>
+where
+    RelPtr<T>: Schema,
+{
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Niche {
+            inner: graph.register::<RelPtr<T>>(),
+        })
+    }
+}
+
+impl Schema for ArchivedFutureEvolution {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Niche {
+            inner: graph.register::<RawRelPtr>(),
+        })
+    }
+}
@@ -0,0 +1,70 @@
+//! Validation implementations for evolvable structs.
+
+use super::{ArchivedEvolution, ArchivedFutureEvolution};
+use crate::{
+    offset_of,
+    std_impl::validation::OwnedPointerError,
+    validation::{ArchiveBoundsContext, ArchiveMemoryContext},
+    RawRelPtr,
+};
+use bytecheck::{CheckBytes, Error};
+use core::{alloc::Layout, convert::Infallible};
+
+impl<T: CheckBytes<C>, C: ArchiveBoundsContext + ArchiveMemoryContext + ?Sized> CheckBytes<C>
+    for ArchivedEvolution<T>
+where
+    C::Error: Error,
+{
+    type Error = OwnedPointerError<Infallible, T::Error, C::Error>;
+
+    unsafe fn check_bytes<'a>(
+        value: *const Self,
+        context: &mut C,
+    ) -> Result<&'a Self, Self::Error> {
+        let ptr = RawRelPtr::manual_check_bytes(
+            value.cast::<u8>().add(offset_of!(Self, ptr)).cast(),
+            context,
+        )
+        .map_err(OwnedPointerError::PointerCheckBytesError)?;
+        // Fields that weren't written are stored as a null pointer
+        if !ptr.is_null() {
+            let data = context
+                .check_rel_ptr(ptr.base(), ptr.offset())
+                .map_err(OwnedPointerError::ContextError)?;
+            let layout = Layout::new::<T>();
+            context
+                .bounds_check_ptr(data, &layout)
+                .map_err(OwnedPointerError::ContextError)?;
+            context
+                .claim_bytes(data, layout.size())
+                .map_err(OwnedPointerError::ContextError)?;
+            T::check_bytes(data.cast(), context)
+                .map_err(OwnedPointerError::ValueCheckBytesError)?;
+        }
+        Ok(&*value)
+    }
+}
+
+impl<C: ArchiveBoundsContext + ?Sized> CheckBytes<C> for ArchivedFutureEvolution
+where
+    C::Error: Error,
+{
+    type Error = C::Error;
+
+    unsafe fn check_bytes<'a>(
+        value: *const Self,
+        context: &mut C,
+    ) -> Result<&'a Self, Self::Error> {
+        let ptr = RawRelPtr::manual_check_bytes(
+            value.cast::<u8>().add(offset_of!(Self, ptr)).cast(),
+            context,
+        )
+        .unwrap();
+        // The layout of newer fields isn't known, so they can only be checked
+        // to be in bounds. They're never accessed through this version.
+        if !ptr.is_null() {
+            context.check_rel_ptr(ptr.base(), ptr.offset())?;
+        }
+        Ok(&*value)
+    }
+}
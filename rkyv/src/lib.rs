@@ -54,11 +54,26 @@
 //!
 //! ## Features
 //!
+//! - `alloc`: Enables support for types from the `alloc` crate, like `String`,
+//!   `Vec`, `Box`, `Rc`, and `Arc`, without the rest of the standard library
+//! - `archive_be`: Archives multibyte primitives in big-endian byte order,
+//!   regardless of the native endianness of the target. This makes archives
+//!   portable across platforms with different endianness.
+//! - `archive_le`: Archives multibyte primitives in little-endian byte order.
+//!   Mutually exclusive with `archive_be`.
 //! - `const_generics`: Improves the trait implementations for arrays with
 //!   support for all lengths
+//! - `schema`: Implements [`Schema`](schema::Schema) for archived types so
+//!   their layouts can be described to tools written in other languages
+//! - `size_16`: Archives `*size` as `*16` instead of `*32`. This shrinks
+//!   relative pointers and lengths for small archives, but limits relative
+//!   pointers to offsets within 32 KiB and lengths to 65,535. Mutually
+//!   exclusive with `size_64`.
 //! - `size_64`: Archives `*size` as `*64` instead of `*32`. This is for large
 //!   archive support
-//! - `std`: Enables standard library support (enabled by default)
+//! - `std`: Enables standard library support, including `HashMap` and the
+//!   serializers that write to an `io::Write` (enabled by default). Implies
+//!   `alloc`.
 //! - `strict`: Guarantees that types will have the same representations across
 //!   platforms and compilations. This is already the case in practice, but this
 //!   feature provides a guarantee. It additionally provides C type
@@ -71,26 +86,51 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(all(feature = "archive_le", feature = "archive_be"))]
+compile_error!("the `archive_le` and `archive_be` features are mutually exclusive");
+
+#[cfg(all(feature = "size_16", feature = "size_64"))]
+compile_error!("the `size_16` and `size_64` features are mutually exclusive");
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "std")]
+mod aligned_vec;
 pub mod core_impl;
 pub mod de;
-pub mod ser;
 #[cfg(feature = "std")]
+pub mod diff;
+pub mod endian;
+pub mod evolve;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod ser;
+#[cfg(feature = "alloc")]
 pub mod std_impl;
 #[cfg(feature = "validation")]
 pub mod validation;
+pub mod with;
 
 use core::{
+    convert::TryFrom,
     fmt,
     marker::{PhantomData, PhantomPinned},
+    mem,
     ops::{Deref, DerefMut},
     pin::Pin,
 };
 
+#[cfg(feature = "std")]
+pub use aligned_vec::AlignedVec;
 pub use memoffset::offset_of;
 use ptr_meta::Pointee;
 pub use rkyv_derive::{Archive, Deserialize, Serialize};
 #[cfg(feature = "validation")]
-pub use validation::check_archive;
+pub use validation::{check_archive, check_archive_with_footer, check_archived_root};
 
 /// Contains the error type for traits with methods that can fail
 pub trait Fallible {
@@ -168,6 +208,7 @@ pub trait Fallible {
 ///     Archived,
 ///     ArchiveUnsized,
 ///     MetadataResolver,
+///     OffsetError,
 ///     RelPtr,
 ///     Serialize,
 ///     SerializeUnsized,
@@ -210,9 +251,10 @@ pub trait Fallible {
 ///     type Resolver = OwnedStrResolver;
 ///
 ///     // The resolve function consumes the resolver and produces the archived
-///     // value at the given position.
-///     fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
-///         Self::Archived {
+///     // value at the given position. It fails if a relative pointer in the
+///     // archived value can't reach what it points to.
+///     fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+///         Ok(Self::Archived {
 ///             // We have to be careful to add the offset of the ptr field,
 ///             // otherwise we'll be using the position of the ArchivedOwnedStr
 ///             // instead of the position of the relative pointer.
@@ -220,8 +262,8 @@ pub trait Fallible {
 ///                 pos + offset_of!(Self::Archived, ptr),
 ///                 resolver.pos,
 ///                 resolver.metadata_resolver,
-///             ) },
-///         }
+///             )? },
+///         })
 ///     }
 /// }
 ///
@@ -260,7 +302,10 @@ pub trait Archive {
     type Resolver;
 
     /// Creates the archived version of this value at the given position.
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived;
+    ///
+    /// Returns an error if a relative pointer in the archived value would be
+    /// out of range.
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError>;
 }
 
 /// Converts a type to its archived form.
@@ -297,12 +342,14 @@ pub trait Deserialize<T: Archive<Archived = Self>, D: Fallible + ?Sized> {
 ///
 /// ```
 /// use core::{
+///     convert::TryFrom,
 ///     mem,
 ///     ops::{Deref, DerefMut},
 /// };
 /// use ptr_meta::Pointee;
 /// use rkyv::{
 ///     archived_unsized_value,
+///     from_archived,
 ///     offset_of,
 ///     ser::{serializers::WriteSerializer, Serializer},
 ///     Archive,
@@ -311,9 +358,12 @@ pub trait Deserialize<T: Archive<Archived = Self>, D: Fallible + ?Sized> {
 ///     ArchivedUsize,
 ///     ArchivePointee,
 ///     ArchiveUnsized,
+///     FixedUsize,
+///     OffsetError,
 ///     RelPtr,
 ///     Serialize,
 ///     SerializeUnsized,
+///     to_archived,
 /// };
 ///
 /// // We're going to be dealing mostly with blocks that have a trailing slice
@@ -344,7 +394,7 @@ pub trait Deserialize<T: Archive<Archived = Self>, D: Fallible + ?Sized> {
 ///     // We need to be able to turn our archived metadata into regular
 ///     // metadata for our type
 ///     fn pointer_metadata(archived: &Self::ArchivedMetadata) -> <Self as Pointee>::Metadata {
-///         archived.len as usize
+///         from_archived!(archived.len) as usize
 ///     }
 /// }
 ///
@@ -361,15 +411,19 @@ pub trait Deserialize<T: Archive<Archived = Self>, D: Fallible + ?Sized> {
 ///
 ///     // Here's where we make the metadata for our pointer.
 ///     // This also gets the position and resolver for the metadata, but we
-///     // don't need it in this case.
+///     // don't need it in this case. If the length is too large to archive,
+///     // we return an error instead of truncating it.
 ///     fn resolve_metadata(
 ///         &self,
 ///         _: usize,
 ///         _: Self::MetadataResolver
-///     ) -> ArchivedMetadata<Self> {
-///         BlockSliceMetadata {
-///             len: self.tail.len() as ArchivedUsize,
-///         }
+///     ) -> Result<ArchivedMetadata<Self>, OffsetError> {
+///         let len = self.tail.len();
+///         Ok(BlockSliceMetadata {
+///             len: to_archived!(
+///                 FixedUsize::try_from(len).map_err(|_| OffsetError::UsizeOutOfRange(len))?
+///             ),
+///         })
 ///     }
 /// }
 ///
@@ -437,15 +491,19 @@ pub trait ArchiveUnsized: Pointee {
 
     /// Creates the archived version of the metadata for this value at the given
     /// position.
+    ///
+    /// Returns an error if the metadata can't be stored in the archive.
     fn resolve_metadata(
         &self,
         pos: usize,
         resolver: Self::MetadataResolver,
-    ) -> ArchivedMetadata<Self>;
+    ) -> Result<ArchivedMetadata<Self>, OffsetError>;
 
     /// Resolves a relative pointer to this value with the given `from` and
     /// `to`.
     ///
+    /// Returns an error if the offset between `from` and `to` is out of range.
+    ///
     /// # Safety
     ///
     /// `to` must be the location of an archived value and `resolver` must be
@@ -455,8 +513,8 @@ pub trait ArchiveUnsized: Pointee {
         from: usize,
         to: usize,
         resolver: Self::MetadataResolver,
-    ) -> RelPtr<Self::Archived> {
-        RelPtr::resolve(from, to, self, resolver)
+    ) -> Result<RelPtr<Self::Archived>, OffsetError> {
+        RelPtr::try_resolve(from, to, self, resolver)
     }
 }
 
@@ -513,7 +571,9 @@ pub trait DeserializeUnsized<T: ArchiveUnsized<Archived = Self> + ?Sized, D: Fal
 ///
 /// `ArchiveCopy` must be manually implemented even if a type implements
 /// [`Archive`] and [`Copy`](core::marker::Copy) because some types may
-/// transform their data when writing to an archive.
+/// transform their data when writing to an archive. For example, multibyte
+/// primitives are not `ArchiveCopy` with the `archive_le` or `archive_be`
+/// features because they may need to be byte swapped.
 ///
 /// ## Examples
 /// ```
@@ -529,30 +589,127 @@ pub trait DeserializeUnsized<T: ArchiveUnsized<Archived = Self> + ?Sized, D: Fal
 /// struct Vector4<T>(T, T, T, T);
 ///
 /// let mut serializer = WriteSerializer::new(Vec::new());
-/// let value = Vector4(1f32, 2f32, 3f32, 4f32);
+/// let value = Vector4(1u8, 2u8, 3u8, 4u8);
 /// let pos = serializer.serialize_value(&value)
 ///     .expect("failed to archive Vector4");
 /// let buf = serializer.into_inner();
-/// let archived_value = unsafe { archived_value::<Vector4<f32>>(buf.as_ref(), pos) };
+/// let archived_value = unsafe { archived_value::<Vector4<u8>>(buf.as_ref(), pos) };
 /// assert_eq!(&value, archived_value);
 /// ```
 pub unsafe trait ArchiveCopy: Archive<Archived = Self> + Copy {}
 
-/// The type used for sizes in archived types.
-#[cfg(not(feature = "size_64"))]
-pub type ArchivedUsize = u32;
+/// The native type that `usize` is converted to for archiving.
+#[cfg(feature = "size_16")]
+pub type FixedUsize = u16;
 
-/// The type used for offsets in relative pointers.
-#[cfg(not(feature = "size_64"))]
-pub type ArchivedIsize = i32;
+/// The native type that `isize` is converted to for archiving.
+#[cfg(feature = "size_16")]
+pub type FixedIsize = i16;
 
-/// The type used for sizes in archived types.
+/// The native type that `usize` is converted to for archiving.
+#[cfg(not(any(feature = "size_16", feature = "size_64")))]
+pub type FixedUsize = u32;
+
+/// The native type that `isize` is converted to for archiving.
+#[cfg(not(any(feature = "size_16", feature = "size_64")))]
+pub type FixedIsize = i32;
+
+/// The native type that `usize` is converted to for archiving.
 #[cfg(feature = "size_64")]
-pub type ArchivedUsize = u64;
+pub type FixedUsize = u64;
 
-/// The type used for offsets in relative pointers.
+/// The native type that `isize` is converted to for archiving.
 #[cfg(feature = "size_64")]
-pub type ArchivedIsize = i64;
+pub type FixedIsize = i64;
+
+/// The type used for sizes in archived types.
+pub type ArchivedUsize = Archived<FixedUsize>;
+
+/// The type used for offsets in relative pointers.
+pub type ArchivedIsize = Archived<FixedIsize>;
+
+macro_rules! archived_primitives {
+    ($($name:ident: $prim:ty,)*) => {
+        $(
+            #[doc = concat!("The archived form of `", stringify!($prim), "`.")]
+            #[cfg(not(any(feature = "archive_le", feature = "archive_be")))]
+            pub type $name = $prim;
+
+            #[doc = concat!("The archived form of `", stringify!($prim), "`.")]
+            #[cfg(feature = "archive_le")]
+            pub type $name = endian::LittleEndian<$prim>;
+
+            #[doc = concat!("The archived form of `", stringify!($prim), "`.")]
+            #[cfg(feature = "archive_be")]
+            pub type $name = endian::BigEndian<$prim>;
+        )*
+    };
+}
+
+archived_primitives! {
+    ArchivedI16: i16,
+    ArchivedI32: i32,
+    ArchivedI64: i64,
+    ArchivedI128: i128,
+    ArchivedU16: u16,
+    ArchivedU32: u32,
+    ArchivedU64: u64,
+    ArchivedU128: u128,
+    ArchivedF32: f32,
+    ArchivedF64: f64,
+    ArchivedChar: char,
+    ArchivedNonZeroI16: core::num::NonZeroI16,
+    ArchivedNonZeroI32: core::num::NonZeroI32,
+    ArchivedNonZeroI64: core::num::NonZeroI64,
+    ArchivedNonZeroI128: core::num::NonZeroI128,
+    ArchivedNonZeroU16: core::num::NonZeroU16,
+    ArchivedNonZeroU32: core::num::NonZeroU32,
+    ArchivedNonZeroU64: core::num::NonZeroU64,
+    ArchivedNonZeroU128: core::num::NonZeroU128,
+}
+
+/// The error returned when an offset or size can't be stored in an archive.
+///
+/// Relative pointers store their offset as an [`ArchivedIsize`] and lengths are
+/// stored as an [`ArchivedUsize`], so archives with relative pointers that
+/// reach more than [`FixedIsize::MAX`] bytes or with lengths larger than
+/// [`FixedUsize::MAX`] can't be written. Enable the `size_64` feature to
+/// support larger archives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OffsetError {
+    /// The offset between two positions can't be stored in a relative pointer.
+    OutOfRange {
+        /// The position of the relative pointer.
+        from: usize,
+        /// The position the relative pointer points to.
+        to: usize,
+    },
+    /// A `usize` or length can't be stored in an [`ArchivedUsize`].
+    UsizeOutOfRange(usize),
+    /// An `isize` can't be stored in an [`ArchivedIsize`].
+    IsizeOutOfRange(isize),
+}
+
+impl fmt::Display for OffsetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OffsetError::OutOfRange { from, to } => write!(
+                f,
+                "offset from {} to {} is out of range for a relative pointer",
+                from, to
+            ),
+            OffsetError::UsizeOutOfRange(value) => {
+                write!(f, "usize {} is out of range for an archived usize", value)
+            }
+            OffsetError::IsizeOutOfRange(value) => {
+                write!(f, "isize {} is out of range for an archived isize", value)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OffsetError {}
 
 /// An untyped pointer which resolves relative to its position in memory.
 #[derive(Debug)]
@@ -564,24 +721,42 @@ pub struct RawRelPtr {
 
 impl RawRelPtr {
     /// Creates a new relative pointer between the given positions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset between the positions is out of range. Use
+    /// [`try_new`](RawRelPtr::try_new) to handle this case instead.
     pub fn new(from: usize, to: usize) -> Self {
-        Self {
-            offset: (to as isize - from as isize) as ArchivedIsize,
-            _phantom: PhantomPinned,
+        match Self::try_new(from, to) {
+            Ok(result) => result,
+            Err(e) => panic!("{}", e),
         }
     }
 
+    /// Attempts to create a new relative pointer between the given positions.
+    ///
+    /// Returns an error if the offset between the positions can't be stored in
+    /// an [`ArchivedIsize`].
+    pub fn try_new(from: usize, to: usize) -> Result<Self, OffsetError> {
+        let offset = FixedIsize::try_from(to as i128 - from as i128)
+            .map_err(|_| OffsetError::OutOfRange { from, to })?;
+        Ok(Self {
+            offset: to_archived!(offset),
+            _phantom: PhantomPinned,
+        })
+    }
+
     /// Creates a new relative pointer that has an offset of 0.
     pub fn null() -> Self {
         Self {
-            offset: 0,
+            offset: to_archived!(0),
             _phantom: PhantomPinned,
         }
     }
 
     /// Checks whether the relative pointer is null.
     pub fn is_null(&self) -> bool {
-        self.offset == 0
+        self.offset() == 0
     }
 
     /// Gets the base pointer for the relative pointer.
@@ -591,7 +766,7 @@ impl RawRelPtr {
 
     /// Gets the offset of the relative pointer.
     pub fn offset(&self) -> isize {
-        self.offset as isize
+        from_archived!(self.offset) as isize
     }
 
     /// Calculates the memory address being pointed to by this relative pointer.
@@ -599,7 +774,7 @@ impl RawRelPtr {
         unsafe {
             (self as *const Self)
                 .cast::<u8>()
-                .offset(self.offset as isize)
+                .offset(self.offset())
                 .cast()
         }
     }
@@ -610,7 +785,7 @@ impl RawRelPtr {
         unsafe {
             (self as *mut Self)
                 .cast::<u8>()
-                .offset(self.offset as isize)
+                .offset(self.offset())
                 .cast()
         }
     }
@@ -648,19 +823,49 @@ impl<T: ArchivePointee + ?Sized> RelPtr<T> {
     ///
     /// `from` must be the position of the relative pointer and `to` must be the
     /// position of some valid memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset between the positions is out of range. Use
+    /// [`try_resolve`](RelPtr::try_resolve) to handle this case instead.
     pub unsafe fn resolve<U: ArchiveUnsized<Archived = T> + ?Sized>(
         from: usize,
         to: usize,
         value: &U,
         metadata_resolver: U::MetadataResolver,
     ) -> Self {
+        match Self::try_resolve(from, to, value, metadata_resolver) {
+            Ok(result) => result,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Attempts to create a relative pointer from one position to another.
+    ///
+    /// Returns an error if the offset between the positions is out of range.
+    ///
+    /// # Safety
+    ///
+    /// `from` must be the position of the relative pointer and `to` must be the
+    /// position of some valid memory.
+    pub unsafe fn try_resolve<U: ArchiveUnsized<Archived = T> + ?Sized>(
+        from: usize,
+        to: usize,
+        value: &U,
+        metadata_resolver: U::MetadataResolver,
+    ) -> Result<Self, OffsetError> {
         let raw_ptr_pos = from + offset_of!(Self, raw_ptr);
         let metadata_pos = from + offset_of!(Self, metadata);
 
-        Self::new(
-            RawRelPtr::new(raw_ptr_pos, to),
-            value.resolve_metadata(metadata_pos, metadata_resolver),
-        )
+        Ok(Self::new(
+            RawRelPtr::try_new(raw_ptr_pos, to)?,
+            value.resolve_metadata(metadata_pos, metadata_resolver)?,
+        ))
+    }
+
+    /// Checks whether the relative pointer is null.
+    pub fn is_null(&self) -> bool {
+        self.raw_ptr.is_null()
     }
 
     /// Gets the base pointer for the relative pointer.
@@ -693,6 +898,18 @@ impl<T: ArchivePointee + ?Sized> RelPtr<T> {
     }
 }
 
+impl<T: ArchivePointee + ?Sized> RelPtr<T>
+where
+    T::ArchivedMetadata: Default,
+{
+    /// Creates a null relative pointer with default metadata.
+    ///
+    /// A null relative pointer points to itself and must not be dereferenced.
+    pub fn null() -> Self {
+        unsafe { Self::new(RawRelPtr::null(), Default::default()) }
+    }
+}
+
 impl<T: ArchivePointee + ?Sized> fmt::Debug for RelPtr<T>
 where
     T::ArchivedMetadata: fmt::Debug,
@@ -719,6 +936,8 @@ pub type MetadataResolver<T> = <T as ArchiveUnsized>::MetadataResolver;
 /// Wraps a type and aligns it to at least 16 bytes. Mainly used to align byte
 /// buffers for [`BufferSerializer`](ser::serializers::BufferSerializer).
 ///
+/// For a growable buffer with the same alignment, use [`AlignedVec`].
+///
 /// ## Examples
 /// ```
 /// use core::mem;
@@ -789,6 +1008,79 @@ pub unsafe fn archived_value_mut<T: Archive + ?Sized>(
     Pin::new_unchecked(&mut *bytes.get_unchecked_mut().as_mut_ptr().add(pos).cast())
 }
 
+/// Casts the archived root value from the end of the given byte array.
+///
+/// The root value must be the last object in the byte array, which is the case
+/// when it was archived with
+/// [`serialize_root`](ser::Serializer::serialize_root) and nothing was written
+/// after it.
+///
+/// # Safety
+///
+/// This is only safe to call if the root value is archived at the end of the
+/// byte array.
+#[inline]
+pub unsafe fn archived_root<T: Archive + ?Sized>(bytes: &[u8]) -> &T::Archived {
+    archived_value::<T>(bytes, bytes.len() - mem::size_of::<T::Archived>())
+}
+
+/// Casts the mutable archived root value from the end of the given byte array.
+///
+/// The root value must be the last object in the byte array, which is the case
+/// when it was archived with
+/// [`serialize_root`](ser::Serializer::serialize_root) and nothing was written
+/// after it.
+///
+/// # Safety
+///
+/// This is only safe to call if the root value is archived at the end of the
+/// byte array.
+#[inline]
+pub unsafe fn archived_root_mut<T: Archive + ?Sized>(
+    bytes: Pin<&mut [u8]>,
+) -> Pin<&mut T::Archived> {
+    let pos = bytes.len() - mem::size_of::<T::Archived>();
+    archived_value_mut::<T>(bytes, pos)
+}
+
+/// Casts the archived root value from a byte array that ends with an
+/// [`ArchiveFooter`](ser::ArchiveFooter).
+///
+/// Returns an error if the footer is missing or the archive was written with a
+/// different format version or different layout features.
+///
+/// # Safety
+///
+/// This is only safe to call if the byte array holds an archive written with
+/// [`serialize_with_footer`](ser::Serializer::serialize_with_footer) for the
+/// given type.
+#[inline]
+pub unsafe fn archived_value_with_footer<T: Archive>(
+    bytes: &[u8],
+) -> Result<&T::Archived, ser::FooterError> {
+    let pos = ser::ArchiveFooter::locate_root::<T>(bytes)?;
+    Ok(archived_value::<T>(bytes, pos))
+}
+
+/// Casts the mutable archived root value from a byte array that ends with an
+/// [`ArchiveFooter`](ser::ArchiveFooter).
+///
+/// Returns an error if the footer is missing or the archive was written with a
+/// different format version or different layout features.
+///
+/// # Safety
+///
+/// This is only safe to call if the byte array holds an archive written with
+/// [`serialize_with_footer`](ser::Serializer::serialize_with_footer) for the
+/// given type.
+#[inline]
+pub unsafe fn archived_value_with_footer_mut<T: Archive>(
+    bytes: Pin<&mut [u8]>,
+) -> Result<Pin<&mut T::Archived>, ser::FooterError> {
+    let pos = ser::ArchiveFooter::locate_root::<T>(&bytes)?;
+    Ok(archived_value_mut::<T>(bytes, pos))
+}
+
 /// Casts a [`RelPtr`] to the given unsized type from the given byte array at
 /// the given position and returns the value it points to.
 ///
@@ -0,0 +1,59 @@
+/// Converts an archived primitive to its native counterpart.
+///
+/// Without the `archive_le` or `archive_be` features, archived primitives are
+/// the same as their native counterparts and this does nothing.
+#[cfg(not(any(feature = "archive_le", feature = "archive_be")))]
+#[macro_export]
+macro_rules! from_archived {
+    ($expr:expr) => {{
+        $expr
+    }};
+}
+
+/// Converts an archived primitive to its native counterpart.
+///
+/// Without the `archive_le` or `archive_be` features, archived primitives are
+/// the same as their native counterparts and this does nothing.
+#[cfg(any(feature = "archive_le", feature = "archive_be"))]
+#[macro_export]
+macro_rules! from_archived {
+    ($expr:expr) => {{
+        ($expr).value()
+    }};
+}
+
+/// Converts a native primitive to its archived counterpart.
+///
+/// Without the `archive_le` or `archive_be` features, archived primitives are
+/// the same as their native counterparts and this does nothing.
+#[cfg(not(any(feature = "archive_le", feature = "archive_be")))]
+#[macro_export]
+macro_rules! to_archived {
+    ($expr:expr) => {{
+        $expr
+    }};
+}
+
+/// Converts a native primitive to its archived counterpart.
+///
+/// Without the `archive_le` or `archive_be` features, archived primitives are
+/// the same as their native counterparts and this does nothing.
+#[cfg(feature = "archive_le")]
+#[macro_export]
+macro_rules! to_archived {
+    ($expr:expr) => {{
+        $crate::endian::LittleEndian::new($expr)
+    }};
+}
+
+/// Converts a native primitive to its archived counterpart.
+///
+/// Without the `archive_le` or `archive_be` features, archived primitives are
+/// the same as their native counterparts and this does nothing.
+#[cfg(feature = "archive_be")]
+#[macro_export]
+macro_rules! to_archived {
+    ($expr:expr) => {{
+        $crate::endian::BigEndian::new($expr)
+    }};
+}
@@ -0,0 +1,456 @@
+//! C header generation for schema graphs.
+
+use super::{Endian, FieldSchema, SchemaGraph, TypeKind, TypeSchema};
+use core::fmt::Write;
+use std::collections::{HashMap, HashSet};
+
+const PREAMBLE: &str = "\
+#pragma once
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifndef RKYV_STATIC_ASSERT
+#ifdef __cplusplus
+#define RKYV_STATIC_ASSERT(cond) static_assert(cond, #cond)
+#else
+#define RKYV_STATIC_ASSERT(cond) _Static_assert(cond, #cond)
+#endif
+#endif
+
+#ifndef RKYV_ALIGNOF
+#ifdef __cplusplus
+#define RKYV_ALIGNOF(type) alignof(type)
+#else
+#define RKYV_ALIGNOF(type) _Alignof(type)
+#endif
+#endif
+
+/* Relative pointers point to the address of their offset plus its value.
+ * These helpers assume the offset is in native byte order. */
+#ifndef RKYV_REL_PTR
+#define RKYV_REL_PTR(type, ptr) \\
+    ((const type *)((const char *)&(ptr)->offset + (ptr)->offset))
+#endif
+
+#ifndef RKYV_REL_PTR_IS_NULL
+#define RKYV_REL_PTR_IS_NULL(ptr) ((ptr)->offset == 0)
+#endif
+";
+
+/// Gets the C type of a primitive.
+fn primitive_type(name: &str) -> &'static str {
+    match name {
+        "bool" => "bool",
+        "i8" | "NonZeroI8" => "int8_t",
+        "i16" | "NonZeroI16" => "int16_t",
+        "i32" | "NonZeroI32" => "int32_t",
+        "i64" | "NonZeroI64" => "int64_t",
+        "i128" | "NonZeroI128" => "__int128",
+        "u8" | "NonZeroU8" => "uint8_t",
+        "u16" | "NonZeroU16" => "uint16_t",
+        "u32" | "NonZeroU32" | "char" => "uint32_t",
+        "u64" | "NonZeroU64" => "uint64_t",
+        "u128" | "NonZeroU128" => "unsigned __int128",
+        "f32" => "float",
+        "f64" => "double",
+        _ => "void",
+    }
+}
+
+/// Turns a Rust type name into a C identifier by dropping module paths and
+/// joining the remaining identifiers with underscores.
+fn c_identifier(rust_name: &str) -> String {
+    let mut result = String::new();
+    let mut ident = String::new();
+    for c in rust_name.chars().chain(core::iter::once(' ')) {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            ident.push(c);
+        } else if c == ':' {
+            ident.clear();
+        } else if !ident.is_empty() {
+            if !result.is_empty() {
+                result.push('_');
+            }
+            result.push_str(&ident);
+            ident.clear();
+        }
+    }
+    result
+}
+
+/// Gets the C name of a field, prefixing the indices of tuple fields.
+fn field_name(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn tag_value(tag: i128) -> String {
+    if tag > i64::MAX as i128 {
+        format!("{}ULL", tag)
+    } else if tag > i32::MAX as i128 || tag < i32::MIN as i128 {
+        format!("{}LL", tag)
+    } else {
+        tag.to_string()
+    }
+}
+
+/// A field of a C struct.
+struct CField {
+    name: String,
+    offset: usize,
+    ty: String,
+    size: usize,
+}
+
+struct CHeader<'a> {
+    graph: &'a SchemaGraph,
+    names: HashMap<&'a str, String>,
+    emitted: HashSet<&'a str>,
+    out: String,
+}
+
+impl<'a> CHeader<'a> {
+    fn new(graph: &'a SchemaGraph) -> Self {
+        let mut names = HashMap::new();
+        let mut used = HashSet::new();
+        for (name, schema) in graph.iter() {
+            // Zero-sized and unsized types can't be declared in C
+            if schema.size.unwrap_or(0) == 0 {
+                continue;
+            }
+            let c_name = match schema.kind {
+                TypeKind::Primitive {
+                    name: primitive, ..
+                } if primitive == name => {
+                    names.insert(name, primitive_type(primitive).to_string());
+                    continue;
+                }
+                TypeKind::Array { ref element, len } => {
+                    format!("{}_array_{}", c_identifier(element), len)
+                }
+                _ => c_identifier(name),
+            };
+            let mut unique = c_name.clone();
+            let mut suffix = 2;
+            while !used.insert(unique.clone()) {
+                unique = format!("{}_{}", c_name, suffix);
+                suffix += 1;
+            }
+            names.insert(name, unique);
+        }
+
+        Self {
+            graph,
+            names,
+            emitted: HashSet::new(),
+            out: String::new(),
+        }
+    }
+
+    fn schema(&self, name: &str) -> &'a TypeSchema {
+        self.graph
+            .get(name)
+            .expect("schema graph is missing a type")
+    }
+
+    /// Gets the C name of a type, or the Rust name if it can't be declared in
+    /// C.
+    fn c_name(&self, name: &str) -> String {
+        self.names
+            .get(name)
+            .map_or_else(|| name.to_string(), |name| name.clone())
+    }
+
+    /// Gets a field as a C field, or `None` if it's zero-sized.
+    fn c_field(&self, name: String, field: &FieldSchema) -> Option<CField> {
+        self.names.get(field.ty.as_str()).map(|ty| CField {
+            name,
+            offset: field.offset,
+            ty: ty.clone(),
+            size: self.schema(&field.ty).size.unwrap(),
+        })
+    }
+
+    /// Writes a type after the types it contains by value.
+    fn emit(&mut self, name: &'a str) {
+        if !self.names.contains_key(name) || !self.emitted.insert(name) {
+            return;
+        }
+
+        let schema = self.schema(name);
+        match schema.kind {
+            TypeKind::Primitive { .. } | TypeKind::Str | TypeKind::Slice { .. } => (),
+            TypeKind::Struct(ref fields) => {
+                for field in fields.iter() {
+                    self.emit(&field.ty);
+                }
+            }
+            TypeKind::Enum {
+                ref tag,
+                ref variants,
+            } => {
+                self.emit(tag);
+                for field in variants.iter().flat_map(|v| v.fields.iter()) {
+                    self.emit(&field.ty);
+                }
+            }
+            TypeKind::Array { ref element, .. } => self.emit(element),
+            TypeKind::RelPtr {
+                ref offset,
+                ref metadata,
+                ..
+            } => {
+                self.emit(&offset.ty);
+                if let Some(metadata) = metadata {
+                    self.emit(&metadata.ty);
+                }
+            }
+            TypeKind::Niche { ref inner } => self.emit(inner),
+            TypeKind::HashMap {
+                ref len,
+                ref displace,
+                ref entries,
+                ..
+            } => {
+                self.emit(&len.ty);
+                self.emit(&displace.ty);
+                self.emit(&entries.ty);
+            }
+        }
+
+        self.write_type(name, schema);
+    }
+
+    fn write_type(&mut self, name: &str, schema: &TypeSchema) {
+        let c_name = self.c_name(name);
+        match schema.kind {
+            TypeKind::Primitive {
+                name: primitive,
+                endian,
+            } => {
+                if primitive == name {
+                    return;
+                }
+                let endian = match endian {
+                    Endian::Little if schema.align > 1 => "\n * Little-endian",
+                    Endian::Big if schema.align > 1 => "\n * Big-endian",
+                    _ => "",
+                };
+                writeln!(
+                    self.out,
+                    "\n/* {}{} */\ntypedef {} {};",
+                    name,
+                    endian,
+                    primitive_type(primitive),
+                    c_name
+                )
+                .unwrap();
+            }
+            TypeKind::Struct(ref fields) => {
+                writeln!(self.out, "\n/* {} */", name).unwrap();
+                let fields = fields
+                    .iter()
+                    .filter_map(|f| self.c_field(field_name(&f.name), f))
+                    .collect();
+                self.write_struct(&c_name, fields);
+            }
+            TypeKind::Enum {
+                ref tag,
+                ref variants,
+            } => {
+                writeln!(self.out, "\n/* {} */", name).unwrap();
+                for variant in variants.iter() {
+                    writeln!(
+                        self.out,
+                        "#define {}_TAG_{} {}",
+                        c_name,
+                        variant.name,
+                        tag_value(variant.tag)
+                    )
+                    .unwrap();
+                }
+
+                // Each variant with fields is a struct that starts with the
+                // tag, and the enum is a union of the tag and those structs
+                let tag = FieldSchema {
+                    name: "rkyv_tag".to_string(),
+                    offset: 0,
+                    ty: tag.clone(),
+                };
+                let mut members = vec![(self.c_name(&tag.ty), tag.name.clone())];
+                for variant in variants.iter() {
+                    let fields = variant
+                        .fields
+                        .iter()
+                        .filter_map(|f| self.c_field(field_name(&f.name), f))
+                        .collect::<Vec<_>>();
+                    if !fields.is_empty() {
+                        let variant_name = format!("{}_{}", c_name, variant.name);
+                        let variant_fields = self
+                            .c_field(tag.name.clone(), &tag)
+                            .into_iter()
+                            .chain(fields)
+                            .collect();
+                        self.write_struct(&variant_name, variant_fields);
+                        members.push((variant_name, variant.name.clone()));
+                    }
+                }
+
+                writeln!(self.out, "typedef union {} {{", c_name).unwrap();
+                for (ty, name) in members.iter() {
+                    writeln!(self.out, "    {} {};", ty, name).unwrap();
+                }
+                writeln!(self.out, "}} {};", c_name).unwrap();
+            }
+            TypeKind::Array { ref element, len } => {
+                writeln!(
+                    self.out,
+                    "\n/* {} */\ntypedef {} {}[{}];",
+                    name,
+                    self.c_name(element),
+                    c_name,
+                    len
+                )
+                .unwrap();
+            }
+            TypeKind::RelPtr {
+                ref offset,
+                ref metadata,
+                ref pointee,
+            } => {
+                let pointee = pointee
+                    .as_ref()
+                    .map(|pointee| match self.schema(pointee).kind {
+                        TypeKind::Str => "metadata bytes of UTF-8".to_string(),
+                        TypeKind::Slice { ref element } => {
+                            format!("metadata elements of {}", self.c_name(element))
+                        }
+                        _ => self.c_name(pointee),
+                    });
+                match pointee {
+                    Some(pointee) => {
+                        writeln!(self.out, "\n/* {}\n * Points to {} */", name, pointee).unwrap()
+                    }
+                    None => writeln!(self.out, "\n/* {} */", name).unwrap(),
+                }
+                let fields = core::iter::once(offset)
+                    .chain(metadata.iter())
+                    .filter_map(|f| self.c_field(f.name.clone(), f))
+                    .collect();
+                self.write_struct(&c_name, fields);
+            }
+            TypeKind::Niche { ref inner } => {
+                writeln!(
+                    self.out,
+                    "\n/* {}\n * Zero represents None */\ntypedef {} {};",
+                    name,
+                    self.c_name(inner),
+                    c_name
+                )
+                .unwrap();
+            }
+            TypeKind::HashMap {
+                ref len,
+                ref displace,
+                ref displacement,
+                ref entries,
+                ref entry,
+            } => {
+                writeln!(
+                    self.out,
+                    "\n/* {}\n * displace points to len {}, entries points to len {} */",
+                    name,
+                    self.c_name(displacement),
+                    self.c_name(entry)
+                )
+                .unwrap();
+                let fields = [len, displace, entries]
+                    .iter()
+                    .filter_map(|f| self.c_field(f.name.clone(), f))
+                    .collect();
+                self.write_struct(&c_name, fields);
+            }
+            TypeKind::Str | TypeKind::Slice { .. } => return,
+        }
+
+        writeln!(
+            self.out,
+            "RKYV_STATIC_ASSERT(sizeof({}) == {});",
+            c_name,
+            schema.size.unwrap()
+        )
+        .unwrap();
+        writeln!(
+            self.out,
+            "RKYV_STATIC_ASSERT(RKYV_ALIGNOF({}) == {});",
+            c_name, schema.align
+        )
+        .unwrap();
+    }
+
+    /// Writes a struct with its fields at their offsets. Padding is added
+    /// explicitly, so the C struct has the same layout even if the Rust fields
+    /// were reordered.
+    fn write_struct(&mut self, c_name: &str, mut fields: Vec<CField>) {
+        fields.sort_by_key(|field| field.offset);
+
+        writeln!(self.out, "typedef struct {} {{", c_name).unwrap();
+        let mut position = 0;
+        let mut padding = 0;
+        for field in fields.iter() {
+            if field.offset > position {
+                writeln!(
+                    self.out,
+                    "    uint8_t rkyv_pad{}[{}];",
+                    padding,
+                    field.offset - position
+                )
+                .unwrap();
+                padding += 1;
+            }
+            writeln!(self.out, "    {} {};", field.ty, field.name).unwrap();
+            position = field.offset + field.size;
+        }
+        writeln!(self.out, "}} {};", c_name).unwrap();
+
+        for field in fields.iter() {
+            writeln!(
+                self.out,
+                "RKYV_STATIC_ASSERT(offsetof({}, {}) == {});",
+                c_name, field.name, field.offset
+            )
+            .unwrap();
+        }
+    }
+}
+
+impl SchemaGraph {
+    /// Writes C declarations for the types in the graph.
+    ///
+    /// Each type that isn't zero-sized or unsized is declared with a `typedef`
+    /// named after its Rust type without module paths. Structs lay out their
+    /// fields at the same offsets as the archived types, and enums are unions
+    /// of their tag and a struct for each variant with fields. The header
+    /// asserts the sizes, alignments, and field offsets of the declarations so
+    /// any differences from the Rust layouts fail to compile.
+    ///
+    /// The layouts are only guaranteed to be stable across compilations with
+    /// the `strict` feature enabled.
+    pub fn to_c_header(&self) -> String {
+        let mut header = CHeader::new(self);
+        for (name, _) in self.iter() {
+            header.emit(name);
+        }
+
+        let mut result = String::new();
+        writeln!(result, "/* Archived layouts for {} */", self.root()).unwrap();
+        result.push_str(PREAMBLE);
+        result.push_str(&header.out);
+        result
+    }
+}
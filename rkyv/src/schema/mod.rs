@@ -0,0 +1,460 @@
+//! Machine-readable descriptions of the layouts of archived types.
+//!
+//! Archived types that implement [`Schema`] can describe their size, alignment,
+//! and the offsets and types of their fields. A [`SchemaGraph`] collects the
+//! descriptions of a type and every type it refers to, and can be written out
+//! as JSON for tools that read archives without using rkyv.
+//!
+//! Deriving `Archive` also implements `Schema` for the archived type when the
+//! `schema` feature is enabled.
+//!
+//! ## Example
+//!
+//! ```
+//! use rkyv::{schema::{SchemaGraph, TypeKind}, Archive, Archived};
+//!
+//! #[derive(Archive)]
+//! struct Point {
+//!     x: f32,
+//!     y: f32,
+//! }
+//!
+//! let graph = SchemaGraph::of::<Archived<Point>>();
+//! let root = graph.get(graph.root()).unwrap();
+//! assert_eq!(root.size, Some(8));
+//! if let TypeKind::Struct(fields) = &root.kind {
+//!     assert_eq!(fields[1].name, "y");
+//!     assert_eq!(fields[1].offset, 4);
+//! } else {
+//!     panic!("expected a struct");
+//! }
+//!
+//! let json = graph.to_json();
+//! assert!(json.contains("\"kind\":\"struct\""));
+//! ```
+//!
+//! ## JSON format
+//!
+//! The JSON description is an object with the name of the root type and the
+//! schemas of all the types in the graph keyed by their names:
+//!
+//! ```json
+//! {
+//!   "root": "example::ArchivedPoint",
+//!   "types": {
+//!     "example::ArchivedPoint": {
+//!       "size": 8,
+//!       "align": 4,
+//!       "kind": "struct",
+//!       "fields": [
+//!         { "name": "x", "offset": 0, "type": "f32" },
+//!         { "name": "y", "offset": 4, "type": "f32" }
+//!       ]
+//!     },
+//!     "f32": { "size": 4, "align": 4, "kind": "primitive", "name": "f32", "endian": "little" }
+//!   }
+//! }
+//! ```
+//!
+//! Each type has a `size` (`null` for unsized types like `str`), an `align`,
+//! and a `kind` with the same name as its [`TypeKind`] in snake case. The other
+//! properties of each kind are the same as the fields of its variant.
+//!
+//! ## C headers
+//!
+//! [`SchemaGraph::to_c_header`] writes C declarations for the types in the
+//! graph, so C and C++ programs can read archives through the same layouts:
+//!
+//! ```c
+//! /* example::ArchivedPoint */
+//! typedef struct ArchivedPoint {
+//!     float x;
+//!     float y;
+//! } ArchivedPoint;
+//! RKYV_STATIC_ASSERT(offsetof(ArchivedPoint, x) == 0);
+//! RKYV_STATIC_ASSERT(offsetof(ArchivedPoint, y) == 4);
+//! RKYV_STATIC_ASSERT(sizeof(ArchivedPoint) == 8);
+//! RKYV_STATIC_ASSERT(RKYV_ALIGNOF(ArchivedPoint) == 4);
+//! ```
+//!
+//! Enable the `strict` feature to guarantee that the layouts stay the same
+//! across compilations.
+
+mod c;
+
+use crate::{ArchivePointee, RawRelPtr, RelPtr};
+use core::{any::type_name, fmt::Write, mem};
+use std::collections::BTreeMap;
+
+/// An archived type that can describe its layout.
+pub trait Schema {
+    /// Describes the layout of the type, registering any types it refers to
+    /// with the given graph.
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema;
+}
+
+/// The byte order of a primitive type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Endian {
+    /// Least significant byte first
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
+impl Endian {
+    /// The byte order of the target.
+    pub const NATIVE: Endian = if cfg!(target_endian = "little") {
+        Endian::Little
+    } else {
+        Endian::Big
+    };
+}
+
+/// The layout of an archived type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeSchema {
+    /// The size of the type in bytes, or `None` if the type is unsized
+    pub size: Option<usize>,
+    /// The alignment of the type in bytes
+    pub align: usize,
+    /// What the bytes of the type contain
+    pub kind: TypeKind,
+}
+
+impl TypeSchema {
+    /// Creates the schema of a sized type with the given kind.
+    pub fn sized<T>(kind: TypeKind) -> Self {
+        Self {
+            size: Some(mem::size_of::<T>()),
+            align: mem::align_of::<T>(),
+            kind,
+        }
+    }
+}
+
+/// What the bytes of an archived type contain.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeKind {
+    /// A primitive type like `u32` or `bool` with the given name and byte order
+    Primitive {
+        /// The name of the primitive type
+        name: &'static str,
+        /// The byte order the primitive is stored in
+        endian: Endian,
+    },
+    /// A struct with fields at fixed offsets
+    Struct(Vec<FieldSchema>),
+    /// An enum that starts with a tag of the given type, followed by the fields
+    /// of the variant with that tag
+    Enum {
+        /// The name of the type of the tag
+        tag: String,
+        /// The variants of the enum
+        variants: Vec<VariantSchema>,
+    },
+    /// A fixed number of elements of the same type
+    Array {
+        /// The name of the type of the elements
+        element: String,
+        /// The number of elements
+        len: usize,
+    },
+    /// A pointer to a value located at the address of the pointer plus its
+    /// signed offset
+    RelPtr {
+        /// The offset to the value
+        offset: FieldSchema,
+        /// The metadata of the pointer, like the length of a slice
+        metadata: Option<FieldSchema>,
+        /// The name of the type pointed to, or `None` if it isn't known
+        pointee: Option<String>,
+    },
+    /// An optional value stored as its inner type, where a relative pointer
+    /// offset or integer of zero means there is no value
+    Niche {
+        /// The name of the inner type
+        inner: String,
+    },
+    /// A UTF-8 string whose length in bytes is the metadata of the pointer to
+    /// it
+    Str,
+    /// A slice of elements whose length is the metadata of the pointer to it
+    Slice {
+        /// The name of the type of the elements
+        element: String,
+    },
+    /// A hash map built with compress, hash, and displace. The displacements
+    /// and entries each have `len` elements.
+    HashMap {
+        /// The number of entries in the map
+        len: FieldSchema,
+        /// A pointer to the displacements of the map
+        displace: FieldSchema,
+        /// The name of the type of the displacements
+        displacement: String,
+        /// A pointer to the entries of the map
+        entries: FieldSchema,
+        /// The name of the type of the entries, which holds a key and a value
+        entry: String,
+    },
+}
+
+/// A field of an archived type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldSchema {
+    /// The name of the field, or its index for tuple fields
+    pub name: String,
+    /// The offset of the field from the start of the type in bytes
+    pub offset: usize,
+    /// The name of the type of the field
+    pub ty: String,
+}
+
+impl FieldSchema {
+    /// Creates the schema of a field of type `T` and registers `T` with the
+    /// given graph.
+    pub fn new<T: Schema + ?Sized>(graph: &mut SchemaGraph, name: &str, offset: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            offset,
+            ty: graph.register::<T>(),
+        }
+    }
+}
+
+/// A variant of an archived enum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariantSchema {
+    /// The name of the variant
+    pub name: String,
+    /// The value of the tag for the variant
+    pub tag: i128,
+    /// The fields of the variant, with offsets from the start of the enum
+    pub fields: Vec<FieldSchema>,
+}
+
+/// The schemas of an archived type and every type it refers to.
+#[derive(Debug)]
+pub struct SchemaGraph {
+    root: String,
+    types: BTreeMap<String, Option<TypeSchema>>,
+}
+
+impl SchemaGraph {
+    /// Builds the schema graph rooted at the given archived type.
+    pub fn of<T: Schema + ?Sized>() -> Self {
+        let mut result = Self {
+            root: String::new(),
+            types: BTreeMap::new(),
+        };
+        result.root = result.register::<T>();
+        result
+    }
+
+    /// Adds the schema of the given type to the graph if it isn't already in
+    /// it, and returns the name of the type.
+    pub fn register<T: Schema + ?Sized>(&mut self) -> String {
+        let name = type_name::<T>().to_string();
+        if !self.types.contains_key(&name) {
+            // Types can refer to themselves, so they're marked before they're
+            // described
+            self.types.insert(name.clone(), None);
+            let schema = T::describe(self);
+            self.types.insert(name.clone(), Some(schema));
+        }
+        name
+    }
+
+    /// Returns the name of the root type of the graph.
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Gets the schema of the type with the given name.
+    pub fn get(&self, name: &str) -> Option<&TypeSchema> {
+        self.types.get(name).and_then(|schema| schema.as_ref())
+    }
+
+    /// Returns an iterator over the names and schemas of the types in the
+    /// graph, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &TypeSchema)> {
+        self.types
+            .iter()
+            .filter_map(|(name, schema)| schema.as_ref().map(|schema| (name.as_str(), schema)))
+    }
+
+    /// Writes the graph as a JSON object.
+    pub fn to_json(&self) -> String {
+        let mut result = String::new();
+        result.push_str("{\"root\":");
+        write_json_str(&mut result, &self.root);
+        result.push_str(",\"types\":{");
+        for (i, (name, schema)) in self.iter().enumerate() {
+            if i != 0 {
+                result.push(',');
+            }
+            write_json_str(&mut result, name);
+            result.push(':');
+            write_type_json(&mut result, schema);
+        }
+        result.push_str("}}");
+        result
+    }
+}
+
+fn write_json_str(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_fields_json(out: &mut String, fields: &[FieldSchema]) {
+    out.push('[');
+    for (i, field) in fields.iter().enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+        write_field_json(out, field);
+    }
+    out.push(']');
+}
+
+fn write_field_json(out: &mut String, field: &FieldSchema) {
+    out.push_str("{\"name\":");
+    write_json_str(out, &field.name);
+    write!(out, ",\"offset\":{},\"type\":", field.offset).unwrap();
+    write_json_str(out, &field.ty);
+    out.push('}');
+}
+
+fn write_type_json(out: &mut String, schema: &TypeSchema) {
+    match schema.size {
+        Some(size) => write!(out, "{{\"size\":{}", size).unwrap(),
+        None => out.push_str("{\"size\":null"),
+    }
+    write!(out, ",\"align\":{},\"kind\":", schema.align).unwrap();
+    match schema.kind {
+        TypeKind::Primitive { name, endian } => {
+            out.push_str("\"primitive\",\"name\":");
+            write_json_str(out, name);
+            out.push_str(match endian {
+                Endian::Little => ",\"endian\":\"little\"",
+                Endian::Big => ",\"endian\":\"big\"",
+            });
+        }
+        TypeKind::Struct(ref fields) => {
+            out.push_str("\"struct\",\"fields\":");
+            write_fields_json(out, fields);
+        }
+        TypeKind::Enum {
+            ref tag,
+            ref variants,
+        } => {
+            out.push_str("\"enum\",\"tag\":");
+            write_json_str(out, tag);
+            out.push_str(",\"variants\":[");
+            for (i, variant) in variants.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                out.push_str("{\"name\":");
+                write_json_str(out, &variant.name);
+                write!(out, ",\"tag\":{},\"fields\":", variant.tag).unwrap();
+                write_fields_json(out, &variant.fields);
+                out.push('}');
+            }
+            out.push(']');
+        }
+        TypeKind::Array { ref element, len } => {
+            out.push_str("\"array\",\"element\":");
+            write_json_str(out, element);
+            write!(out, ",\"len\":{}", len).unwrap();
+        }
+        TypeKind::RelPtr {
+            ref offset,
+            ref metadata,
+            ref pointee,
+        } => {
+            out.push_str("\"rel_ptr\",\"offset\":");
+            write_field_json(out, offset);
+            out.push_str(",\"metadata\":");
+            match metadata {
+                Some(metadata) => write_field_json(out, metadata),
+                None => out.push_str("null"),
+            }
+            out.push_str(",\"pointee\":");
+            match pointee {
+                Some(pointee) => write_json_str(out, pointee),
+                None => out.push_str("null"),
+            }
+        }
+        TypeKind::Niche { ref inner } => {
+            out.push_str("\"niche\",\"inner\":");
+            write_json_str(out, inner);
+        }
+        TypeKind::Str => out.push_str("\"str\""),
+        TypeKind::Slice { ref element } => {
+            out.push_str("\"slice\",\"element\":");
+            write_json_str(out, element);
+        }
+        TypeKind::HashMap {
+            ref len,
+            ref displace,
+            ref displacement,
+            ref entries,
+            ref entry,
+        } => {
+            out.push_str("\"hash_map\",\"len\":");
+            write_field_json(out, len);
+            out.push_str(",\"displace\":");
+            write_field_json(out, displace);
+            out.push_str(",\"displacement\":");
+            write_json_str(out, displacement);
+            out.push_str(",\"entries\":");
+            write_field_json(out, entries);
+            out.push_str(",\"entry\":");
+            write_json_str(out, entry);
+        }
+    }
+    out.push('}');
+}
+
+impl Schema for RawRelPtr {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::RelPtr {
+            offset: FieldSchema::new::<crate::ArchivedIsize>(graph, "offset", 0),
+            metadata: None,
+            pointee: None,
+        })
+    }
+}
+
+impl<T: ArchivePointee + Schema + ?Sized> Schema for RelPtr<T>
+where
+    T::ArchivedMetadata: Schema,
+{
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::RelPtr {
+            offset: FieldSchema::new::<crate::ArchivedIsize>(
+                graph,
+                "offset",
+                crate::offset_of!(Self, raw_ptr),
+            ),
+            metadata: Some(FieldSchema::new::<T::ArchivedMetadata>(
+                graph,
+                "metadata",
+                crate::offset_of!(Self, metadata),
+            )),
+            pointee: Some(graph.register::<T>()),
+        })
+    }
+}
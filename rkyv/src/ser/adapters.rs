@@ -1,23 +1,29 @@
 //! Adapters wrap serializers and add support for serializer traits.
 
+#[cfg(feature = "alloc")]
+use crate::{ser::SharedSerializer, SerializeUnsized};
 use crate::{
-    ser::{Serializer, SharedSerializer},
-    Archive, Fallible, SerializeUnsized,
+    ser::{ScratchSpace, SeekSerializer, Serializer},
+    Archive, Fallible, OffsetError,
 };
-use std::collections::HashMap;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+use core::alloc::Layout;
 
 /// An adapter that adds shared serialization support to a serializer.
+#[cfg(feature = "alloc")]
 pub struct SharedSerializerAdapter<S> {
     inner: S,
-    shared_resolvers: HashMap<*const u8, usize>,
+    shared_resolvers: BTreeMap<*const u8, usize>,
 }
 
+#[cfg(feature = "alloc")]
 impl<S> SharedSerializerAdapter<S> {
     /// Wraps the given serializer and adds shared memory support.
     pub fn new(inner: S) -> Self {
         Self {
             inner,
-            shared_resolvers: HashMap::new(),
+            shared_resolvers: BTreeMap::new(),
         }
     }
 
@@ -27,10 +33,12 @@ impl<S> SharedSerializerAdapter<S> {
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<S: Fallible> Fallible for SharedSerializerAdapter<S> {
     type Error = S::Error;
 }
 
+#[cfg(feature = "alloc")]
 impl<S: Serializer> Serializer for SharedSerializerAdapter<S> {
     fn pos(&self) -> usize {
         self.inner.pos()
@@ -40,6 +48,10 @@ impl<S: Serializer> Serializer for SharedSerializerAdapter<S> {
         self.inner.write(bytes)
     }
 
+    fn offset_error(&self, error: OffsetError) -> Self::Error {
+        self.inner.offset_error(error)
+    }
+
     fn pad(&mut self, padding: usize) -> Result<(), Self::Error> {
         self.inner.pad(padding)
     }
@@ -61,6 +73,25 @@ impl<S: Serializer> Serializer for SharedSerializerAdapter<S> {
     }
 }
 
+#[cfg(feature = "alloc")]
+impl<S: SeekSerializer> SeekSerializer for SharedSerializerAdapter<S> {
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<S: ScratchSpace> ScratchSpace for SharedSerializerAdapter<S> {
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<*mut u8, Self::Error> {
+        self.inner.push_scratch(layout)
+    }
+
+    unsafe fn pop_scratch(&mut self, ptr: *mut u8, layout: Layout) -> Result<(), Self::Error> {
+        self.inner.pop_scratch(ptr, layout)
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl<S: Serializer> SharedSerializer for SharedSerializerAdapter<S> {
     fn archive_shared<T: SerializeUnsized<Self> + ?Sized>(
         &mut self,
@@ -76,3 +107,115 @@ impl<S: Serializer> SharedSerializer for SharedSerializerAdapter<S> {
         }
     }
 }
+
+/// An adapter that allocates scratch space for a serializer from the given
+/// scratch space.
+///
+/// Errors from the scratch space are converted into errors of the serializer.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_value,
+///     ser::{
+///         adapters::ScratchSpaceAdapter,
+///         serializers::{BufferScratch, BufferSerializer},
+///         Serializer,
+///     },
+///     Aligned,
+/// };
+/// use std::collections::HashMap;
+///
+/// let mut value = HashMap::new();
+/// value.insert("one".to_string(), "uno".to_string());
+/// value.insert("two".to_string(), "dos".to_string());
+///
+/// let mut serializer = ScratchSpaceAdapter::new(
+///     BufferSerializer::new(Aligned([0u8; 256])),
+///     BufferScratch::new(Aligned([0u8; 1024])),
+/// );
+/// let pos = serializer.serialize_value(&value).unwrap();
+/// let buf = serializer.into_inner().into_inner();
+/// let archived = unsafe { archived_value::<HashMap<String, String>>(buf.as_ref(), pos) };
+/// assert_eq!(archived.get("two").unwrap().as_str(), "dos");
+/// ```
+pub struct ScratchSpaceAdapter<S, C> {
+    inner: S,
+    scratch: C,
+}
+
+impl<S, C> ScratchSpaceAdapter<S, C> {
+    /// Wraps the given serializer and allocates its scratch space from the
+    /// given scratch space.
+    pub fn new(inner: S, scratch: C) -> Self {
+        Self { inner, scratch }
+    }
+
+    /// Consumes the adapter and returns the underlying serializer.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Consumes the adapter and returns the underlying serializer and scratch
+    /// space.
+    pub fn into_parts(self) -> (S, C) {
+        (self.inner, self.scratch)
+    }
+}
+
+impl<S: Fallible, C> Fallible for ScratchSpaceAdapter<S, C> {
+    type Error = S::Error;
+}
+
+impl<S: Serializer, C> Serializer for ScratchSpaceAdapter<S, C> {
+    fn pos(&self) -> usize {
+        self.inner.pos()
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.inner.write(bytes)
+    }
+
+    fn offset_error(&self, error: OffsetError) -> Self::Error {
+        self.inner.offset_error(error)
+    }
+
+    fn pad(&mut self, padding: usize) -> Result<(), Self::Error> {
+        self.inner.pad(padding)
+    }
+
+    fn align(&mut self, align: usize) -> Result<usize, Self::Error> {
+        self.inner.align(align)
+    }
+
+    fn align_for<T>(&mut self) -> Result<usize, Self::Error> {
+        self.inner.align_for::<T>()
+    }
+
+    unsafe fn resolve_aligned<T: Archive + ?Sized>(
+        &mut self,
+        value: &T,
+        resolver: T::Resolver,
+    ) -> Result<usize, Self::Error> {
+        self.inner.resolve_aligned(value, resolver)
+    }
+}
+
+impl<S: SeekSerializer, C> SeekSerializer for ScratchSpaceAdapter<S, C> {
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        self.inner.seek(pos)
+    }
+}
+
+impl<S: Fallible, C: ScratchSpace> ScratchSpace for ScratchSpaceAdapter<S, C>
+where
+    S::Error: From<C::Error>,
+{
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<*mut u8, Self::Error> {
+        Ok(self.scratch.push_scratch(layout)?)
+    }
+
+    unsafe fn pop_scratch(&mut self, ptr: *mut u8, layout: Layout) -> Result<(), Self::Error> {
+        Ok(self.scratch.pop_scratch(ptr, layout)?)
+    }
+}
@@ -1,14 +1,16 @@
 //! Serialization traits, serializers, and adapters.
 
-#[cfg(feature = "std")]
 pub mod adapters;
+mod scratch_vec;
 pub mod serializers;
 
 use crate::{
-    Archive, ArchivePointee, ArchiveUnsized, Archived, Fallible, RelPtr, Serialize,
+    Archive, ArchivePointee, ArchiveUnsized, Archived, Fallible, OffsetError, RelPtr, Serialize,
     SerializeUnsized,
 };
-use core::{mem, slice};
+use core::{alloc::Layout, convert::TryInto, fmt, mem, slice};
+
+pub use scratch_vec::{Drain, ScratchVec};
 
 /// A byte sink that knows where it is.
 ///
@@ -40,6 +42,14 @@ pub trait Serializer: Fallible {
         Ok(())
     }
 
+    /// Converts the error returned when a value can't be resolved into an error
+    /// for this serializer.
+    ///
+    /// Relative pointers can only store offsets that fit in an
+    /// [`ArchivedIsize`](crate::ArchivedIsize), so archives that grow past that
+    /// size may contain pointers that can't be resolved.
+    fn offset_error(&self, error: OffsetError) -> Self::Error;
+
     /// Aligns the position of the serializer to the given alignment.
     fn align(&mut self, align: usize) -> Result<usize, Self::Error> {
         debug_assert!(align & (align - 1) == 0);
@@ -72,7 +82,9 @@ pub trait Serializer: Fallible {
     ) -> Result<usize, Self::Error> {
         let pos = self.pos();
         debug_assert!(pos & (mem::align_of::<T::Archived>() - 1) == 0);
-        let archived = &value.resolve(pos, resolver);
+        let archived = &value
+            .resolve(pos, resolver)
+            .map_err(|e| self.offset_error(e))?;
         let data = (archived as *const T::Archived).cast::<u8>();
         let len = mem::size_of::<T::Archived>();
         self.write(slice::from_raw_parts(data, len))?;
@@ -86,6 +98,18 @@ pub trait Serializer: Fallible {
         unsafe { self.resolve_aligned(value, resolver) }
     }
 
+    /// Archives the given object as the root of the archive and returns the
+    /// position it was archived at.
+    ///
+    /// The root object is written after all of its dependencies, so as long as
+    /// nothing else is written afterward, it will be the last object in the
+    /// archive. It can then be found from the end of the archive with
+    /// [`archived_root`](crate::archived_root) without storing its position.
+    /// This works with any serializer, including ones that can't seek.
+    fn serialize_root<T: Serialize<Self>>(&mut self, value: &T) -> Result<usize, Self::Error> {
+        self.serialize_value(value)
+    }
+
     /// Resolves the given reference with its resolver and writes the archived
     /// reference.
     ///
@@ -103,7 +127,9 @@ pub trait Serializer: Fallible {
     ) -> Result<usize, Self::Error> {
         let from = self.pos();
         debug_assert!(from & (mem::align_of::<RelPtr<T::Archived>>() - 1) == 0);
-        let rel_ptr = value.resolve_unsized(from, to, metadata_resolver);
+        let rel_ptr = value
+            .resolve_unsized(from, to, metadata_resolver)
+            .map_err(|e| self.offset_error(e))?;
         let data = (&rel_ptr as *const RelPtr<T::Archived>).cast::<u8>();
         let len = mem::size_of::<RelPtr<T::Archived>>();
         self.write(slice::from_raw_parts(data, len))?;
@@ -121,6 +147,22 @@ pub trait Serializer: Fallible {
         self.align_for::<RelPtr<T::Archived>>()?;
         unsafe { self.resolve_unsized_aligned(value, to, metadata_resolver) }
     }
+
+    /// Archives the given object followed by an [`ArchiveFooter`] that records
+    /// its position and the format it was archived with. Returns the position
+    /// the object was archived at.
+    ///
+    /// The footer must be the last thing written to the archive. Use
+    /// [`archived_value_with_footer`](crate::archived_value_with_footer) to get
+    /// the value back from the archive.
+    fn serialize_with_footer<T: Serialize<Self>>(
+        &mut self,
+        value: &T,
+    ) -> Result<usize, Self::Error> {
+        let pos = self.serialize_value(value)?;
+        self.write(&ArchiveFooter::new(pos).to_bytes())?;
+        Ok(pos)
+    }
 }
 
 /// A serializer that can seek to an absolute position.
@@ -175,3 +217,234 @@ pub trait SharedSerializer: Serializer {
         value: &T,
     ) -> Result<usize, Self::Error>;
 }
+
+/// A serializer that can allocate scratch space.
+///
+/// Scratch space is temporary memory that types use while they serialize, like
+/// the buckets a hashmap is sorted into before its entries are written. It's
+/// allocated and freed like a stack, so every allocation must be popped before
+/// the allocations that were pushed before it. [`ScratchVec`] manages scratch
+/// space for a fixed-capacity vector.
+///
+/// [`WriteSerializer`](serializers::WriteSerializer) and
+/// [`AlignedSerializer`](serializers::AlignedSerializer) allocate scratch space
+/// from the heap. [`BufferSerializer`](serializers::BufferSerializer) takes its
+/// scratch space as a type parameter, and any other serializer can be wrapped in
+/// a [`ScratchSpaceAdapter`](adapters::ScratchSpaceAdapter) with a scratch space
+/// like [`BufferScratch`](serializers::BufferScratch).
+pub trait ScratchSpace: Fallible {
+    /// Allocates scratch space with the given layout.
+    ///
+    /// # Safety
+    ///
+    /// `layout` must have a non-zero size.
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<*mut u8, Self::Error>;
+
+    /// Frees scratch space that was allocated with
+    /// [`push_scratch`](ScratchSpace::push_scratch).
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be the most recent allocation that hasn't been popped yet, and
+    /// `layout` must be the layout it was allocated with.
+    unsafe fn pop_scratch(&mut self, ptr: *mut u8, layout: Layout) -> Result<(), Self::Error>;
+}
+
+/// A fixed-size footer written after the root object of an archive.
+///
+/// The footer records the position of the root object along with the format
+/// version and the layout features the archive was written with. Its bytes are
+/// independent of the features and platform used, so any build of rkyv can
+/// read it and reject archives it can't read instead of reading garbage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct ArchiveFooter {
+    magic: [u8; 4],
+    version: [u8; 2],
+    flags: [u8; 2],
+    root: [u8; 8],
+}
+
+impl ArchiveFooter {
+    /// The magic bytes that begin every footer.
+    pub const MAGIC: [u8; 4] = *b"rkyv";
+
+    /// The current version of the archive format.
+    pub const VERSION: u16 = 1;
+
+    /// The size of a serialized footer in bytes.
+    pub const SIZE: usize = mem::size_of::<Self>();
+
+    /// The flag set when `usize` and `isize` are archived as 64-bit integers.
+    pub const SIZE_64: u16 = 1 << 0;
+
+    /// The flag set when archived types have strict layouts.
+    pub const STRICT: u16 = 1 << 1;
+
+    /// The flag set when archived primitives are big-endian.
+    pub const BIG_ENDIAN: u16 = 1 << 2;
+
+    /// The flag set when `usize` and `isize` are archived as 16-bit integers.
+    pub const SIZE_16: u16 = 1 << 3;
+
+    /// Creates a new footer for a root object at the given position using the
+    /// current format.
+    pub fn new(root: usize) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION.to_le_bytes(),
+            flags: Self::native_flags().to_le_bytes(),
+            root: (root as u64).to_le_bytes(),
+        }
+    }
+
+    /// Returns the flags for the features that this build of rkyv archives
+    /// with.
+    pub fn native_flags() -> u16 {
+        let mut flags = 0;
+        if cfg!(feature = "size_16") {
+            flags |= Self::SIZE_16;
+        }
+        if cfg!(feature = "size_64") {
+            flags |= Self::SIZE_64;
+        }
+        if cfg!(feature = "strict") {
+            flags |= Self::STRICT;
+        }
+        if cfg!(feature = "archive_be")
+            || (cfg!(target_endian = "big") && !cfg!(feature = "archive_le"))
+        {
+            flags |= Self::BIG_ENDIAN;
+        }
+        flags
+    }
+
+    /// Reads the footer from the end of the given archive and checks that it
+    /// can be read by this build of rkyv.
+    pub fn read(bytes: &[u8]) -> Result<Self, FooterError> {
+        if bytes.len() < Self::SIZE {
+            return Err(FooterError::MissingFooter);
+        }
+        let footer_bytes = &bytes[bytes.len() - Self::SIZE..];
+        let footer = Self {
+            magic: footer_bytes[0..4].try_into().unwrap(),
+            version: footer_bytes[4..6].try_into().unwrap(),
+            flags: footer_bytes[6..8].try_into().unwrap(),
+            root: footer_bytes[8..16].try_into().unwrap(),
+        };
+
+        if footer.magic != Self::MAGIC {
+            return Err(FooterError::InvalidMagic(footer.magic));
+        }
+        if footer.version() != Self::VERSION {
+            return Err(FooterError::UnsupportedVersion(footer.version()));
+        }
+        if footer.flags() != Self::native_flags() {
+            return Err(FooterError::MismatchedFlags {
+                expected: Self::native_flags(),
+                found: footer.flags(),
+            });
+        }
+        if footer.root_u64() >= (bytes.len() - Self::SIZE) as u64 {
+            return Err(FooterError::InvalidRoot {
+                root: footer.root_u64(),
+                data_len: bytes.len() - Self::SIZE,
+            });
+        }
+        Ok(footer)
+    }
+
+    /// Returns the bytes of the footer.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut result = [0; Self::SIZE];
+        result[0..4].copy_from_slice(&self.magic);
+        result[4..6].copy_from_slice(&self.version);
+        result[6..8].copy_from_slice(&self.flags);
+        result[8..16].copy_from_slice(&self.root);
+        result
+    }
+
+    /// Returns the format version of the archive.
+    pub fn version(&self) -> u16 {
+        u16::from_le_bytes(self.version)
+    }
+
+    /// Returns the feature flags of the archive.
+    pub fn flags(&self) -> u16 {
+        u16::from_le_bytes(self.flags)
+    }
+
+    /// Reads the footer from the end of the given archive and returns the
+    /// position of its root object, checking that an archived `T` fits there.
+    pub(crate) fn locate_root<T: Archive>(bytes: &[u8]) -> Result<usize, FooterError> {
+        let root = Self::read(bytes)?.root();
+        let data_len = bytes.len() - Self::SIZE;
+        if data_len - root < mem::size_of::<T::Archived>() {
+            Err(FooterError::InvalidRoot {
+                root: root as u64,
+                data_len,
+            })
+        } else {
+            Ok(root)
+        }
+    }
+
+    fn root_u64(&self) -> u64 {
+        u64::from_le_bytes(self.root)
+    }
+
+    /// Returns the position of the root object.
+    pub fn root(&self) -> usize {
+        self.root_u64() as usize
+    }
+}
+
+/// An error that can occur while reading an [`ArchiveFooter`].
+#[derive(Debug)]
+pub enum FooterError {
+    /// The archive is too small to contain a footer
+    MissingFooter,
+    /// The footer did not begin with the footer magic
+    InvalidMagic([u8; 4]),
+    /// The archive was written with an unsupported format version
+    UnsupportedVersion(u16),
+    /// The archive was written with different layout features
+    MismatchedFlags {
+        /// The flags for this build of rkyv
+        expected: u16,
+        /// The flags the archive was written with
+        found: u16,
+    },
+    /// The root object does not fit in the archive
+    InvalidRoot {
+        /// The position of the root object
+        root: u64,
+        /// The length of the archive without the footer
+        data_len: usize,
+    },
+}
+
+impl fmt::Display for FooterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FooterError::MissingFooter => write!(f, "archive is too small to contain a footer"),
+            FooterError::InvalidMagic(magic) => write!(f, "invalid footer magic: {:?}", magic),
+            FooterError::UnsupportedVersion(version) => {
+                write!(f, "unsupported archive format version: {}", version)
+            }
+            FooterError::MismatchedFlags { expected, found } => write!(
+                f,
+                "mismatched archive feature flags: expected {:#06x}, found {:#06x}",
+                expected, found
+            ),
+            FooterError::InvalidRoot { root, data_len } => write!(
+                f,
+                "root position {} is out of bounds for archive data of length {}",
+                root, data_len
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FooterError {}
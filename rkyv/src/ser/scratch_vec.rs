@@ -0,0 +1,219 @@
+//! A fixed-capacity vector that uses scratch space.
+
+use crate::ser::ScratchSpace;
+use core::{
+    alloc::Layout,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// A fixed-capacity vector whose memory is allocated from a [`ScratchSpace`].
+///
+/// A scratch vector must be freed with [`free`](ScratchVec::free) before any
+/// scratch space that was allocated before it. Dropping a scratch vector drops
+/// its elements, but its memory isn't returned to the scratch space.
+///
+/// ## Examples
+/// ```
+/// use rkyv::ser::{serializers::WriteSerializer, ScratchVec};
+///
+/// let mut serializer = WriteSerializer::new(Vec::new());
+/// unsafe {
+///     let mut squares = ScratchVec::new(&mut serializer, 4).unwrap();
+///     for i in 0..4 {
+///         squares.push(i * i);
+///     }
+///     assert_eq!(squares.as_slice(), &[0, 1, 4, 9]);
+///     squares.free(&mut serializer).unwrap();
+/// }
+/// ```
+pub struct ScratchVec<T> {
+    ptr: NonNull<T>,
+    cap: usize,
+    len: usize,
+}
+
+impl<T> ScratchVec<T> {
+    /// Creates a new, empty scratch vector with the given capacity.
+    ///
+    /// # Safety
+    ///
+    /// The vector must be freed with the same scratch space before any scratch
+    /// space that was allocated before it is popped.
+    pub unsafe fn new<S: ScratchSpace + ?Sized>(
+        scratch_space: &mut S,
+        capacity: usize,
+    ) -> Result<Self, S::Error> {
+        let layout = Self::layout_for(capacity);
+        let ptr = if layout.size() == 0 {
+            NonNull::dangling()
+        } else {
+            NonNull::new_unchecked(scratch_space.push_scratch(layout)?.cast())
+        };
+        Ok(Self {
+            ptr,
+            cap: capacity,
+            len: 0,
+        })
+    }
+
+    /// Drops the elements of the vector and returns its memory to the scratch
+    /// space.
+    ///
+    /// # Safety
+    ///
+    /// The vector must have been created with the given scratch space, and any
+    /// scratch space allocated after it must have been popped.
+    pub unsafe fn free<S: ScratchSpace + ?Sized>(
+        mut self,
+        scratch_space: &mut S,
+    ) -> Result<(), S::Error> {
+        self.clear();
+        let layout = Self::layout_for(self.cap);
+        let ptr = self.ptr.as_ptr().cast();
+        mem::forget(self);
+        if layout.size() != 0 {
+            scratch_space.pop_scratch(ptr, layout)?;
+        }
+        Ok(())
+    }
+
+    fn layout_for(capacity: usize) -> Layout {
+        Layout::array::<T>(capacity).expect("scratch vec capacity overflow")
+    }
+
+    /// Returns the number of elements the vector can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns the number of elements in the vector.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the vector has no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the elements of the vector as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Gets the elements of the vector as a mutable slice.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Appends an element to the end of the vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vector is full.
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        assert!(self.len < self.cap, "scratch vec is full");
+        unsafe {
+            self.ptr.as_ptr().add(self.len).write(value);
+        }
+        self.len += 1;
+    }
+
+    /// Drops all of the elements of the vector.
+    #[inline]
+    pub fn clear(&mut self) {
+        let elements = self.as_mut_slice() as *mut [T];
+        self.len = 0;
+        unsafe {
+            ptr::drop_in_place(elements);
+        }
+    }
+
+    /// Removes all of the elements from the vector and returns an iterator over
+    /// them.
+    #[inline]
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        let len = self.len;
+        self.len = 0;
+        Drain {
+            ptr: self.ptr,
+            index: 0,
+            len,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for ScratchVec<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Deref for ScratchVec<T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T> DerefMut for ScratchVec<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+/// An iterator over the elements drained from a [`ScratchVec`].
+pub struct Drain<'a, T> {
+    ptr: NonNull<T>,
+    index: usize,
+    len: usize,
+    _phantom: PhantomData<&'a mut ScratchVec<T>>,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index < self.len {
+            let value = unsafe { self.ptr.as_ptr().add(self.index).read() };
+            self.index += 1;
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.ptr.as_ptr().add(self.index),
+                self.len - self.index,
+            ));
+        }
+    }
+}
@@ -1,18 +1,29 @@
 //! Serializers that can be used standalone and provide basic capabilities.
 
+#[cfg(feature = "std")]
+use crate::AlignedVec;
 use crate::{
-    ser::{SeekSerializer, Serializer},
-    Fallible,
+    ser::{ScratchSpace, SeekSerializer, Serializer},
+    Fallible, OffsetError,
 };
-use core::ptr;
+#[cfg(feature = "alloc")]
+use ::alloc::{alloc, vec::Vec};
+#[cfg(feature = "std")]
+use core::borrow::{Borrow, BorrowMut};
+use core::{alloc::Layout, fmt, mem, num::NonZeroUsize, ptr};
 #[cfg(feature = "std")]
-use std::io;
+use std::{error::Error, io};
 
 /// Wraps a byte buffer and equips it with [`Serializer`].
 ///
 /// Common uses include archiving in `#![no_std]` environments and archiving
 /// small objects without allocating.
 ///
+/// A buffer serializer only implements [`ScratchSpace`] when it's created with
+/// a scratch space through [`with_scratch`](BufferSerializer::with_scratch).
+/// Without one, it never allocates, but it can't serialize types that need
+/// scratch space like hash maps.
+///
 /// ## Examples
 /// ```
 /// use rkyv::{
@@ -42,9 +53,10 @@ use std::io;
 ///     panic!("archived event was of the wrong type");
 /// }
 /// ```
-pub struct BufferSerializer<T> {
+pub struct BufferSerializer<T, S = ()> {
     inner: T,
     pos: usize,
+    scratch: S,
 }
 
 impl<T> BufferSerializer<T> {
@@ -57,13 +69,35 @@ impl<T> BufferSerializer<T> {
     /// writing at the given position, but the buffer must contain all bytes
     /// (otherwise the alignments of types may not be correct).
     pub fn with_pos(inner: T, pos: usize) -> Self {
-        Self { inner, pos }
+        Self {
+            inner,
+            pos,
+            scratch: (),
+        }
+    }
+}
+
+impl<T, S> BufferSerializer<T, S> {
+    /// Creates a new archive buffer from a byte buffer that allocates scratch
+    /// space from the given scratch space.
+    pub fn with_scratch(inner: T, scratch: S) -> Self {
+        Self {
+            inner,
+            pos: 0,
+            scratch,
+        }
     }
 
     /// Consumes the buffer and returns the internal buffer used to create it.
     pub fn into_inner(self) -> T {
         self.inner
     }
+
+    /// Consumes the buffer and returns the internal buffer and scratch space
+    /// used to create it.
+    pub fn into_parts(self) -> (T, S) {
+        (self.inner, self.scratch)
+    }
 }
 
 /// The error type returned by an [`BufferSerializer`].
@@ -80,17 +114,31 @@ pub enum BufferSerializerError {
         seek_position: usize,
         archive_len: usize,
     },
+    /// A relative pointer offset or a size was too large to be archived.
+    OffsetOutOfRange(OffsetError),
+    /// Scratch space couldn't be allocated or freed.
+    ScratchSpace(ScratchSpaceError),
 }
 
-impl<T: AsRef<[u8]> + AsMut<[u8]>> Fallible for BufferSerializer<T> {
+impl From<ScratchSpaceError> for BufferSerializerError {
+    fn from(e: ScratchSpaceError) -> Self {
+        BufferSerializerError::ScratchSpace(e)
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>, S> Fallible for BufferSerializer<T, S> {
     type Error = BufferSerializerError;
 }
 
-impl<T: AsRef<[u8]> + AsMut<[u8]>> Serializer for BufferSerializer<T> {
+impl<T: AsRef<[u8]> + AsMut<[u8]>, S> Serializer for BufferSerializer<T, S> {
     fn pos(&self) -> usize {
         self.pos
     }
 
+    fn offset_error(&self, error: OffsetError) -> Self::Error {
+        BufferSerializerError::OffsetOutOfRange(error)
+    }
+
     fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
         let end_pos = self.pos + bytes.len();
         let archive_len = self.inner.as_ref().len();
@@ -129,7 +177,20 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> Serializer for BufferSerializer<T> {
     }
 }
 
-impl<T: AsRef<[u8]> + AsMut<[u8]>> SeekSerializer for BufferSerializer<T> {
+impl<T: AsRef<[u8]> + AsMut<[u8]>, S: ScratchSpace> ScratchSpace for BufferSerializer<T, S>
+where
+    BufferSerializerError: From<S::Error>,
+{
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<*mut u8, Self::Error> {
+        Ok(self.scratch.push_scratch(layout)?)
+    }
+
+    unsafe fn pop_scratch(&mut self, ptr: *mut u8, layout: Layout) -> Result<(), Self::Error> {
+        Ok(self.scratch.pop_scratch(ptr, layout)?)
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>, S> SeekSerializer for BufferSerializer<T, S> {
     fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
         let len = self.inner.as_ref().len();
         if pos > len {
@@ -163,6 +224,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> SeekSerializer for BufferSerializer<T> {
 pub struct WriteSerializer<W: io::Write> {
     inner: W,
     pos: usize,
+    scratch: AllocScratch,
 }
 
 #[cfg(feature = "std")]
@@ -175,7 +237,11 @@ impl<W: io::Write> WriteSerializer<W> {
     /// Creates a new serializer from a writer, and assumes that the underlying
     /// writer is currently at the given position.
     pub fn with_pos(inner: W, pos: usize) -> Self {
-        Self { inner, pos }
+        Self {
+            inner,
+            pos,
+            scratch: AllocScratch::new(),
+        }
     }
 
     /// Consumes the serializer and returns the internal writer used to create
@@ -196,12 +262,27 @@ impl<W: io::Write> Serializer for WriteSerializer<W> {
         self.pos
     }
 
+    fn offset_error(&self, error: OffsetError) -> Self::Error {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+
     fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
         self.pos += self.inner.write(bytes)?;
         Ok(())
     }
 }
 
+#[cfg(feature = "std")]
+impl<W: io::Write> ScratchSpace for WriteSerializer<W> {
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<*mut u8, Self::Error> {
+        Ok(self.scratch.push_scratch(layout)?)
+    }
+
+    unsafe fn pop_scratch(&mut self, ptr: *mut u8, layout: Layout) -> Result<(), Self::Error> {
+        Ok(self.scratch.pop_scratch(ptr, layout)?)
+    }
+}
+
 #[cfg(feature = "std")]
 impl<W: io::Write + io::Seek> SeekSerializer for WriteSerializer<W> {
     fn seek(&mut self, offset: usize) -> Result<(), Self::Error> {
@@ -210,3 +291,431 @@ impl<W: io::Write + io::Seek> SeekSerializer for WriteSerializer<W> {
         Ok(())
     }
 }
+
+/// The error type returned by an [`AlignedSerializer`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum AlignedSerializerError {
+    /// A relative pointer offset or a size was too large to be archived.
+    OffsetOutOfRange(OffsetError),
+    /// Scratch space couldn't be allocated or freed.
+    ScratchSpace(ScratchSpaceError),
+}
+
+#[cfg(feature = "std")]
+impl From<ScratchSpaceError> for AlignedSerializerError {
+    fn from(e: ScratchSpaceError) -> Self {
+        AlignedSerializerError::ScratchSpace(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for AlignedSerializerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignedSerializerError::OffsetOutOfRange(e) => write!(f, "{}", e),
+            AlignedSerializerError::ScratchSpace(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for AlignedSerializerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AlignedSerializerError::OffsetOutOfRange(e) => Some(e as &dyn Error),
+            AlignedSerializerError::ScratchSpace(e) => Some(e as &dyn Error),
+        }
+    }
+}
+
+/// Wraps an [`AlignedVec`] and equips it with [`Serializer`].
+///
+/// The buffer grows as needed, and because an `AlignedVec` is always aligned,
+/// the serialized bytes can be accessed directly without copying them into an
+/// aligned buffer first.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_value,
+///     ser::{Serializer, serializers::AlignedSerializer},
+///     AlignedVec,
+///     Archive,
+///     Archived,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// enum Event {
+///     Spawn,
+///     Speak(String),
+///     Die,
+/// }
+///
+/// let mut serializer = AlignedSerializer::new(AlignedVec::new());
+/// let pos = serializer.serialize_value(&Event::Speak("Help me!".to_string()))
+///     .expect("failed to archive event");
+/// let buf = serializer.into_inner();
+/// let archived = unsafe { archived_value::<Event>(buf.as_ref(), pos) };
+/// if let Archived::<Event>::Speak(message) = archived {
+///     assert_eq!(message.as_str(), "Help me!");
+/// } else {
+///     panic!("archived event was of the wrong type");
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub struct AlignedSerializer<A> {
+    inner: A,
+    pos: usize,
+    scratch: AllocScratch,
+}
+
+#[cfg(feature = "std")]
+impl<A: Borrow<AlignedVec>> AlignedSerializer<A> {
+    /// Creates a new serializer from an aligned vector. Bytes are written
+    /// starting at the end of the vector's current contents.
+    pub fn new(inner: A) -> Self {
+        let pos = inner.borrow().len();
+        Self {
+            inner,
+            pos,
+            scratch: AllocScratch::new(),
+        }
+    }
+
+    /// Consumes the serializer and returns the aligned vector used to create
+    /// it.
+    pub fn into_inner(self) -> A {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A> Fallible for AlignedSerializer<A> {
+    type Error = AlignedSerializerError;
+}
+
+#[cfg(feature = "std")]
+impl<A: Borrow<AlignedVec> + BorrowMut<AlignedVec>> Serializer for AlignedSerializer<A> {
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    fn offset_error(&self, error: OffsetError) -> Self::Error {
+        AlignedSerializerError::OffsetOutOfRange(error)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        let inner = self.inner.borrow_mut();
+        let end_pos = self.pos + bytes.len();
+        if end_pos > inner.len() {
+            inner.resize(end_pos, 0);
+        }
+        inner[self.pos..end_pos].copy_from_slice(bytes);
+        self.pos = end_pos;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A> ScratchSpace for AlignedSerializer<A> {
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<*mut u8, Self::Error> {
+        Ok(self.scratch.push_scratch(layout)?)
+    }
+
+    unsafe fn pop_scratch(&mut self, ptr: *mut u8, layout: Layout) -> Result<(), Self::Error> {
+        Ok(self.scratch.pop_scratch(ptr, layout)?)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Borrow<AlignedVec> + BorrowMut<AlignedVec>> SeekSerializer for AlignedSerializer<A> {
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        let inner = self.inner.borrow_mut();
+        if pos > inner.len() {
+            inner.resize(pos, 0);
+        }
+        self.pos = pos;
+        Ok(())
+    }
+}
+
+/// The error type returned by the scratch spaces in this module.
+#[derive(Debug)]
+pub enum ScratchSpaceError {
+    /// There wasn't enough scratch space left for an allocation.
+    ExceededLimit {
+        /// The number of bytes requested, not including padding
+        requested: usize,
+        /// The number of bytes that were left
+        remaining: usize,
+    },
+    /// Scratch space was popped that wasn't the most recent allocation, or
+    /// with a different layout than it was allocated with.
+    NotPoppedInReverseOrder,
+    /// Scratch space was popped when there were no allocations.
+    NoAllocationsToPop,
+}
+
+impl fmt::Display for ScratchSpaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScratchSpaceError::ExceededLimit {
+                requested,
+                remaining,
+            } => write!(
+                f,
+                "exceeded the scratch space limit: requested {} bytes with {} bytes remaining",
+                requested, remaining
+            ),
+            ScratchSpaceError::NotPoppedInReverseOrder => {
+                write!(f, "scratch space was not popped in reverse order")
+            }
+            ScratchSpaceError::NoAllocationsToPop => {
+                write!(f, "scratch space was popped with no allocations")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ScratchSpaceError {}
+
+#[cfg(feature = "std")]
+impl From<ScratchSpaceError> for io::Error {
+    fn from(e: ScratchSpaceError) -> Self {
+        io::Error::other(e)
+    }
+}
+
+/// A fixed-size stack arena for scratch space that allocates from a byte
+/// buffer.
+///
+/// Allocations are aligned within the buffer, and each one is preceded by a
+/// small header that records the allocation before it, so some bytes go to
+/// bookkeeping and padding. The buffer must not move while it has
+/// allocations.
+///
+/// ## Examples
+/// ```
+/// use core::alloc::Layout;
+/// use rkyv::{ser::{serializers::BufferScratch, ScratchSpace}, Aligned};
+///
+/// let mut scratch = BufferScratch::new(Aligned([0u8; 64]));
+/// unsafe {
+///     let layout = Layout::new::<[u32; 8]>();
+///     let ptr = scratch.push_scratch(layout).unwrap();
+///     assert!(scratch.push_scratch(Layout::new::<[u32; 16]>()).is_err());
+///     scratch.pop_scratch(ptr, layout).unwrap();
+/// }
+/// ```
+pub struct BufferScratch<T> {
+    buffer: T,
+    pos: usize,
+    last: Option<NonZeroUsize>,
+}
+
+/// Written before each allocation of a [`BufferScratch`] so it can be popped.
+///
+/// The size of the allocation isn't recorded because it always ends at the
+/// current position of the scratch space.
+#[derive(Clone, Copy)]
+struct BufferScratchHeader {
+    pos: usize,
+    last: Option<NonZeroUsize>,
+    align: usize,
+}
+
+impl<T> BufferScratch<T> {
+    /// Creates a new scratch space that allocates from the given buffer.
+    pub fn new(buffer: T) -> Self {
+        Self {
+            buffer,
+            pos: 0,
+            last: None,
+        }
+    }
+
+    /// Consumes the scratch space and returns the buffer used to create it.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+}
+
+impl<T> Fallible for BufferScratch<T> {
+    type Error = ScratchSpaceError;
+}
+
+impl<T: AsMut<[u8]>> ScratchSpace for BufferScratch<T> {
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<*mut u8, Self::Error> {
+        const HEADER_SIZE: usize = mem::size_of::<BufferScratchHeader>();
+
+        let bytes = self.buffer.as_mut();
+        let remaining = bytes.len() - self.pos;
+        let exceeded = ScratchSpaceError::ExceededLimit {
+            requested: layout.size(),
+            remaining,
+        };
+        if HEADER_SIZE > remaining {
+            return Err(exceeded);
+        }
+        let padding = bytes
+            .as_mut_ptr()
+            .add(self.pos + HEADER_SIZE)
+            .align_offset(layout.align());
+        if padding > remaining - HEADER_SIZE || layout.size() > remaining - HEADER_SIZE - padding {
+            return Err(exceeded);
+        }
+
+        let start = self.pos + HEADER_SIZE + padding;
+        let ptr = bytes.as_mut_ptr().add(start);
+        ptr.sub(HEADER_SIZE)
+            .cast::<BufferScratchHeader>()
+            .write_unaligned(BufferScratchHeader {
+                pos: self.pos,
+                last: self.last,
+                align: layout.align(),
+            });
+        self.pos = start + layout.size();
+        // The header always comes first, so allocations never start at 0
+        self.last = NonZeroUsize::new(start);
+        Ok(ptr)
+    }
+
+    unsafe fn pop_scratch(&mut self, ptr: *mut u8, layout: Layout) -> Result<(), Self::Error> {
+        let last = self
+            .last
+            .ok_or(ScratchSpaceError::NoAllocationsToPop)?
+            .get();
+        if ptr != self.buffer.as_mut().as_mut_ptr().add(last) {
+            return Err(ScratchSpaceError::NotPoppedInReverseOrder);
+        }
+        let header = ptr
+            .sub(mem::size_of::<BufferScratchHeader>())
+            .cast::<BufferScratchHeader>()
+            .read_unaligned();
+        if last + layout.size() != self.pos || header.align != layout.align() {
+            return Err(ScratchSpaceError::NotPoppedInReverseOrder);
+        }
+        self.pos = header.pos;
+        self.last = header.last;
+        Ok(())
+    }
+}
+
+/// Scratch space that allocates from the heap.
+///
+/// Any allocations that haven't been popped when it's dropped are freed, so
+/// serializers that fail partway through don't leak their scratch space.
+#[cfg(feature = "alloc")]
+pub struct AllocScratch {
+    allocations: Vec<(*mut u8, Layout)>,
+}
+
+// AllocScratch owns its allocations
+#[cfg(feature = "alloc")]
+unsafe impl Send for AllocScratch {}
+#[cfg(feature = "alloc")]
+unsafe impl Sync for AllocScratch {}
+
+#[cfg(feature = "alloc")]
+impl AllocScratch {
+    /// Creates a new heap scratch space.
+    pub fn new() -> Self {
+        Self {
+            allocations: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for AllocScratch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Drop for AllocScratch {
+    fn drop(&mut self) {
+        for (ptr, layout) in self.allocations.drain(..).rev() {
+            unsafe {
+                alloc::dealloc(ptr, layout);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Fallible for AllocScratch {
+    type Error = ScratchSpaceError;
+}
+
+#[cfg(feature = "alloc")]
+impl ScratchSpace for AllocScratch {
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<*mut u8, Self::Error> {
+        let ptr = alloc::alloc(layout);
+        if ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        self.allocations.push((ptr, layout));
+        Ok(ptr)
+    }
+
+    unsafe fn pop_scratch(&mut self, ptr: *mut u8, layout: Layout) -> Result<(), Self::Error> {
+        match self.allocations.last() {
+            Some(&(last_ptr, last_layout)) if last_ptr == ptr && last_layout == layout => {
+                self.allocations.pop();
+                alloc::dealloc(ptr, layout);
+                Ok(())
+            }
+            Some(_) => Err(ScratchSpaceError::NotPoppedInReverseOrder),
+            None => Err(ScratchSpaceError::NoAllocationsToPop),
+        }
+    }
+}
+
+/// Scratch space that allocates from a primary scratch space, and from a
+/// fallback scratch space when the primary one fails.
+///
+/// This is usually used with a fixed-size [`BufferScratch`] as the primary and
+/// an [`AllocScratch`] as the fallback, so small serializations never touch
+/// the heap and large ones still succeed.
+pub struct FallbackScratch<P, F> {
+    primary: P,
+    fallback: F,
+}
+
+impl<P, F> FallbackScratch<P, F> {
+    /// Creates a new scratch space from a primary and fallback scratch space.
+    pub fn new(primary: P, fallback: F) -> Self {
+        Self { primary, fallback }
+    }
+
+    /// Consumes the scratch space and returns the primary and fallback scratch
+    /// spaces.
+    pub fn into_inner(self) -> (P, F) {
+        (self.primary, self.fallback)
+    }
+}
+
+impl<P, F: Fallible> Fallible for FallbackScratch<P, F> {
+    type Error = F::Error;
+}
+
+impl<P: ScratchSpace, F: ScratchSpace> ScratchSpace for FallbackScratch<P, F> {
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<*mut u8, Self::Error> {
+        match self.primary.push_scratch(layout) {
+            Ok(ptr) => Ok(ptr),
+            Err(_) => self.fallback.push_scratch(layout),
+        }
+    }
+
+    unsafe fn pop_scratch(&mut self, ptr: *mut u8, layout: Layout) -> Result<(), Self::Error> {
+        match self.primary.pop_scratch(ptr, layout) {
+            Ok(()) => Ok(()),
+            Err(_) => self.fallback.pop_scratch(ptr, layout),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Diff implementations for B-tree maps and sets.
+
+use super::{ArchivedBTreeMap, ArchivedBTreeSet};
+use crate::diff::{ArchiveDiff, Differ, PathSegment};
+use core::{cmp::Ordering, fmt};
+
+impl<K: Ord + fmt::Debug, V: ArchiveDiff + fmt::Debug> ArchiveDiff for ArchivedBTreeMap<K, V> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        // Both maps are sorted by key, so changes are reported in key order
+        let mut old_iter = self.iter().peekable();
+        let mut new_iter = other.iter().peekable();
+        loop {
+            let ordering = match (old_iter.peek(), new_iter.peek()) {
+                (Some((old_key, _)), Some((new_key, _))) => old_key.cmp(new_key),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match ordering {
+                Ordering::Less => {
+                    let (key, old) = old_iter.next().unwrap();
+                    differ.removed(PathSegment::Key(key), old);
+                }
+                Ordering::Greater => {
+                    let (key, new) = new_iter.next().unwrap();
+                    differ.added(PathSegment::Key(key), new);
+                }
+                Ordering::Equal => {
+                    let (key, old) = old_iter.next().unwrap();
+                    let (_, new) = new_iter.next().unwrap();
+                    differ.diff(PathSegment::Key(key), old, new);
+                }
+            }
+        }
+    }
+}
+
+impl<K: Ord + fmt::Debug> ArchiveDiff for ArchivedBTreeSet<K> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        let mut old_iter = self.iter().peekable();
+        let mut new_iter = other.iter().peekable();
+        loop {
+            let ordering = match (old_iter.peek(), new_iter.peek()) {
+                (Some(old_key), Some(new_key)) => old_key.cmp(new_key),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match ordering {
+                Ordering::Less => {
+                    let key = old_iter.next().unwrap();
+                    differ.removed(PathSegment::Key(key), key);
+                }
+                Ordering::Greater => {
+                    let key = new_iter.next().unwrap();
+                    differ.added(PathSegment::Key(key), key);
+                }
+                Ordering::Equal => {
+                    old_iter.next();
+                    new_iter.next();
+                }
+            }
+        }
+    }
+}
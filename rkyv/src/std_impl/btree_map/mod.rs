@@ -0,0 +1,710 @@
+//! [`Archive`] implementation for B-tree maps and sets.
+//!
+//! During archiving, B-tree maps are flattened into arrays of entries sorted
+//! by key. Lookups and range queries are done with binary searches, and
+//! iteration is in key order.
+
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(feature = "validation")]
+pub mod validation;
+
+use crate::{
+    offset_of,
+    ser::{ScratchSpace, ScratchVec, Serializer},
+    Archive, Archived, Deserialize, Fallible, FixedUsize, OffsetError, RawRelPtr, RelPtr,
+    Serialize,
+};
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt,
+    iter::FusedIterator,
+    mem::size_of,
+    ops::{Bound, Index, RangeBounds},
+    pin::Pin,
+    slice,
+};
+
+/// A type that can be used to look up keys of type `K` in an archived B-tree
+/// map.
+///
+/// This is implemented for every type that `K` can be borrowed as. With the
+/// `archive_le` or `archive_be` features, it's also implemented for native
+/// primitives so that maps with fixed-endian keys can be queried with native
+/// keys. Comparable values must be ordered the same as the keys they're
+/// compared to.
+pub trait Comparable<K: ?Sized> {
+    /// Compares this value to the given key.
+    fn compare(&self, key: &K) -> Ordering;
+}
+
+impl<Q: Ord + ?Sized, K: Borrow<Q> + ?Sized> Comparable<K> for Q {
+    #[inline]
+    fn compare(&self, key: &K) -> Ordering {
+        self.cmp(key.borrow())
+    }
+}
+
+#[cfg_attr(feature = "strict", repr(C))]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+}
+
+/// An archived `BTreeMap`.
+///
+/// The entries of the map are stored in an array sorted by key.
+#[repr(transparent)]
+pub struct ArchivedBTreeMap<K, V> {
+    entries: RelPtr<[Entry<K, V>]>,
+}
+
+impl<K, V> ArchivedBTreeMap<K, V> {
+    /// Gets the number of items in the B-tree map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    /// Returns whether there are no items in the B-tree map.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    fn entries(&self) -> &[Entry<K, V>] {
+        unsafe { &*self.entries.as_ptr() }
+    }
+
+    #[inline]
+    fn entries_mut(&mut self) -> &mut [Entry<K, V>] {
+        unsafe { &mut *self.entries.as_mut_ptr() }
+    }
+
+    /// Gets an iterator over the key-value entries in the B-tree map, sorted
+    /// by key.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.entries().iter(),
+        }
+    }
+
+    /// Gets an iterator over the keys and mutable values in the B-tree map,
+    /// sorted by key.
+    #[inline]
+    pub fn iter_pin(self: Pin<&mut Self>) -> IterPin<'_, K, V> {
+        IterPin {
+            inner: unsafe { self.get_unchecked_mut().entries_mut().iter_mut() },
+        }
+    }
+
+    /// Gets an iterator over the keys in the B-tree map, in sorted order.
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys {
+            inner: self.entries().iter(),
+        }
+    }
+
+    /// Gets an iterator over the values in the B-tree map, sorted by key.
+    #[inline]
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values {
+            inner: self.entries().iter(),
+        }
+    }
+
+    /// Gets an iterator over the mutable values in the B-tree map, sorted by
+    /// key.
+    #[inline]
+    pub fn values_pin(self: Pin<&mut Self>) -> ValuesPin<'_, K, V> {
+        ValuesPin {
+            inner: unsafe { self.get_unchecked_mut().entries_mut().iter_mut() },
+        }
+    }
+
+    /// Gets the entry with the smallest key in the B-tree map.
+    #[inline]
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.entries()
+            .first()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+
+    /// Gets the entry with the largest key in the B-tree map.
+    #[inline]
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.entries()
+            .last()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+}
+
+impl<K: Ord, V> ArchivedBTreeMap<K, V> {
+    #[inline]
+    fn index<Q: Comparable<K> + ?Sized>(&self, k: &Q) -> Option<usize> {
+        self.entries()
+            .binary_search_by(|entry| k.compare(&entry.key).reverse())
+            .ok()
+    }
+
+    /// Finds the number of entries whose keys are before the given bound.
+    #[inline]
+    fn lower_bound<Q: Comparable<K> + ?Sized>(&self, bound: Bound<&Q>) -> usize {
+        match bound {
+            Bound::Included(k) => self.partition_point(|key| k.compare(key) == Ordering::Greater),
+            Bound::Excluded(k) => self.partition_point(|key| k.compare(key) != Ordering::Less),
+            Bound::Unbounded => 0,
+        }
+    }
+
+    /// Finds the number of entries whose keys are before or inside the given
+    /// bound.
+    #[inline]
+    fn upper_bound<Q: Comparable<K> + ?Sized>(&self, bound: Bound<&Q>) -> usize {
+        match bound {
+            Bound::Included(k) => self.partition_point(|key| k.compare(key) != Ordering::Less),
+            Bound::Excluded(k) => self.partition_point(|key| k.compare(key) == Ordering::Greater),
+            Bound::Unbounded => self.len(),
+        }
+    }
+
+    #[inline]
+    fn partition_point(&self, pred: impl Fn(&K) -> bool) -> usize {
+        self.entries()
+            .binary_search_by(|entry| {
+                if pred(&entry.key) {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            })
+            .unwrap_or_else(|index| index)
+    }
+
+    /// Finds the key-value entry for a key.
+    #[inline]
+    pub fn get_key_value<Q: Comparable<K> + ?Sized>(&self, k: &Q) -> Option<(&K, &V)> {
+        self.index(k).map(|index| {
+            let entry = &self.entries()[index];
+            (&entry.key, &entry.value)
+        })
+    }
+
+    /// Finds the mutable key-value entry for a key.
+    #[inline]
+    pub fn get_key_value_pin<Q: Comparable<K> + ?Sized>(
+        self: Pin<&mut Self>,
+        k: &Q,
+    ) -> Option<(&K, Pin<&mut V>)> {
+        unsafe {
+            let map = self.get_unchecked_mut();
+            map.index(k).map(move |index| {
+                let entry = &mut map.entries_mut()[index];
+                (&entry.key, Pin::new_unchecked(&mut entry.value))
+            })
+        }
+    }
+
+    /// Returns whether a key is present in the B-tree map.
+    #[inline]
+    pub fn contains_key<Q: Comparable<K> + ?Sized>(&self, k: &Q) -> bool {
+        self.index(k).is_some()
+    }
+
+    /// Gets the value associated with the given key.
+    #[inline]
+    pub fn get<Q: Comparable<K> + ?Sized>(&self, k: &Q) -> Option<&V> {
+        self.index(k).map(|index| &self.entries()[index].value)
+    }
+
+    /// Gets the mutable value associated with the given key.
+    #[inline]
+    pub fn get_pin<Q: Comparable<K> + ?Sized>(self: Pin<&mut Self>, k: &Q) -> Option<Pin<&mut V>> {
+        unsafe {
+            let map = self.get_unchecked_mut();
+            map.index(k)
+                .map(move |index| Pin::new_unchecked(&mut map.entries_mut()[index].value))
+        }
+    }
+
+    /// Gets an iterator over the entries whose keys are in the given range,
+    /// sorted by key.
+    ///
+    /// Unlike `BTreeMap::range`, this returns an empty iterator if the start
+    /// of the range is after its end.
+    #[inline]
+    pub fn range<Q: Comparable<K> + ?Sized, R: RangeBounds<Q>>(&self, range: R) -> Iter<'_, K, V> {
+        let start = self.lower_bound(range.start_bound());
+        let end = self.upper_bound(range.end_bound()).max(start);
+        Iter {
+            inner: self.entries()[start..end].iter(),
+        }
+    }
+
+    /// Serializes an iterator of key-value pairs as a B-tree map.
+    ///
+    /// `len` must be the number of pairs the iterator yields, and the pairs
+    /// must be sorted by key with no duplicate keys. The archived keys must
+    /// have the same order as the keys they were serialized from.
+    #[inline]
+    pub fn serialize_from_iter<
+        'a,
+        KU: 'a + Serialize<S, Archived = K>,
+        VU: 'a + Serialize<S, Archived = V>,
+        S: ScratchSpace + Serializer + ?Sized,
+    >(
+        iter: impl Iterator<Item = (&'a KU, &'a VU)>,
+        len: usize,
+        serializer: &mut S,
+    ) -> Result<ArchivedBTreeMapResolver, S::Error> {
+        unsafe {
+            let mut entries = ScratchVec::new(serializer, len)?;
+            for (key, value) in iter {
+                entries.push((
+                    key,
+                    value,
+                    key.serialize(serializer)?,
+                    value.serialize(serializer)?,
+                ));
+            }
+
+            let entries_pos = serializer.align_for::<Entry<K, V>>()?;
+            for (key, value, key_resolver, value_resolver) in entries.drain() {
+                let entry_pos = serializer.pos();
+                let entry = Entry {
+                    key: key
+                        .resolve(entry_pos + offset_of!(Entry<K, V>, key), key_resolver)
+                        .map_err(|e| serializer.offset_error(e))?,
+                    value: value
+                        .resolve(entry_pos + offset_of!(Entry<K, V>, value), value_resolver)
+                        .map_err(|e| serializer.offset_error(e))?,
+                };
+                let entry_slice = slice::from_raw_parts(
+                    (&entry as *const Entry<K, V>).cast::<u8>(),
+                    size_of::<Entry<K, V>>(),
+                );
+                serializer.write(entry_slice)?;
+            }
+            entries.free(serializer)?;
+
+            Ok(ArchivedBTreeMapResolver { entries_pos })
+        }
+    }
+}
+
+/// An iterator over the key-value entries of a B-tree map.
+pub struct Iter<'a, K, V> {
+    inner: slice::Iter<'a, Entry<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (&entry.key, &entry.value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+/// An iterator over the keys and mutable values of a B-tree map.
+pub struct IterPin<'a, K, V> {
+    inner: slice::IterMut<'a, Entry<K, V>>,
+}
+
+impl<'a, K, V> Iterator for IterPin<'a, K, V> {
+    type Item = (&'a K, Pin<&'a mut V>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|entry| (&entry.key, unsafe { Pin::new_unchecked(&mut entry.value) }))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterPin<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|entry| (&entry.key, unsafe { Pin::new_unchecked(&mut entry.value) }))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterPin<'_, K, V> {}
+impl<K, V> FusedIterator for IterPin<'_, K, V> {}
+
+/// An iterator over the keys of a B-tree map.
+pub struct Keys<'a, K, V> {
+    inner: slice::Iter<'a, Entry<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| &entry.key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Keys<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|entry| &entry.key)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+/// An iterator over the values of a B-tree map.
+pub struct Values<'a, K, V> {
+    inner: slice::Iter<'a, Entry<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| &entry.value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Values<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|entry| &entry.value)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+/// An iterator over the mutable values of a B-tree map.
+pub struct ValuesPin<'a, K, V> {
+    inner: slice::IterMut<'a, Entry<K, V>>,
+}
+
+impl<'a, K, V> Iterator for ValuesPin<'a, K, V> {
+    type Item = Pin<&'a mut V>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|entry| unsafe { Pin::new_unchecked(&mut entry.value) })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for ValuesPin<'_, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|entry| unsafe { Pin::new_unchecked(&mut entry.value) })
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesPin<'_, K, V> {}
+impl<K, V> FusedIterator for ValuesPin<'_, K, V> {}
+
+/// The resolver for archived B-tree maps.
+pub struct ArchivedBTreeMapResolver {
+    entries_pos: usize,
+}
+
+impl ArchivedBTreeMapResolver {
+    /// Resolves an archived B-tree map with `len` entries at the given
+    /// position.
+    pub fn resolve_from_len<K, V>(
+        self,
+        pos: usize,
+        len: usize,
+    ) -> Result<ArchivedBTreeMap<K, V>, OffsetError> {
+        let len = FixedUsize::try_from(len).map_err(|_| OffsetError::UsizeOutOfRange(len))?;
+        unsafe {
+            Ok(ArchivedBTreeMap {
+                entries: RelPtr::new(
+                    RawRelPtr::try_new(
+                        pos + offset_of!(ArchivedBTreeMap<K, V>, entries)
+                            + offset_of!(RelPtr<[Entry<K, V>]>, raw_ptr),
+                        self.entries_pos,
+                    )?,
+                    to_archived!(len),
+                ),
+            })
+        }
+    }
+}
+
+impl<K: Archive + Ord, V: Archive> Archive for BTreeMap<K, V>
+where
+    K::Archived: Ord,
+{
+    type Archived = ArchivedBTreeMap<K::Archived, V::Archived>;
+    type Resolver = ArchivedBTreeMapResolver;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        resolver.resolve_from_len(pos, self.len())
+    }
+}
+
+impl<K: Serialize<S> + Ord, V: Serialize<S>, S: ScratchSpace + Serializer + ?Sized> Serialize<S>
+    for BTreeMap<K, V>
+where
+    K::Archived: Ord,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        ArchivedBTreeMap::serialize_from_iter(self.iter(), self.len(), serializer)
+    }
+}
+
+impl<K: Archive + Ord, V: Archive, D: Fallible + ?Sized> Deserialize<BTreeMap<K, V>, D>
+    for Archived<BTreeMap<K, V>>
+where
+    K::Archived: Deserialize<K, D> + Ord,
+    V::Archived: Deserialize<V, D>,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<BTreeMap<K, V>, D::Error> {
+        let mut result = BTreeMap::new();
+        for (k, v) in self.iter() {
+            result.insert(k.deserialize(deserializer)?, v.deserialize(deserializer)?);
+        }
+        Ok(result)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for ArchivedBTreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for ArchivedBTreeMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for ArchivedBTreeMap<K, V> {}
+
+impl<K, V, AK: PartialEq<K>, AV: PartialEq<V>> PartialEq<BTreeMap<K, V>>
+    for ArchivedBTreeMap<AK, AV>
+{
+    fn eq(&self, other: &BTreeMap<K, V>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|((ak, av), (k, v))| ak == k && av == v)
+    }
+}
+
+impl<K, V, AK: PartialEq<K>, AV: PartialEq<V>> PartialEq<ArchivedBTreeMap<AK, AV>>
+    for BTreeMap<K, V>
+{
+    fn eq(&self, other: &ArchivedBTreeMap<AK, AV>) -> bool {
+        other.eq(self)
+    }
+}
+
+impl<K: Ord, Q: Comparable<K> + ?Sized, V> Index<&'_ Q> for ArchivedBTreeMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).unwrap()
+    }
+}
+
+/// An archived `BTreeSet`. This is a wrapper around a B-tree map with the same
+/// key and a value of `()`.
+#[derive(Eq, PartialEq)]
+#[repr(transparent)]
+pub struct ArchivedBTreeSet<K>(ArchivedBTreeMap<K, ()>);
+
+impl<K> ArchivedBTreeSet<K> {
+    /// Gets the number of items in the B-tree set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether there are no items in the B-tree set.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets an iterator over the keys of the B-tree set, in sorted order.
+    #[inline]
+    pub fn iter(&self) -> Keys<'_, K, ()> {
+        self.0.keys()
+    }
+
+    /// Gets the smallest key in the B-tree set.
+    #[inline]
+    pub fn first(&self) -> Option<&K> {
+        self.0.first_key_value().map(|(k, _)| k)
+    }
+
+    /// Gets the largest key in the B-tree set.
+    #[inline]
+    pub fn last(&self) -> Option<&K> {
+        self.0.last_key_value().map(|(k, _)| k)
+    }
+}
+
+impl<K: Ord> ArchivedBTreeSet<K> {
+    /// Gets the key corresponding to the given key in the B-tree set.
+    #[inline]
+    pub fn get<Q: Comparable<K> + ?Sized>(&self, k: &Q) -> Option<&K> {
+        self.0.get_key_value(k).map(|(k, _)| k)
+    }
+
+    /// Returns whether the given key is in the B-tree set.
+    #[inline]
+    pub fn contains<Q: Comparable<K> + ?Sized>(&self, k: &Q) -> bool {
+        self.0.contains_key(k)
+    }
+
+    /// Gets an iterator over the keys in the given range, in sorted order.
+    #[inline]
+    pub fn range<Q: Comparable<K> + ?Sized, R: RangeBounds<Q>>(&self, range: R) -> Keys<'_, K, ()> {
+        Keys {
+            inner: self.0.range(range).inner,
+        }
+    }
+
+    /// Serializes an iterator of keys as a B-tree set.
+    ///
+    /// `len` must be the number of keys the iterator yields, and the keys must
+    /// be sorted with no duplicates. The archived keys must have the same
+    /// order as the keys they were serialized from.
+    #[inline]
+    pub fn serialize_from_iter<
+        'a,
+        KU: 'a + Serialize<S, Archived = K>,
+        S: ScratchSpace + Serializer + ?Sized,
+    >(
+        iter: impl Iterator<Item = &'a KU>,
+        len: usize,
+        serializer: &mut S,
+    ) -> Result<ArchivedBTreeSetResolver, S::Error> {
+        Ok(ArchivedBTreeSetResolver(
+            ArchivedBTreeMap::serialize_from_iter(iter.map(|x| (x, &())), len, serializer)?,
+        ))
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for ArchivedBTreeSet<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<K, AK: PartialEq<K>> PartialEq<BTreeSet<K>> for ArchivedBTreeSet<AK> {
+    fn eq(&self, other: &BTreeSet<K>) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(ak, k)| ak == k)
+    }
+}
+
+impl<K, AK: PartialEq<K>> PartialEq<ArchivedBTreeSet<AK>> for BTreeSet<K> {
+    fn eq(&self, other: &ArchivedBTreeSet<AK>) -> bool {
+        other.eq(self)
+    }
+}
+
+/// The resolver for archived B-tree sets.
+pub struct ArchivedBTreeSetResolver(ArchivedBTreeMapResolver);
+
+impl ArchivedBTreeSetResolver {
+    /// Resolves an archived B-tree set with `len` keys at the given position.
+    pub fn resolve_from_len<K>(
+        self,
+        pos: usize,
+        len: usize,
+    ) -> Result<ArchivedBTreeSet<K>, OffsetError> {
+        Ok(ArchivedBTreeSet(self.0.resolve_from_len(pos, len)?))
+    }
+}
+
+impl<K: Archive + Ord> Archive for BTreeSet<K>
+where
+    K::Archived: Ord,
+{
+    type Archived = ArchivedBTreeSet<K::Archived>;
+    type Resolver = ArchivedBTreeSetResolver;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        resolver.resolve_from_len(pos, self.len())
+    }
+}
+
+impl<K: Serialize<S> + Ord, S: ScratchSpace + Serializer + ?Sized> Serialize<S> for BTreeSet<K>
+where
+    K::Archived: Ord,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        ArchivedBTreeSet::serialize_from_iter(self.iter(), self.len(), serializer)
+    }
+}
+
+impl<K: Archive + Ord, D: Fallible + ?Sized> Deserialize<BTreeSet<K>, D> for Archived<BTreeSet<K>>
+where
+    K::Archived: Deserialize<K, D> + Ord,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<BTreeSet<K>, D::Error> {
+        let mut result = BTreeSet::new();
+        for k in self.iter() {
+            result.insert(k.deserialize(deserializer)?);
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,37 @@
+//! Schema implementations for BTreeMap and BTreeSet.
+
+use super::{ArchivedBTreeMap, ArchivedBTreeSet, Entry};
+use crate::{
+    offset_of,
+    schema::{FieldSchema, Schema, SchemaGraph, TypeKind, TypeSchema},
+    RelPtr,
+};
+
+impl<K: Schema, V: Schema> Schema for Entry<K, V> {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![
+            FieldSchema::new::<K>(graph, "key", offset_of!(Self, key)),
+            FieldSchema::new::<V>(graph, "value", offset_of!(Self, value)),
+        ]))
+    }
+}
+
+impl<K: Schema, V: Schema> Schema for ArchivedBTreeMap<K, V> {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![FieldSchema::new::<
+            RelPtr<[Entry<K, V>]>,
+        >(
+            graph,
+            "entries",
+            offset_of!(Self, entries),
+        )]))
+    }
+}
+
+impl<K: Schema> Schema for ArchivedBTreeSet<K> {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![FieldSchema::new::<
+            ArchivedBTreeMap<K, ()>,
+        >(graph, "0", 0)]))
+    }
+}
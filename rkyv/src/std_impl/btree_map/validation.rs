@@ -0,0 +1,155 @@
+//! Validation implementations for BTreeMap and BTreeSet.
+
+use super::{ArchivedBTreeMap, ArchivedBTreeSet, Entry};
+use crate::{
+    offset_of,
+    validation::{ArchiveBoundsContext, ArchiveMemoryContext},
+    RelPtr,
+};
+use bytecheck::{CheckBytes, Error, SliceCheckError};
+use core::{convert::Infallible, fmt};
+
+/// Errors that can occur while checking an archived B-tree map entry.
+#[derive(Debug)]
+pub enum ArchivedBTreeMapEntryError<K, V> {
+    /// An error occurred while checking the bytes of a key
+    KeyCheckError(K),
+    /// An error occurred while checking the bytes of a value
+    ValueCheckError(V),
+}
+
+impl<K: fmt::Display, V: fmt::Display> fmt::Display for ArchivedBTreeMapEntryError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchivedBTreeMapEntryError::KeyCheckError(e) => write!(f, "key check error: {}", e),
+            ArchivedBTreeMapEntryError::ValueCheckError(e) => {
+                write!(f, "value check error: {}", e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: fmt::Debug + fmt::Display, V: fmt::Debug + fmt::Display> std::error::Error
+    for ArchivedBTreeMapEntryError<K, V>
+{
+}
+
+impl<K: CheckBytes<C>, V: CheckBytes<C>, C: ArchiveMemoryContext + ?Sized> CheckBytes<C>
+    for Entry<K, V>
+{
+    type Error = ArchivedBTreeMapEntryError<K::Error, V::Error>;
+
+    unsafe fn check_bytes<'a>(
+        value: *const Self,
+        context: &mut C,
+    ) -> Result<&'a Self, Self::Error> {
+        let bytes = value.cast::<u8>();
+        K::check_bytes(bytes.add(offset_of!(Entry<K, V>, key)).cast(), context)
+            .map_err(ArchivedBTreeMapEntryError::KeyCheckError)?;
+        V::check_bytes(bytes.add(offset_of!(Entry<K, V>, value)).cast(), context)
+            .map_err(ArchivedBTreeMapEntryError::ValueCheckError)?;
+        Ok(&*value)
+    }
+}
+
+/// Errors that can occur while checking an archived B-tree map.
+#[derive(Debug)]
+pub enum BTreeMapError<K, V, C> {
+    /// The pointer to the entries failed to validate due to invalid metadata
+    PointerCheckBytesError(Infallible),
+    /// An error occured while checking the entries
+    CheckEntryError(SliceCheckError<ArchivedBTreeMapEntryError<K, V>>),
+    /// The keys of the entries were not in strictly increasing order
+    UnsortedKeys {
+        /// The index of the first key that was not greater than the key
+        /// before it
+        index: usize,
+    },
+    /// A bounds error occurred
+    ContextError(C),
+}
+
+impl<K: fmt::Display, V: fmt::Display, E: fmt::Display> fmt::Display for BTreeMapError<K, V, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BTreeMapError::PointerCheckBytesError(e) => e.fmt(f),
+            BTreeMapError::CheckEntryError(e) => write!(f, "entry check error: {}", e),
+            BTreeMapError::UnsortedKeys { index } => {
+                write!(f, "unsorted keys: key at index {} is out of order", index)
+            }
+            BTreeMapError::ContextError(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Error, V: Error, C: Error> std::error::Error for BTreeMapError<K, V, C> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BTreeMapError::PointerCheckBytesError(e) => Some(e as &dyn std::error::Error),
+            BTreeMapError::CheckEntryError(e) => Some(e as &dyn std::error::Error),
+            BTreeMapError::UnsortedKeys { .. } => None,
+            BTreeMapError::ContextError(e) => Some(e as &dyn std::error::Error),
+        }
+    }
+}
+
+impl<K, V, C> From<SliceCheckError<ArchivedBTreeMapEntryError<K, V>>> for BTreeMapError<K, V, C> {
+    fn from(e: SliceCheckError<ArchivedBTreeMapEntryError<K, V>>) -> Self {
+        Self::CheckEntryError(e)
+    }
+}
+
+impl<
+        K: CheckBytes<C> + Ord,
+        V: CheckBytes<C>,
+        C: ArchiveBoundsContext + ArchiveMemoryContext + ?Sized,
+    > CheckBytes<C> for ArchivedBTreeMap<K, V>
+where
+    C::Error: Error,
+{
+    type Error = BTreeMapError<K::Error, V::Error, C::Error>;
+
+    unsafe fn check_bytes<'a>(
+        value: *const Self,
+        context: &mut C,
+    ) -> Result<&'a Self, Self::Error> {
+        let rel_ptr = RelPtr::<[Entry<K, V>]>::manual_check_bytes(
+            value
+                .cast::<u8>()
+                .add(offset_of!(ArchivedBTreeMap<K, V>, entries))
+                .cast(),
+            context,
+        )
+        .map_err(BTreeMapError::PointerCheckBytesError)?;
+        let ptr = context
+            .claim_owned_rel_ptr(rel_ptr)
+            .map_err(BTreeMapError::ContextError)?;
+        let entries = <[Entry<K, V>]>::check_bytes(ptr, context)?;
+
+        for (i, pair) in entries.windows(2).enumerate() {
+            if pair[0].key >= pair[1].key {
+                return Err(BTreeMapError::UnsortedKeys { index: i + 1 });
+            }
+        }
+
+        Ok(&*value)
+    }
+}
+
+impl<K: CheckBytes<C> + Ord, C: ArchiveBoundsContext + ArchiveMemoryContext + ?Sized> CheckBytes<C>
+    for ArchivedBTreeSet<K>
+where
+    C::Error: Error,
+{
+    type Error = BTreeMapError<K::Error, <() as CheckBytes<C>>::Error, C::Error>;
+
+    unsafe fn check_bytes<'a>(
+        value: *const Self,
+        context: &mut C,
+    ) -> Result<&'a Self, Self::Error> {
+        ArchivedBTreeMap::<K, ()>::check_bytes(value.cast(), context)?;
+        Ok(&*value)
+    }
+}
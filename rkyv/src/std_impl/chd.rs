@@ -0,0 +1,113 @@
+//! [`Archive`] implementations for `HashMap` and `HashSet`.
+//!
+//! The archived types are in [`core_impl::chd`](crate::core_impl::chd) so
+//! that they can be used without the standard library.
+
+pub use crate::core_impl::chd::{
+    ArchivedHashMap, ArchivedHashMapResolver, ArchivedHashSet, ArchivedHashSetResolver, Equivalent,
+    Iter, IterPin, Keys, Values, ValuesPin,
+};
+use crate::{
+    ser::{ScratchSpace, Serializer},
+    Archive, Archived, Deserialize, Fallible, OffsetError, Serialize,
+};
+use core::hash::Hash;
+use std::collections::{HashMap, HashSet};
+
+impl<K: Archive + Hash + Eq, V: Archive> Archive for HashMap<K, V>
+where
+    K::Archived: Hash + Eq,
+{
+    type Archived = ArchivedHashMap<K::Archived, V::Archived>;
+    type Resolver = ArchivedHashMapResolver;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        resolver.resolve_from_len(pos, self.len())
+    }
+}
+
+impl<K: Serialize<S> + Hash + Eq, V: Serialize<S>, S: ScratchSpace + Serializer + ?Sized>
+    Serialize<S> for HashMap<K, V>
+where
+    K::Archived: Hash + Eq,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(ArchivedHashMap::serialize_from_iter(
+            self.iter(),
+            self.len(),
+            serializer,
+        )?)
+    }
+}
+
+impl<K: Archive + Hash + Eq, V: Archive, D: Fallible + ?Sized> Deserialize<HashMap<K, V>, D>
+    for Archived<HashMap<K, V>>
+where
+    K::Archived: Deserialize<K, D> + Hash + Eq,
+    V::Archived: Deserialize<V, D>,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<HashMap<K, V>, D::Error> {
+        let mut result = HashMap::new();
+        for (k, v) in self.iter() {
+            result.insert(k.deserialize(deserializer)?, v.deserialize(deserializer)?);
+        }
+        Ok(result)
+    }
+}
+
+impl<K: Hash + Eq + Equivalent<AK>, V, AK: Hash + Eq, AV: PartialEq<V>> PartialEq<HashMap<K, V>>
+    for ArchivedHashMap<AK, AV>
+{
+    fn eq(&self, other: &HashMap<K, V>) -> bool {
+        if self.len() != other.len() {
+            false
+        } else {
+            other
+                .iter()
+                .all(|(key, value)| self.get(key).map_or(false, |v| *v == *value))
+        }
+    }
+}
+
+impl<K: Hash + Eq + Equivalent<AK>, V, AK: Hash + Eq, AV: PartialEq<V>>
+    PartialEq<ArchivedHashMap<AK, AV>> for HashMap<K, V>
+{
+    fn eq(&self, other: &ArchivedHashMap<AK, AV>) -> bool {
+        other.eq(self)
+    }
+}
+
+impl<K: Archive + Hash + Eq> Archive for HashSet<K>
+where
+    K::Archived: Hash + Eq,
+{
+    type Archived = ArchivedHashSet<K::Archived>;
+    type Resolver = ArchivedHashSetResolver;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        resolver.resolve_from_len(pos, self.len())
+    }
+}
+
+impl<K: Serialize<S> + Hash + Eq, S: ScratchSpace + Serializer + ?Sized> Serialize<S> for HashSet<K>
+where
+    K::Archived: Hash + Eq,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        ArchivedHashSet::serialize_from_iter(self.iter(), self.len(), serializer)
+    }
+}
+
+impl<K: Archive + Hash + Eq, D: Fallible + ?Sized> Deserialize<HashSet<K>, D>
+    for Archived<HashSet<K>>
+where
+    K::Archived: Deserialize<K, D> + Hash + Eq,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<HashSet<K>, D::Error> {
+        let mut result = HashSet::new();
+        for k in self.iter() {
+            result.insert(k.deserialize(deserializer)?);
+        }
+        Ok(result)
+    }
+}
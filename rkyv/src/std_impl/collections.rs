@@ -0,0 +1,281 @@
+//! [`Archive`] implementations for `VecDeque`, `BinaryHeap` and `LinkedList`.
+//!
+//! All of these collections are archived as a contiguous slice of their
+//! elements.
+
+use super::{ArchivedVec, VecResolver};
+use crate::{
+    ser::{ScratchSpace, Serializer},
+    Archive, Archived, Deserialize, Fallible, MetadataResolver, OffsetError, Serialize,
+};
+use alloc::collections::{BinaryHeap, LinkedList, VecDeque};
+use core::{fmt, ops::Index, slice};
+
+/// An archived [`VecDeque`].
+///
+/// The elements are stored in a single slice from front to back.
+#[repr(transparent)]
+pub struct ArchivedVecDeque<T>(ArchivedVec<T>);
+
+impl<T> ArchivedVecDeque<T> {
+    /// Gets the number of elements in the archived deque.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the archived deque is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the element at the given index, where the front of the deque has
+    /// index 0.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.0.get(index)
+    }
+
+    /// Gets the element at the front of the archived deque.
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        self.0.first()
+    }
+
+    /// Gets the element at the back of the archived deque.
+    #[inline]
+    pub fn back(&self) -> Option<&T> {
+        self.0.last()
+    }
+
+    /// Gets an iterator over the elements from front to back.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    /// Gets the elements of the archived deque as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        self.0.as_slice()
+    }
+}
+
+impl<T> Index<usize> for ArchivedVecDeque<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.0[index]
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedVecDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<VecDeque<U>> for ArchivedVecDeque<T> {
+    fn eq(&self, other: &VecDeque<U>) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedVecDeque<T>> for VecDeque<U> {
+    fn eq(&self, other: &ArchivedVecDeque<T>) -> bool {
+        other.eq(self)
+    }
+}
+
+impl<T: Archive> Archive for VecDeque<T> {
+    type Archived = ArchivedVecDeque<T::Archived>;
+    type Resolver = VecResolver<MetadataResolver<[T]>>;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        Ok(ArchivedVecDeque(ArchivedVec::resolve_from_len(
+            pos,
+            self.len(),
+            resolver,
+        )?))
+    }
+}
+
+impl<T: Serialize<S>, S: ScratchSpace + Serializer + ?Sized> Serialize<S> for VecDeque<T> {
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        ArchivedVec::serialize_from_iter(self.iter(), serializer)
+    }
+}
+
+impl<T: Archive, D: Fallible + ?Sized> Deserialize<VecDeque<T>, D> for Archived<VecDeque<T>>
+where
+    T::Archived: Deserialize<T, D>,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<VecDeque<T>, D::Error> {
+        self.iter().map(|x| x.deserialize(deserializer)).collect()
+    }
+}
+
+/// An archived [`BinaryHeap`].
+///
+/// The elements are stored in heap order, so the greatest element is first.
+#[repr(transparent)]
+pub struct ArchivedBinaryHeap<T>(ArchivedVec<T>);
+
+impl<T> ArchivedBinaryHeap<T> {
+    /// Gets the number of elements in the archived heap.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the archived heap is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the greatest element in the archived heap.
+    #[inline]
+    pub fn peek(&self) -> Option<&T> {
+        self.0.first()
+    }
+
+    /// Gets an iterator over the elements in heap order.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    /// Gets the elements of the archived heap as a slice in heap order.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        self.0.as_slice()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedBinaryHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Archive + Ord> Archive for BinaryHeap<T> {
+    type Archived = ArchivedBinaryHeap<T::Archived>;
+    type Resolver = VecResolver<MetadataResolver<[T]>>;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        Ok(ArchivedBinaryHeap(ArchivedVec::resolve_from_len(
+            pos,
+            self.len(),
+            resolver,
+        )?))
+    }
+}
+
+impl<T: Serialize<S> + Ord, S: ScratchSpace + Serializer + ?Sized> Serialize<S> for BinaryHeap<T> {
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        // Iterating a binary heap yields its elements in heap order
+        ArchivedVec::serialize_from_iter(self.iter(), serializer)
+    }
+}
+
+impl<T: Archive + Ord, D: Fallible + ?Sized> Deserialize<BinaryHeap<T>, D>
+    for Archived<BinaryHeap<T>>
+where
+    T::Archived: Deserialize<T, D>,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<BinaryHeap<T>, D::Error> {
+        self.iter().map(|x| x.deserialize(deserializer)).collect()
+    }
+}
+
+/// An archived [`LinkedList`].
+///
+/// The elements are stored in a single slice from front to back.
+#[repr(transparent)]
+pub struct ArchivedLinkedList<T>(ArchivedVec<T>);
+
+impl<T> ArchivedLinkedList<T> {
+    /// Gets the number of elements in the archived list.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the archived list is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the element at the front of the archived list.
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        self.0.first()
+    }
+
+    /// Gets the element at the back of the archived list.
+    #[inline]
+    pub fn back(&self) -> Option<&T> {
+        self.0.last()
+    }
+
+    /// Gets an iterator over the elements from front to back.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    /// Gets the elements of the archived list as a slice.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        self.0.as_slice()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedLinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<LinkedList<U>> for ArchivedLinkedList<T> {
+    fn eq(&self, other: &LinkedList<U>) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedLinkedList<T>> for LinkedList<U> {
+    fn eq(&self, other: &ArchivedLinkedList<T>) -> bool {
+        other.eq(self)
+    }
+}
+
+impl<T: Archive> Archive for LinkedList<T> {
+    type Archived = ArchivedLinkedList<T::Archived>;
+    type Resolver = VecResolver<MetadataResolver<[T]>>;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        Ok(ArchivedLinkedList(ArchivedVec::resolve_from_len(
+            pos,
+            self.len(),
+            resolver,
+        )?))
+    }
+}
+
+impl<T: Serialize<S>, S: ScratchSpace + Serializer + ?Sized> Serialize<S> for LinkedList<T> {
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        ArchivedVec::serialize_from_iter(self.iter(), serializer)
+    }
+}
+
+impl<T: Archive, D: Fallible + ?Sized> Deserialize<LinkedList<T>, D> for Archived<LinkedList<T>>
+where
+    T::Archived: Deserialize<T, D>,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<LinkedList<T>, D::Error> {
+        self.iter().map(|x| x.deserialize(deserializer)).collect()
+    }
+}
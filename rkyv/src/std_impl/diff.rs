@@ -0,0 +1,49 @@
+//! Diff implementations for std types.
+
+use crate::{
+    diff::{ArchiveDiff, Differ},
+    std_impl::{
+        collections::{ArchivedBinaryHeap, ArchivedLinkedList, ArchivedVecDeque},
+        ArchivedBox, ArchivedString, ArchivedVec,
+    },
+    ArchivePointee,
+};
+use core::fmt;
+
+impl ArchiveDiff for ArchivedString {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        if self.as_str() != other.as_str() {
+            differ.changed(self, other);
+        }
+    }
+}
+
+impl<T: ArchiveDiff + fmt::Debug> ArchiveDiff for ArchivedVec<T> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        self.as_slice().diff(other.as_slice(), differ);
+    }
+}
+
+impl<T: ArchiveDiff + fmt::Debug> ArchiveDiff for ArchivedVecDeque<T> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        self.as_slice().diff(other.as_slice(), differ);
+    }
+}
+
+impl<T: ArchiveDiff + fmt::Debug> ArchiveDiff for ArchivedBinaryHeap<T> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        self.as_slice().diff(other.as_slice(), differ);
+    }
+}
+
+impl<T: ArchiveDiff + fmt::Debug> ArchiveDiff for ArchivedLinkedList<T> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        self.as_slice().diff(other.as_slice(), differ);
+    }
+}
+
+impl<T: ArchivePointee + ArchiveDiff + ?Sized> ArchiveDiff for ArchivedBox<T> {
+    fn diff<'a>(&'a self, other: &'a Self, differ: &mut Differ<'a>) {
+        (**self).diff(&**other, differ);
+    }
+}
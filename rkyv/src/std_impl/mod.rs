@@ -1,17 +1,32 @@
 //! [`Archive`] implementations for std types.
 
+pub mod btree_map;
+#[cfg(feature = "std")]
 pub mod chd;
+pub mod collections;
+#[cfg(feature = "std")]
+pub mod diff;
+pub mod niche;
+#[cfg(feature = "schema")]
+pub mod schema;
 pub mod shared;
 #[cfg(feature = "validation")]
 pub mod validation;
+mod with;
 
 use crate::{
-    de::Deserializer, Archive, ArchivePointee, ArchiveUnsized, Archived, Deserialize,
-    DeserializeUnsized, Fallible, MetadataResolver, RelPtr, Serialize, SerializeUnsized,
+    de::Deserializer,
+    offset_of,
+    ser::{ScratchSpace, ScratchVec, Serializer},
+    Archive, ArchivePointee, ArchiveUnsized, Archived, Deserialize, DeserializeUnsized, Fallible,
+    FixedUsize, MetadataResolver, OffsetError, RawRelPtr, RelPtr, Serialize, SerializeUnsized,
 };
+use alloc::{borrow::Cow, boxed::Box, string::String, vec::Vec};
 use core::{
     borrow::Borrow,
-    cmp, fmt, hash,
+    cmp,
+    convert::TryFrom,
+    fmt, hash,
     ops::{Deref, DerefMut, Index, IndexMut},
     pin::Pin,
 };
@@ -19,7 +34,6 @@ use core::{
 /// An archived [`String`].
 ///
 /// Uses a [`RelPtr`] to a `str` under the hood.
-#[derive(Debug)]
 #[repr(transparent)]
 pub struct ArchivedString(RelPtr<str>);
 
@@ -40,6 +54,12 @@ impl ArchivedString {
     }
 }
 
+impl fmt::Debug for ArchivedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
 impl cmp::Eq for ArchivedString {}
 
 impl hash::Hash for ArchivedString {
@@ -110,6 +130,18 @@ impl PartialEq<ArchivedString> for String {
     }
 }
 
+impl PartialOrd<String> for ArchivedString {
+    fn partial_cmp(&self, other: &String) -> Option<cmp::Ordering> {
+        self.as_str().partial_cmp(other.as_str())
+    }
+}
+
+impl PartialOrd<ArchivedString> for String {
+    fn partial_cmp(&self, other: &ArchivedString) -> Option<cmp::Ordering> {
+        self.as_str().partial_cmp(other.as_str())
+    }
+}
+
 impl fmt::Display for ArchivedString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(self.as_str(), f)
@@ -126,14 +158,14 @@ impl Archive for String {
     type Archived = ArchivedString;
     type Resolver = StringResolver;
 
-    fn resolve(&self, pos: usize, resolver: StringResolver) -> Self::Archived {
+    fn resolve(&self, pos: usize, resolver: StringResolver) -> Result<Self::Archived, OffsetError> {
         #[allow(clippy::unit_arg)]
         unsafe {
-            ArchivedString(self.as_str().resolve_unsized(
+            Ok(ArchivedString(self.as_str().resolve_unsized(
                 pos,
                 resolver.pos,
                 resolver.metadata_resolver,
-            ))
+            )?))
         }
     }
 }
@@ -170,12 +202,9 @@ where
 #[repr(transparent)]
 pub struct ArchivedBox<T: ArchivePointee + ?Sized>(RelPtr<T>);
 
-impl<T: ArchivePointee + ?Sized> fmt::Debug for ArchivedBox<T>
-where
-    T::ArchivedMetadata: fmt::Debug,
-{
+impl<T: ArchivePointee + fmt::Debug + ?Sized> fmt::Debug for ArchivedBox<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("ArchivedBox").field(&self.0).finish()
+        (**self).fmt(f)
     }
 }
 
@@ -216,13 +245,13 @@ impl<T: ArchiveUnsized + ?Sized> Archive for Box<T> {
     type Archived = ArchivedBox<T::Archived>;
     type Resolver = BoxResolver<T::MetadataResolver>;
 
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
         unsafe {
-            ArchivedBox(self.as_ref().resolve_unsized(
+            Ok(ArchivedBox(self.as_ref().resolve_unsized(
                 pos,
                 resolver.pos,
                 resolver.metadata_resolver,
-            ))
+            )?))
         }
     }
 }
@@ -254,7 +283,6 @@ where
 /// An archived [`Vec`].
 ///
 /// Uses a [`RelPtr`] to a `T` slice under the hood.
-#[derive(Debug)]
 #[repr(transparent)]
 pub struct ArchivedVec<T>(RelPtr<[T]>);
 
@@ -277,6 +305,64 @@ impl<T> ArchivedVec<T> {
     {
         unsafe { self.map_unchecked_mut(|s| &mut s.deref_mut()[index]) }
     }
+
+    /// Serializes an iterator of values as the elements of an archived vec.
+    ///
+    /// This is used to archive collections that don't store their elements in
+    /// a single slice.
+    pub fn serialize_from_iter<
+        'a,
+        U: 'a + Serialize<S, Archived = T>,
+        S: ScratchSpace + Serializer + ?Sized,
+    >(
+        iter: impl ExactSizeIterator<Item = &'a U>,
+        serializer: &mut S,
+    ) -> Result<VecResolver<()>, S::Error> {
+        if iter.len() == 0 {
+            return Ok(VecResolver {
+                pos: 0,
+                metadata_resolver: (),
+            });
+        }
+
+        unsafe {
+            let mut values = ScratchVec::new(serializer, iter.len())?;
+            for value in iter {
+                values.push((value, value.serialize(serializer)?));
+            }
+            let pos = serializer.align_for::<T>()?;
+            for (value, resolver) in values.drain() {
+                serializer.resolve_aligned(value, resolver)?;
+            }
+            values.free(serializer)?;
+            Ok(VecResolver {
+                pos,
+                metadata_resolver: (),
+            })
+        }
+    }
+
+    /// Resolves an archived vec with `len` elements from the resolver returned
+    /// by [`serialize_from_iter`](ArchivedVec::serialize_from_iter).
+    pub fn resolve_from_len(
+        pos: usize,
+        len: usize,
+        resolver: VecResolver<()>,
+    ) -> Result<Self, OffsetError> {
+        let len = FixedUsize::try_from(len).map_err(|_| OffsetError::UsizeOutOfRange(len))?;
+        unsafe {
+            Ok(ArchivedVec(RelPtr::new(
+                RawRelPtr::try_new(pos + offset_of!(RelPtr<[T]>, raw_ptr), resolver.pos)?,
+                to_archived!(len),
+            )))
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
 }
 
 impl<T> Deref for ArchivedVec<T> {
@@ -303,14 +389,14 @@ impl<T: Archive> Archive for Vec<T> {
     type Archived = ArchivedVec<T::Archived>;
     type Resolver = VecResolver<MetadataResolver<[T]>>;
 
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
         #[allow(clippy::unit_arg)]
         unsafe {
-            ArchivedVec(self.as_slice().resolve_unsized(
+            Ok(ArchivedVec(self.as_slice().resolve_unsized(
                 pos,
                 resolver.pos,
                 resolver.metadata_resolver,
-            ))
+            )?))
         }
     }
 }
@@ -346,3 +432,204 @@ impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for ArchivedVec<T> {
         self.as_slice().eq(other.as_slice())
     }
 }
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedVec<T>> for Vec<U> {
+    fn eq(&self, other: &ArchivedVec<T>) -> bool {
+        other.eq(self)
+    }
+}
+
+impl<T: PartialOrd<U>, U> PartialOrd<Vec<U>> for ArchivedVec<T> {
+    fn partial_cmp(&self, other: &Vec<U>) -> Option<cmp::Ordering> {
+        for (self_value, other_value) in self.iter().zip(other.iter()) {
+            match self_value.partial_cmp(other_value) {
+                Some(cmp::Ordering::Equal) => (),
+                cmp => return cmp,
+            }
+        }
+        self.len().partial_cmp(&other.len())
+    }
+}
+
+impl<T: PartialOrd<U>, U> PartialOrd<ArchivedVec<T>> for Vec<U> {
+    fn partial_cmp(&self, other: &ArchivedVec<T>) -> Option<cmp::Ordering> {
+        other.partial_cmp(self).map(cmp::Ordering::reverse)
+    }
+}
+
+impl<T: Archive> Archive for &T {
+    type Archived = ArchivedBox<T::Archived>;
+    type Resolver = BoxResolver<()>;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        #[allow(clippy::unit_arg)]
+        unsafe {
+            Ok(ArchivedBox((**self).resolve_unsized(
+                pos,
+                resolver.pos,
+                resolver.metadata_resolver,
+            )?))
+        }
+    }
+}
+
+impl<T: Serialize<S>, S: Serializer + ?Sized> Serialize<S> for &T {
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(BoxResolver {
+            pos: (**self).serialize_unsized(serializer)?,
+            metadata_resolver: (**self).serialize_metadata(serializer)?,
+        })
+    }
+}
+
+impl Archive for &str {
+    type Archived = ArchivedString;
+    type Resolver = StringResolver;
+
+    fn resolve(&self, pos: usize, resolver: StringResolver) -> Result<Self::Archived, OffsetError> {
+        #[allow(clippy::unit_arg)]
+        unsafe {
+            Ok(ArchivedString((**self).resolve_unsized(
+                pos,
+                resolver.pos,
+                resolver.metadata_resolver,
+            )?))
+        }
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for &str
+where
+    str: SerializeUnsized<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(StringResolver {
+            pos: (**self).serialize_unsized(serializer)?,
+            metadata_resolver: (**self).serialize_metadata(serializer)?,
+        })
+    }
+}
+
+impl<T: Archive> Archive for &[T] {
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = VecResolver<MetadataResolver<[T]>>;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        #[allow(clippy::unit_arg)]
+        unsafe {
+            Ok(ArchivedVec((**self).resolve_unsized(
+                pos,
+                resolver.pos,
+                resolver.metadata_resolver,
+            )?))
+        }
+    }
+}
+
+impl<T: Serialize<S>, S: Fallible + ?Sized> Serialize<S> for &[T]
+where
+    [T]: SerializeUnsized<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(VecResolver {
+            pos: (**self).serialize_unsized(serializer)?,
+            metadata_resolver: (**self).serialize_metadata(serializer)?,
+        })
+    }
+}
+
+impl<T: Archive + Clone> Archive for Cow<'_, T> {
+    type Archived = ArchivedBox<T::Archived>;
+    type Resolver = BoxResolver<()>;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        #[allow(clippy::unit_arg)]
+        unsafe {
+            Ok(ArchivedBox((**self).resolve_unsized(
+                pos,
+                resolver.pos,
+                resolver.metadata_resolver,
+            )?))
+        }
+    }
+}
+
+impl<T: Serialize<S> + Clone, S: Serializer + ?Sized> Serialize<S> for Cow<'_, T> {
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(BoxResolver {
+            pos: (**self).serialize_unsized(serializer)?,
+            metadata_resolver: (**self).serialize_metadata(serializer)?,
+        })
+    }
+}
+
+impl<'a, T: Archive + Clone, D: Deserializer + ?Sized> Deserialize<Cow<'a, T>, D>
+    for ArchivedBox<T::Archived>
+where
+    T::Archived: Deserialize<T, D>,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<Cow<'a, T>, D::Error> {
+        Ok(Cow::Owned(self.deref().deserialize(deserializer)?))
+    }
+}
+
+impl Archive for Cow<'_, str> {
+    type Archived = ArchivedString;
+    type Resolver = StringResolver;
+
+    fn resolve(&self, pos: usize, resolver: StringResolver) -> Result<Self::Archived, OffsetError> {
+        self.as_ref().resolve(pos, resolver)
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for Cow<'_, str>
+where
+    str: SerializeUnsized<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        self.as_ref().serialize(serializer)
+    }
+}
+
+impl<'a, D: Fallible + ?Sized> Deserialize<Cow<'a, str>, D> for Archived<Cow<'a, str>>
+where
+    str: DeserializeUnsized<str, D>,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<Cow<'a, str>, D::Error> {
+        Ok(Cow::Owned(Deserialize::<String, D>::deserialize(
+            self,
+            deserializer,
+        )?))
+    }
+}
+
+impl<T: Archive + Clone> Archive for Cow<'_, [T]> {
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = VecResolver<MetadataResolver<[T]>>;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        self.as_ref().resolve(pos, resolver)
+    }
+}
+
+impl<T: Serialize<S> + Clone, S: Fallible + ?Sized> Serialize<S> for Cow<'_, [T]>
+where
+    [T]: SerializeUnsized<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        self.as_ref().serialize(serializer)
+    }
+}
+
+impl<'a, T: Archive + Clone, D: Fallible + ?Sized> Deserialize<Cow<'a, [T]>, D>
+    for Archived<Cow<'a, [T]>>
+where
+    [T::Archived]: DeserializeUnsized<[T], D>,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<Cow<'a, [T]>, D::Error> {
+        Ok(Cow::Owned(Deserialize::<Vec<T>, D>::deserialize(
+            self,
+            deserializer,
+        )?))
+    }
+}
@@ -0,0 +1,410 @@
+//! Niche-optimized archived options for owned pointers.
+//!
+//! An [`ArchivedOption`](crate::core_impl::ArchivedOption) stores a tag before
+//! its value, which usually costs as much as the value's alignment. The
+//! relative pointers in owned pointers are never null when they point to a
+//! value, so these types use a null relative pointer to represent `None`
+//! instead and are the same size as the pointers they wrap.
+
+use super::{ArchivedBox, ArchivedString, ArchivedVec, BoxResolver, StringResolver, VecResolver};
+use crate::{
+    de::Deserializer, ser::Serializer, Archive, ArchivePointee, ArchiveUnsized, Deserialize,
+    DeserializeUnsized, Fallible, MetadataResolver, OffsetError, RelPtr, Serialize,
+    SerializeUnsized,
+};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::{fmt, pin::Pin};
+
+/// Makes sure that a value serialized at `pos` can't be pointed to by a null
+/// relative pointer.
+///
+/// Values are always serialized before the pointers to them, so a relative
+/// pointer can only be null if its target is empty and nothing was written
+/// between them. Padding a byte in that case guarantees the pointer will be
+/// written after its target.
+fn separate_from<S: Serializer + ?Sized>(pos: usize, serializer: &mut S) -> Result<(), S::Error> {
+    if pos == serializer.pos() {
+        serializer.pad(1)?;
+    }
+    Ok(())
+}
+
+/// An archived `Option<Box<T>>` that uses a null relative pointer to represent
+/// `None`.
+#[repr(transparent)]
+pub struct ArchivedOptionBox<T: ArchivePointee + ?Sized>(ArchivedBox<T>);
+
+impl<T: ArchivePointee + ?Sized> ArchivedOptionBox<T> {
+    /// Returns `true` if the option is a `None` value.
+    pub fn is_none(&self) -> bool {
+        self.0 .0.is_null()
+    }
+
+    /// Returns `true` if the option is a `Some` value.
+    pub fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+
+    /// Converts to an `Option<&ArchivedBox<T>>`.
+    pub fn as_ref(&self) -> Option<&ArchivedBox<T>> {
+        if self.is_none() {
+            None
+        } else {
+            Some(&self.0)
+        }
+    }
+
+    /// Converts to an `Option<&mut ArchivedBox<T>>`.
+    pub fn as_mut(&mut self) -> Option<&mut ArchivedBox<T>> {
+        if self.is_none() {
+            None
+        } else {
+            Some(&mut self.0)
+        }
+    }
+
+    /// Converts from `Pin<&mut ArchivedOptionBox<T>>` to
+    /// `Option<Pin<&mut ArchivedBox<T>>>`.
+    pub fn as_pin_mut(self: Pin<&mut Self>) -> Option<Pin<&mut ArchivedBox<T>>> {
+        unsafe {
+            Pin::get_unchecked_mut(self)
+                .as_mut()
+                .map(|x| Pin::new_unchecked(x))
+        }
+    }
+}
+
+impl<T: ArchivePointee + ?Sized> ArchivedOptionBox<T>
+where
+    T::ArchivedMetadata: Default,
+{
+    /// Resolves an `ArchivedOptionBox` from the contents of an
+    /// `Option<Box<U>>` and the resolver returned by
+    /// [`serialize_from_option`](Self::serialize_from_option).
+    pub fn resolve_from_option<U: ArchiveUnsized<Archived = T> + ?Sized>(
+        field: Option<&U>,
+        pos: usize,
+        resolver: Option<BoxResolver<U::MetadataResolver>>,
+    ) -> Result<Self, OffsetError> {
+        match (field, resolver) {
+            (Some(value), Some(resolver)) => unsafe {
+                Ok(Self(ArchivedBox(value.resolve_unsized(
+                    pos,
+                    resolver.pos,
+                    resolver.metadata_resolver,
+                )?)))
+            },
+            _ => Ok(Self(ArchivedBox(RelPtr::null()))),
+        }
+    }
+
+    /// Serializes the contents of an `Option<Box<U>>` so they can be resolved
+    /// as an `ArchivedOptionBox`.
+    pub fn serialize_from_option<U, S>(
+        field: Option<&U>,
+        serializer: &mut S,
+    ) -> Result<Option<BoxResolver<U::MetadataResolver>>, S::Error>
+    where
+        U: SerializeUnsized<S, Archived = T> + ?Sized,
+        S: Serializer + ?Sized,
+    {
+        field
+            .map(|value| {
+                let resolver = BoxResolver {
+                    pos: value.serialize_unsized(serializer)?,
+                    metadata_resolver: value.serialize_metadata(serializer)?,
+                };
+                separate_from(resolver.pos, serializer)?;
+                Ok(resolver)
+            })
+            .transpose()
+    }
+}
+
+impl<T: ArchivePointee + ?Sized> ArchivedOptionBox<T> {
+    /// Deserializes the archived option into an `Option<Box<U>>`.
+    pub fn deserialize_to_option<U, D>(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<Option<Box<U>>, D::Error>
+    where
+        U: ArchiveUnsized<Archived = T> + ?Sized,
+        T: DeserializeUnsized<U, D>,
+        D: Deserializer + ?Sized,
+    {
+        self.as_ref()
+            .map(|value| value.deserialize(deserializer))
+            .transpose()
+    }
+}
+
+impl<T: ArchivePointee + fmt::Debug + ?Sized> fmt::Debug for ArchivedOptionBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_ref().fmt(f)
+    }
+}
+
+impl<T: ArchivePointee + PartialEq<U> + ?Sized, U: ?Sized> PartialEq<Option<Box<U>>>
+    for ArchivedOptionBox<T>
+{
+    fn eq(&self, other: &Option<Box<U>>) -> bool {
+        match (self.as_ref(), other) {
+            (Some(self_value), Some(other_value)) => self_value.eq(other_value),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// An archived `Option<String>` that uses a null relative pointer to represent
+/// `None`.
+#[repr(transparent)]
+pub struct ArchivedOptionString(ArchivedString);
+
+impl ArchivedOptionString {
+    /// Returns `true` if the option is a `None` value.
+    pub fn is_none(&self) -> bool {
+        self.0 .0.is_null()
+    }
+
+    /// Returns `true` if the option is a `Some` value.
+    pub fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+
+    /// Converts to an `Option<&ArchivedString>`.
+    pub fn as_ref(&self) -> Option<&ArchivedString> {
+        if self.is_none() {
+            None
+        } else {
+            Some(&self.0)
+        }
+    }
+
+    /// Converts to an `Option<&mut ArchivedString>`.
+    pub fn as_mut(&mut self) -> Option<&mut ArchivedString> {
+        if self.is_none() {
+            None
+        } else {
+            Some(&mut self.0)
+        }
+    }
+
+    /// Converts from `Pin<&mut ArchivedOptionString>` to
+    /// `Option<Pin<&mut ArchivedString>>`.
+    pub fn as_pin_mut(self: Pin<&mut Self>) -> Option<Pin<&mut ArchivedString>> {
+        unsafe {
+            Pin::get_unchecked_mut(self)
+                .as_mut()
+                .map(|x| Pin::new_unchecked(x))
+        }
+    }
+
+    /// Resolves an `ArchivedOptionString` from the contents of an
+    /// `Option<String>` and the resolver returned by
+    /// [`serialize_from_option`](Self::serialize_from_option).
+    pub fn resolve_from_option(
+        field: Option<&str>,
+        pos: usize,
+        resolver: Option<StringResolver>,
+    ) -> Result<Self, OffsetError> {
+        match (field, resolver) {
+            #[allow(clippy::unit_arg)]
+            (Some(value), Some(resolver)) => unsafe {
+                Ok(Self(ArchivedString(value.resolve_unsized(
+                    pos,
+                    resolver.pos,
+                    resolver.metadata_resolver,
+                )?)))
+            },
+            _ => Ok(Self(ArchivedString(RelPtr::null()))),
+        }
+    }
+
+    /// Serializes the contents of an `Option<String>` so they can be resolved
+    /// as an `ArchivedOptionString`.
+    pub fn serialize_from_option<S: Serializer + ?Sized>(
+        field: Option<&str>,
+        serializer: &mut S,
+    ) -> Result<Option<StringResolver>, S::Error>
+    where
+        str: SerializeUnsized<S>,
+    {
+        field
+            .map(|value| {
+                let resolver = StringResolver {
+                    pos: value.serialize_unsized(serializer)?,
+                    metadata_resolver: value.serialize_metadata(serializer)?,
+                };
+                separate_from(resolver.pos, serializer)?;
+                Ok(resolver)
+            })
+            .transpose()
+    }
+}
+
+impl ArchivedOptionString {
+    /// Deserializes the archived option into an `Option<String>`.
+    pub fn deserialize_to_option<D: Fallible + ?Sized>(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<Option<String>, D::Error>
+    where
+        str: DeserializeUnsized<str, D>,
+    {
+        self.as_ref()
+            .map(|value| value.deserialize(deserializer))
+            .transpose()
+    }
+}
+
+impl fmt::Debug for ArchivedOptionString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_ref().fmt(f)
+    }
+}
+
+impl PartialEq<Option<String>> for ArchivedOptionString {
+    fn eq(&self, other: &Option<String>) -> bool {
+        match (self.as_ref(), other) {
+            (Some(self_value), Some(other_value)) => self_value.eq(other_value),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<ArchivedOptionString> for Option<String> {
+    fn eq(&self, other: &ArchivedOptionString) -> bool {
+        other.eq(self)
+    }
+}
+
+/// An archived `Option<Vec<T>>` that uses a null relative pointer to represent
+/// `None`.
+#[repr(transparent)]
+pub struct ArchivedOptionVec<T>(ArchivedVec<T>);
+
+impl<T> ArchivedOptionVec<T> {
+    /// Returns `true` if the option is a `None` value.
+    pub fn is_none(&self) -> bool {
+        self.0 .0.is_null()
+    }
+
+    /// Returns `true` if the option is a `Some` value.
+    pub fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+
+    /// Converts to an `Option<&ArchivedVec<T>>`.
+    pub fn as_ref(&self) -> Option<&ArchivedVec<T>> {
+        if self.is_none() {
+            None
+        } else {
+            Some(&self.0)
+        }
+    }
+
+    /// Converts to an `Option<&mut ArchivedVec<T>>`.
+    pub fn as_mut(&mut self) -> Option<&mut ArchivedVec<T>> {
+        if self.is_none() {
+            None
+        } else {
+            Some(&mut self.0)
+        }
+    }
+
+    /// Converts from `Pin<&mut ArchivedOptionVec<T>>` to
+    /// `Option<Pin<&mut ArchivedVec<T>>>`.
+    pub fn as_pin_mut(self: Pin<&mut Self>) -> Option<Pin<&mut ArchivedVec<T>>> {
+        unsafe {
+            Pin::get_unchecked_mut(self)
+                .as_mut()
+                .map(|x| Pin::new_unchecked(x))
+        }
+    }
+
+    /// Resolves an `ArchivedOptionVec` from the contents of an
+    /// `Option<Vec<U>>` and the resolver returned by
+    /// [`serialize_from_option`](Self::serialize_from_option).
+    pub fn resolve_from_option<U: Archive<Archived = T>>(
+        field: Option<&[U]>,
+        pos: usize,
+        resolver: Option<VecResolver<MetadataResolver<[U]>>>,
+    ) -> Result<Self, OffsetError> {
+        match (field, resolver) {
+            #[allow(clippy::unit_arg)]
+            (Some(value), Some(resolver)) => unsafe {
+                Ok(Self(ArchivedVec(value.resolve_unsized(
+                    pos,
+                    resolver.pos,
+                    resolver.metadata_resolver,
+                )?)))
+            },
+            _ => Ok(Self(ArchivedVec(RelPtr::null()))),
+        }
+    }
+
+    /// Serializes the contents of an `Option<Vec<U>>` so they can be resolved
+    /// as an `ArchivedOptionVec`.
+    #[allow(clippy::type_complexity)]
+    pub fn serialize_from_option<U, S>(
+        field: Option<&[U]>,
+        serializer: &mut S,
+    ) -> Result<Option<VecResolver<MetadataResolver<[U]>>>, S::Error>
+    where
+        U: Serialize<S, Archived = T>,
+        [U]: SerializeUnsized<S>,
+        S: Serializer + ?Sized,
+    {
+        field
+            .map(|value| {
+                let resolver = VecResolver {
+                    pos: value.serialize_unsized(serializer)?,
+                    metadata_resolver: value.serialize_metadata(serializer)?,
+                };
+                separate_from(resolver.pos, serializer)?;
+                Ok(resolver)
+            })
+            .transpose()
+    }
+}
+
+impl<T> ArchivedOptionVec<T> {
+    /// Deserializes the archived option into an `Option<Vec<U>>`.
+    pub fn deserialize_to_option<U, D>(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<Option<Vec<U>>, D::Error>
+    where
+        U: Archive<Archived = T>,
+        [T]: DeserializeUnsized<[U], D>,
+        D: Fallible + ?Sized,
+    {
+        self.as_ref()
+            .map(|value| value.deserialize(deserializer))
+            .transpose()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ArchivedOptionVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_ref().fmt(f)
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<Option<Vec<U>>> for ArchivedOptionVec<T> {
+    fn eq(&self, other: &Option<Vec<U>>) -> bool {
+        match (self.as_ref(), other) {
+            (Some(self_value), Some(other_value)) => self_value.eq(other_value),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: PartialEq<U>, U> PartialEq<ArchivedOptionVec<T>> for Option<Vec<U>> {
+    fn eq(&self, other: &ArchivedOptionVec<T>) -> bool {
+        other.eq(self)
+    }
+}
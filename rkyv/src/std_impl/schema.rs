@@ -0,0 +1,90 @@
+//! Schema implementations for std types.
+
+use super::{
+    collections::{ArchivedBinaryHeap, ArchivedLinkedList, ArchivedVecDeque},
+    niche::{ArchivedOptionBox, ArchivedOptionString, ArchivedOptionVec},
+    ArchivedBox, ArchivedString, ArchivedVec,
+};
+use crate::{
+    schema::{FieldSchema, Schema, SchemaGraph, TypeKind, TypeSchema},
+    ArchivePointee, RelPtr,
+};
+
+impl Schema for ArchivedString {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![FieldSchema::new::<RelPtr<str>>(
+            graph, "0", 0,
+        )]))
+    }
+}
+
+impl<T: ArchivePointee + ?Sized> Schema for ArchivedBox<T>
+where
+    RelPtr<T>: Schema,
+{
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![FieldSchema::new::<RelPtr<T>>(
+            graph, "0", 0,
+        )]))
+    }
+}
+
+impl<T> Schema for ArchivedVec<T>
+where
+    RelPtr<[T]>: Schema,
+{
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Struct(vec![FieldSchema::new::<RelPtr<[T]>>(
+            graph, "0", 0,
+        )]))
+    }
+}
+
+macro_rules! impl_vec_wrapper_schema {
+    ($name:ident) => {
+        impl<T> Schema for $name<T>
+        where
+            ArchivedVec<T>: Schema,
+        {
+            fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+                TypeSchema::sized::<Self>(TypeKind::Struct(vec![
+                    FieldSchema::new::<ArchivedVec<T>>(graph, "0", 0),
+                ]))
+            }
+        }
+    };
+}
+
+impl_vec_wrapper_schema!(ArchivedVecDeque);
+impl_vec_wrapper_schema!(ArchivedBinaryHeap);
+impl_vec_wrapper_schema!(ArchivedLinkedList);
+
+impl Schema for ArchivedOptionString {
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Niche {
+            inner: graph.register::<ArchivedString>(),
+        })
+    }
+}
+
+impl<T: ArchivePointee + ?Sized> Schema for ArchivedOptionBox<T>
+where
+    ArchivedBox<T>: Schema,
+{
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Niche {
+            inner: graph.register::<ArchivedBox<T>>(),
+        })
+    }
+}
+
+impl<T> Schema for ArchivedOptionVec<T>
+where
+    ArchivedVec<T>: Schema,
+{
+    fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+        TypeSchema::sized::<Self>(TypeKind::Niche {
+            inner: graph.register::<ArchivedVec<T>>(),
+        })
+    }
+}
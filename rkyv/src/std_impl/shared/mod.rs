@@ -1,17 +1,19 @@
 //! [`Archive`] implementation for shared pointers.
 
+#[cfg(feature = "schema")]
+pub mod schema;
 #[cfg(feature = "validation")]
 pub mod validation;
 
-use core::{cmp::PartialEq, mem, ops::Deref, pin::Pin};
-use std::{rc, sync};
+use alloc::{boxed::Box, rc, sync};
+use core::{cmp::PartialEq, fmt, mem, ops::Deref, pin::Pin};
 
 use crate::{
     de::{SharedDeserializer, SharedPointer},
     offset_of,
     ser::SharedSerializer,
-    Archive, ArchivePointee, ArchiveUnsized, Archived, Deserialize, DeserializeUnsized, RelPtr,
-    Serialize, SerializeUnsized,
+    Archive, ArchivePointee, ArchiveUnsized, Archived, Deserialize, DeserializeUnsized,
+    OffsetError, RelPtr, Serialize, SerializeUnsized,
 };
 
 impl<T: ?Sized> SharedPointer for rc::Rc<T> {
@@ -52,6 +54,12 @@ impl<T: ArchivePointee + ?Sized> Deref for ArchivedRc<T> {
     }
 }
 
+impl<T: ArchivePointee + fmt::Debug + ?Sized> fmt::Debug for ArchivedRc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
 impl<T: ArchivePointee + PartialEq<U> + ?Sized, U: ?Sized> PartialEq<rc::Rc<U>> for ArchivedRc<T> {
     fn eq(&self, other: &rc::Rc<U>) -> bool {
         self.deref().eq(other.deref())
@@ -62,12 +70,13 @@ impl<T: ArchiveUnsized + ?Sized> Archive for rc::Rc<T> {
     type Archived = ArchivedRc<T::Archived>;
     type Resolver = RcResolver<T::MetadataResolver>;
 
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
         unsafe {
-            ArchivedRc(
-                self.as_ref()
-                    .resolve_unsized(pos, resolver.pos, resolver.metadata_resolver),
-            )
+            Ok(ArchivedRc(self.as_ref().resolve_unsized(
+                pos,
+                resolver.pos,
+                resolver.metadata_resolver,
+            )?))
         }
     }
 }
@@ -148,18 +157,24 @@ impl<T: ArchivePointee + ?Sized> ArchivedRcWeak<T> {
     }
 }
 
+impl<T: ArchivePointee + ?Sized> fmt::Debug for ArchivedRcWeak<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(Weak)")
+    }
+}
+
 impl<T: ArchiveUnsized + ?Sized> Archive for rc::Weak<T> {
     type Archived = ArchivedRcWeak<T::Archived>;
     type Resolver = RcWeakResolver<T::MetadataResolver>;
 
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
         match resolver {
-            RcWeakResolver::None => ArchivedRcWeak::None,
+            RcWeakResolver::None => Ok(ArchivedRcWeak::None),
             RcWeakResolver::Some(resolver) => unsafe {
-                ArchivedRcWeak::Some(self.upgrade().unwrap().resolve(
+                Ok(ArchivedRcWeak::Some(self.upgrade().unwrap().resolve(
                     pos + offset_of!(ArchivedRcWeakVariantSome<T::Archived>, 1),
                     resolver,
-                ))
+                )?))
             },
         }
     }
@@ -229,6 +244,12 @@ impl<T: ArchivePointee + ?Sized> Deref for ArchivedArc<T> {
     }
 }
 
+impl<T: ArchivePointee + fmt::Debug + ?Sized> fmt::Debug for ArchivedArc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
 impl<T: ArchivePointee + PartialEq<U> + ?Sized, U: ?Sized> PartialEq<sync::Arc<U>>
     for ArchivedArc<T>
 {
@@ -241,13 +262,13 @@ impl<T: ArchiveUnsized + ?Sized> Archive for sync::Arc<T> {
     type Archived = ArchivedArc<T::Archived>;
     type Resolver = ArcResolver<T::MetadataResolver>;
 
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
         unsafe {
-            ArchivedArc(self.as_ref().resolve_unsized(
+            Ok(ArchivedArc(self.as_ref().resolve_unsized(
                 pos,
                 resolver.pos,
                 resolver.metadata_resolver,
-            ))
+            )?))
         }
     }
 }
@@ -327,18 +348,24 @@ impl<T: ArchivePointee + ?Sized> ArchivedArcWeak<T> {
     }
 }
 
+impl<T: ArchivePointee + ?Sized> fmt::Debug for ArchivedArcWeak<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(Weak)")
+    }
+}
+
 impl<T: ArchiveUnsized + ?Sized> Archive for sync::Weak<T> {
     type Archived = ArchivedArcWeak<T::Archived>;
     type Resolver = ArcWeakResolver<T::MetadataResolver>;
 
-    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
         match resolver {
-            ArcWeakResolver::None => ArchivedArcWeak::None,
+            ArcWeakResolver::None => Ok(ArchivedArcWeak::None),
             ArcWeakResolver::Some(resolver) => unsafe {
-                ArchivedArcWeak::Some(self.upgrade().unwrap().resolve(
+                Ok(ArchivedArcWeak::Some(self.upgrade().unwrap().resolve(
                     pos + offset_of!(ArchivedArcWeakVariantSome<T::Archived>, 1),
                     resolver,
-                ))
+                )?))
             },
         }
     }
@@ -0,0 +1,66 @@
+//! Schema implementations for shared pointers.
+
+use super::{
+    ArchivedArc, ArchivedArcWeak, ArchivedArcWeakTag, ArchivedArcWeakVariantSome, ArchivedRc,
+    ArchivedRcWeak, ArchivedRcWeakTag, ArchivedRcWeakVariantSome,
+};
+use crate::{
+    offset_of,
+    schema::{FieldSchema, Schema, SchemaGraph, TypeKind, TypeSchema, VariantSchema},
+    ArchivePointee, RelPtr,
+};
+
+macro_rules! impl_shared {
+    ($pointer:ident, $weak:ident, $tag:ident, $variant:ident) => {
+        impl<T: ArchivePointee + ?Sized> Schema for $pointer<T>
+        where
+            RelPtr<T>: Schema,
+        {
+            fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+                TypeSchema::sized::<Self>(TypeKind::Struct(vec![FieldSchema::new::<RelPtr<T>>(
+                    graph, "0", 0,
+                )]))
+            }
+        }
+
+        impl<T: ArchivePointee + ?Sized> Schema for $weak<T>
+        where
+            RelPtr<T>: Schema,
+        {
+            fn describe(graph: &mut SchemaGraph) -> TypeSchema {
+                TypeSchema::sized::<Self>(TypeKind::Enum {
+                    tag: graph.register::<u8>(),
+                    variants: vec![
+                        VariantSchema {
+                            name: "None".to_string(),
+                            tag: $tag::None as i128,
+                            fields: Vec::new(),
+                        },
+                        VariantSchema {
+                            name: "Some".to_string(),
+                            tag: $tag::Some as i128,
+                            fields: vec![FieldSchema::new::<$pointer<T>>(
+                                graph,
+                                "0",
+                                offset_of!($variant<T>, 1),
+                            )],
+                        },
+                    ],
+                })
+            }
+        }
+    };
+}
+
+impl_shared!(
+    ArchivedRc,
+    ArchivedRcWeak,
+    ArchivedRcWeakTag,
+    ArchivedRcWeakVariantSome
+);
+impl_shared!(
+    ArchivedArc,
+    ArchivedArcWeak,
+    ArchivedArcWeakTag,
+    ArchivedArcWeakVariantSome
+);
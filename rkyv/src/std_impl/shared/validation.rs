@@ -9,10 +9,9 @@ use crate::{
     validation::{ArchiveBoundsContext, LayoutMetadata, SharedArchiveContext},
     ArchivePointee, RelPtr,
 };
-use bytecheck::{CheckBytes, Unreachable};
-use core::{any::TypeId, fmt};
+use bytecheck::{CheckBytes, Error};
+use core::{any::TypeId, convert::Infallible, fmt};
 use ptr_meta::Pointee;
-use std::error::Error;
 
 /// Errors that can occur while checking archived shared pointers.
 #[derive(Debug)]
@@ -37,14 +36,13 @@ impl<T: fmt::Display, R: fmt::Display, C: fmt::Display> fmt::Display
     }
 }
 
-impl<T: Error + 'static, R: Error + 'static, C: Error + 'static> Error
-    for SharedPointerError<T, R, C>
-{
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
+#[cfg(feature = "std")]
+impl<T: Error, R: Error, C: Error> std::error::Error for SharedPointerError<T, R, C> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            SharedPointerError::PointerCheckBytesError(e) => Some(e as &dyn Error),
-            SharedPointerError::ValueCheckBytesError(e) => Some(e as &dyn Error),
-            SharedPointerError::ContextError(e) => Some(e as &dyn Error),
+            SharedPointerError::PointerCheckBytesError(e) => Some(e as &dyn std::error::Error),
+            SharedPointerError::ValueCheckBytesError(e) => Some(e as &dyn std::error::Error),
+            SharedPointerError::ContextError(e) => Some(e as &dyn std::error::Error),
         }
     }
 }
@@ -69,19 +67,18 @@ impl<T: fmt::Display, R: fmt::Display, C: fmt::Display> fmt::Display for WeakPoi
     }
 }
 
-impl<T: Error + 'static, R: Error + 'static, C: Error + 'static> Error
-    for WeakPointerError<T, R, C>
-{
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
+#[cfg(feature = "std")]
+impl<T: Error, R: Error, C: Error> std::error::Error for WeakPointerError<T, R, C> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             WeakPointerError::InvalidTag(_) => None,
-            WeakPointerError::CheckBytes(e) => Some(e as &dyn Error),
+            WeakPointerError::CheckBytes(e) => Some(e as &dyn std::error::Error),
         }
     }
 }
 
-impl<T, R, C> From<Unreachable> for WeakPointerError<T, R, C> {
-    fn from(_: Unreachable) -> Self {
+impl<T, R, C> From<Infallible> for WeakPointerError<T, R, C> {
+    fn from(_: Infallible) -> Self {
         unreachable!();
     }
 }
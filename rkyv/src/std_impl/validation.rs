@@ -1,14 +1,17 @@
 //! Validation implementations for std types.
 
-use super::{ArchivedBox, ArchivedString, ArchivedVec};
+use super::{
+    collections::{ArchivedBinaryHeap, ArchivedLinkedList, ArchivedVecDeque},
+    niche::{ArchivedOptionBox, ArchivedOptionString, ArchivedOptionVec},
+    ArchivedBox, ArchivedString, ArchivedVec,
+};
 use crate::{
     validation::{ArchiveBoundsContext, ArchiveMemoryContext, LayoutMetadata},
     ArchivePointee, RelPtr,
 };
-use bytecheck::CheckBytes;
+use bytecheck::{CheckBytes, Error};
 use core::fmt;
 use ptr_meta::Pointee;
-use std::error::Error;
 
 /// Errors that can occur while chechking archived owned pointers
 #[derive(Debug)]
@@ -33,14 +36,13 @@ impl<T: fmt::Display, R: fmt::Display, C: fmt::Display> fmt::Display
     }
 }
 
-impl<T: Error + 'static, R: Error + 'static, C: Error + 'static> Error
-    for OwnedPointerError<T, R, C>
-{
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
+#[cfg(feature = "std")]
+impl<T: Error, R: Error, C: Error> std::error::Error for OwnedPointerError<T, R, C> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            OwnedPointerError::PointerCheckBytesError(e) => Some(e as &dyn Error),
-            OwnedPointerError::ValueCheckBytesError(e) => Some(e as &dyn Error),
-            OwnedPointerError::ContextError(e) => Some(e as &dyn Error),
+            OwnedPointerError::PointerCheckBytesError(e) => Some(e as &dyn std::error::Error),
+            OwnedPointerError::ValueCheckBytesError(e) => Some(e as &dyn std::error::Error),
+            OwnedPointerError::ContextError(e) => Some(e as &dyn std::error::Error),
         }
     }
 }
@@ -123,3 +125,75 @@ where
         Ok(&*value)
     }
 }
+
+macro_rules! impl_vec_wrapper_check_bytes {
+    ($name:ident) => {
+        impl<T, C: ?Sized> CheckBytes<C> for $name<T>
+        where
+            ArchivedVec<T>: CheckBytes<C>,
+        {
+            type Error = <ArchivedVec<T> as CheckBytes<C>>::Error;
+
+            unsafe fn check_bytes<'a>(
+                value: *const Self,
+                context: &mut C,
+            ) -> Result<&'a Self, Self::Error> {
+                ArchivedVec::<T>::check_bytes(value.cast(), context)?;
+                Ok(&*value)
+            }
+        }
+    };
+}
+
+impl_vec_wrapper_check_bytes!(ArchivedVecDeque);
+impl_vec_wrapper_check_bytes!(ArchivedBinaryHeap);
+impl_vec_wrapper_check_bytes!(ArchivedLinkedList);
+
+macro_rules! impl_niche_check_bytes {
+    ($pointee:ty, $inner:ty) => {
+        type Error = <$inner as CheckBytes<C>>::Error;
+
+        unsafe fn check_bytes<'a>(
+            value: *const Self,
+            context: &mut C,
+        ) -> Result<&'a Self, Self::Error> {
+            let rel_ptr = RelPtr::<$pointee>::manual_check_bytes(value.cast(), context)
+                .map_err(OwnedPointerError::PointerCheckBytesError)?;
+            // A null pointer represents `None` and doesn't point to anything
+            if !rel_ptr.is_null() {
+                <$inner>::check_bytes(value.cast(), context)?;
+            }
+            Ok(&*value)
+        }
+    };
+}
+
+impl<C: ArchiveBoundsContext + ArchiveMemoryContext + ?Sized> CheckBytes<C> for ArchivedOptionString
+where
+    C::Error: Error,
+{
+    impl_niche_check_bytes!(str, ArchivedString);
+}
+
+impl<
+        T: ArchivePointee + CheckBytes<C> + Pointee + ?Sized,
+        C: ArchiveBoundsContext + ArchiveMemoryContext + ?Sized,
+    > CheckBytes<C> for ArchivedOptionBox<T>
+where
+    T::ArchivedMetadata: CheckBytes<C>,
+    C::Error: Error,
+    <T as Pointee>::Metadata: LayoutMetadata<T>,
+{
+    impl_niche_check_bytes!(T, ArchivedBox<T>);
+}
+
+impl<T: CheckBytes<C>, C: ArchiveBoundsContext + ArchiveMemoryContext + ?Sized> CheckBytes<C>
+    for ArchivedOptionVec<T>
+where
+    [T]: ArchivePointee,
+    <[T] as ArchivePointee>::ArchivedMetadata: CheckBytes<C>,
+    C::Error: Error,
+    <[T] as Pointee>::Metadata: LayoutMetadata<[T]>,
+{
+    impl_niche_check_bytes!([T], ArchivedVec<T>);
+}
@@ -0,0 +1,234 @@
+//! [`ArchiveWith`] implementations for std types.
+
+use crate::{
+    de::Deserializer,
+    ser::Serializer,
+    std_impl::{
+        niche::{ArchivedOptionBox, ArchivedOptionString, ArchivedOptionVec},
+        ArchivedBox, ArchivedVec, BoxResolver, StringResolver, VecResolver,
+    },
+    with::{ArchiveWith, DeserializeWith, Niche, Raw, SerializeWith, Unshare},
+    Archive, ArchiveCopy, ArchivePointee, ArchiveUnsized, Archived, Deserialize,
+    DeserializeUnsized, Fallible, MetadataResolver, OffsetError, Serialize, SerializeUnsized,
+};
+use alloc::{boxed::Box, rc::Rc, string::String, sync::Arc, vec::Vec};
+use core::{mem, slice};
+
+macro_rules! impl_unshare {
+    ($ptr:ident) => {
+        impl<T: ArchiveUnsized + ?Sized> ArchiveWith<$ptr<T>> for Unshare {
+            type Archived = ArchivedBox<T::Archived>;
+            type Resolver = BoxResolver<T::MetadataResolver>;
+
+            fn resolve_with(
+                field: &$ptr<T>,
+                pos: usize,
+                resolver: Self::Resolver,
+            ) -> Result<Self::Archived, OffsetError> {
+                unsafe {
+                    Ok(ArchivedBox(field.as_ref().resolve_unsized(
+                        pos,
+                        resolver.pos,
+                        resolver.metadata_resolver,
+                    )?))
+                }
+            }
+        }
+
+        impl<T: SerializeUnsized<S> + ?Sized, S: Fallible + ?Sized> SerializeWith<$ptr<T>, S>
+            for Unshare
+        {
+            fn serialize_with(
+                field: &$ptr<T>,
+                serializer: &mut S,
+            ) -> Result<Self::Resolver, S::Error> {
+                Ok(BoxResolver {
+                    pos: field.as_ref().serialize_unsized(serializer)?,
+                    metadata_resolver: field.as_ref().serialize_metadata(serializer)?,
+                })
+            }
+        }
+
+        impl<T: ArchiveUnsized + ?Sized, D: Deserializer + ?Sized>
+            DeserializeWith<Archived<Box<T>>, $ptr<T>, D> for Unshare
+        where
+            T::Archived: DeserializeUnsized<T, D>,
+        {
+            fn deserialize_with(
+                field: &Archived<Box<T>>,
+                deserializer: &mut D,
+            ) -> Result<$ptr<T>, D::Error> {
+                let boxed: Box<T> = field.deserialize(deserializer)?;
+                Ok($ptr::from(boxed))
+            }
+        }
+    };
+}
+
+impl_unshare!(Rc);
+impl_unshare!(Arc);
+
+impl<T: ArchiveCopy> ArchiveWith<Vec<T>> for Raw {
+    type Archived = ArchivedVec<T>;
+    type Resolver = VecResolver<()>;
+
+    fn resolve_with(
+        field: &Vec<T>,
+        pos: usize,
+        resolver: Self::Resolver,
+    ) -> Result<Self::Archived, OffsetError> {
+        #[allow(clippy::unit_arg)]
+        unsafe {
+            Ok(ArchivedVec(field.as_slice().resolve_unsized(
+                pos,
+                resolver.pos,
+                resolver.metadata_resolver,
+            )?))
+        }
+    }
+}
+
+impl<T: ArchiveCopy, S: Serializer + ?Sized> SerializeWith<Vec<T>, S> for Raw {
+    fn serialize_with(field: &Vec<T>, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let pos = if field.is_empty() {
+            0
+        } else {
+            let bytes = unsafe {
+                slice::from_raw_parts(
+                    field.as_ptr().cast::<u8>(),
+                    field.len() * mem::size_of::<T>(),
+                )
+            };
+            let pos = serializer.align_for::<T>()?;
+            serializer.write(bytes)?;
+            pos
+        };
+        Ok(VecResolver {
+            pos,
+            metadata_resolver: (),
+        })
+    }
+}
+
+impl<T: ArchiveCopy, D: Fallible + ?Sized> DeserializeWith<ArchivedVec<T>, Vec<T>, D> for Raw {
+    fn deserialize_with(field: &ArchivedVec<T>, _: &mut D) -> Result<Vec<T>, D::Error> {
+        Ok(field.as_slice().to_vec())
+    }
+}
+
+impl<T: ArchiveUnsized + ?Sized> ArchiveWith<Option<Box<T>>> for Niche
+where
+    <T::Archived as ArchivePointee>::ArchivedMetadata: Default,
+{
+    type Archived = ArchivedOptionBox<T::Archived>;
+    type Resolver = Option<BoxResolver<T::MetadataResolver>>;
+
+    fn resolve_with(
+        field: &Option<Box<T>>,
+        pos: usize,
+        resolver: Self::Resolver,
+    ) -> Result<Self::Archived, OffsetError> {
+        ArchivedOptionBox::resolve_from_option(field.as_deref(), pos, resolver)
+    }
+}
+
+impl<T: SerializeUnsized<S> + ?Sized, S: Serializer + ?Sized> SerializeWith<Option<Box<T>>, S>
+    for Niche
+where
+    <T::Archived as ArchivePointee>::ArchivedMetadata: Default,
+{
+    fn serialize_with(
+        field: &Option<Box<T>>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedOptionBox::serialize_from_option(field.as_deref(), serializer)
+    }
+}
+
+impl<T: ArchiveUnsized + ?Sized, D: Deserializer + ?Sized>
+    DeserializeWith<ArchivedOptionBox<T::Archived>, Option<Box<T>>, D> for Niche
+where
+    T::Archived: DeserializeUnsized<T, D>,
+{
+    fn deserialize_with(
+        field: &ArchivedOptionBox<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Option<Box<T>>, D::Error> {
+        field.deserialize_to_option(deserializer)
+    }
+}
+
+impl ArchiveWith<Option<String>> for Niche {
+    type Archived = ArchivedOptionString;
+    type Resolver = Option<StringResolver>;
+
+    fn resolve_with(
+        field: &Option<String>,
+        pos: usize,
+        resolver: Self::Resolver,
+    ) -> Result<Self::Archived, OffsetError> {
+        ArchivedOptionString::resolve_from_option(field.as_deref(), pos, resolver)
+    }
+}
+
+impl<S: Serializer + ?Sized> SerializeWith<Option<String>, S> for Niche
+where
+    str: SerializeUnsized<S>,
+{
+    fn serialize_with(
+        field: &Option<String>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedOptionString::serialize_from_option(field.as_deref(), serializer)
+    }
+}
+
+impl<D: Fallible + ?Sized> DeserializeWith<ArchivedOptionString, Option<String>, D> for Niche
+where
+    str: DeserializeUnsized<str, D>,
+{
+    fn deserialize_with(
+        field: &ArchivedOptionString,
+        deserializer: &mut D,
+    ) -> Result<Option<String>, D::Error> {
+        field.deserialize_to_option(deserializer)
+    }
+}
+
+impl<T: Archive> ArchiveWith<Option<Vec<T>>> for Niche {
+    type Archived = ArchivedOptionVec<T::Archived>;
+    type Resolver = Option<VecResolver<MetadataResolver<[T]>>>;
+
+    fn resolve_with(
+        field: &Option<Vec<T>>,
+        pos: usize,
+        resolver: Self::Resolver,
+    ) -> Result<Self::Archived, OffsetError> {
+        ArchivedOptionVec::resolve_from_option(field.as_deref(), pos, resolver)
+    }
+}
+
+impl<T: Serialize<S>, S: Serializer + ?Sized> SerializeWith<Option<Vec<T>>, S> for Niche
+where
+    [T]: SerializeUnsized<S>,
+{
+    fn serialize_with(
+        field: &Option<Vec<T>>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        ArchivedOptionVec::serialize_from_option(field.as_deref(), serializer)
+    }
+}
+
+impl<T: Archive, D: Fallible + ?Sized>
+    DeserializeWith<ArchivedOptionVec<T::Archived>, Option<Vec<T>>, D> for Niche
+where
+    [T::Archived]: DeserializeUnsized<[T], D>,
+{
+    fn deserialize_with(
+        field: &ArchivedOptionVec<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Option<Vec<T>>, D::Error> {
+        field.deserialize_to_option(deserializer)
+    }
+}
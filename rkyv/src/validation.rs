@@ -1,17 +1,23 @@
 //! Validation implementations and helper types.
 
 use crate::{
-    offset_of, Archive, ArchivePointee, Archived, ArchivedIsize, Fallible, RawRelPtr, RelPtr,
+    offset_of,
+    ser::{ArchiveFooter, FooterError},
+    Archive, ArchivePointee, Archived, ArchivedIsize, Fallible, RawRelPtr, RelPtr,
 };
-use bytecheck::{CheckBytes, Unreachable};
+use alloc::{collections::BTreeMap, vec::Vec};
+use bytecheck::CheckBytes;
 use core::{
     alloc::Layout,
     any::TypeId,
+    convert::Infallible,
     fmt,
     marker::{PhantomData, PhantomPinned},
+    mem,
 };
 use ptr_meta::{DynMetadata, Pointee};
-use std::{collections::HashMap, error::Error};
+#[cfg(feature = "std")]
+use std::error::Error;
 
 impl RawRelPtr {
     /// Checks the bytes of the given raw relative pointer.
@@ -22,7 +28,7 @@ impl RawRelPtr {
     pub unsafe fn manual_check_bytes<'a, C: Fallible + ?Sized>(
         value: *const RawRelPtr,
         context: &mut C,
-    ) -> Result<&'a Self, Unreachable> {
+    ) -> Result<&'a Self, Infallible> {
         let bytes = value.cast::<u8>();
         ArchivedIsize::check_bytes(bytes.add(offset_of!(Self, offset)).cast(), context).unwrap();
         PhantomPinned::check_bytes(bytes.add(offset_of!(Self, _phantom)).cast(), context).unwrap();
@@ -144,6 +150,7 @@ impl fmt::Display for ArchiveBoundsError {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for ArchiveBoundsError {}
 
 /// A context that can check relative pointers.
@@ -277,6 +284,7 @@ impl<E: fmt::Display> fmt::Display for ArchiveMemoryError<E> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<E: Error + 'static> Error for ArchiveMemoryError<E> {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
@@ -452,6 +460,7 @@ impl<E: fmt::Display> fmt::Display for SharedArchiveError<E> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<E: Error + 'static> Error for SharedArchiveError<E> {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
@@ -510,7 +519,7 @@ pub trait SharedArchiveContext: Fallible {
 /// An adapter that adds shared memory validation.
 pub struct SharedArchiveValidator<C> {
     inner: C,
-    shared_blocks: HashMap<*const u8, TypeId>,
+    shared_blocks: BTreeMap<*const u8, TypeId>,
 }
 
 impl<C> SharedArchiveValidator<C> {
@@ -518,7 +527,7 @@ impl<C> SharedArchiveValidator<C> {
     pub fn new(inner: C) -> Self {
         Self {
             inner,
-            shared_blocks: HashMap::new(),
+            shared_blocks: BTreeMap::new(),
         }
     }
 
@@ -595,6 +604,8 @@ pub enum CheckArchiveError<T, C> {
     CheckBytesError(T),
     /// A context error occurred
     ContextError(C),
+    /// The archive footer was missing or did not match
+    FooterError(FooterError),
 }
 
 impl<T: fmt::Display, C: fmt::Display> fmt::Display for CheckArchiveError<T, C> {
@@ -602,15 +613,18 @@ impl<T: fmt::Display, C: fmt::Display> fmt::Display for CheckArchiveError<T, C>
         match self {
             CheckArchiveError::CheckBytesError(e) => write!(f, "check bytes error: {}", e),
             CheckArchiveError::ContextError(e) => write!(f, "context error: {}", e),
+            CheckArchiveError::FooterError(e) => write!(f, "footer error: {}", e),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl<T: Error + 'static, C: Error + 'static> Error for CheckArchiveError<T, C> {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             CheckArchiveError::CheckBytesError(e) => Some(e as &dyn Error),
             CheckArchiveError::ContextError(e) => Some(e as &dyn Error),
+            CheckArchiveError::FooterError(e) => Some(e as &dyn Error),
         }
     }
 }
@@ -700,3 +714,107 @@ where
         Ok(Archived::<T>::check_bytes(ptr, context).map_err(CheckArchiveError::CheckBytesError)?)
     }
 }
+
+/// Checks the given archive for an archived version of the given type at the
+/// end of the archive.
+///
+/// This is a safe alternative to [`archived_root`](crate::archived_root) for
+/// types that implement `CheckBytes`.
+///
+/// # Example
+/// ```
+/// use rkyv::{
+///     check_archived_root,
+///     ser::{Serializer, serializers::WriteSerializer},
+///     Archive,
+///     Serialize,
+/// };
+/// use bytecheck::CheckBytes;
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(derive(CheckBytes))]
+/// struct Example {
+///     name: String,
+///     value: i32,
+/// }
+///
+/// let value = Example {
+///     name: "pi".to_string(),
+///     value: 31415926,
+/// };
+///
+/// let mut serializer = WriteSerializer::new(Vec::new());
+/// serializer.serialize_root(&value)
+///     .expect("failed to archive test");
+/// let buf = serializer.into_inner();
+/// let archived = check_archived_root::<Example>(buf.as_ref()).unwrap();
+/// assert_eq!(archived.name.as_str(), "pi");
+/// ```
+pub fn check_archived_root<T: Archive>(
+    buf: &[u8],
+) -> Result<
+    &T::Archived,
+    CheckArchiveError<
+        <T::Archived as CheckBytes<DefaultArchiveValidator>>::Error,
+        <DefaultArchiveValidator as Fallible>::Error,
+    >,
+>
+where
+    T::Archived: CheckBytes<DefaultArchiveValidator>,
+{
+    let pos = buf.len().saturating_sub(mem::size_of::<T::Archived>());
+    check_archive::<T>(buf, pos)
+}
+
+/// Checks an archive that ends with an [`ArchiveFooter`] for an archived
+/// version of the given type at the root position recorded in the footer.
+///
+/// This is a safe alternative to
+/// [`archived_value_with_footer`](crate::archived_value_with_footer) for types
+/// that implement `CheckBytes`. The footer itself is not considered part of the
+/// archive data.
+///
+/// # Example
+/// ```
+/// use rkyv::{
+///     check_archive_with_footer,
+///     ser::{Serializer, serializers::WriteSerializer},
+///     Archive,
+///     Serialize,
+/// };
+/// use bytecheck::CheckBytes;
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(derive(CheckBytes))]
+/// struct Example {
+///     name: String,
+///     value: i32,
+/// }
+///
+/// let value = Example {
+///     name: "pi".to_string(),
+///     value: 31415926,
+/// };
+///
+/// let mut serializer = WriteSerializer::new(Vec::new());
+/// serializer.serialize_with_footer(&value)
+///     .expect("failed to archive test");
+/// let buf = serializer.into_inner();
+/// let archived = check_archive_with_footer::<Example>(buf.as_ref()).unwrap();
+/// assert_eq!(archived.name.as_str(), "pi");
+/// ```
+pub fn check_archive_with_footer<T: Archive>(
+    buf: &[u8],
+) -> Result<
+    &T::Archived,
+    CheckArchiveError<
+        <T::Archived as CheckBytes<DefaultArchiveValidator>>::Error,
+        <DefaultArchiveValidator as Fallible>::Error,
+    >,
+>
+where
+    T::Archived: CheckBytes<DefaultArchiveValidator>,
+{
+    let footer = ArchiveFooter::read(buf).map_err(CheckArchiveError::FooterError)?;
+    check_archive::<T>(&buf[..buf.len() - ArchiveFooter::SIZE], footer.root())
+}
@@ -0,0 +1,366 @@
+//! Wrapper types that customize how fields are archived.
+//!
+//! A wrapper type implements [`ArchiveWith`], [`SerializeWith`], and
+//! [`DeserializeWith`] for the types of the fields it can be applied to. When
+//! deriving `Archive`, add `#[with(...)]` to a field to archive it with a
+//! wrapper instead of its own [`Archive`] implementation:
+//!
+//! ```
+//! use rkyv::{
+//!     archived_value,
+//!     ser::{Serializer, serializers::WriteSerializer},
+//!     with::Raw,
+//!     Archive,
+//!     Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize)]
+//! struct Image {
+//!     width: u32,
+//!     height: u32,
+//!     #[with(Raw)]
+//!     pixels: Vec<u8>,
+//! }
+//!
+//! let value = Image {
+//!     width: 2,
+//!     height: 2,
+//!     pixels: vec![1, 2, 3, 4],
+//! };
+//!
+//! let mut serializer = WriteSerializer::new(Vec::new());
+//! let pos = serializer.serialize_value(&value)
+//!     .expect("failed to archive image");
+//! let buf = serializer.into_inner();
+//!
+//! let archived = unsafe { archived_value::<Image>(buf.as_ref(), pos) };
+//! assert_eq!(archived.pixels.as_slice(), &[1, 2, 3, 4]);
+//! ```
+//!
+//! Values can be archived with a wrapper outside of a derive by casting them
+//! to a [`With`].
+
+use crate::{Archive, Fallible, OffsetError, Serialize};
+use core::marker::PhantomData;
+
+/// A variant of [`Archive`] that archives a field of type `F` with a wrapper.
+///
+/// The wrapper determines the archived type and resolver of the field, which
+/// don't need to be the same as the field type's own.
+pub trait ArchiveWith<F> {
+    /// The archived type of the field when archived with this wrapper.
+    type Archived;
+    /// The resolver of the field when archived with this wrapper.
+    type Resolver;
+
+    /// Creates the archived version of the field at the given position.
+    ///
+    /// Returns an error if a relative pointer in the archived field can't
+    /// reach its target from the given position.
+    fn resolve_with(
+        field: &F,
+        pos: usize,
+        resolver: Self::Resolver,
+    ) -> Result<Self::Archived, OffsetError>;
+}
+
+/// A variant of [`Serialize`] that serializes a field of type `F` with a
+/// wrapper.
+pub trait SerializeWith<F, S: Fallible + ?Sized>: ArchiveWith<F> {
+    /// Writes the dependencies of the field and returns a resolver that can
+    /// create its archived type.
+    fn serialize_with(field: &F, serializer: &mut S) -> Result<Self::Resolver, S::Error>;
+}
+
+/// A variant of [`Deserialize`](crate::Deserialize) that deserializes an
+/// archived field of type `F` into a `T` with a wrapper.
+pub trait DeserializeWith<F, T, D: Fallible + ?Sized> {
+    /// Deserializes the archived field using the given deserializer.
+    fn deserialize_with(field: &F, deserializer: &mut D) -> Result<T, D::Error>;
+}
+
+/// A field of type `F` that is archived with the wrapper `W`.
+///
+/// `With` has the same layout as `F`, so a reference to a field can be cast to
+/// a reference to a `With` and archived like any other value:
+///
+/// ```
+/// use rkyv::{
+///     archived_value,
+///     ser::{Serializer, serializers::WriteSerializer},
+///     with::{Raw, With},
+/// };
+///
+/// let bytes = vec![1u8, 2, 3, 4];
+///
+/// let mut serializer = WriteSerializer::new(Vec::new());
+/// let pos = serializer.serialize_value(With::<_, Raw>::cast(&bytes))
+///     .expect("failed to archive bytes");
+/// let buf = serializer.into_inner();
+///
+/// let archived = unsafe { archived_value::<With<Vec<u8>, Raw>>(buf.as_ref(), pos) };
+/// assert_eq!(archived.as_slice(), bytes.as_slice());
+/// ```
+#[repr(transparent)]
+pub struct With<F, W> {
+    _phantom: PhantomData<W>,
+    field: F,
+}
+
+impl<F, W> With<F, W> {
+    /// Casts a reference to a field into a reference to a `With`.
+    pub fn cast(field: &F) -> &Self {
+        // With is repr(transparent) over F
+        unsafe { &*(field as *const F).cast::<Self>() }
+    }
+
+    /// Unwraps the field.
+    pub fn into_inner(self) -> F {
+        self.field
+    }
+}
+
+impl<F, W: ArchiveWith<F>> Archive for With<F, W> {
+    type Archived = W::Archived;
+    type Resolver = W::Resolver;
+
+    fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        W::resolve_with(&self.field, pos, resolver)
+    }
+}
+
+impl<F, W: SerializeWith<F, S>, S: Fallible + ?Sized> Serialize<S> for With<F, W> {
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        W::serialize_with(&self.field, serializer)
+    }
+}
+
+/// A wrapper that archives a reference as if it were the value it points to.
+///
+/// The referenced value is archived in place of the reference instead of
+/// behind a relative pointer. Because the original value is borrowed, fields
+/// archived with `Inline` can't be deserialized.
+///
+/// ## Example
+///
+/// ```
+/// use rkyv::{with::Inline, Archive, Serialize};
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example<'a> {
+///     #[with(Inline)]
+///     value: &'a i32,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Inline;
+
+impl<F: Archive> ArchiveWith<&F> for Inline {
+    type Archived = F::Archived;
+    type Resolver = F::Resolver;
+
+    fn resolve_with(
+        field: &&F,
+        pos: usize,
+        resolver: Self::Resolver,
+    ) -> Result<Self::Archived, OffsetError> {
+        (*field).resolve(pos, resolver)
+    }
+}
+
+impl<F: Serialize<S>, S: Fallible + ?Sized> SerializeWith<&F, S> for Inline {
+    fn serialize_with(field: &&F, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        (*field).serialize(serializer)
+    }
+}
+
+/// A wrapper that skips archiving a field.
+///
+/// The field is archived as a `()`, and deserialized as the default value of
+/// its type.
+///
+/// ## Example
+///
+/// ```
+/// use rkyv::{with::Skip, Archive, Deserialize, Serialize};
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// struct Example {
+///     #[with(Skip)]
+///     cache: Option<u64>,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Skip;
+
+impl<F> ArchiveWith<F> for Skip {
+    type Archived = ();
+    type Resolver = ();
+
+    fn resolve_with(_: &F, _: usize, _: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        Ok(())
+    }
+}
+
+impl<F, S: Fallible + ?Sized> SerializeWith<F, S> for Skip {
+    fn serialize_with(_: &F, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(())
+    }
+}
+
+impl<F: Default, D: Fallible + ?Sized> DeserializeWith<(), F, D> for Skip {
+    fn deserialize_with(_: &(), _: &mut D) -> Result<F, D::Error> {
+        Ok(F::default())
+    }
+}
+
+/// A [`Copy`] type that can be archived as a bitwise copy of itself with
+/// [`AsCopy`].
+///
+/// # Safety
+///
+/// The type must not contain any pointers, references, or interior mutability,
+/// and must have a stable layout (e.g. `#[repr(C)]`). A copy of its bytes at any
+/// position in an archive must be usable as a value of the type.
+pub unsafe trait CopySafe: Copy {}
+
+macro_rules! impl_copy_safe {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            unsafe impl CopySafe for $ty {}
+        )*
+    };
+}
+
+impl_copy_safe!(
+    (),
+    bool,
+    char,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    f32,
+    f64,
+);
+
+/// A wrapper that archives a [`CopySafe`] value as a bitwise copy of itself,
+/// as if it implemented [`ArchiveCopy`](crate::ArchiveCopy).
+///
+/// This can be used to archive types that don't implement [`Archive`]. The
+/// value is archived in its native representation, so it will not be converted
+/// to the byte order selected by the `archive_le` or `archive_be` features.
+///
+/// ## Example
+///
+/// ```
+/// use rkyv::{
+///     with::{AsCopy, CopySafe},
+///     Archive, Deserialize, Serialize,
+/// };
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Color {
+///     r: u8,
+///     g: u8,
+///     b: u8,
+/// }
+///
+/// // Color only contains bytes and has a stable layout
+/// unsafe impl CopySafe for Color {}
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// struct Example {
+///     #[with(AsCopy)]
+///     color: Color,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AsCopy;
+
+impl<F: CopySafe> ArchiveWith<F> for AsCopy {
+    type Archived = F;
+    type Resolver = ();
+
+    fn resolve_with(field: &F, _: usize, _: Self::Resolver) -> Result<Self::Archived, OffsetError> {
+        Ok(*field)
+    }
+}
+
+impl<F: CopySafe, S: Fallible + ?Sized> SerializeWith<F, S> for AsCopy {
+    fn serialize_with(_: &F, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(())
+    }
+}
+
+impl<F: CopySafe, D: Fallible + ?Sized> DeserializeWith<F, F, D> for AsCopy {
+    fn deserialize_with(field: &F, _: &mut D) -> Result<F, D::Error> {
+        Ok(*field)
+    }
+}
+
+/// A wrapper that archives an `Option` using a niche in its value instead of a
+/// separate tag.
+///
+/// Options of nonzero integers are archived as the types in
+/// [`core_impl::niche`](crate::core_impl::niche), which use zero to represent
+/// `None`. Options of `Box`, `String` and `Vec` are archived as the types in
+/// [`std_impl::niche`](crate::std_impl::niche), which use a null relative
+/// pointer. Either way, the archived option is the same size as the archived
+/// value.
+///
+/// ## Example
+///
+/// ```
+/// use core::num::NonZeroU32;
+/// use rkyv::{with::Niche, Archive, Deserialize, Serialize};
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// struct Example {
+///     #[with(Niche)]
+///     id: Option<NonZeroU32>,
+///     #[with(Niche)]
+///     name: Option<String>,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Niche;
+
+/// A wrapper that archives an `Rc` or `Arc` without sharing its value.
+///
+/// The value is archived as if it were in a `Box`, so serializing doesn't
+/// require a [`SharedSerializer`](crate::ser::SharedSerializer) and
+/// deserializing creates a new shared pointer for each field.
+///
+/// ## Example
+///
+/// ```
+/// use rkyv::{with::Unshare, Archive, Deserialize, Serialize};
+/// use std::rc::Rc;
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// struct Example {
+///     #[with(Unshare)]
+///     value: Rc<String>,
+/// }
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct Unshare;
+
+/// A wrapper that archives a `Vec` of [`ArchiveCopy`](crate::ArchiveCopy) values by copying its
+/// bytes directly into the archive.
+///
+/// The archived field is the same [`ArchivedVec`](crate::std_impl::ArchivedVec)
+/// as normal, but it's written with a single copy instead of serializing each
+/// element individually. This is most useful for byte buffers.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct Raw;
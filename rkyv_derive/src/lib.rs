@@ -2,11 +2,12 @@
 
 extern crate proc_macro;
 
-use proc_macro2::{Span, TokenStream};
+use proc_macro2::{Literal, Span, TokenStream};
 use quote::{quote, quote_spanned};
 use syn::{
-    parse_macro_input, spanned::Spanned, AttrStyle, Data, DeriveInput, Error, Fields, Ident, Index,
-    Lit, Meta, MetaList, NestedMeta,
+    parse_macro_input, punctuated::Punctuated, spanned::Spanned, AttrStyle, Data, DataEnum,
+    DataStruct, DeriveInput, Error, Field, Fields, FieldsNamed, GenericParam, Ident, Index, Lit,
+    LitStr, Meta, MetaList, NestedMeta, Path, PathArguments, Token, Type, WherePredicate,
 };
 
 struct Repr {
@@ -14,7 +15,7 @@ struct Repr {
     transparent: Option<Span>,
     packed: Option<Span>,
     c: Option<Span>,
-    int: Option<Span>,
+    int: Option<Ident>,
 }
 
 impl Default for Repr {
@@ -31,20 +32,40 @@ impl Default for Repr {
 
 struct Attributes {
     copy: Option<Span>,
+    evolvable: Option<Span>,
     repr: Repr,
     derives: Option<MetaList>,
+    compare: Option<MetaList>,
+    diff: Option<Span>,
+    archive_repr: Option<(Ident, Span)>,
     archived: Option<(Ident, Span)>,
     resolver: Option<(Ident, Span)>,
+    remote: Option<(Path, Span)>,
+    archive_bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    serialize_bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    deserialize_bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    debug_bound: Option<Punctuated<WherePredicate, Token![,]>>,
+    check_bytes_bound: Option<LitStr>,
 }
 
 impl Default for Attributes {
     fn default() -> Self {
         Self {
             copy: None,
+            evolvable: None,
             repr: Default::default(),
             derives: None,
+            compare: None,
+            diff: None,
+            archive_repr: None,
             archived: None,
             resolver: None,
+            remote: None,
+            archive_bound: None,
+            serialize_bound: None,
+            deserialize_bound: None,
+            debug_bound: None,
+            check_bytes_bound: None,
         }
     }
 }
@@ -69,6 +90,26 @@ fn parse_attributes(input: &DeriveInput) -> Result<Attributes, TokenStream> {
                                             )
                                             .to_compile_error());
                                         }
+                                    } else if path.is_ident("evolvable") {
+                                        if result.evolvable.is_none() {
+                                            result.evolvable = Some(path.span());
+                                        } else {
+                                            return Err(Error::new(
+                                                meta.span(),
+                                                "evolvable already specified",
+                                            )
+                                            .to_compile_error());
+                                        }
+                                    } else if path.is_ident("diff") {
+                                        if result.diff.is_none() {
+                                            result.diff = Some(path.span());
+                                        } else {
+                                            return Err(Error::new(
+                                                meta.span(),
+                                                "diff already specified",
+                                            )
+                                            .to_compile_error());
+                                        }
                                     } else {
                                         return Err(Error::new(
                                             path.span(),
@@ -80,6 +121,27 @@ fn parse_attributes(input: &DeriveInput) -> Result<Attributes, TokenStream> {
                                 Meta::List(meta) => {
                                     if meta.path.is_ident("derive") {
                                         result.derives = Some(meta.clone());
+                                    } else if meta.path.is_ident("compare") {
+                                        if result.compare.is_none() {
+                                            result.compare = Some(meta.clone());
+                                        } else {
+                                            return Err(Error::new(
+                                                meta.span(),
+                                                "compare already specified",
+                                            )
+                                            .to_compile_error());
+                                        }
+                                    } else if meta.path.is_ident("repr") {
+                                        if result.archive_repr.is_some() {
+                                            return Err(Error::new(
+                                                meta.span(),
+                                                "repr already specified",
+                                            )
+                                            .to_compile_error());
+                                        }
+                                        result.archive_repr = Some(parse_archive_repr(meta)?);
+                                    } else if meta.path.is_ident("bound") {
+                                        parse_bounds(&mut result, meta)?;
                                     } else {
                                         return Err(Error::new(
                                             meta.path.span(),
@@ -131,6 +193,27 @@ fn parse_attributes(input: &DeriveInput) -> Result<Attributes, TokenStream> {
                                             )
                                             .to_compile_error());
                                         }
+                                    } else if meta.path.is_ident("remote") {
+                                        if let Lit::Str(ref lit_str) = meta.lit {
+                                            if result.remote.is_none() {
+                                                let path = lit_str
+                                                    .parse::<Path>()
+                                                    .map_err(|e| e.to_compile_error())?;
+                                                result.remote = Some((path, lit_str.span()));
+                                            } else {
+                                                return Err(Error::new(
+                                                    meta.span(),
+                                                    "remote already specified",
+                                                )
+                                                .to_compile_error());
+                                            }
+                                        } else {
+                                            return Err(Error::new(
+                                                meta.span(),
+                                                "remote must be a string",
+                                            )
+                                            .to_compile_error());
+                                        }
                                     } else {
                                         return Err(Error::new(
                                             meta.span(),
@@ -154,9 +237,180 @@ fn parse_attributes(input: &DeriveInput) -> Result<Attributes, TokenStream> {
                             } else if path.is_ident("C") {
                                 result.repr.c = Some(path.span());
                             } else {
-                                result.repr.int = Some(path.span());
+                                result.repr.int = path.get_ident().cloned();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if let Some(ref bound) = result.debug_bound {
+        if derives_debug(&result) {
+            return Err(Error::new(
+                bound.span(),
+                "debug bound can't be used with a derived Debug for the archived type",
+            )
+            .to_compile_error());
+        }
+    }
+    if let Some(ref bound) = result.check_bytes_bound {
+        if !derives_trait(&result, "CheckBytes") {
+            return Err(Error::new(
+                bound.span(),
+                "check_bytes bound requires deriving CheckBytes for the archived type",
+            )
+            .to_compile_error());
+        }
+    }
+    Ok(result)
+}
+
+const ARCHIVE_REPRS: [&str; 9] = ["u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "C"];
+
+fn parse_archive_repr(meta: &MetaList) -> Result<(Ident, Span), TokenStream> {
+    let mut nested = meta.nested.iter();
+    if let (Some(NestedMeta::Meta(Meta::Path(path))), None) = (nested.next(), nested.next()) {
+        if let Some(repr) = path.get_ident() {
+            if ARCHIVE_REPRS.iter().any(|r| repr == r) {
+                return Ok((repr.clone(), meta.span()));
+            }
+        }
+    }
+    Err(Error::new(
+        meta.span(),
+        "archive repr must be one of u8, u16, u32, u64, i8, i16, i32, i64, or C",
+    )
+    .to_compile_error())
+}
+
+fn parse_bounds(result: &mut Attributes, meta: &MetaList) -> Result<(), TokenStream> {
+    for n in meta.nested.iter() {
+        if let NestedMeta::Meta(Meta::NameValue(meta)) = n {
+            if meta.path.is_ident("check_bytes") {
+                if result.check_bytes_bound.is_some() {
+                    return Err(
+                        Error::new(meta.span(), "bound already specified").to_compile_error()
+                    );
+                }
+                if let Lit::Str(ref lit_str) = meta.lit {
+                    result.check_bytes_bound = Some(lit_str.clone());
+                    continue;
+                } else {
+                    return Err(
+                        Error::new(meta.span(), "bound must be a string").to_compile_error()
+                    );
+                }
+            }
+            let bound = if meta.path.is_ident("archive") {
+                &mut result.archive_bound
+            } else if meta.path.is_ident("serialize") {
+                &mut result.serialize_bound
+            } else if meta.path.is_ident("deserialize") {
+                &mut result.deserialize_bound
+            } else if meta.path.is_ident("debug") {
+                &mut result.debug_bound
+            } else {
+                return Err(
+                    Error::new(meta.path.span(), "unrecognized bound parameter").to_compile_error()
+                );
+            };
+            if bound.is_some() {
+                return Err(Error::new(meta.span(), "bound already specified").to_compile_error());
+            }
+            if let Lit::Str(ref lit_str) = meta.lit {
+                *bound = Some(
+                    lit_str
+                        .parse_with(Punctuated::parse_terminated)
+                        .map_err(|e| e.to_compile_error())?,
+                );
+            } else {
+                return Err(Error::new(meta.span(), "bound must be a string").to_compile_error());
+            }
+        } else {
+            return Err(Error::new(n.span(), "unrecognized bound parameter").to_compile_error());
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct FieldAttributes {
+    with: Option<Type>,
+    getter: Option<Path>,
+    skip: Option<Span>,
+    default: Option<Path>,
+    since: Option<(u32, Span)>,
+    omit_bounds: Option<Span>,
+}
+
+fn parse_field_attributes(field: &Field) -> Result<FieldAttributes, Error> {
+    let mut result = FieldAttributes::default();
+    for a in field.attrs.iter() {
+        if a.path.is_ident("with") {
+            if result.with.is_some() {
+                return Err(Error::new(a.span(), "with already specified"));
+            }
+            result.with = Some(a.parse_args::<Type>()?);
+        } else if a.path.is_ident("omit_bounds") {
+            if result.omit_bounds.is_some() {
+                return Err(Error::new(a.span(), "omit_bounds already specified"));
+            }
+            result.omit_bounds = Some(a.span());
+        } else if a.path.is_ident("recursive") {
+            return Err(Error::new(
+                a.span(),
+                "#[recursive] has been renamed to #[omit_bounds]",
+            ));
+        } else if a.path.is_ident("archive") {
+            if let Meta::List(meta) = a.parse_meta()? {
+                for n in meta.nested.iter() {
+                    match n {
+                        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => {
+                            if result.skip.is_some() {
+                                return Err(Error::new(path.span(), "skip already specified"));
+                            }
+                            result.skip = Some(path.span());
+                        }
+                        NestedMeta::Meta(Meta::NameValue(meta))
+                            if meta.path.is_ident("default") =>
+                        {
+                            if let Lit::Str(ref lit_str) = meta.lit {
+                                if result.default.is_some() {
+                                    return Err(Error::new(
+                                        meta.span(),
+                                        "default already specified",
+                                    ));
+                                }
+                                result.default = Some(lit_str.parse::<Path>()?);
+                            } else {
+                                return Err(Error::new(meta.span(), "default must be a string"));
+                            }
+                        }
+                        NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("since") => {
+                            if let Lit::Int(ref lit_int) = meta.lit {
+                                if result.since.is_some() {
+                                    return Err(Error::new(meta.span(), "since already specified"));
+                                }
+                                result.since = Some((lit_int.base10_parse::<u32>()?, meta.span()));
+                            } else {
+                                return Err(Error::new(meta.span(), "since must be an integer"));
+                            }
+                        }
+                        NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("getter") => {
+                            if let Lit::Str(ref lit_str) = meta.lit {
+                                if result.getter.is_some() {
+                                    return Err(Error::new(
+                                        meta.span(),
+                                        "getter already specified",
+                                    ));
+                                }
+                                result.getter = Some(lit_str.parse::<Path>()?);
+                            } else {
+                                return Err(Error::new(meta.span(), "getter must be a string"));
                             }
                         }
+                        _ => return Err(Error::new(n.span(), "unrecognized archive parameter")),
                     }
                 }
             }
@@ -165,24 +419,370 @@ fn parse_attributes(input: &DeriveInput) -> Result<Attributes, TokenStream> {
     Ok(result)
 }
 
+fn field_attributes(field: &Field) -> FieldAttributes {
+    parse_field_attributes(field).unwrap_or_default()
+}
+
+fn all_fields(input: &DeriveInput) -> Vec<&Field> {
+    match input.data {
+        Data::Struct(ref data) => data.fields.iter().collect(),
+        Data::Enum(ref data) => data.variants.iter().flat_map(|v| v.fields.iter()).collect(),
+        Data::Union(_) => Vec::new(),
+    }
+}
+
+fn check_field_attributes(input: &DeriveInput, attributes: &Attributes) -> Result<(), TokenStream> {
+    let mut errors = TokenStream::new();
+    for field in all_fields(input) {
+        match parse_field_attributes(field) {
+            Ok(field_attributes) => {
+                if let Some(default) = &field_attributes.default {
+                    if field_attributes.skip.is_none() && field_attributes.since.is_none() {
+                        errors.extend(
+                            Error::new(
+                                default.span(),
+                                "default can only be used on skipped or evolved fields",
+                            )
+                            .to_compile_error(),
+                        );
+                    }
+                }
+                if let Some((since, span)) = field_attributes.since {
+                    if attributes.evolvable.is_none() {
+                        errors.extend(
+                            Error::new(span, "since can only be used in evolvable structs")
+                                .to_compile_error(),
+                        );
+                    } else if field_attributes.skip.is_some() {
+                        errors.extend(
+                            Error::new(span, "skipped fields can't be evolved").to_compile_error(),
+                        );
+                    } else if since == 0 {
+                        errors.extend(
+                            Error::new(span, "since must be at least 1").to_compile_error(),
+                        );
+                    }
+                }
+                if let Some(span) = field_attributes.skip {
+                    if field_attributes.with.is_some() || field_attributes.getter.is_some() {
+                        errors.extend(
+                            Error::new(span, "skipped fields can't have wrappers or getters")
+                                .to_compile_error(),
+                        );
+                    }
+                }
+                if let Some(getter) = field_attributes.getter {
+                    if attributes.remote.is_none() {
+                        errors.extend(
+                            Error::new(
+                                getter.span(),
+                                "getters can only be used with remote derives",
+                            )
+                            .to_compile_error(),
+                        );
+                    } else if let Data::Enum(_) = input.data {
+                        errors.extend(
+                            Error::new(getter.span(), "getters can't be used on enum fields")
+                                .to_compile_error(),
+                        );
+                    }
+                }
+            }
+            Err(error) => errors.extend(error.to_compile_error()),
+        }
+    }
+    if let Some(span) = attributes.evolvable {
+        errors.extend(check_evolvable(input, attributes, span));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks that an evolvable struct only adds fields at the end of its newest
+/// version.
+fn check_evolvable(input: &DeriveInput, attributes: &Attributes, span: Span) -> TokenStream {
+    let fields = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(ref fields),
+            ..
+        }) => fields,
+        _ => {
+            return Error::new(
+                span,
+                "evolvable can only be used on structs with named fields",
+            )
+            .to_compile_error()
+        }
+    };
+    if attributes.copy.is_some() {
+        return Error::new(span, "archive copy types can't be evolvable").to_compile_error();
+    }
+    if attributes.remote.is_some() {
+        return Error::new(span, "remote types can't be evolvable").to_compile_error();
+    }
+    if let Some(ref compare) = attributes.compare {
+        return Error::new(compare.span(), "evolvable types can't derive comparisons")
+            .to_compile_error();
+    }
+
+    let mut errors = TokenStream::new();
+    let mut latest = 0;
+    for field in fields.named.iter().filter(|f| !is_skipped(f)) {
+        let since = since(field);
+        if since < latest {
+            errors.extend(
+                Error::new(
+                    field.span(),
+                    format!(
+                        "this field must come before the fields added in version {}",
+                        latest
+                    ),
+                )
+                .to_compile_error(),
+            );
+        } else {
+            latest = since;
+        }
+    }
+    errors
+}
+
+fn is_skipped(field: &Field) -> bool {
+    field_attributes(field).skip.is_some()
+}
+
+fn omits_bounds(field: &Field) -> bool {
+    field_attributes(field).omit_bounds.is_some()
+}
+
+/// Gets the attributes added to the archived type for the derives given with
+/// `#[archive(derive(...))]`.
+fn archive_derives(attributes: &Attributes) -> TokenStream {
+    if let Some(derives) = attributes.derives.as_ref() {
+        let check_bytes_bound = attributes
+            .check_bytes_bound
+            .as_ref()
+            .map(|bound| quote! { #[check_bytes(bound = #bound)] });
+        quote! {
+            #[#derives]
+            #check_bytes_bound
+        }
+    } else {
+        quote! {}
+    }
+}
+
+/// Gets the attributes added to a field of the archived type. Fields that omit
+/// their bounds also omit them from a derived `CheckBytes` impl.
+fn archived_field_attrs(attributes: &Attributes, field: &Field) -> TokenStream {
+    if omits_bounds(field) && derives_trait(attributes, "CheckBytes") {
+        quote! { #[omit_bounds] }
+    } else {
+        quote! {}
+    }
+}
+
+/// Gets the version of an evolvable struct that added a field.
+fn since(field: &Field) -> u32 {
+    field_attributes(field).since.map_or(0, |(since, _)| since)
+}
+
+/// Gets the value a skipped field is deserialized as.
+fn default_value(field: &Field) -> TokenStream {
+    match field_attributes(field).default {
+        Some(default) => quote_spanned! { field.span() => #default() },
+        None => quote_spanned! { field.span() => Default::default() },
+    }
+}
+
+/// Gets the type a field is archived as, which is wrapped in `With` if the
+/// field has a `#[with(...)]` attribute.
+fn archive_type(field: &Field) -> TokenStream {
+    let ty = &field.ty;
+    match field_attributes(field).with {
+        Some(with) => quote_spanned! { field.span() => rkyv::with::With<#ty, #with> },
+        None => quote_spanned! { field.span() => #ty },
+    }
+}
+
+/// Casts a reference to a field to a reference to its archive type.
+fn archive_ref(field: &Field, value: TokenStream) -> TokenStream {
+    let ty = &field.ty;
+    match field_attributes(field).with {
+        Some(with) => {
+            quote_spanned! { field.span() => rkyv::with::With::<#ty, #with>::cast(#value) }
+        }
+        None => value,
+    }
+}
+
+/// Gets a reference to a field of a value, calling the field's getter if it
+/// has one.
+fn field_ref(field: &Field, this: &TokenStream, member: TokenStream) -> TokenStream {
+    match field_attributes(field).getter {
+        Some(getter) => {
+            let ty = &field.ty;
+            quote_spanned! { field.span() => core::borrow::Borrow::<#ty>::borrow(&#getter(#this)) }
+        }
+        None => quote! { &#this.#member },
+    }
+}
+
+/// Gets the path of a remote type without generic arguments so it can be used
+/// in patterns.
+fn pattern_path(remote: &Path) -> TokenStream {
+    let mut path = remote.clone();
+    if let Some(segment) = path.segments.last_mut() {
+        segment.arguments = PathArguments::None;
+    }
+    quote! { #path }
+}
+
+/// Gets the where clause predicates of the labeled type followed by the custom
+/// bounds given for a derived trait.
+fn generic_predicates(
+    input: &DeriveInput,
+    bound: &Option<Punctuated<WherePredicate, Token![,]>>,
+) -> TokenStream {
+    let predicates = input
+        .generics
+        .where_clause
+        .iter()
+        .flat_map(|clause| clause.predicates.iter())
+        .chain(bound.iter().flat_map(|bound| bound.iter()));
+    quote! { #(#predicates,)* }
+}
+
+fn generic_args(input: &DeriveInput) -> TokenStream {
+    let generic_args = input.generics.params.iter().map(|p| match p {
+        GenericParam::Type(p) => {
+            let name = &p.ident;
+            quote_spanned! { name.span() => #name }
+        }
+        GenericParam::Lifetime(p) => {
+            let lifetime = &p.lifetime;
+            quote_spanned! { lifetime.span() => #lifetime }
+        }
+        GenericParam::Const(p) => {
+            let name = &p.ident;
+            quote_spanned! { name.span() => #name }
+        }
+    });
+    quote! { #(#generic_args,)* }
+}
+
 /// Derives `Archive` for the labeled type.
 ///
 /// Additional arguments can be specified using the `#[archive(...)]` attribute:
 ///
 /// - `copy`: Implements `ArchiveCopy` as well as `Archive`. Only suitable for
-/// types that can be directly archived.
+///   types that can be directly archived.
+/// - `bound(archive = "...", serialize = "...", deserialize = "...")`: Adds the
+///   given where clause predicates to the derived `Archive`, `Serialize`, and
+///   `Deserialize` impls. The archive bounds are also added to the archived type,
+///   so they apply to traits derived for it like `CheckBytes`. Serialize bounds
+///   can refer to the serializer as `__S`, and deserialize bounds can refer to the
+///   deserializer as `__D`. `check_bytes = "..."` adds the given predicates to a
+///   `CheckBytes` impl derived for the archived type, which can refer to the
+///   validation context as `__C`. `debug = "..."` adds the given predicates to the
+///   `Debug` impl of the archived type.
+/// - `compare(...)`: Implements comparisons between the archived type and the
+///   labeled type in both directions. Supports `PartialEq` and `PartialOrd`, which
+///   compare each field of the archived type with the same field of the labeled
+///   type. Skipped fields are not compared.
 /// - `derive(...)`: Adds a `#[derive(...)]` attribute to the archived type.
+/// - `diff`: Implements `ArchiveDiff` for the archived type, which reports the
+///   paths of the fields that differ between two archived values. Changes to the
+///   variant of an enum are reported with the names of the variants. Requires the
+///   `std` feature of `rkyv`. See the `diff` module in `rkyv` for more details.
+/// - `evolvable`: Lets new fields be added to a struct with named fields
+///   without breaking archives written by older versions of it. See below for
+///   details.
 /// - `name`, `name = "..."`: Exposes the archived type with the given name. If
-/// used without a name assignment, uses the name `"Archived" + name`.
+///   used without a name assignment, uses the name `"Archived" + name`.
+/// - `repr(...)`: Sets the representation of an archived enum. Supports `u8`,
+///   `u16`, `u32`, `u64`, `i8`, `i16`, `i32`, `i64`, and `C`. Without it, the
+///   integer repr of the labeled enum is used if it has one of these, and
+///   otherwise the smallest unsigned integer that can hold every variant. Explicit
+///   discriminants are only kept in archived enums with one of these reprs, so
+///   their layout matches an enum with the same repr and discriminants. Derives
+///   on the archived type like `CheckBytes` see the same repr and discriminants.
+///   With the `archive_le` and `archive_be` features, multi-byte tags are stored
+///   in the archive's byte order.
+/// - `remote = "..."`: Derives for a type defined in another crate. The labeled
+///   type must have the same fields as the remote type, and implements
+///   `ArchiveWith`, `SerializeWith`, and `DeserializeWith` for it instead of
+///   `Archive`, `Serialize`, and `Deserialize` for itself. Deserializing requires
+///   the remote type to implement `From` the labeled type. Use the labeled type
+///   as a wrapper on fields of the remote type with `#[with(...)]`.
+///
+/// Fields can be left out of the archive by adding `#[archive(skip)]` to them.
+/// Skipped fields take up no space in the archived type, and are deserialized
+/// with `Default::default()`. Use `#[archive(skip, default = "...")]` to
+/// deserialize them with the given function instead.
+///
+/// Fields added to an evolvable struct are marked with `#[archive(since = N)]`,
+/// where `N` is the version of the struct that added them. They must come after
+/// all of the fields of older versions. The fields of each newer version are
+/// archived out of line, and the archived struct has a method for each of them
+/// that returns `None` if the archive was written by an older version. A
+/// `..._pin` method does the same for pinned mutable references. Deserializing
+/// fills in missing fields with `Default::default()`, or with the function given
+/// by `#[archive(since = N, default = "...")]`. Archives written by newer
+/// versions can still be read by older ones, which ignore the newer fields. See
+/// the `evolve` module in `rkyv` for more details.
+///
+/// Private fields of a remote struct can be read with a getter function by
+/// adding `#[archive(getter = "...")]` to the field. The getter takes a
+/// reference to the remote type and returns the field by value or by reference.
 ///
 /// This derive macro automatically adds a type bound `field: Archive` for each
 /// field type. This can cause an overflow while evaluating trait bounds if the
 /// structure eventually references its own type, as the implementation of
 /// `Archive` for a struct depends on each field type implementing it as well.
-/// Adding the attribute `#[recursive]` to a field will suppress this trait
-/// bound and allow recursive structures. This may be too coarse for some types,
-/// in which case `Archive` will have to be implemented manually.
-#[proc_macro_derive(Archive, attributes(archive, recursive))]
+/// Adding the attribute `#[omit_bounds]` to a field will suppress its trait
+/// bounds and allow recursive structures. This attribute was previously named
+/// `#[recursive]`. The bounds that the field still needs can then be added back
+/// with `#[archive(bound(...))]`:
+///
+/// ```ignore
+/// #[derive(Archive, Serialize, Deserialize)]
+/// #[archive(bound(serialize = "__S: Serializer", deserialize = "__D: Deserializer"))]
+/// enum Node<T> {
+///     Nil,
+///     Cons(T, #[omit_bounds] Box<Node<T>>),
+/// }
+/// ```
+///
+/// The archived type gets safe pin projections for mutating archived values in
+/// place. Archived structs have a method for each field that takes
+/// `self: Pin<&mut Self>` and returns a `Pin<&mut Archived<T>>` for the field.
+/// The methods have the same names as the fields, or `field_0`, `field_1`, etc.
+/// for tuple structs. Archived enums have a `project` method that returns an
+/// enum named after the archived type with a `Projection` suffix, which holds
+/// the pinned fields of the current variant. To keep projections sound, the
+/// archived type is only `Unpin` if all of its fields are and must not implement
+/// `Drop`.
+///
+/// Adding the attribute `#[with(...)]` to a field archives it with the given
+/// wrapper type instead of its own `Archive` implementation. See the `with`
+/// module in `rkyv` for the wrapper types that are provided.
+///
+/// The archived type implements `Debug` when the archived types of all of its
+/// fields do, and prints with the name and fields of the labeled type. Skipped
+/// fields are left out, as are evolvable fields missing from the archive. Fields
+/// with `#[omit_bounds]` don't add bounds to the impl, so any that they need must
+/// be added with `#[archive(bound(debug = "..."))]`. Adding `Debug` to
+/// `#[archive(derive(...))]` derives it for the archived type instead.
+///
+/// With the `schema` feature, the archived type also implements `Schema` when
+/// the archived types of all of its fields do. Archive copy types implement it
+/// for themselves, except for enums with fields. See the `schema` module in
+/// `rkyv` for more details.
+#[proc_macro_derive(Archive, attributes(archive, omit_bounds, recursive, with))]
 pub fn archive_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -191,6 +791,10 @@ pub fn archive_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         Err(errors) => return proc_macro::TokenStream::from(errors),
     };
 
+    if let Err(errors) = check_field_attributes(&input, &attributes) {
+        return proc_macro::TokenStream::from(errors);
+    }
+
     let archive_impl = if attributes.copy.is_some() {
         derive_archive_copy_impl(&input, &attributes)
     } else {
@@ -211,25 +815,24 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
         .map(|p| quote_spanned! { p.span() => #p });
     let generic_params = quote! { #(#generic_params,)* };
 
-    let generic_args = input.generics.type_params().map(|p| {
-        let name = &p.ident;
-        quote_spanned! { name.span() => #name }
-    });
-    let generic_args = quote! { #(#generic_args,)* };
+    let generic_args = generic_args(input);
 
-    let generic_predicates = match input.generics.where_clause {
-        Some(ref clause) => {
-            let predicates = clause.predicates.iter().map(|p| quote! { #p });
-            quote! { #(#predicates,)* }
+    let phantom_args = input.generics.params.iter().map(|p| match p {
+        GenericParam::Lifetime(p) => {
+            let lifetime = &p.lifetime;
+            quote_spanned! { lifetime.span() => &#lifetime () }
         }
-        None => quote! {},
-    };
+        GenericParam::Type(p) => {
+            let name = &p.ident;
+            quote_spanned! { name.span() => #name }
+        }
+        GenericParam::Const(_) => quote! { () },
+    });
+    let phantom_args = quote! { #(#phantom_args,)* };
 
-    let archive_derives = if let Some(derives) = attributes.derives.as_ref() {
-        quote! { #[#derives] }
-    } else {
-        quote! {}
-    };
+    let generic_predicates = generic_predicates(input, &attributes.archive_bound);
+
+    let archive_derives = archive_derives(attributes);
 
     let archived = if let Some((ref archived, _)) = attributes.archived {
         archived.clone()
@@ -243,40 +846,67 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
         Ident::new(&format!("{}Resolver", name), name.span())
     };
 
+    let (archive_trait, this, value_path) = match attributes.remote {
+        Some((ref remote, _)) => (
+            quote! { rkyv::with::ArchiveWith<#remote> },
+            quote! { field },
+            pattern_path(remote),
+        ),
+        None => (quote! { Archive }, quote! { self }, quote! { #name }),
+    };
+    let resolve_fn = |pos: TokenStream, resolver: TokenStream| match attributes.remote {
+        Some((ref remote, _)) => quote! {
+            fn resolve_with(field: &#remote, #pos: usize, #resolver: Self::Resolver) -> Result<Self::Archived, rkyv::OffsetError>
+        },
+        None => quote! {
+            fn resolve(&self, #pos: usize, #resolver: Self::Resolver) -> Result<Self::Archived, rkyv::OffsetError>
+        },
+    };
+    let resolve = resolve_fn(quote! { pos }, quote! { resolver });
+
     #[cfg(feature = "strict")]
     let strict = quote! { #[repr(C)] };
     #[cfg(not(feature = "strict"))]
     let strict = quote! {};
 
+    if let (Data::Struct(_), Some((_, span))) = (&input.data, &attributes.archive_repr) {
+        return Error::new(*span, "archive repr can only be used on enums").to_compile_error();
+    }
+
     let (archive_types, archive_impls) = match input.data {
         Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) if attributes.evolvable.is_some() => {
+                derive_evolvable_archive(input, attributes, fields, &archived, &resolver)
+            }
             Fields::Named(ref fields) => {
                 let archive_predicates = fields.named.iter().filter_map(|f| {
-                    if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                    if is_skipped(f) || omits_bounds(f) {
                         None
                     } else {
-                        let ty = &f.ty;
+                        let ty = archive_type(f);
                         Some(quote_spanned! { f.span() => #ty: rkyv::Archive })
                     }
                 });
                 let archive_predicates = quote! { #(#archive_predicates,)* };
 
-                let resolver_fields = fields.named.iter().map(|f| {
+                let resolver_fields = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                     let name = &f.ident;
-                    let ty = &f.ty;
+                    let ty = archive_type(f);
                     quote_spanned! { f.span() => #name: rkyv::Resolver<#ty> }
                 });
 
-                let archived_fields = fields.named.iter().map(|f| {
+                let archived_fields = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                     let name = &f.ident;
-                    let ty = &f.ty;
+                    let ty = archive_type(f);
                     let vis = &f.vis;
-                    quote_spanned! { f.span() => #vis #name: rkyv::Archived<#ty> }
+                    let field_attrs = archived_field_attrs(attributes, f);
+                    quote_spanned! { f.span() => #field_attrs #vis #name: rkyv::Archived<#ty> }
                 });
 
-                let archived_values = fields.named.iter().map(|f| {
+                let archived_values = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                     let name = &f.ident;
-                    quote_spanned! { f.span() => #name: self.#name.resolve(pos + offset_of!(#archived<#generic_args>, #name), resolver.#name) }
+                    let field = archive_ref(f, field_ref(f, &this, quote! { #name }));
+                    quote_spanned! { f.span() => #name: Archive::resolve(#field, pos + offset_of!(#archived<#generic_args>, #name), resolver.#name)? }
                 });
 
                 (
@@ -300,7 +930,7 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                         }
                     },
                     quote! {
-                        impl<#generic_params> Archive for #name<#generic_args>
+                        impl<#generic_params> #archive_trait for #name<#generic_args>
                         where
                             #generic_predicates
                             #archive_predicates
@@ -308,10 +938,10 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                             type Archived = #archived<#generic_args>;
                             type Resolver = #resolver<#generic_args>;
 
-                            fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
-                                Self::Archived {
+                            #resolve {
+                                Ok(Self::Archived {
                                     #(#archived_values,)*
-                                }
+                                })
                             }
                         }
                     },
@@ -319,29 +949,32 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
             }
             Fields::Unnamed(ref fields) => {
                 let archive_predicates = fields.unnamed.iter().filter_map(|f| {
-                    if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                    if is_skipped(f) || omits_bounds(f) {
                         None
                     } else {
-                        let ty = &f.ty;
+                        let ty = archive_type(f);
                         Some(quote_spanned! { f.span() => #ty: rkyv::Archive })
                     }
                 });
                 let archive_predicates = quote! { #(#archive_predicates,)* };
 
-                let resolver_fields = fields.unnamed.iter().map(|f| {
-                    let ty = &f.ty;
+                let resolver_fields = fields.unnamed.iter().filter(|f| !is_skipped(f)).map(|f| {
+                    let ty = archive_type(f);
                     quote_spanned! { f.span() => rkyv::Resolver<#ty> }
                 });
 
-                let archived_fields = fields.unnamed.iter().map(|f| {
-                    let ty = &f.ty;
+                let archived_fields = fields.unnamed.iter().filter(|f| !is_skipped(f)).map(|f| {
+                    let ty = archive_type(f);
                     let vis = &f.vis;
-                    quote_spanned! { f.span() => #vis rkyv::Archived<#ty> }
+                    let field_attrs = archived_field_attrs(attributes, f);
+                    quote_spanned! { f.span() => #field_attrs #vis rkyv::Archived<#ty> }
                 });
 
-                let archived_values = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                let archived_values = fields.unnamed.iter().enumerate().filter(|(_, f)| !is_skipped(f)).enumerate().map(|(j, (i, f))| {
                     let index = Index::from(i);
-                    quote_spanned! { f.span() => self.#index.resolve(pos + offset_of!(#archived<#generic_args>, #index), resolver.#index) }
+                    let archived_index = Index::from(j);
+                    let field = archive_ref(f, field_ref(f, &this, quote! { #index }));
+                    quote_spanned! { f.span() => Archive::resolve(#field, pos + offset_of!(#archived<#generic_args>, #archived_index), resolver.#archived_index)? }
                 });
 
                 (
@@ -359,7 +992,7 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                             #archive_predicates;
                     },
                     quote! {
-                        impl<#generic_params> Archive for #name<#generic_args>
+                        impl<#generic_params> #archive_trait for #name<#generic_args>
                         where
                             #generic_predicates
                             #archive_predicates
@@ -367,50 +1000,66 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                             type Archived = #archived<#generic_args>;
                             type Resolver = #resolver<#generic_args>;
 
-                            fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
-                                #archived::<#generic_args>(
+                            #resolve {
+                                Ok(#archived::<#generic_args>(
                                     #(#archived_values,)*
-                                )
+                                ))
                             }
                         }
                     },
                 )
             }
-            Fields::Unit => (
-                quote! {
-                    #archive_derives
-                    #strict
-                    #vis struct #archived<#generic_params>
-                    where
-                        #generic_predicates;
+            Fields::Unit => {
+                let unit_resolve = resolve_fn(quote! { _pos }, quote! { _resolver });
+                (
+                    quote! {
+                        #archive_derives
+                        #strict
+                        #vis struct #archived<#generic_params>
+                        where
+                            #generic_predicates;
 
-                    #vis struct #resolver<#generic_params>
-                    where
-                        #generic_predicates;
-                },
-                quote! {
-                    impl<#generic_params> Archive for #name<#generic_args>
-                    where
-                        #generic_predicates
-                    {
-                        type Archived = #archived<#generic_args>;
-                        type Resolver = #resolver<#generic_args>;
+                        #vis struct #resolver<#generic_params>
+                        where
+                            #generic_predicates;
+                    },
+                    quote! {
+                        impl<#generic_params> #archive_trait for #name<#generic_args>
+                        where
+                            #generic_predicates
+                        {
+                            type Archived = #archived<#generic_args>;
+                            type Resolver = #resolver<#generic_args>;
 
-                        fn resolve(&self, _pos: usize, _resolver: Self::Resolver) -> Self::Archived {
-                            #archived::<#generic_args>
+                            #unit_resolve {
+                                Ok(#archived::<#generic_args>)
+                            }
                         }
-                    }
-                },
-            ),
+                    },
+                )
+            }
         },
         Data::Enum(ref data) => {
+            // repr(C) enums put the fields of each variant in a union after the
+            // tag instead of in a struct that starts with the tag
+            let repr_c = match attributes.archive_repr {
+                Some((ref repr, _)) => repr == "C",
+                None => false,
+            };
+            let payload_offset = if repr_c {
+                quote! { offset_of!(ArchivedVariantRepr<#generic_args>, __payload) + }
+            } else {
+                quote! {}
+            };
+            let first_field = if repr_c { 0 } else { 1 };
+
             let archive_predicates = data.variants.iter().map(|v| match v.fields {
                 Fields::Named(ref fields) => {
                     let archive_predicates = fields.named.iter().filter_map(|f| {
-                        if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                        if is_skipped(f) || omits_bounds(f) {
                             None
                         } else {
-                            let ty = &f.ty;
+                            let ty = archive_type(f);
                             Some(quote_spanned! { f.span() => #ty: rkyv::Archive })
                         }
                     });
@@ -418,10 +1067,10 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                 }
                 Fields::Unnamed(ref fields) => {
                     let archive_predicates = fields.unnamed.iter().filter_map(|f| {
-                        if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                        if is_skipped(f) || omits_bounds(f) {
                             None
                         } else {
-                            let ty = &f.ty;
+                            let ty = archive_type(f);
                             Some(quote_spanned! { f.span() => #ty: rkyv::Archive })
                         }
                     });
@@ -435,9 +1084,9 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                 let variant = &v.ident;
                 match v.fields {
                     Fields::Named(ref fields) => {
-                        let fields = fields.named.iter().map(|f| {
+                        let fields = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                             let name = &f.ident;
-                            let ty = &f.ty;
+                            let ty = archive_type(f);
                             quote_spanned! { f.span() => #name: rkyv::Resolver<#ty> }
                         });
                         quote_spanned! { variant.span() =>
@@ -447,8 +1096,8 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                         }
                     }
                     Fields::Unnamed(ref fields) => {
-                        let fields = fields.unnamed.iter().map(|f| {
-                            let ty = &f.ty;
+                        let fields = fields.unnamed.iter().filter(|f| !is_skipped(f)).map(|f| {
+                            let ty = archive_type(f);
                             quote_spanned! { f.span() => rkyv::Resolver<#ty> }
                         });
                         quote_spanned! { variant.span() =>
@@ -466,48 +1115,58 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                     Fields::Named(ref fields) => {
                         let self_bindings = fields.named.iter().map(|f| {
                             let name = &f.ident;
-                            let binding = Ident::new(&format!("self_{}", name.as_ref().unwrap().to_string()), name.span());
-                            quote_spanned! { name.span() => #name: #binding }
+                            if is_skipped(f) {
+                                quote_spanned! { name.span() => #name: _ }
+                            } else {
+                                let binding = Ident::new(&format!("self_{}", name.as_ref().unwrap().to_string()), name.span());
+                                quote_spanned! { name.span() => #name: #binding }
+                            }
                         });
-                        let resolver_bindings = fields.named.iter().map(|f| {
+                        let resolver_bindings = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                             let name = &f.ident;
                             let binding = Ident::new(&format!("resolver_{}", name.as_ref().unwrap().to_string()), name.span());
                             quote_spanned! { binding.span() => #name: #binding }
                         });
-                        let fields = fields.named.iter().map(|f| {
+                        let fields = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                             let name = &f.ident;
                             let self_binding = Ident::new(&format!("self_{}", name.as_ref().unwrap().to_string()), name.span());
                             let resolver_binding = Ident::new(&format!("resolver_{}", name.as_ref().unwrap().to_string()), name.span());
+                            let field = archive_ref(f, quote! { #self_binding });
                             quote! {
-                                #name: #self_binding.resolve(pos + offset_of!(#archived_variant_name<#generic_args>, #name), #resolver_binding)
+                                #name: Archive::resolve(#field, pos + #payload_offset offset_of!(#archived_variant_name<#generic_args>, #name), #resolver_binding)?
                             }
                         });
                         quote_spanned! { name.span() =>
                             #resolver::#variant { #(#resolver_bindings,)* } => {
-                                if let #name::#variant { #(#self_bindings,)* } = self { #archived::#variant { #(#fields,)* } } else { panic!("enum resolver variant does not match value variant") }
+                                if let #value_path::#variant { #(#self_bindings,)* } = #this { #archived::#variant { #(#fields,)* } } else { panic!("enum resolver variant does not match value variant") }
                             }
                         }
                     }
                     Fields::Unnamed(ref fields) => {
                         let self_bindings = fields.unnamed.iter().enumerate().map(|(i, f)| {
-                            let name = Ident::new(&format!("self_{}", i), f.span());
-                            quote_spanned! { f.span() => #name }
+                            if is_skipped(f) {
+                                quote_spanned! { f.span() => _ }
+                            } else {
+                                let name = Ident::new(&format!("self_{}", i), f.span());
+                                quote_spanned! { f.span() => #name }
+                            }
                         });
-                        let resolver_bindings = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                        let resolver_bindings = fields.unnamed.iter().enumerate().filter(|(_, f)| !is_skipped(f)).map(|(i, f)| {
                             let name = Ident::new(&format!("resolver_{}", i), f.span());
                             quote_spanned! { f.span() => #name }
                         });
-                        let fields = fields.unnamed.iter().enumerate().map(|(i, f)| {
-                            let index = Index::from(i + 1);
+                        let fields = fields.unnamed.iter().enumerate().filter(|(_, f)| !is_skipped(f)).enumerate().map(|(j, (i, f))| {
+                            let index = Index::from(j + first_field);
                             let self_binding = Ident::new(&format!("self_{}", i), f.span());
                             let resolver_binding = Ident::new(&format!("resolver_{}", i), f.span());
+                            let field = archive_ref(f, quote! { #self_binding });
                             quote! {
-                                #self_binding.resolve(pos + offset_of!(#archived_variant_name<#generic_args>, #index), #resolver_binding)
+                                Archive::resolve(#field, pos + #payload_offset offset_of!(#archived_variant_name<#generic_args>, #index), #resolver_binding)?
                             }
                         });
                         quote_spanned! { name.span() =>
                             #resolver::#variant( #(#resolver_bindings,)* ) => {
-                                if let #name::#variant(#(#self_bindings,)*) = self { #archived::#variant(#(#fields,)*) } else { panic!("enum resolver variant does not match value variant") }
+                                if let #value_path::#variant(#(#self_bindings,)*) = #this { #archived::#variant(#(#fields,)*) } else { panic!("enum resolver variant does not match value variant") }
                             }
                         }
                     }
@@ -515,57 +1174,73 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                 }
             });
 
-            let archived_repr = match data.variants.len() {
-                0..=255 => quote! { u8 },
-                256..=65_535 => quote! { u16 },
-                65_536..=4_294_967_295 => quote! { u32 },
-                4_294_967_296..=18_446_744_073_709_551_615 => quote! { u64 },
-                _ => quote! { u128 },
+            let archived_repr = archived_repr(attributes, data);
+            let archived_tag_repr = match swapped_tag_type(attributes, data) {
+                Some(ty) if repr_c => ty,
+                _ => archived_repr.clone(),
             };
 
-            let archived_variants = data.variants.iter().map(|v| {
+            let discriminants = archived_discriminants(attributes, data)
+                .into_iter()
+                .map(|d| match d {
+                    Some(discriminant) => quote! { = #discriminant },
+                    None => quote! {},
+                })
+                .collect::<Vec<_>>();
+
+            let archived_variants = data.variants.iter().enumerate().map(|(i, v)| {
                 let variant = &v.ident;
+                let discriminant = &discriminants[i];
                 match v.fields {
                     Fields::Named(ref fields) => {
-                        let fields = fields.named.iter().map(|f| {
+                        let fields = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                             let name = &f.ident;
-                            let ty = &f.ty;
+                            let ty = archive_type(f);
                             let vis = &f.vis;
-                            quote_spanned! { f.span() => #vis #name: rkyv::Archived<#ty> }
+                            let field_attrs = archived_field_attrs(attributes, f);
+                            quote_spanned! { f.span() => #field_attrs #vis #name: rkyv::Archived<#ty> }
                         });
                         quote_spanned! { variant.span() =>
                             #variant {
                                 #(#fields,)*
-                            }
+                            } #discriminant
                         }
                     }
                     Fields::Unnamed(ref fields) => {
-                        let fields = fields.unnamed.iter().map(|f| {
-                            let ty = &f.ty;
+                        let fields = fields.unnamed.iter().filter(|f| !is_skipped(f)).map(|f| {
+                            let ty = archive_type(f);
                             let vis = &f.vis;
-                            quote_spanned! { f.span() => #vis rkyv::Archived<#ty> }
+                            let field_attrs = archived_field_attrs(attributes, f);
+                            quote_spanned! { f.span() => #field_attrs #vis rkyv::Archived<#ty> }
                         });
                         quote_spanned! { variant.span() =>
-                            #variant(#(#fields,)*)
+                            #variant(#(#fields,)*) #discriminant
                         }
                     }
-                    Fields::Unit => quote_spanned! { variant.span() => #variant },
+                    Fields::Unit => quote_spanned! { variant.span() => #variant #discriminant },
                 }
             });
 
-            let archived_variant_tags = data.variants.iter().map(|v| {
+            let archived_variant_tags = data.variants.iter().enumerate().map(|(i, v)| {
                 let variant = &v.ident;
-                quote_spanned! { variant.span() => #variant }
+                let discriminant = &discriminants[i];
+                quote_spanned! { variant.span() => #variant #discriminant }
             });
 
+            let (tag_field, unnamed_tag_field) = if repr_c {
+                (quote! {}, quote! {})
+            } else {
+                (quote! { __tag: ArchivedTag, }, quote! { ArchivedTag, })
+            };
+
             let archived_variant_structs = data.variants.iter().map(|v| {
                 let variant = &v.ident;
                 let archived_variant_name = Ident::new(&format!("ArchivedVariant{}", variant.to_string()), v.span());
                 match v.fields {
                     Fields::Named(ref fields) => {
-                        let fields = fields.named.iter().map(|f| {
+                        let fields = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                             let name = &f.ident;
-                            let ty = &f.ty;
+                            let ty = archive_type(f);
                             quote_spanned! { f.span() => #name: rkyv::Archived<#ty> }
                         });
                         quote_spanned! { name.span() =>
@@ -575,20 +1250,20 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                                 #generic_predicates
                                 #archive_predicates
                             {
-                                __tag: ArchivedTag,
+                                #tag_field
                                 #(#fields,)*
-                                __phantom: PhantomData<(#generic_args)>,
+                                __phantom: PhantomData<(#phantom_args)>,
                             }
                         }
                     }
                     Fields::Unnamed(ref fields) => {
-                        let fields = fields.unnamed.iter().map(|f| {
-                            let ty = &f.ty;
+                        let fields = fields.unnamed.iter().filter(|f| !is_skipped(f)).map(|f| {
+                            let ty = archive_type(f);
                             quote_spanned! { f.span() => rkyv::Archived<#ty> }
                         });
                         quote_spanned! { name.span() =>
                             #[repr(C)]
-                            struct #archived_variant_name<#generic_params>(ArchivedTag, #(#fields,)* PhantomData<(#generic_args)>)
+                            struct #archived_variant_name<#generic_params>(#unnamed_tag_field #(#fields,)* PhantomData<(#phantom_args)>)
                             where
                                 #generic_predicates
                                 #archive_predicates;
@@ -598,6 +1273,43 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                 }
             });
 
+            let payload_variants = data
+                .variants
+                .iter()
+                .filter(|v| !matches!(v.fields, Fields::Unit))
+                .enumerate()
+                .map(|(i, v)| {
+                    let variant = &v.ident;
+                    let archived_variant_name = Ident::new(&format!("ArchivedVariant{}", variant), v.span());
+                    let field = Ident::new(&format!("__variant_{}", i), v.span());
+                    quote! { #field: core::mem::ManuallyDrop<#archived_variant_name<#generic_args>> }
+                })
+                .collect::<Vec<_>>();
+            let archived_variant_repr = if repr_c && !payload_variants.is_empty() {
+                quote! {
+                    #[repr(C)]
+                    union ArchivedVariantPayload<#generic_params>
+                    where
+                        #generic_predicates
+                        #archive_predicates
+                    {
+                        #(#payload_variants,)*
+                    }
+
+                    #[repr(C)]
+                    struct ArchivedVariantRepr<#generic_params>
+                    where
+                        #generic_predicates
+                        #archive_predicates
+                    {
+                        __tag: ArchivedTag,
+                        __payload: ArchivedVariantPayload<#generic_args>,
+                    }
+                }
+            } else {
+                quote! {}
+            };
+
             (
                 quote! {
                     #archive_derives
@@ -610,54 +1322,1505 @@ fn derive_archive_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStr
                         #(#archived_variants,)*
                     }
 
-                    #vis enum #resolver<#generic_params>
-                    where
-                        #generic_predicates
-                        #archive_predicates
-                    {
-                        #(#resolver_variants,)*
-                    }
-                },
+                    #vis enum #resolver<#generic_params>
+                    where
+                        #generic_predicates
+                        #archive_predicates
+                    {
+                        #(#resolver_variants,)*
+                    }
+                },
+                quote! {
+                    #[repr(#archived_tag_repr)]
+                    enum ArchivedTag {
+                        #(#archived_variant_tags,)*
+                    }
+
+                    #(#archived_variant_structs)*
+
+                    #archived_variant_repr
+
+                    impl<#generic_params> #archive_trait for #name<#generic_args>
+                    where
+                        #generic_predicates
+                        #archive_predicates
+                    {
+                        type Archived = #archived<#generic_args>;
+                        type Resolver = #resolver<#generic_args>;
+
+                        #resolve {
+                            Ok(match resolver {
+                                #(#resolve_arms,)*
+                            })
+                        }
+                    }
+                },
+            )
+        }
+        Data::Union(_) => {
+            return Error::new(input.span(), "Archive cannot be derived for unions")
+                .to_compile_error()
+        }
+    };
+
+    let compare_impls = match attributes.compare {
+        Some(ref compare) => derive_compare_impls(input, attributes, &archived, compare),
+        None => quote! {},
+    };
+
+    let (projection_types, projection_impls) = derive_projections(input, attributes, &archived);
+
+    let debug_impls = if derives_debug(attributes) {
+        quote! {}
+    } else {
+        derive_debug_impls(input, attributes, &archived)
+    };
+
+    let diff_impls = if attributes.diff.is_some() {
+        derive_diff_impls(input, attributes, &archived)
+    } else {
+        quote! {}
+    };
+
+    let schema_impls = if cfg!(feature = "schema") {
+        derive_schema_impls(input, attributes, &archived)
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        #archive_types
+        #projection_types
+
+        const _: () = {
+            use core::marker::PhantomData;
+            use rkyv::{Archive, offset_of};
+
+            #archive_impls
+            #compare_impls
+            #debug_impls
+            #diff_impls
+            #projection_impls
+            #schema_impls
+        };
+    }
+}
+
+/// Gets the repr given to an archived enum, either with `#[archive(repr(...))]`
+/// or with an integer `#[repr(...)]` on the labeled enum.
+fn enum_archive_repr(attributes: &Attributes) -> Option<&Ident> {
+    match attributes.archive_repr {
+        Some((ref repr, _)) => Some(repr),
+        None => attributes
+            .repr
+            .int
+            .as_ref()
+            .filter(|int| ARCHIVE_REPRS.iter().any(|r| *int == r && *r != "C")),
+    }
+}
+
+/// Gets the repr of an archived enum, which is the smallest unsigned integer
+/// that can hold every variant unless one was given for it.
+fn archived_repr(attributes: &Attributes, data: &DataEnum) -> TokenStream {
+    match enum_archive_repr(attributes) {
+        Some(repr) if repr == "C" && swapped_tag_type(attributes, data).is_some() => {
+            if data
+                .variants
+                .iter()
+                .all(|v| matches!(v.fields, Fields::Unit))
+            {
+                quote! { i32 }
+            } else {
+                quote! { C, i32 }
+            }
+        }
+        Some(repr) => quote! { #repr },
+        None => match data.variants.len() {
+            0..=255 => quote! { u8 },
+            256..=65_535 => quote! { u16 },
+            65_536..=4_294_967_295 => quote! { u32 },
+            4_294_967_296..=18_446_744_073_709_551_615 => quote! { u64 },
+            _ => quote! { u128 },
+        },
+    }
+}
+
+/// Gets the type that the tags of an archived enum are swapped as to give them
+/// a fixed endianness, or `None` if they don't need to be swapped.
+///
+/// Single-byte tags are the same in either byte order. The tags of repr(C)
+/// enums are given an `i32` repr so that they can be swapped.
+fn swapped_tag_type(attributes: &Attributes, data: &DataEnum) -> Option<TokenStream> {
+    if !cfg!(any(feature = "archive_le", feature = "archive_be")) {
+        return None;
+    }
+    match enum_archive_repr(attributes) {
+        Some(repr) if repr == "C" => Some(quote! { i32 }),
+        Some(repr) if repr == "u8" || repr == "i8" => None,
+        Some(repr) => Some(quote! { #repr }),
+        None if data.variants.len() <= 256 => None,
+        None => Some(archived_repr(attributes, data)),
+    }
+}
+
+/// Gets the discriminants to give each variant of an archived enum.
+///
+/// Explicit discriminants are kept in the archived enum if it was given a repr.
+/// Otherwise they might not fit in the archived repr, so the variants are
+/// numbered from zero instead. With the `archive_le` or `archive_be` features,
+/// multi-byte tags are byte-swapped so that they're stored in the archive's byte
+/// order. The logical value of each tag can be read back with
+/// [`archived_tag_value`].
+fn archived_discriminants(attributes: &Attributes, data: &DataEnum) -> Vec<Option<TokenStream>> {
+    let swapped = swapped_tag_type(attributes, data);
+    let keep_discriminants = enum_archive_repr(attributes).is_some();
+
+    let mut base = None;
+    let mut offset = 0usize;
+    data.variants
+        .iter()
+        .map(|v| {
+            let discriminant = v.discriminant.as_ref().filter(|_| keep_discriminants);
+            if let Some((_, ref expr)) = discriminant {
+                base = Some(expr);
+                offset = 0;
+            } else {
+                offset += 1;
+            }
+
+            match swapped {
+                Some(ref ty) => {
+                    let value = match base {
+                        Some(expr) => {
+                            let offset = Literal::usize_unsuffixed(offset);
+                            quote! { (#expr) + #offset }
+                        }
+                        None => {
+                            let index = Literal::usize_unsuffixed(offset - 1);
+                            quote! { #index }
+                        }
+                    };
+                    if cfg!(feature = "archive_le") {
+                        Some(quote! { #ty::to_le(#value) })
+                    } else {
+                        Some(quote! { #ty::to_be(#value) })
+                    }
+                }
+                None => discriminant.map(|(_, expr)| quote! { #expr }),
+            }
+        })
+        .collect()
+}
+
+/// Gets the logical value of the tag of an archived enum variant as an `i128`.
+fn archived_tag_value(attributes: &Attributes, data: &DataEnum, tag: TokenStream) -> TokenStream {
+    match swapped_tag_type(attributes, data) {
+        Some(ty) => {
+            if cfg!(feature = "archive_le") {
+                quote! { #ty::from_le(#tag as #ty) as i128 }
+            } else {
+                quote! { #ty::from_be(#tag as #ty) as i128 }
+            }
+        }
+        None => quote! { #tag as i128 },
+    }
+}
+
+/// Gets the fields of an evolvable struct grouped by the version that added
+/// them. The fields of the first version are in version 0.
+fn evolvable_versions(fields: &FieldsNamed) -> Vec<(u32, Vec<&Field>)> {
+    let mut versions = vec![(0, Vec::new())];
+    for field in fields.named.iter().filter(|f| !is_skipped(f)) {
+        let since = since(field);
+        match versions.last_mut() {
+            Some((version, fields)) if *version == since => fields.push(field),
+            _ => versions.push((since, vec![field])),
+        }
+    }
+    versions
+}
+
+/// Gets the name of the archived fields added in a version of an evolvable
+/// struct.
+fn evolved_archived(archived: &Ident, version: u32) -> Ident {
+    if version == 0 {
+        archived.clone()
+    } else {
+        Ident::new(&format!("{}V{}", archived, version), archived.span())
+    }
+}
+
+/// Gets the type of the evolution that links a version of an evolvable struct
+/// to the fields of the version after it.
+fn evolution_type(
+    versions: &[(u32, Vec<&Field>)],
+    index: usize,
+    archived: &Ident,
+    generic_args: &TokenStream,
+) -> TokenStream {
+    match versions.get(index + 1) {
+        Some((version, _)) => {
+            let next = evolved_archived(archived, *version);
+            quote! { rkyv::evolve::ArchivedEvolution<#next<#generic_args>> }
+        }
+        None => quote! { rkyv::evolve::ArchivedFutureEvolution },
+    }
+}
+
+fn derive_evolvable_archive(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    fields: &FieldsNamed,
+    archived: &Ident,
+    resolver: &Ident,
+) -> (TokenStream, TokenStream) {
+    let name = &input.ident;
+    let vis = &input.vis;
+
+    let generic_params = input
+        .generics
+        .params
+        .iter()
+        .map(|p| quote_spanned! { p.span() => #p });
+    let generic_params = quote! { #(#generic_params,)* };
+
+    let generic_args = generic_args(input);
+
+    let phantom_args = input.generics.params.iter().map(|p| match p {
+        GenericParam::Lifetime(p) => {
+            let lifetime = &p.lifetime;
+            quote_spanned! { lifetime.span() => &#lifetime () }
+        }
+        GenericParam::Type(p) => {
+            let name = &p.ident;
+            quote_spanned! { name.span() => #name }
+        }
+        GenericParam::Const(_) => quote! { () },
+    });
+    let phantom_args = quote! { #(#phantom_args,)* };
+
+    let generic_predicates = generic_predicates(input, &attributes.archive_bound);
+
+    let archive_derives = archive_derives(attributes);
+
+    let archive_predicates = fields.named.iter().filter_map(|f| {
+        if is_skipped(f) || omits_bounds(f) {
+            None
+        } else {
+            let ty = archive_type(f);
+            Some(quote_spanned! { f.span() => #ty: rkyv::Archive })
+        }
+    });
+    let archive_predicates = quote! { #(#archive_predicates,)* };
+
+    let versions = evolvable_versions(fields);
+    let base_fields = &versions[0].1;
+
+    let archived_types = versions.iter().enumerate().map(|(i, (version, fields))| {
+        let evolution = evolution_type(&versions, i, archived, &generic_args);
+        if i == 0 {
+            let archived_fields = fields.iter().map(|f| {
+                let name = &f.ident;
+                let ty = archive_type(f);
+                let vis = &f.vis;
+                let field_attrs = archived_field_attrs(attributes, f);
+                quote_spanned! { f.span() => #field_attrs #vis #name: rkyv::Archived<#ty> }
+            });
+            quote! {
+                #archive_derives
+                #[repr(C)]
+                #vis struct #archived<#generic_params>
+                where
+                    #generic_predicates
+                    #archive_predicates
+                {
+                    #(#archived_fields,)*
+                    __evolution: #evolution,
+                }
+            }
+        } else {
+            let evolved = evolved_archived(archived, *version);
+            let archived_fields = fields.iter().map(|f| {
+                let name = &f.ident;
+                let ty = archive_type(f);
+                let field_attrs = archived_field_attrs(attributes, f);
+                quote_spanned! { f.span() => #field_attrs #name: rkyv::Archived<#ty> }
+            });
+            let doc = format!(
+                "The fields added to [`{}`] in version {}.",
+                archived, version
+            );
+            quote! {
+                #[doc = #doc]
+                #archive_derives
+                #[repr(C)]
+                #vis struct #evolved<#generic_params>
+                where
+                    #generic_predicates
+                    #archive_predicates
+                {
+                    #(#archived_fields,)*
+                    __evolution: #evolution,
+                    __phantom: core::marker::PhantomData<(#phantom_args)>,
+                }
+            }
+        }
+    });
+
+    let resolver_fields = base_fields.iter().map(|f| {
+        let name = &f.ident;
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => #name: rkyv::Resolver<#ty> }
+    });
+    let resolver_evolution = if versions.len() > 1 {
+        quote! { __evolution: usize, }
+    } else {
+        quote! {}
+    };
+
+    let archived_values = base_fields.iter().map(|f| {
+        let name = &f.ident;
+        let field = archive_ref(f, quote! { &self.#name });
+        quote_spanned! { f.span() => #name: Archive::resolve(#field, pos + offset_of!(#archived<#generic_args>, #name), resolver.#name)? }
+    });
+    let evolution_value = if versions.len() > 1 {
+        quote! {
+            rkyv::evolve::ArchivedEvolution::resolve_from_pos(
+                pos + offset_of!(#archived<#generic_args>, __evolution),
+                resolver.__evolution,
+            )?
+        }
+    } else {
+        quote! { rkyv::evolve::ArchivedFutureEvolution::absent() }
+    };
+
+    // Each accessor follows the evolutions up to the version that added its
+    // field, and returns None if any of them weren't written
+    let accessors = versions.iter().enumerate().skip(1).flat_map(|(i, (_, fields))| {
+        let steps = (1..i).map(|_| quote! { let fields = fields.__evolution.get()?; });
+        let steps = quote! { #(#steps)* };
+        let pin_steps = (1..i).map(|_| {
+            quote! { let fields = fields.map_unchecked_mut(|s| &mut s.__evolution).get_pin()?; }
+        });
+        let pin_steps = quote! { #(#pin_steps)* };
+        fields.iter().map(move |f| {
+            let name = &f.ident;
+            let pin_name = Ident::new(
+                &format!("{}_pin", f.ident.as_ref().unwrap()),
+                f.span(),
+            );
+            let ty = archive_type(f);
+            let vis = &f.vis;
+            quote! {
+                #vis fn #name(&self) -> Option<&rkyv::Archived<#ty>> {
+                    let fields = self.__evolution.get()?;
+                    #steps
+                    Some(&fields.#name)
+                }
+
+                #vis fn #pin_name(self: core::pin::Pin<&mut Self>) -> Option<core::pin::Pin<&mut rkyv::Archived<#ty>>> {
+                    unsafe {
+                        let fields = self.map_unchecked_mut(|s| &mut s.__evolution).get_pin()?;
+                        #pin_steps
+                        Some(fields.map_unchecked_mut(|s| &mut s.#name))
+                    }
+                }
+            }
+        })
+    });
+
+    // The projection impls forbid Drop on the archived type, and the newer
+    // fields are pinned the same way
+    let evolved_must_not_impl_drop = versions.iter().skip(1).map(|(version, _)| {
+        let evolved = evolved_archived(archived, *version);
+        quote! {
+            impl<#generic_params> ArchivedMustNotImplDrop for #evolved<#generic_args>
+            where
+                #generic_predicates
+                #archive_predicates
+            {
+            }
+        }
+    });
+
+    (
+        quote! {
+            #(#archived_types)*
+
+            #vis struct #resolver<#generic_params>
+            where
+                #generic_predicates
+                #archive_predicates
+            {
+                #(#resolver_fields,)*
+                #resolver_evolution
+                __phantom: core::marker::PhantomData<(#phantom_args)>,
+            }
+        },
+        quote! {
+            impl<#generic_params> Archive for #name<#generic_args>
+            where
+                #generic_predicates
+                #archive_predicates
+            {
+                type Archived = #archived<#generic_args>;
+                type Resolver = #resolver<#generic_args>;
+
+                fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Result<Self::Archived, rkyv::OffsetError> {
+                    Ok(Self::Archived {
+                        #(#archived_values,)*
+                        __evolution: #evolution_value,
+                    })
+                }
+            }
+
+            #[allow(dead_code)]
+            impl<#generic_params> #archived<#generic_args>
+            where
+                #generic_predicates
+                #archive_predicates
+            {
+                #(#accessors)*
+            }
+
+            #(#evolved_must_not_impl_drop)*
+        },
+    )
+}
+
+/// Generates safe pin projections for the fields of an archived type.
+///
+/// Projecting is only sound if the archived type doesn't implement `Drop` and
+/// is only `Unpin` when all of its fields are, so those impls are generated or
+/// forbidden as well.
+fn derive_projections(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+) -> (TokenStream, TokenStream) {
+    let vis = &input.vis;
+
+    let generic_params = input
+        .generics
+        .params
+        .iter()
+        .map(|p| quote_spanned! { p.span() => #p });
+    let generic_params = quote! { #(#generic_params,)* };
+
+    let generic_args = generic_args(input);
+
+    let generic_predicates = generic_predicates(input, &attributes.archive_bound);
+
+    // Fields added to evolvable structs are projected by their accessors
+    let fields = all_fields(input)
+        .into_iter()
+        .filter(|f| !is_skipped(f) && since(f) == 0)
+        .collect::<Vec<_>>();
+    if fields.is_empty() && attributes.evolvable.is_none() {
+        return (quote! {}, quote! {});
+    }
+
+    let archive_predicates = fields.iter().filter(|f| !omits_bounds(f)).map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => #ty: rkyv::Archive }
+    });
+    let archive_predicates = quote! { #(#archive_predicates,)* };
+    let origin_fields = fields.iter().map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => rkyv::Archived<#ty> }
+    });
+    // Evolvable structs hold a relative pointer to their newer fields
+    let origin_pinned = attributes
+        .evolvable
+        .map(|_| quote! { core::marker::PhantomPinned, });
+
+    let (projection_types, projection_fns) = match input.data {
+        Data::Struct(ref data) => {
+            let projection_fns = data
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| !is_skipped(f))
+                .enumerate()
+                .filter(|(_, (_, f))| since(f) == 0)
+                .map(|(j, (i, f))| {
+                    let ty = archive_type(f);
+                    let vis = &f.vis;
+                    let (fn_name, member) = match f.ident {
+                        Some(ref name) => (name.clone(), quote! { #name }),
+                        None => {
+                            let index = Index::from(j);
+                            (
+                                Ident::new(&format!("field_{}", i), f.span()),
+                                quote! { #index },
+                            )
+                        }
+                    };
+                    quote! {
+                        #vis fn #fn_name(self: Pin<&mut Self>) -> Pin<&mut rkyv::Archived<#ty>> {
+                            unsafe { self.map_unchecked_mut(|s| &mut s.#member) }
+                        }
+                    }
+                });
+            (quote! {}, quote! { #(#projection_fns)* })
+        }
+        Data::Enum(ref data) => {
+            let projection = Ident::new(&format!("{}Projection", archived), archived.span());
+
+            let projection_variants = data.variants.iter().map(|v| {
+                let variant = &v.ident;
+                let fields = v.fields.iter().filter(|f| !is_skipped(f)).map(|f| {
+                    let name = f.ident.as_ref().map(|name| quote! { #name: });
+                    let ty = archive_type(f);
+                    quote_spanned! { f.span() => #name core::pin::Pin<&'__a mut rkyv::Archived<#ty>> }
+                });
+                match v.fields {
+                    Fields::Named(_) => quote! { #variant { #(#fields,)* } },
+                    Fields::Unnamed(_) => quote! { #variant(#(#fields,)*) },
+                    Fields::Unit => quote! { #variant },
+                }
+            });
+
+            let project_arms = data.variants.iter().map(|v| {
+                let variant = &v.ident;
+                let fields = v.fields.iter().filter(|f| !is_skipped(f));
+                match v.fields {
+                    Fields::Named(_) => {
+                        let names = fields.clone().map(|f| &f.ident);
+                        let values = fields.map(|f| {
+                            let name = &f.ident;
+                            quote! { #name: Pin::new_unchecked(#name) }
+                        });
+                        quote! {
+                            #archived::#variant { #(#names,)* } => #projection::#variant { #(#values,)* }
+                        }
+                    }
+                    Fields::Unnamed(_) => {
+                        let bindings = fields
+                            .enumerate()
+                            .map(|(i, f)| Ident::new(&format!("field_{}", i), f.span()))
+                            .collect::<Vec<_>>();
+                        quote! {
+                            #archived::#variant(#(#bindings,)*) => #projection::#variant(#(Pin::new_unchecked(#bindings),)*)
+                        }
+                    }
+                    Fields::Unit => quote! { #archived::#variant => #projection::#variant },
+                }
+            });
+
+            let doc = format!(
+                "A projection of a pinned [`{}`] that pins the fields of its variant.",
+                archived
+            );
+
+            // Fields with omitted bounds may not have their lifetimes inferred
+            let outlives_predicates = input.generics.type_params().map(|p| {
+                let ident = &p.ident;
+                quote! { #ident: '__a }
+            });
+
+            (
+                quote! {
+                    #[doc = #doc]
+                    #[allow(dead_code)]
+                    #vis enum #projection<'__a, #generic_params>
+                    where
+                        #generic_predicates
+                        #archive_predicates
+                        #(#outlives_predicates,)*
+                    {
+                        #(#projection_variants,)*
+                    }
+                },
+                quote! {
+                    #vis fn project(self: Pin<&mut Self>) -> #projection<'_, #generic_args> {
+                        unsafe {
+                            match self.get_unchecked_mut() {
+                                #(#project_arms,)*
+                            }
+                        }
+                    }
+                },
+            )
+        }
+        Data::Union(_) => (quote! {}, quote! {}),
+    };
+
+    (
+        projection_types,
+        quote! {
+            use core::pin::Pin;
+
+            #[allow(dead_code)]
+            impl<#generic_params> #archived<#generic_args>
+            where
+                #generic_predicates
+                #archive_predicates
+            {
+                #projection_fns
+            }
+
+            // The archived type is only Unpin if all of its fields are. The
+            // lifetime keeps the bound from being checked for concrete types.
+            #[allow(dead_code)]
+            struct ArchivedUnpinOrigin<'__pin, #generic_params>(
+                PhantomData<&'__pin ()>,
+                #(#origin_fields,)*
+                #origin_pinned
+            )
+            where
+                #generic_predicates
+                #archive_predicates;
+
+            impl<'__pin, #generic_params> Unpin for #archived<#generic_args>
+            where
+                #generic_predicates
+                #archive_predicates
+                ArchivedUnpinOrigin<'__pin, #generic_args>: Unpin,
+            {
+            }
+
+            trait ArchivedMustNotImplDrop {}
+
+            #[allow(drop_bounds)]
+            impl<T: Drop> ArchivedMustNotImplDrop for T {}
+
+            impl<#generic_params> ArchivedMustNotImplDrop for #archived<#generic_args>
+            where
+                #generic_predicates
+                #archive_predicates
+            {
+            }
+        },
+    )
+}
+
+fn derive_compare_impls(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+    compare: &MetaList,
+) -> TokenStream {
+    let name = &input.ident;
+
+    let generic_params = input
+        .generics
+        .params
+        .iter()
+        .map(|p| quote_spanned! { p.span() => #p });
+    let generic_params = quote! { #(#generic_params,)* };
+
+    let generic_args = generic_args(input);
+
+    let generic_predicates = generic_predicates(input, &attributes.archive_bound);
+
+    let (other_ty, other_path) = match attributes.remote {
+        Some((ref remote, _)) => (quote! { #remote }, pattern_path(remote)),
+        None => (quote! { #name<#generic_args> }, quote! { #name }),
+    };
+    let other = quote! { other };
+
+    let fields = all_fields(input)
+        .into_iter()
+        .filter(|f| !is_skipped(f))
+        .collect::<Vec<_>>();
+    let archive_predicates = fields.iter().filter(|f| !omits_bounds(f)).map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => #ty: rkyv::Archive }
+    });
+    let archive_predicates = quote! { #(#archive_predicates,)* };
+    let compare_predicates = |compare_trait: TokenStream| {
+        let predicates = fields.iter().filter(|f| !omits_bounds(f)).map(|f| {
+            let ty = &f.ty;
+            let archive_ty = archive_type(f);
+            quote_spanned! { f.span() => rkyv::Archived<#archive_ty>: #compare_trait<#ty> }
+        });
+        quote! { #(#predicates,)* }
+    };
+
+    // Gets the pairs of archived and unarchived fields to compare
+    let struct_fields = |fields: &Fields| {
+        fields
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| !is_skipped(f))
+            .enumerate()
+            .map(|(j, (i, f))| match f.ident {
+                Some(ref name) => (
+                    quote! { &self.#name },
+                    field_ref(f, &other, quote! { #name }),
+                ),
+                None => {
+                    let index = Index::from(i);
+                    let archived_index = Index::from(j);
+                    (
+                        quote! { &self.#archived_index },
+                        field_ref(f, &other, quote! { #index }),
+                    )
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Gets the patterns for an archived and unarchived variant, and the pairs
+    // of fields they bind to compare
+    let variant_fields = |fields: &Fields| match fields {
+        Fields::Named(ref fields) => {
+            let fields = fields.named.iter().filter(|f| !is_skipped(f));
+            let self_bindings = fields.clone().map(|f| {
+                let name = f.ident.as_ref().unwrap();
+                Ident::new(&format!("self_{}", name), name.span())
+            });
+            let other_bindings = fields.clone().map(|f| {
+                let name = f.ident.as_ref().unwrap();
+                Ident::new(&format!("other_{}", name), name.span())
+            });
+            let names = fields.map(|f| &f.ident).collect::<Vec<_>>();
+            let pairs = self_bindings
+                .clone()
+                .zip(other_bindings.clone())
+                .map(|(a, b)| (quote! { #a }, quote! { #b }))
+                .collect::<Vec<_>>();
+            (
+                quote! { { #(#names: #self_bindings,)* } },
+                quote! { { #(#names: #other_bindings,)* .. } },
+                pairs,
+            )
+        }
+        Fields::Unnamed(ref fields) => {
+            let bindings = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                if is_skipped(f) {
+                    None
+                } else {
+                    Some((
+                        Ident::new(&format!("self_{}", i), f.span()),
+                        Ident::new(&format!("other_{}", i), f.span()),
+                    ))
+                }
+            });
+            let self_bindings = bindings.clone().flatten().map(|(a, _)| a);
+            let other_bindings = bindings.clone().map(|b| match b {
+                Some((_, b)) => quote! { #b },
+                None => quote! { _ },
+            });
+            let pairs = bindings
+                .flatten()
+                .map(|(a, b)| (quote! { #a }, quote! { #b }))
+                .collect::<Vec<_>>();
+            (
+                quote! { (#(#self_bindings,)*) },
+                quote! { (#(#other_bindings,)*) },
+                pairs,
+            )
+        }
+        Fields::Unit => (quote! {}, quote! {}, Vec::new()),
+    };
+
+    let mut result = TokenStream::new();
+    for meta in compare.nested.iter() {
+        let compare_trait = match meta {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("PartialEq") => path,
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("PartialOrd") => path,
+            _ => return Error::new(
+                meta.span(),
+                "unrecognized compare argument, supported compares are PartialEq and PartialOrd",
+            )
+            .to_compile_error(),
+        };
+
+        let predicates = compare_predicates(quote! { #compare_trait });
+
+        let (compare_fn, reverse_fn) = if compare_trait.is_ident("PartialEq") {
+            let body = match input.data {
+                Data::Struct(ref data) => {
+                    let pairs = struct_fields(&data.fields);
+                    let compares = pairs.iter().map(|(a, b)| quote! { PartialEq::eq(#a, #b) });
+                    quote! { true #(&& #compares)* }
+                }
+                Data::Enum(ref data) => {
+                    let arms = data.variants.iter().map(|v| {
+                        let variant = &v.ident;
+                        let (self_pattern, other_pattern, pairs) = variant_fields(&v.fields);
+                        let compares = pairs.iter().map(|(a, b)| quote! { PartialEq::eq(#a, #b) });
+                        quote! {
+                            (#archived::#variant #self_pattern, #other_path::#variant #other_pattern) => true #(&& #compares)*
+                        }
+                    });
+                    quote! {
+                        match (self, other) {
+                            #(#arms,)*
+                            #[allow(unreachable_patterns)]
+                            _ => false,
+                        }
+                    }
+                }
+                Data::Union(_) => unreachable!(),
+            };
+            (
+                quote! {
+                    fn eq(&self, other: &#other_ty) -> bool {
+                        #body
+                    }
+                },
+                quote! {
+                    fn eq(&self, other: &#archived<#generic_args>) -> bool {
+                        PartialEq::eq(other, self)
+                    }
+                },
+            )
+        } else {
+            let partial_cmp = |pairs: &[(TokenStream, TokenStream)]| {
+                let compares = pairs.iter().map(|(a, b)| {
+                    quote! {
+                        match PartialOrd::partial_cmp(#a, #b) {
+                            Some(core::cmp::Ordering::Equal) => (),
+                            cmp => return cmp,
+                        }
+                    }
+                });
+                quote! {
+                    #(#compares)*
+                    Some(core::cmp::Ordering::Equal)
+                }
+            };
+            let body = match input.data {
+                Data::Struct(ref data) => {
+                    let pairs = struct_fields(&data.fields);
+                    partial_cmp(&pairs)
+                }
+                Data::Enum(ref data) => {
+                    let self_discriminants = data.variants.iter().enumerate().map(|(i, v)| {
+                        let variant = &v.ident;
+                        quote! { #archived::#variant { .. } => #i }
+                    });
+                    let other_discriminants = data.variants.iter().enumerate().map(|(i, v)| {
+                        let variant = &v.ident;
+                        quote! { #other_path::#variant { .. } => #i }
+                    });
+                    let arms = data.variants.iter().map(|v| {
+                        let variant = &v.ident;
+                        let (self_pattern, other_pattern, pairs) = variant_fields(&v.fields);
+                        let body = partial_cmp(&pairs);
+                        quote! {
+                            (#archived::#variant #self_pattern, #other_path::#variant #other_pattern) => {
+                                #body
+                            }
+                        }
+                    });
+                    quote! {
+                        let self_discriminant = match self {
+                            #(#self_discriminants,)*
+                        };
+                        let other_discriminant = match other {
+                            #(#other_discriminants,)*
+                        };
+                        if self_discriminant != other_discriminant {
+                            return self_discriminant.partial_cmp(&other_discriminant);
+                        }
+                        match (self, other) {
+                            #(#arms,)*
+                            #[allow(unreachable_patterns)]
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+                Data::Union(_) => unreachable!(),
+            };
+            (
+                quote! {
+                    fn partial_cmp(&self, other: &#other_ty) -> Option<core::cmp::Ordering> {
+                        #body
+                    }
+                },
+                quote! {
+                    fn partial_cmp(&self, other: &#archived<#generic_args>) -> Option<core::cmp::Ordering> {
+                        PartialOrd::partial_cmp(other, self).map(core::cmp::Ordering::reverse)
+                    }
+                },
+            )
+        };
+
+        result.extend(quote! {
+            impl<#generic_params> #compare_trait<#other_ty> for #archived<#generic_args>
+            where
+                #generic_predicates
+                #archive_predicates
+                #predicates
+            {
+                #compare_fn
+            }
+
+            impl<#generic_params> #compare_trait<#archived<#generic_args>> for #other_ty
+            where
+                #generic_predicates
+                #archive_predicates
+                #predicates
+            {
+                #reverse_fn
+            }
+        });
+    }
+    result
+}
+
+/// Returns whether `Debug` is one of the derives given with
+/// `#[archive(derive(...))]`.
+fn derives_debug(attributes: &Attributes) -> bool {
+    derives_trait(attributes, "Debug")
+}
+
+/// Returns whether the trait with the given name is one of the derives given
+/// with `#[archive(derive(...))]`.
+fn derives_trait(attributes: &Attributes, name: &str) -> bool {
+    match attributes.derives {
+        Some(ref derives) => derives.nested.iter().any(|n| match n {
+            NestedMeta::Meta(Meta::Path(path)) => {
+                matches!(path.segments.last(), Some(s) if s.ident == name)
+            }
+            _ => false,
+        }),
+        None => false,
+    }
+}
+
+fn derive_debug_impls(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+) -> TokenStream {
+    let generic_params = input
+        .generics
+        .params
+        .iter()
+        .map(|p| quote_spanned! { p.span() => #p });
+    let generic_params = quote! { #(#generic_params,)* };
+
+    let generic_args = generic_args(input);
+
+    let generic_predicates = generic_predicates(input, &attributes.archive_bound);
+
+    // Fields that omit their bounds get them from the debug bound instead
+    let fields = all_fields(input)
+        .into_iter()
+        .filter(|f| !is_skipped(f) && !omits_bounds(f))
+        .collect::<Vec<_>>();
+    let archive_predicates = fields.iter().map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => #ty: rkyv::Archive }
+    });
+    let debug_predicates = fields.iter().map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => for<'__debug> rkyv::Archived<#ty>: core::fmt::Debug }
+    });
+    let debug_bound = attributes.debug_bound.iter().flat_map(|bound| bound.iter());
+
+    // Archived values print with the name of the type they were archived from
+    let name = match attributes.remote {
+        Some((ref remote, _)) => remote.segments.last().unwrap().ident.to_string(),
+        None => input.ident.to_string(),
+    };
+
+    // Gets the pattern for an archived variant and the statements that add its
+    // fields to a formatter
+    let variant_fields = |fields: &Fields| match fields {
+        Fields::Named(ref fields) => {
+            let fields = fields
+                .named
+                .iter()
+                .filter(|f| !is_skipped(f))
+                .collect::<Vec<_>>();
+            let names = fields.iter().map(|f| &f.ident);
+            let bindings = fields.iter().map(|f| {
+                let name = f.ident.as_ref().unwrap();
+                Ident::new(&format!("self_{}", name), name.span())
+            });
+            let debug_fields = fields.iter().zip(bindings.clone()).map(|(f, binding)| {
+                let name = f.ident.as_ref().unwrap().to_string();
+                quote! { .field(#name, #binding) }
+            });
+            (
+                quote! { { #(#names: #bindings,)* } },
+                quote! { #(#debug_fields)* },
+            )
+        }
+        Fields::Unnamed(ref fields) => {
+            let bindings = fields
+                .unnamed
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| !is_skipped(f))
+                .map(|(i, f)| Ident::new(&format!("self_{}", i), f.span()))
+                .collect::<Vec<_>>();
+            (
+                quote! { (#(#bindings,)*) },
+                quote! { #(.field(#bindings))* },
+            )
+        }
+        Fields::Unit => (quote! {}, quote! {}),
+    };
+
+    let fmt_body = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                // Fields added to an evolvable struct are left out when the
+                // archive was written by an older version
+                let debug_fields = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
+                    let name = &f.ident;
+                    let name_str = name.as_ref().unwrap().to_string();
+                    if since(f) > 0 {
+                        quote! {
+                            if let Some(value) = self.#name() {
+                                debug.field(#name_str, value);
+                            }
+                        }
+                    } else {
+                        quote! { debug.field(#name_str, &self.#name); }
+                    }
+                });
+                quote! {
+                    let mut debug = f.debug_struct(#name);
+                    #(#debug_fields)*
+                    debug.finish()
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let debug_fields = fields
+                    .unnamed
+                    .iter()
+                    .filter(|f| !is_skipped(f))
+                    .enumerate()
+                    .map(|(i, _)| {
+                        let index = Index::from(i);
+                        quote! { .field(&self.#index) }
+                    });
+                quote! { f.debug_tuple(#name) #(#debug_fields)*.finish() }
+            }
+            Fields::Unit => quote! { f.write_str(#name) },
+        },
+        Data::Enum(ref data) => {
+            let variants = data.variants.iter().map(|v| {
+                let variant = &v.ident;
+                let variant_name = variant.to_string();
+                let (pattern, debug_fields) = variant_fields(&v.fields);
+                let debug = match v.fields {
+                    Fields::Named(_) => {
+                        quote! { f.debug_struct(#variant_name) #debug_fields.finish() }
+                    }
+                    Fields::Unnamed(_) => {
+                        quote! { f.debug_tuple(#variant_name) #debug_fields.finish() }
+                    }
+                    Fields::Unit => quote! { f.write_str(#variant_name) },
+                };
+                quote! { #archived::#variant #pattern => #debug }
+            });
+            quote! {
+                match self {
+                    #(#variants,)*
+                }
+            }
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    quote! {
+        impl<#generic_params> core::fmt::Debug for #archived<#generic_args>
+        where
+            #generic_predicates
+            #(#archive_predicates,)*
+            #(#debug_predicates,)*
+            #(#debug_bound,)*
+        {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                #fmt_body
+            }
+        }
+    }
+}
+
+fn derive_diff_impls(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+) -> TokenStream {
+    let generic_params = input
+        .generics
+        .params
+        .iter()
+        .map(|p| quote_spanned! { p.span() => #p });
+    let generic_params = quote! { #(#generic_params,)* };
+
+    let generic_args = generic_args(input);
+
+    let generic_predicates = generic_predicates(input, &attributes.archive_bound);
+
+    let fields = all_fields(input)
+        .into_iter()
+        .filter(|f| !is_skipped(f) && !omits_bounds(f))
+        .collect::<Vec<_>>();
+    let archive_predicates = fields.iter().map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => #ty: rkyv::Archive }
+    });
+    let diff_predicates = fields.iter().map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => rkyv::Archived<#ty>: rkyv::diff::ArchiveDiff }
+    });
+    // Fields added to an evolvable struct are reported whole when only one
+    // side has them
+    let debug_predicates = fields.iter().filter(|f| since(f) > 0).map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => rkyv::Archived<#ty>: core::fmt::Debug }
+    });
+
+    let diff_field = |name: String, old: TokenStream, new: TokenStream| {
+        quote! {
+            differ.diff(rkyv::diff::PathSegment::Field(#name), #old, #new);
+        }
+    };
+
+    // Gets the pattern for an archived variant with the given binding prefix
+    // and the names and bindings of its fields
+    let variant_fields = |fields: &Fields, prefix: &str| match fields {
+        Fields::Named(ref fields) => {
+            let fields = fields.named.iter().filter(|f| !is_skipped(f));
+            let names = fields.clone().map(|f| &f.ident);
+            let bindings = fields
+                .clone()
+                .map(|f| {
+                    let name = f.ident.as_ref().unwrap();
+                    (
+                        name.to_string(),
+                        Ident::new(&format!("{}_{}", prefix, name), name.span()),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let pattern_bindings = bindings.iter().map(|(_, b)| b);
+            (quote! { { #(#names: #pattern_bindings,)* } }, bindings)
+        }
+        Fields::Unnamed(ref fields) => {
+            let bindings = fields
+                .unnamed
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| !is_skipped(f))
+                .map(|(i, f)| {
+                    (
+                        i.to_string(),
+                        Ident::new(&format!("{}_{}", prefix, i), f.span()),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let pattern_bindings = bindings.iter().map(|(_, b)| b);
+            (quote! { (#(#pattern_bindings,)*) }, bindings)
+        }
+        Fields::Unit => (quote! {}, Vec::new()),
+    };
+
+    let diff_body = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let diffs = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
+                    let name = &f.ident;
+                    let name_str = name.as_ref().unwrap().to_string();
+                    if since(f) > 0 {
+                        quote! {
+                            match (self.#name(), other.#name()) {
+                                (Some(old), Some(new)) => differ.diff(rkyv::diff::PathSegment::Field(#name_str), old, new),
+                                (Some(old), None) => differ.removed(rkyv::diff::PathSegment::Field(#name_str), old),
+                                (None, Some(new)) => differ.added(rkyv::diff::PathSegment::Field(#name_str), new),
+                                (None, None) => (),
+                            }
+                        }
+                    } else {
+                        diff_field(name_str, quote! { &self.#name }, quote! { &other.#name })
+                    }
+                });
+                quote! { #(#diffs)* }
+            }
+            Fields::Unnamed(ref fields) => {
+                let diffs = fields
+                    .unnamed
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| !is_skipped(f))
+                    .enumerate()
+                    .map(|(j, (i, _))| {
+                        let index = Index::from(j);
+                        diff_field(
+                            i.to_string(),
+                            quote! { &self.#index },
+                            quote! { &other.#index },
+                        )
+                    });
+                quote! { #(#diffs)* }
+            }
+            Fields::Unit => quote! {},
+        },
+        Data::Enum(ref data) => {
+            let same_variants = data.variants.iter().map(|v| {
+                let variant = &v.ident;
+                let (self_pattern, self_bindings) = variant_fields(&v.fields, "self");
+                let (other_pattern, other_bindings) = variant_fields(&v.fields, "other");
+                let diffs = self_bindings
+                    .into_iter()
+                    .zip(other_bindings)
+                    .map(|((name, a), (_, b))| diff_field(name, quote! { #a }, quote! { #b }));
+                quote! {
+                    (#archived::#variant #self_pattern, #archived::#variant #other_pattern) => {
+                        #(#diffs)*
+                    }
+                }
+            });
+            let variant_names = data.variants.iter().map(|v| {
+                let variant = &v.ident;
+                let name = variant.to_string();
+                let pattern = match v.fields {
+                    Fields::Named(_) => quote! { { .. } },
+                    Fields::Unnamed(_) => quote! { (..) },
+                    Fields::Unit => quote! {},
+                };
+                quote! { #archived::#variant #pattern => &#name }
+            });
+            quote! {
+                let variant_name = |value: &Self| -> &'static &'static str {
+                    match value {
+                        #(#variant_names,)*
+                    }
+                };
+                match (self, other) {
+                    #(#same_variants)*
+                    _ => differ.changed(variant_name(self), variant_name(other)),
+                }
+            }
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    quote! {
+        impl<#generic_params> rkyv::diff::ArchiveDiff for #archived<#generic_args>
+        where
+            #generic_predicates
+            #(#archive_predicates,)*
+            #(#diff_predicates,)*
+            #(#debug_predicates,)*
+        {
+            fn diff<'__a>(&'__a self, other: &'__a Self, differ: &mut rkyv::diff::Differ<'__a>) {
+                #diff_body
+            }
+        }
+    }
+}
+
+/// Generates `Schema` impls that describe the layout of the archived type.
+///
+/// Each field type's archived type must also implement `Schema`. The bounds are
+/// higher-ranked so that types with fields that don't implement it still
+/// compile, they just don't get a schema.
+fn derive_schema_impls(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+) -> TokenStream {
+    let generic_params = input
+        .generics
+        .params
+        .iter()
+        .map(|p| quote_spanned! { p.span() => #p });
+    let generic_params = quote! { #(#generic_params,)* };
+
+    let generic_args = generic_args(input);
+
+    let generic_predicates = generic_predicates(input, &attributes.archive_bound);
+
+    let fields = all_fields(input)
+        .into_iter()
+        .filter(|f| !is_skipped(f) && !omits_bounds(f))
+        .collect::<Vec<_>>();
+    let archive_predicates = fields.iter().map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => #ty: rkyv::Archive }
+    });
+    let schema_predicates = fields.iter().map(|f| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() => for<'__schema> rkyv::Archived<#ty>: rkyv::schema::Schema }
+    });
+    let predicates = quote! {
+        #generic_predicates
+        #(#archive_predicates,)*
+        #(#schema_predicates,)*
+    };
+
+    let field_schema = |f: &Field, name: String, offset: TokenStream| {
+        let ty = archive_type(f);
+        quote_spanned! { f.span() =>
+            rkyv::schema::FieldSchema::new::<rkyv::Archived<#ty>>(graph, #name, #offset)
+        }
+    };
+    let schema_impl = |ty: TokenStream, kind: TokenStream| {
+        quote! {
+            impl<#generic_params> rkyv::schema::Schema for #ty
+            where
+                #predicates
+            {
+                fn describe(graph: &mut rkyv::schema::SchemaGraph) -> rkyv::schema::TypeSchema {
+                    rkyv::schema::TypeSchema::sized::<Self>(#kind)
+                }
+            }
+        }
+    };
+
+    match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) if attributes.evolvable.is_some() => {
+                let versions = evolvable_versions(fields);
+                let impls = versions.iter().enumerate().map(|(i, (version, fields))| {
+                    let evolved = evolved_archived(archived, *version);
+                    let evolution = evolution_type(&versions, i, archived, &generic_args);
+                    let fields = fields.iter().map(|f| {
+                        let name = &f.ident;
+                        field_schema(
+                            f,
+                            name.as_ref().unwrap().to_string(),
+                            quote! { offset_of!(#evolved<#generic_args>, #name) },
+                        )
+                    });
+                    schema_impl(
+                        quote! { #evolved<#generic_args> },
+                        quote! {
+                            rkyv::schema::TypeKind::Struct(vec![
+                                #(#fields,)*
+                                rkyv::schema::FieldSchema::new::<#evolution>(
+                                    graph,
+                                    "__evolution",
+                                    offset_of!(#evolved<#generic_args>, __evolution),
+                                ),
+                            ])
+                        },
+                    )
+                });
+                quote! { #(#impls)* }
+            }
+            Fields::Named(ref fields) => {
+                let fields = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
+                    let name = &f.ident;
+                    field_schema(
+                        f,
+                        name.as_ref().unwrap().to_string(),
+                        quote! { offset_of!(#archived<#generic_args>, #name) },
+                    )
+                });
+                schema_impl(
+                    quote! { #archived<#generic_args> },
+                    quote! { rkyv::schema::TypeKind::Struct(vec![#(#fields,)*]) },
+                )
+            }
+            Fields::Unnamed(ref fields) => {
+                let fields = fields
+                    .unnamed
+                    .iter()
+                    .filter(|f| !is_skipped(f))
+                    .enumerate()
+                    .map(|(i, f)| {
+                        let index = Index::from(i);
+                        field_schema(
+                            f,
+                            i.to_string(),
+                            quote! { offset_of!(#archived<#generic_args>, #index) },
+                        )
+                    });
+                schema_impl(
+                    quote! { #archived<#generic_args> },
+                    quote! { rkyv::schema::TypeKind::Struct(vec![#(#fields,)*]) },
+                )
+            }
+            Fields::Unit => schema_impl(
+                quote! { #archived<#generic_args> },
+                quote! { rkyv::schema::TypeKind::Struct(Vec::new()) },
+            ),
+        },
+        Data::Enum(ref data) => {
+            let repr_c = match attributes.archive_repr {
+                Some((ref repr, _)) => repr == "C",
+                None => false,
+            };
+            let payload_offset = if repr_c {
+                quote! { offset_of!(ArchivedVariantRepr<#generic_args>, __payload) + }
+            } else {
+                quote! {}
+            };
+            let first_field = if repr_c { 0 } else { 1 };
+
+            // The tags of repr(C) enums are C enums, which are as large as they
+            // need to be
+            let tag = if repr_c {
+                quote! {
+                    match core::mem::size_of::<ArchivedTag>() {
+                        1 => graph.register::<rkyv::Archived<i8>>(),
+                        2 => graph.register::<rkyv::Archived<i16>>(),
+                        4 => graph.register::<rkyv::Archived<i32>>(),
+                        _ => graph.register::<rkyv::Archived<i64>>(),
+                    }
+                }
+            } else {
+                let archived_repr = archived_repr(attributes, data);
+                quote! { graph.register::<rkyv::Archived<#archived_repr>>() }
+            };
+
+            let variants = data.variants.iter().map(|v| {
+                let variant = &v.ident;
+                let variant_name = variant.to_string();
+                let archived_variant_name = Ident::new(&format!("ArchivedVariant{}", variant), v.span());
+                let tag_value = archived_tag_value(attributes, data, quote! { ArchivedTag::#variant });
+                let fields = match v.fields {
+                    Fields::Named(ref fields) => fields
+                        .named
+                        .iter()
+                        .filter(|f| !is_skipped(f))
+                        .map(|f| {
+                            let name = &f.ident;
+                            field_schema(
+                                f,
+                                name.as_ref().unwrap().to_string(),
+                                quote! { #payload_offset offset_of!(#archived_variant_name<#generic_args>, #name) },
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                    Fields::Unnamed(ref fields) => fields
+                        .unnamed
+                        .iter()
+                        .filter(|f| !is_skipped(f))
+                        .enumerate()
+                        .map(|(i, f)| {
+                            let index = Index::from(i + first_field);
+                            field_schema(
+                                f,
+                                i.to_string(),
+                                quote! { #payload_offset offset_of!(#archived_variant_name<#generic_args>, #index) },
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                    Fields::Unit => Vec::new(),
+                };
                 quote! {
-                    #[repr(#archived_repr)]
-                    enum ArchivedTag {
-                        #(#archived_variant_tags,)*
+                    rkyv::schema::VariantSchema {
+                        name: #variant_name.to_string(),
+                        tag: #tag_value,
+                        fields: vec![#(#fields,)*],
                     }
+                }
+            });
 
-                    #(#archived_variant_structs)*
-
-                    impl<#generic_params> Archive for #name<#generic_args>
-                    where
-                        #generic_predicates
-                        #archive_predicates
-                    {
-                        type Archived = #archived<#generic_args>;
-                        type Resolver = #resolver<#generic_args>;
-
-                        fn resolve(&self, pos: usize, resolver: Self::Resolver) -> Self::Archived {
-                            match resolver {
-                                #(#resolve_arms,)*
-                            }
-                        }
+            schema_impl(
+                quote! { #archived<#generic_args> },
+                quote! {
+                    rkyv::schema::TypeKind::Enum {
+                        tag: #tag,
+                        variants: vec![#(#variants,)*],
                     }
                 },
             )
         }
-        Data::Union(_) => {
-            return Error::new(input.span(), "Archive cannot be derived for unions")
-                .to_compile_error()
-        }
-    };
-
-    quote! {
-        #archive_types
-
-        const _: () = {
-            use core::marker::PhantomData;
-            use rkyv::{Archive, offset_of};
-
-            #archive_impls
-        };
+        Data::Union(_) => quote! {},
     }
 }
 
@@ -676,6 +2839,36 @@ fn derive_archive_copy_impl(input: &DeriveInput, attributes: &Attributes) -> Tok
         return Error::new(*span, "archive copy resolvers cannot be named").to_compile_error();
     };
 
+    if let Some((_, span)) = &attributes.remote {
+        return Error::new(*span, "archive copy types cannot be remote").to_compile_error();
+    }
+
+    if let Some(compare) = &attributes.compare {
+        return Error::new(
+            compare.span(),
+            "archive copy types are compared with their own PartialEq and PartialOrd",
+        )
+        .to_compile_error();
+    }
+
+    if let Some((_, span)) = &attributes.archive_repr {
+        return Error::new(*span, "archive copy types use their own repr").to_compile_error();
+    }
+
+    if let Some(span) = attributes.diff {
+        return Error::new(span, "archive copy types cannot derive diffs").to_compile_error();
+    }
+
+    for field in all_fields(input) {
+        let field_attributes = field_attributes(field);
+        if field_attributes.with.is_some() {
+            return Error::new(field.span(), "archive copy types cannot use with wrappers")
+                .to_compile_error();
+        } else if let Some(span) = field_attributes.skip {
+            return Error::new(span, "archive copy types cannot skip fields").to_compile_error();
+        }
+    }
+
     let name = &input.ident;
 
     let generic_params = input.generics.params.iter().map(|p| quote! { #p });
@@ -687,13 +2880,7 @@ fn derive_archive_copy_impl(input: &DeriveInput, attributes: &Attributes) -> Tok
     });
     let generic_args = quote! { #(#generic_args,)* };
 
-    let generic_predicates = match input.generics.where_clause {
-        Some(ref clause) => {
-            let predicates = clause.predicates.iter().map(|p| quote! { #p });
-            quote! { #(#predicates,)* }
-        }
-        None => quote! {},
-    };
+    let generic_predicates = generic_predicates(input, &attributes.archive_bound);
 
     let archive_copy_impl = match input.data {
         Data::Struct(ref data) => {
@@ -717,6 +2904,43 @@ fn derive_archive_copy_impl(input: &DeriveInput, attributes: &Attributes) -> Tok
                 Fields::Unit => quote! {},
             };
 
+            let schema_impl = if cfg!(feature = "schema") {
+                let fields = data.fields.iter().enumerate().map(|(i, f)| {
+                    let ty = &f.ty;
+                    let (name, member) = match f.ident {
+                        Some(ref ident) => (ident.to_string(), quote! { #ident }),
+                        None => {
+                            let index = Index::from(i);
+                            (i.to_string(), quote! { #index })
+                        }
+                    };
+                    quote_spanned! { f.span() =>
+                        rkyv::schema::FieldSchema::new::<#ty>(graph, #name, rkyv::offset_of!(Self, #member))
+                    }
+                });
+                let schema_predicates = data.fields.iter().map(|f| {
+                    let ty = &f.ty;
+                    quote_spanned! { f.span() => for<'__schema> #ty: rkyv::schema::Schema }
+                });
+
+                quote! {
+                    impl<#generic_params> rkyv::schema::Schema for #name<#generic_args>
+                    where
+                        #generic_predicates
+                        #copy_predicates
+                        #(#schema_predicates,)*
+                    {
+                        fn describe(graph: &mut rkyv::schema::SchemaGraph) -> rkyv::schema::TypeSchema {
+                            rkyv::schema::TypeSchema::sized::<Self>(
+                                rkyv::schema::TypeKind::Struct(vec![#(#fields,)*])
+                            )
+                        }
+                    }
+                }
+            } else {
+                quote! {}
+            };
+
             quote! {
                 unsafe impl<#generic_params> ArchiveCopy for #name<#generic_args>
                 where
@@ -724,6 +2948,8 @@ fn derive_archive_copy_impl(input: &DeriveInput, attributes: &Attributes) -> Tok
                     #copy_predicates
                 {}
 
+                #schema_impl
+
                 impl<#generic_params> Archive for #name<#generic_args>
                 where
                     #generic_predicates
@@ -732,8 +2958,8 @@ fn derive_archive_copy_impl(input: &DeriveInput, attributes: &Attributes) -> Tok
                     type Archived = Self;
                     type Resolver = ();
 
-                    fn resolve(&self, _pos: usize, _resolver: Self::Resolver) -> Self::Archived {
-                        *self
+                    fn resolve(&self, _pos: usize, _resolver: Self::Resolver) -> Result<Self::Archived, rkyv::OffsetError> {
+                        Ok(*self)
                     }
                 }
             }
@@ -776,6 +3002,54 @@ fn derive_archive_copy_impl(input: &DeriveInput, attributes: &Attributes) -> Tok
             });
             let copy_predicates = quote! { #(#copy_predicates)* };
 
+            // The layouts of copy enums with fields depend on their repr in
+            // ways that aren't described yet, so only fieldless enums get a
+            // schema
+            let is_fieldless = data
+                .variants
+                .iter()
+                .all(|v| matches!(v.fields, Fields::Unit));
+            let schema_impl = if cfg!(feature = "schema") && is_fieldless {
+                let tag = match attributes.repr.int {
+                    Some(ref int) => quote! { graph.register::<#int>() },
+                    None => quote! {
+                        match core::mem::size_of::<Self>() {
+                            1 => graph.register::<i8>(),
+                            2 => graph.register::<i16>(),
+                            4 => graph.register::<i32>(),
+                            _ => graph.register::<i64>(),
+                        }
+                    },
+                };
+                let variants = data.variants.iter().map(|v| {
+                    let variant = &v.ident;
+                    let variant_name = variant.to_string();
+                    quote! {
+                        rkyv::schema::VariantSchema {
+                            name: #variant_name.to_string(),
+                            tag: Self::#variant as i128,
+                            fields: Vec::new(),
+                        }
+                    }
+                });
+
+                quote! {
+                    impl<#generic_params> rkyv::schema::Schema for #name<#generic_args>
+                    where
+                        #generic_predicates
+                    {
+                        fn describe(graph: &mut rkyv::schema::SchemaGraph) -> rkyv::schema::TypeSchema {
+                            rkyv::schema::TypeSchema::sized::<Self>(rkyv::schema::TypeKind::Enum {
+                                tag: #tag,
+                                variants: vec![#(#variants,)*],
+                            })
+                        }
+                    }
+                }
+            } else {
+                quote! {}
+            };
+
             quote! {
                 unsafe impl<#generic_params> ArchiveCopy for #name<#generic_args>
                 where
@@ -783,6 +3057,8 @@ fn derive_archive_copy_impl(input: &DeriveInput, attributes: &Attributes) -> Tok
                     #copy_predicates
                 {}
 
+                #schema_impl
+
                 impl<#generic_params> Archive for #name<#generic_args>
                 where
                     #generic_predicates
@@ -791,8 +3067,8 @@ fn derive_archive_copy_impl(input: &DeriveInput, attributes: &Attributes) -> Tok
                     type Archived = Self;
                     type Resolver = ();
 
-                    fn resolve(&self, _: usize, _: Self::Resolver) -> Self::Archived {
-                        *self
+                    fn resolve(&self, _: usize, _: Self::Resolver) -> Result<Self::Archived, rkyv::OffsetError> {
+                        Ok(*self)
                     }
                 }
             }
@@ -817,9 +3093,9 @@ fn derive_archive_copy_impl(input: &DeriveInput, attributes: &Attributes) -> Tok
 
 /// Derives `Serialize` for the labeled type.
 ///
-/// This macro also supports the `#[archive]` and `#[recursive]` attributes. See
-/// [`Archive`] for more information.
-#[proc_macro_derive(Serialize, attributes(archive, recursive))]
+/// This macro also supports the `#[archive]`, `#[omit_bounds]`, and `#[with]`
+/// attributes. See [`Archive`] for more information.
+#[proc_macro_derive(Serialize, attributes(archive, omit_bounds, recursive, with))]
 pub fn serialize_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -847,19 +3123,9 @@ fn derive_serialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenS
         .map(|p| quote_spanned! { p.span() => #p });
     let generic_params = quote! { #(#generic_params,)* };
 
-    let generic_args = input.generics.type_params().map(|p| {
-        let name = &p.ident;
-        quote_spanned! { name.span() => #name }
-    });
-    let generic_args = quote! { #(#generic_args,)* };
+    let generic_args = generic_args(input);
 
-    let generic_predicates = match input.generics.where_clause {
-        Some(ref clause) => {
-            let predicates = clause.predicates.iter().map(|p| quote! { #p });
-            quote! { #(#predicates,)* }
-        }
-        None => quote! {},
-    };
+    let generic_predicates = generic_predicates(input, &attributes.serialize_bound);
 
     let resolver = if let Some((ref resolver, _)) = attributes.resolver {
         resolver.clone()
@@ -867,31 +3133,54 @@ fn derive_serialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenS
         Ident::new(&format!("{}Resolver", name), name.span())
     };
 
+    let (serialize_trait, serialize_fn, this, value_path) = match attributes.remote {
+        Some((ref remote, _)) => (
+            quote! { rkyv::with::SerializeWith<#remote, __S> },
+            quote! {
+                fn serialize_with(field: &#remote, serializer: &mut __S) -> Result<#resolver<#generic_args>, __S::Error>
+            },
+            quote! { field },
+            pattern_path(remote),
+        ),
+        None => (
+            quote! { Serialize<__S> },
+            quote! {
+                fn serialize(&self, serializer: &mut __S) -> Result<Self::Resolver, __S::Error>
+            },
+            quote! { self },
+            quote! { Self },
+        ),
+    };
+
     let serialize_impl = match input.data {
         Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) if attributes.evolvable.is_some() => {
+                derive_evolvable_serialize(input, attributes, fields, &resolver)
+            }
             Fields::Named(ref fields) => {
                 let serialize_predicates = fields.named.iter().filter_map(|f| {
-                    if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                    if is_skipped(f) || omits_bounds(f) {
                         None
                     } else {
-                        let ty = &f.ty;
+                        let ty = archive_type(f);
                         Some(quote_spanned! { f.span() => #ty: rkyv::Serialize<__S> })
                     }
                 });
                 let serialize_predicates = quote! { #(#serialize_predicates,)* };
 
-                let resolver_values = fields.named.iter().map(|f| {
+                let resolver_values = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                     let name = &f.ident;
-                    quote_spanned! { f.span() => #name: Serialize::<__S>::serialize(&self.#name, serializer)? }
+                    let field = archive_ref(f, field_ref(f, &this, quote! { #name }));
+                    quote_spanned! { f.span() => #name: Serialize::<__S>::serialize(#field, serializer)? }
                 });
 
                 quote! {
-                    impl<__S: Fallible + ?Sized, #generic_params> Serialize<__S> for #name<#generic_args>
+                    impl<#generic_params __S: Fallible + ?Sized> #serialize_trait for #name<#generic_args>
                     where
                         #generic_predicates
                         #serialize_predicates
                     {
-                        fn serialize(&self, serializer: &mut __S) -> Result<Self::Resolver, __S::Error> {
+                        #serialize_fn {
                             Ok(#resolver {
                                 #(#resolver_values,)*
                             })
@@ -901,27 +3190,28 @@ fn derive_serialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenS
             }
             Fields::Unnamed(ref fields) => {
                 let serialize_predicates = fields.unnamed.iter().filter_map(|f| {
-                    if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                    if is_skipped(f) || omits_bounds(f) {
                         None
                     } else {
-                        let ty = &f.ty;
+                        let ty = archive_type(f);
                         Some(quote_spanned! { f.span() => #ty: rkyv::Serialize<__S> })
                     }
                 });
                 let serialize_predicates = quote! { #(#serialize_predicates,)* };
 
-                let resolver_values = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                let resolver_values = fields.unnamed.iter().enumerate().filter(|(_, f)| !is_skipped(f)).map(|(i, f)| {
                     let index = Index::from(i);
-                    quote_spanned! { f.span() => Serialize::<__S>::serialize(&self.#index, serializer)? }
+                    let field = archive_ref(f, field_ref(f, &this, quote! { #index }));
+                    quote_spanned! { f.span() => Serialize::<__S>::serialize(#field, serializer)? }
                 });
 
                 quote! {
-                    impl<__S: Fallible + ?Sized, #generic_params> Serialize<__S> for #name<#generic_args>
+                    impl<#generic_params __S: Fallible + ?Sized> #serialize_trait for #name<#generic_args>
                     where
                         #generic_predicates
                         #serialize_predicates
                     {
-                        fn serialize(&self, serializer: &mut __S) -> Result<Self::Resolver, __S::Error> {
+                        #serialize_fn {
                             Ok(#resolver::<#generic_args>(
                                 #(#resolver_values,)*
                             ))
@@ -931,8 +3221,8 @@ fn derive_serialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenS
             }
             Fields::Unit => {
                 quote! {
-                    impl<__S: Fallible + ?Sized, #generic_params> Serialize<__S> for #name<#generic_args> {
-                        fn serialize(&self, serializer: &mut __S) -> Result<Self::Resolver, __S::Error> {
+                    impl<#generic_params __S: Fallible + ?Sized> #serialize_trait for #name<#generic_args> {
+                        #serialize_fn {
                             Ok(#resolver)
                         }
                     }
@@ -943,10 +3233,10 @@ fn derive_serialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenS
             let serialize_predicates = data.variants.iter().map(|v| match v.fields {
                 Fields::Named(ref fields) => {
                     let serialize_predicates = fields.named.iter().filter_map(|f| {
-                        if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                        if is_skipped(f) || omits_bounds(f) {
                             None
                         } else {
-                            let ty = &f.ty;
+                            let ty = archive_type(f);
                             Some(quote_spanned! { f.span() => #ty: rkyv::Serialize<__S> })
                         }
                     });
@@ -954,10 +3244,10 @@ fn derive_serialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenS
                 }
                 Fields::Unnamed(ref fields) => {
                     let serialize_predicates = fields.unnamed.iter().filter_map(|f| {
-                        if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                        if is_skipped(f) || omits_bounds(f) {
                             None
                         } else {
-                            let ty = &f.ty;
+                            let ty = archive_type(f);
                             Some(quote_spanned! { f.span() => #ty: rkyv::Serialize<__S> })
                         }
                     });
@@ -973,16 +3263,21 @@ fn derive_serialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenS
                     Fields::Named(ref fields) => {
                         let bindings = fields.named.iter().map(|f| {
                             let name = &f.ident;
-                            quote_spanned! { name.span() => #name }
+                            if is_skipped(f) {
+                                quote_spanned! { name.span() => #name: _ }
+                            } else {
+                                quote_spanned! { name.span() => #name }
+                            }
                         });
-                        let fields = fields.named.iter().map(|f| {
+                        let fields = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                             let name = &f.ident;
+                            let field = archive_ref(f, quote! { #name });
                             quote! {
-                                #name: Serialize::<__S>::serialize(#name, serializer)?
+                                #name: Serialize::<__S>::serialize(#field, serializer)?
                             }
                         });
                         quote_spanned! { variant.span() =>
-                            Self::#variant { #(#bindings,)* } => #resolver::#variant {
+                            #value_path::#variant { #(#bindings,)* } => #resolver::#variant {
                                 #(#fields,)*
                             }
                         }
@@ -992,30 +3287,31 @@ fn derive_serialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenS
                             let name = Ident::new(&format!("_{}", i), f.span());
                             quote_spanned! { f.span() => #name }
                         });
-                        let fields = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                        let fields = fields.unnamed.iter().enumerate().filter(|(_, f)| !is_skipped(f)).map(|(i, f)| {
                             let binding = Ident::new(&format!("_{}", i), f.span());
+                            let field = archive_ref(f, quote! { #binding });
                             quote! {
-                                Serialize::<__S>::serialize(#binding, serializer)?
+                                Serialize::<__S>::serialize(#field, serializer)?
                             }
                         });
                         quote_spanned! { variant.span() =>
-                            Self::#variant( #(#bindings,)* ) => #resolver::#variant(#(#fields,)*)
+                            #value_path::#variant( #(#bindings,)* ) => #resolver::#variant(#(#fields,)*)
                         }
                     }
                     Fields::Unit => {
-                        quote_spanned! { name.span() => Self::#variant => #resolver::#variant }
+                        quote_spanned! { name.span() => #value_path::#variant => #resolver::#variant }
                     }
                 }
             });
 
             quote! {
-                impl<__S: Fallible + ?Sized, #generic_params> Serialize<__S> for #name<#generic_args>
+                impl<#generic_params __S: Fallible + ?Sized> #serialize_trait for #name<#generic_args>
                 where
                     #generic_predicates
                     #serialize_predicates
                 {
-                    fn serialize(&self, serializer: &mut __S) -> Result<Self::Resolver, __S::Error> {
-                        Ok(match self {
+                    #serialize_fn {
+                        Ok(match #this {
                             #(#serialize_arms,)*
                         })
                     }
@@ -1040,6 +3336,125 @@ fn derive_serialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenS
     }
 }
 
+/// Serializes an evolvable struct by writing the fields of each newer version
+/// out of line, starting with the newest so each can point to the next.
+fn derive_evolvable_serialize(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    fields: &FieldsNamed,
+    resolver: &Ident,
+) -> TokenStream {
+    let name = &input.ident;
+
+    let generic_params = input
+        .generics
+        .params
+        .iter()
+        .map(|p| quote_spanned! { p.span() => #p });
+    let generic_params = quote! { #(#generic_params,)* };
+
+    let generic_args = generic_args(input);
+
+    let generic_predicates = generic_predicates(input, &attributes.serialize_bound);
+
+    let archived = if let Some((ref archived, _)) = attributes.archived {
+        archived.clone()
+    } else {
+        Ident::new(&format!("Archived{}", name), name.span())
+    };
+
+    let serialize_predicates = fields.named.iter().filter_map(|f| {
+        if is_skipped(f) || omits_bounds(f) {
+            None
+        } else {
+            let ty = archive_type(f);
+            Some(quote_spanned! { f.span() => #ty: rkyv::Serialize<__S> })
+        }
+    });
+    let serialize_predicates = quote! { #(#serialize_predicates,)* };
+
+    let resolver_binding =
+        |f: &Field| Ident::new(&format!("resolver_{}", f.ident.as_ref().unwrap()), f.span());
+    let version_binding = |version: u32| Ident::new(&format!("version_{}", version), name.span());
+
+    let versions = evolvable_versions(fields);
+
+    let field_resolvers = versions.iter().flat_map(|(_, fields)| fields).map(|f| {
+        let name = &f.ident;
+        let binding = resolver_binding(f);
+        let field = archive_ref(f, quote! { &self.#name });
+        quote_spanned! { f.span() =>
+            #[allow(clippy::let_unit_value)]
+            let #binding = Serialize::<__S>::serialize(#field, serializer)?;
+        }
+    });
+
+    let evolution_value = |index: usize, archived: &Ident| match versions.get(index + 1) {
+        Some((version, _)) => {
+            let next = version_binding(*version);
+            quote! {
+                rkyv::evolve::ArchivedEvolution::resolve_from_pos(
+                    pos + rkyv::offset_of!(#archived<#generic_args>, __evolution),
+                    #next,
+                )?
+            }
+        }
+        None => quote! { rkyv::evolve::ArchivedFutureEvolution::absent() },
+    };
+
+    let write_versions = versions
+        .iter()
+        .enumerate()
+        .skip(1)
+        .rev()
+        .map(|(i, (version, fields))| {
+            let evolved = evolved_archived(&archived, *version);
+            let binding = version_binding(*version);
+            let archived_values = fields.iter().map(|f| {
+                let name = &f.ident;
+                let field = archive_ref(f, quote! { &self.#name });
+                let resolver = resolver_binding(f);
+                quote_spanned! { f.span() => #name: Archive::resolve(#field, pos + rkyv::offset_of!(#evolved<#generic_args>, #name), #resolver)? }
+            });
+            let evolution = evolution_value(i, &evolved);
+            quote! {
+                let #binding = rkyv::evolve::ArchivedEvolution::<#evolved<#generic_args>>::serialize_next(serializer, |pos| Ok(#evolved {
+                    #(#archived_values,)*
+                    __evolution: #evolution,
+                    __phantom: core::marker::PhantomData,
+                }))?;
+            }
+        });
+
+    let resolver_values = versions[0].1.iter().map(|f| {
+        let name = &f.ident;
+        let binding = resolver_binding(f);
+        quote! { #name: #binding }
+    });
+    let resolver_evolution = versions.get(1).map(|(version, _)| {
+        let binding = version_binding(*version);
+        quote! { __evolution: #binding, }
+    });
+
+    quote! {
+        impl<#generic_params __S: rkyv::ser::Serializer + ?Sized> Serialize<__S> for #name<#generic_args>
+        where
+            #generic_predicates
+            #serialize_predicates
+        {
+            fn serialize(&self, serializer: &mut __S) -> Result<Self::Resolver, __S::Error> {
+                #(#field_resolvers)*
+                #(#write_versions)*
+                Ok(#resolver {
+                    #(#resolver_values,)*
+                    #resolver_evolution
+                    __phantom: core::marker::PhantomData,
+                })
+            }
+        }
+    }
+}
+
 fn derive_serialize_copy_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStream {
     if let Some((_, span)) = &attributes.archived {
         return Error::new(*span, "archive copy types cannot be named").to_compile_error();
@@ -1058,13 +3473,7 @@ fn derive_serialize_copy_impl(input: &DeriveInput, attributes: &Attributes) -> T
     });
     let generic_args = quote! { #(#generic_args,)* };
 
-    let generic_predicates = match input.generics.where_clause {
-        Some(ref clause) => {
-            let predicates = clause.predicates.iter().map(|p| quote! { #p });
-            quote! { #(#predicates,)* }
-        }
-        None => quote! {},
-    };
+    let generic_predicates = generic_predicates(input, &attributes.serialize_bound);
 
     let serialize_copy_impl = match input.data {
         Data::Struct(ref data) => {
@@ -1171,9 +3580,9 @@ fn derive_serialize_copy_impl(input: &DeriveInput, attributes: &Attributes) -> T
 
 /// Derives `Deserialize` for the labeled type.
 ///
-/// This macro also supports the `#[archive]` and `#[recursive]` attributes. See
-/// [`Archive`] for more information.
-#[proc_macro_derive(Deserialize, attributes(archive, recursive))]
+/// This macro also supports the `#[archive]`, `#[omit_bounds]`, and `#[with]`
+/// attributes. See [`Archive`] for more information.
+#[proc_macro_derive(Deserialize, attributes(archive, omit_bounds, recursive, with))]
 pub fn deserialize_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -1185,13 +3594,13 @@ pub fn deserialize_derive(input: proc_macro::TokenStream) -> proc_macro::TokenSt
     let deserialize_impl = if attributes.copy.is_some() {
         derive_deserialize_copy_impl(&input, &attributes)
     } else {
-        derive_deserialize_impl(&input)
+        derive_deserialize_impl(&input, &attributes)
     };
 
     proc_macro::TokenStream::from(deserialize_impl)
 }
 
-fn derive_deserialize_impl(input: &DeriveInput) -> TokenStream {
+fn derive_deserialize_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStream {
     let name = &input.ident;
 
     let generic_params = input
@@ -1201,118 +3610,194 @@ fn derive_deserialize_impl(input: &DeriveInput) -> TokenStream {
         .map(|p| quote_spanned! { p.span() => #p });
     let generic_params = quote! { #(#generic_params,)* };
 
-    let generic_args = input.generics.type_params().map(|p| {
-        let name = &p.ident;
-        quote_spanned! { name.span() => #name }
-    });
-    let generic_args = quote! { #(#generic_args,)* };
+    let generic_args = generic_args(input);
 
-    let generic_predicates = match input.generics.where_clause {
-        Some(ref clause) => {
-            let predicates = clause.predicates.iter().map(|p| quote! { #p });
-            quote! { #(#predicates,)* }
+    let generic_predicates = generic_predicates(input, &attributes.deserialize_bound);
+
+    let (deserialize_impl_header, deserialize_fn, remote_predicate, this, archived_path) =
+        match attributes.remote {
+            Some((ref remote, _)) => {
+                let archived = if let Some((ref archived, _)) = attributes.archived {
+                    archived.clone()
+                } else {
+                    Ident::new(&format!("Archived{}", name), name.span())
+                };
+                (
+                    quote! {
+                        impl<#generic_params __D: Fallible + ?Sized> rkyv::with::DeserializeWith<#archived<#generic_args>, #remote, __D> for #name<#generic_args>
+                    },
+                    quote! {
+                        fn deserialize_with(field: &#archived<#generic_args>, deserializer: &mut __D) -> Result<#remote, __D::Error>
+                    },
+                    quote! { #remote: From<#name<#generic_args>>, },
+                    quote! { field },
+                    quote! { #archived },
+                )
+            }
+            None => (
+                quote! {
+                    impl<#generic_params __D: Fallible + ?Sized> Deserialize<#name<#generic_args>, __D> for Archived<#name<#generic_args>>
+                },
+                quote! {
+                    fn deserialize(&self, deserializer: &mut __D) -> Result<#name<#generic_args>, __D::Error>
+                },
+                quote! {},
+                quote! { self },
+                quote! { Self },
+            ),
+        };
+    // Remote types are deserialized by converting from the local definition
+    let into_value = |value: TokenStream| {
+        if attributes.remote.is_some() {
+            quote! { From::from(#value) }
+        } else {
+            value
         }
-        None => quote! {},
     };
 
     let deserialize_impl = match input.data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let deserialize_predicates = fields.named.iter().filter_map(|f| {
-                    if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                    if is_skipped(f) {
+                        default_predicate(f)
+                    } else if omits_bounds(f) {
                         None
+                    } else if since(f) != 0 {
+                        let deserialize_predicate = deserialize_predicate(f);
+                        let default_predicate = default_predicate(f).into_iter();
+                        Some(quote! { #deserialize_predicate #(, #default_predicate)* })
                     } else {
-                        let ty = &f.ty;
-                        Some(quote_spanned! { f.span() => #ty: Archive, Archived<#ty>: Deserialize<#ty, __D> })
+                        Some(deserialize_predicate(f))
                     }
                 });
                 let deserialize_predicates = quote! { #(#deserialize_predicates,)* };
 
                 let deserialize_fields = fields.named.iter().map(|f| {
                     let name = &f.ident;
-                    quote! { #name: self.#name.deserialize(deserializer)? }
+                    let value = if is_skipped(f) {
+                        default_value(f)
+                    } else if since(f) != 0 {
+                        // Fields the archive was written without get their
+                        // default value
+                        let field = deserialize_field(f, quote! { field });
+                        let default = default_value(f);
+                        quote! {
+                            match #this.#name() {
+                                Some(field) => #field,
+                                None => #default,
+                            }
+                        }
+                    } else {
+                        deserialize_field(f, quote! { &#this.#name })
+                    };
+                    quote! { #name: #value }
+                });
+
+                let value = into_value(quote! {
+                    #name::<#generic_args> {
+                        #(#deserialize_fields,)*
+                    }
                 });
 
                 quote! {
-                    impl<__D: Fallible + ?Sized, #generic_params> Deserialize<#name<#generic_args>, __D> for Archived<#name<#generic_args>>
+                    #deserialize_impl_header
                     where
                         #generic_predicates
                         #deserialize_predicates
+                        #remote_predicate
                     {
-                        fn deserialize(&self, deserializer: &mut __D) -> Result<#name<#generic_args>, __D::Error> {
-                            Ok(#name::<#generic_args> {
-                                #(#deserialize_fields,)*
-                            })
+                        #deserialize_fn {
+                            Ok(#value)
                         }
                     }
                 }
             }
             Fields::Unnamed(ref fields) => {
                 let deserialize_predicates = fields.unnamed.iter().filter_map(|f| {
-                    if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                    if is_skipped(f) {
+                        default_predicate(f)
+                    } else if omits_bounds(f) {
                         None
                     } else {
-                        let ty = &f.ty;
-                        Some(quote_spanned! { f.span() => #ty: Archive, Archived<#ty>: Deserialize<#ty, __D> })
+                        Some(deserialize_predicate(f))
                     }
                 });
                 let deserialize_predicates = quote! { #(#deserialize_predicates,)* };
 
-                let deserialize_fields = fields.unnamed.iter().enumerate().map(|(i, _)| {
-                    let index = Index::from(i);
-                    quote! { self.#index.deserialize(deserializer)? }
+                let mut archived_index = 0;
+                let deserialize_fields = fields.unnamed.iter().map(|f| {
+                    if is_skipped(f) {
+                        default_value(f)
+                    } else {
+                        let index = Index::from(archived_index);
+                        archived_index += 1;
+                        deserialize_field(f, quote! { &#this.#index })
+                    }
+                });
+
+                let value = into_value(quote! {
+                    #name::<#generic_args>(
+                        #(#deserialize_fields,)*
+                    )
                 });
 
                 quote! {
-                    impl<__D: Fallible + ?Sized, #generic_params> Deserialize<#name<#generic_args>, __D> for Archived<#name<#generic_args>>
+                    #deserialize_impl_header
                     where
                         #generic_predicates
                         #deserialize_predicates
+                        #remote_predicate
                     {
-                        fn deserialize(&self, deserializer: &mut __D) -> Result<#name<#generic_args>, __D::Error> {
-                            Ok(#name::<#generic_args>(
-                                #(#deserialize_fields,)*
-                            ))
+                        #deserialize_fn {
+                            Ok(#value)
                         }
                     }
                 }
             }
-            Fields::Unit => quote! {
-                impl<__D: Fallible + ?Sized, #generic_params> Deserialize<#name<#generic_args>, __D> for Archived<#name<#generic_args>>
-                where
-                    #generic_predicates
-                {
-                    fn deserialize(&self, _: &mut __D) -> Result<#name<#generic_args>, __D::Error> {
-                        Ok(#name::<#generic_args>)
+            Fields::Unit => {
+                let value = into_value(quote! { #name::<#generic_args> });
+
+                quote! {
+                    #deserialize_impl_header
+                    where
+                        #generic_predicates
+                        #remote_predicate
+                    {
+                        #deserialize_fn {
+                            Ok(#value)
+                        }
                     }
                 }
-            },
+            }
         },
         Data::Enum(ref data) => {
             let deserialize_predicates = data.variants.iter().map(|v| match v.fields {
                 Fields::Named(ref fields) => {
                     let deserialize_predicates = fields.named.iter().filter_map(|f| {
-                        if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                        if is_skipped(f) {
+                            default_predicate(f)
+                        } else if omits_bounds(f) {
                             None
                         } else {
-                            let ty = &f.ty;
-                            Some(quote_spanned! { f.span() => #ty: Archive, Archived<#ty>: Deserialize<#ty, __D> })
+                            Some(deserialize_predicate(f))
                         }
                     });
                     quote! { #(#deserialize_predicates,)* }
                 }
                 Fields::Unnamed(ref fields) => {
                     let deserialize_predicates = fields.unnamed.iter().filter_map(|f| {
-                        if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                        if is_skipped(f) {
+                            default_predicate(f)
+                        } else if omits_bounds(f) {
                             None
                         } else {
-                            let ty = &f.ty;
-                            Some(quote_spanned! { f.span() => #ty: Archive, Archived<#ty>: Deserialize<#ty, __D> })
+                            Some(deserialize_predicate(f))
                         }
                     });
                     quote! { #(#deserialize_predicates,)* }
                 }
-                Fields::Unit => quote! {}
+                Fields::Unit => quote! {},
             });
             let deserialize_predicates = quote! { #(#deserialize_predicates)* };
 
@@ -1320,51 +3805,63 @@ fn derive_deserialize_impl(input: &DeriveInput) -> TokenStream {
                 let variant = &v.ident;
                 match v.fields {
                     Fields::Named(ref fields) => {
-                        let bindings = fields.named.iter().map(|f| {
+                        let bindings = fields.named.iter().filter(|f| !is_skipped(f)).map(|f| {
                             let name = &f.ident;
                             quote_spanned! { name.span() => #name }
                         });
                         let fields = fields.named.iter().map(|f| {
                             let name = &f.ident;
+                            let value = if is_skipped(f) {
+                                default_value(f)
+                            } else {
+                                deserialize_field(f, quote! { #name })
+                            };
                             quote! {
-                                #name: #name.deserialize(deserializer)?
+                                #name: #value
                             }
                         });
                         quote_spanned! { variant.span() =>
-                            Self::#variant { #(#bindings,)* } => #name::<#generic_args>::#variant { #(#fields,)* }
+                            #archived_path::#variant { #(#bindings,)* } => #name::<#generic_args>::#variant { #(#fields,)* }
                         }
                     }
                     Fields::Unnamed(ref fields) => {
-                        let bindings = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                        let bindings = fields.unnamed.iter().enumerate().filter(|(_, f)| !is_skipped(f)).map(|(i, f)| {
                             let name = Ident::new(&format!("_{}", i), f.span());
                             quote_spanned! { name.span() => #name }
                         });
                         let fields = fields.unnamed.iter().enumerate().map(|(i, f)| {
-                            let binding = Ident::new(&format!("_{}", i), f.span());
-                            quote! {
-                                #binding.deserialize(deserializer)?
+                            if is_skipped(f) {
+                                default_value(f)
+                            } else {
+                                let binding = Ident::new(&format!("_{}", i), f.span());
+                                deserialize_field(f, quote! { #binding })
                             }
                         });
                         quote_spanned! { variant.span() =>
-                            Self::#variant( #(#bindings,)* ) => #name::<#generic_args>::#variant(#(#fields,)*)
+                            #archived_path::#variant( #(#bindings,)* ) => #name::<#generic_args>::#variant(#(#fields,)*)
                         }
                     }
                     Fields::Unit => {
-                        quote_spanned! { name.span() => Self::#variant => #name::<#generic_args>::#variant }
+                        quote_spanned! { name.span() => #archived_path::#variant => #name::<#generic_args>::#variant }
                     }
                 }
             });
 
+            let value = into_value(quote! {
+                match #this {
+                    #(#deserialize_variants,)*
+                }
+            });
+
             quote! {
-                impl<__D: Fallible + ?Sized, #generic_params> Deserialize<#name<#generic_args>, __D> for Archived<#name<#generic_args>>
+                #deserialize_impl_header
                 where
                     #generic_predicates
                     #deserialize_predicates
+                    #remote_predicate
                 {
-                    fn deserialize(&self, deserializer: &mut __D) -> Result<#name<#generic_args>, __D::Error> {
-                        Ok(match self {
-                            #(#deserialize_variants,)*
-                        })
+                    #deserialize_fn {
+                        Ok(#value)
                     }
                 }
             }
@@ -1383,6 +3880,38 @@ fn derive_deserialize_impl(input: &DeriveInput) -> TokenStream {
     }
 }
 
+fn deserialize_predicate(field: &Field) -> TokenStream {
+    let ty = &field.ty;
+    match field_attributes(field).with {
+        Some(with) => quote_spanned! { field.span() =>
+            rkyv::with::With<#ty, #with>: Archive,
+            #with: rkyv::with::DeserializeWith<Archived<rkyv::with::With<#ty, #with>>, #ty, __D>
+        },
+        None => {
+            quote_spanned! { field.span() => #ty: Archive, Archived<#ty>: Deserialize<#ty, __D> }
+        }
+    }
+}
+
+fn default_predicate(field: &Field) -> Option<TokenStream> {
+    if field_attributes(field).default.is_some() {
+        None
+    } else {
+        let ty = &field.ty;
+        Some(quote_spanned! { field.span() => #ty: Default })
+    }
+}
+
+fn deserialize_field(field: &Field, value: TokenStream) -> TokenStream {
+    let ty = &field.ty;
+    match field_attributes(field).with {
+        Some(with) => quote! {
+            <#with as rkyv::with::DeserializeWith<Archived<rkyv::with::With<#ty, #with>>, #ty, __D>>::deserialize_with(#value, deserializer)?
+        },
+        None => quote! { Deserialize::<#ty, __D>::deserialize(#value, deserializer)? },
+    }
+}
+
 fn derive_deserialize_copy_impl(input: &DeriveInput, attributes: &Attributes) -> TokenStream {
     if let Some((_, span)) = &attributes.archived {
         return Error::new(*span, "archive copy types cannot be named").to_compile_error();
@@ -1405,19 +3934,13 @@ fn derive_deserialize_copy_impl(input: &DeriveInput, attributes: &Attributes) ->
     });
     let generic_args = quote! { #(#generic_args,)* };
 
-    let generic_predicates = match input.generics.where_clause {
-        Some(ref clause) => {
-            let predicates = clause.predicates.iter().map(|p| quote! { #p });
-            quote! { #(#predicates,)* }
-        }
-        None => quote! {},
-    };
+    let generic_predicates = generic_predicates(input, &attributes.deserialize_bound);
 
     let deserialize_impl = match input.data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 let deserialize_predicates = fields.named.iter().filter_map(|f| {
-                    if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                    if omits_bounds(f) {
                         None
                     } else {
                         let ty = &f.ty;
@@ -1440,7 +3963,7 @@ fn derive_deserialize_copy_impl(input: &DeriveInput, attributes: &Attributes) ->
             }
             Fields::Unnamed(ref fields) => {
                 let deserialize_predicates = fields.unnamed.iter().filter_map(|f| {
-                    if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                    if omits_bounds(f) {
                         None
                     } else {
                         let ty = &f.ty;
@@ -1476,7 +3999,7 @@ fn derive_deserialize_copy_impl(input: &DeriveInput, attributes: &Attributes) ->
             let deserialize_predicates = data.variants.iter().map(|v| match v.fields {
                 Fields::Named(ref fields) => {
                     let deserialize_predicates = fields.named.iter().filter_map(|f| {
-                        if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                        if omits_bounds(f) {
                             None
                         } else {
                             let ty = &f.ty;
@@ -1487,7 +4010,7 @@ fn derive_deserialize_copy_impl(input: &DeriveInput, attributes: &Attributes) ->
                 }
                 Fields::Unnamed(ref fields) => {
                     let deserialize_predicates = fields.unnamed.iter().filter_map(|f| {
-                        if f.attrs.iter().any(|a| a.path.is_ident("recursive")) {
+                        if omits_bounds(f) {
                             None
                         } else {
                             let ty = &f.ty;
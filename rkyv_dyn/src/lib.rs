@@ -30,7 +30,12 @@ use core::{
     sync::atomic::AtomicU64,
 };
 use ptr_meta::{DynMetadata, Pointee};
-use rkyv::{de::Deserializer, ser::Serializer, Fallible, Serialize};
+use rkyv::{
+    de::Deserializer,
+    from_archived,
+    ser::{ScratchSpace, Serializer},
+    to_archived, ArchivedU64, Fallible, OffsetError, Serialize,
+};
 pub use rkyv_dyn_derive::archive_dyn;
 use rkyv_typename::TypeName;
 use std::collections::{hash_map::DefaultHasher, HashMap};
@@ -62,6 +67,28 @@ pub trait DynSerializer {
 
     /// Attempts to write the given bytes to the serializer.
     fn write_dyn(&mut self, bytes: &[u8]) -> Result<(), DynError>;
+
+    /// Converts the error returned when a value can't be resolved into a
+    /// `DynError`.
+    fn offset_error_dyn(&self, error: OffsetError) -> DynError;
+
+    /// Allocates scratch space with the given layout.
+    ///
+    /// # Safety
+    ///
+    /// See [`ScratchSpace::push_scratch`].
+    unsafe fn push_scratch_dyn(&mut self, layout: alloc::Layout) -> Result<*mut u8, DynError>;
+
+    /// Frees scratch space that was allocated with `push_scratch_dyn`.
+    ///
+    /// # Safety
+    ///
+    /// See [`ScratchSpace::pop_scratch`].
+    unsafe fn pop_scratch_dyn(
+        &mut self,
+        ptr: *mut u8,
+        layout: alloc::Layout,
+    ) -> Result<(), DynError>;
 }
 
 impl<'a> Fallible for dyn DynSerializer + 'a {
@@ -76,9 +103,27 @@ impl<'a> Serializer for dyn DynSerializer + 'a {
     fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
         self.write_dyn(bytes)
     }
+
+    fn offset_error(&self, error: OffsetError) -> Self::Error {
+        self.offset_error_dyn(error)
+    }
 }
 
-impl<S: Serializer + ?Sized> DynSerializer for &mut S {
+impl<'a> ScratchSpace for dyn DynSerializer + 'a {
+    unsafe fn push_scratch(&mut self, layout: alloc::Layout) -> Result<*mut u8, Self::Error> {
+        self.push_scratch_dyn(layout)
+    }
+
+    unsafe fn pop_scratch(
+        &mut self,
+        ptr: *mut u8,
+        layout: alloc::Layout,
+    ) -> Result<(), Self::Error> {
+        self.pop_scratch_dyn(ptr, layout)
+    }
+}
+
+impl<S: ScratchSpace + Serializer + ?Sized> DynSerializer for &mut S {
     fn pos_dyn(&self) -> usize {
         self.pos()
     }
@@ -89,6 +134,28 @@ impl<S: Serializer + ?Sized> DynSerializer for &mut S {
             Err(e) => Err(Box::new(e)),
         }
     }
+
+    fn offset_error_dyn(&self, error: OffsetError) -> DynError {
+        Box::new(self.offset_error(error))
+    }
+
+    unsafe fn push_scratch_dyn(&mut self, layout: alloc::Layout) -> Result<*mut u8, DynError> {
+        match self.push_scratch(layout) {
+            Ok(ptr) => Ok(ptr),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+
+    unsafe fn pop_scratch_dyn(
+        &mut self,
+        ptr: *mut u8,
+        layout: alloc::Layout,
+    ) -> Result<(), DynError> {
+        match self.pop_scratch(ptr, layout) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
 }
 
 fn hash_type<T: TypeName + ?Sized>() -> u64 {
@@ -272,7 +339,7 @@ pub trait DeserializeDyn<T: Pointee + ?Sized> {
 /// The archived version of `DynMetadata`.
 #[cfg_attr(feature = "strict", repr(C))]
 pub struct ArchivedDynMetadata<T: ?Sized> {
-    type_id: u64,
+    type_id: ArchivedU64,
     #[cfg_attr(not(feature = "vtable_cache"), allow(dead_code))]
     cached_vtable: AtomicU64,
     phantom: PhantomData<T>,
@@ -282,7 +349,7 @@ impl<T: TypeName + ?Sized> ArchivedDynMetadata<T> {
     /// Creates a new `ArchivedDynMetadata` for the given type.
     pub fn new(type_id: u64) -> Self {
         Self {
-            type_id,
+            type_id: to_archived!(type_id),
             cached_vtable: AtomicU64::new(0),
             phantom: PhantomData,
         }
@@ -290,7 +357,7 @@ impl<T: TypeName + ?Sized> ArchivedDynMetadata<T> {
 
     fn lookup_vtable(&self) -> usize {
         IMPL_REGISTRY
-            .get::<T>(self.type_id)
+            .get::<T>(from_archived!(self.type_id))
             .expect("attempted to get vtable for an unregistered impl")
             .vtable
     }
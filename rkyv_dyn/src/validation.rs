@@ -1,18 +1,19 @@
 //! Validation implementations and helper types.
 
 use crate::{ArchivedDynMetadata, RegisteredImpl, IMPL_REGISTRY};
-use bytecheck::{CheckBytes, Unreachable};
+use bytecheck::CheckBytes;
 use core::{
     alloc::Layout,
     any::TypeId,
+    convert::Infallible,
     fmt,
     marker::PhantomData,
     sync::atomic::{AtomicU64, Ordering},
 };
 use rkyv::{
-    offset_of,
+    from_archived, offset_of,
     validation::{ArchiveBoundsContext, ArchiveMemoryContext, SharedArchiveContext},
-    Fallible,
+    ArchivedU64, Fallible,
 };
 use rkyv_typename::TypeName;
 use std::{collections::HashMap, error::Error};
@@ -240,8 +241,8 @@ impl fmt::Display for DynMetadataError {
 
 impl Error for DynMetadataError {}
 
-impl From<Unreachable> for DynMetadataError {
-    fn from(_: Unreachable) -> Self {
+impl From<Infallible> for DynMetadataError {
+    fn from(_: Infallible) -> Self {
         unreachable!();
     }
 }
@@ -255,7 +256,10 @@ impl<T: TypeName + ?Sized, C: ?Sized> CheckBytes<C> for ArchivedDynMetadata<T> {
     ) -> Result<&'a Self, Self::Error> {
         let bytes = value.cast::<u8>();
 
-        let type_id = *u64::check_bytes(bytes.add(offset_of!(Self, type_id)).cast(), context)?;
+        let type_id = from_archived!(*ArchivedU64::check_bytes(
+            bytes.add(offset_of!(Self, type_id)).cast(),
+            context,
+        )?);
         PhantomData::<T>::check_bytes(bytes.add(offset_of!(Self, phantom)).cast(), context)?;
         if let Some(impl_data) = IMPL_REGISTRY.get::<T>(type_id) {
             let cached_vtable =
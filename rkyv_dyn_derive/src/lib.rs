@@ -343,12 +343,13 @@ pub fn archive_dyn(
                 const _: ()  = {
                     use rkyv::{
                         de::Deserializer,
-                        ser::Serializer,
+                        ser::{ScratchSpace, Serializer},
                         Archived,
                         ArchivedMetadata,
                         ArchivePointee,
                         ArchiveUnsized,
                         DeserializeUnsized,
+                        OffsetError,
                         SerializeUnsized,
                     };
                     use rkyv_dyn::{
@@ -381,8 +382,8 @@ pub fn archive_dyn(
                         type Archived = dyn #deserialize_trait<#generic_args>;
                         type MetadataResolver = ();
 
-                        fn resolve_metadata(&self, _: usize, _: Self::MetadataResolver) -> ArchivedMetadata<Self> {
-                            ArchivedDynMetadata::new(self.archived_type_id())
+                        fn resolve_metadata(&self, _: usize, _: Self::MetadataResolver) -> Result<ArchivedMetadata<Self>, OffsetError> {
+                            Ok(ArchivedDynMetadata::new(self.archived_type_id()))
                         }
                     }
 
@@ -394,7 +395,7 @@ pub fn archive_dyn(
                         }
                     }
 
-                    impl<__S: Serializer + ?Sized, #generic_params> SerializeUnsized<__S> for dyn #serialize_trait<#generic_args> {
+                    impl<__S: ScratchSpace + Serializer + ?Sized, #generic_params> SerializeUnsized<__S> for dyn #serialize_trait<#generic_args> {
                         fn serialize_unsized(&self, mut serializer: &mut __S) -> Result<usize, __S::Error> {
                             self.serialize_dyn(&mut serializer).map_err(|e| *e.downcast::<__S::Error>().unwrap())
                         }
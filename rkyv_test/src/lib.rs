@@ -1,10 +1,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 #[cfg(all(test, feature = "validation"))]
 mod validation;
 
 #[cfg(test)]
 mod util {
+    #[cfg(feature = "alloc")]
+    use rkyv::ser::serializers::AllocScratch;
+    #[cfg(not(feature = "alloc"))]
+    use rkyv::ser::serializers::BufferScratch;
     use rkyv::{
         archived_unsized_value, archived_value,
         ser::{serializers::BufferSerializer, Serializer},
@@ -18,13 +25,32 @@ mod util {
 
     pub const BUFFER_SIZE: usize = 256;
 
+    #[cfg(feature = "alloc")]
+    pub type DefaultScratch = AllocScratch;
+
+    #[cfg(feature = "alloc")]
+    pub fn make_default_scratch() -> DefaultScratch {
+        AllocScratch::new()
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    pub type DefaultScratch = BufferScratch<Aligned<[u8; BUFFER_SIZE]>>;
+
+    #[cfg(not(feature = "alloc"))]
+    pub fn make_default_scratch() -> DefaultScratch {
+        BufferScratch::new(Aligned([0u8; BUFFER_SIZE]))
+    }
+
     #[cfg(feature = "std")]
     pub type DefaultSerializer =
-        SharedSerializerAdapter<BufferSerializer<Aligned<[u8; BUFFER_SIZE]>>>;
+        SharedSerializerAdapter<BufferSerializer<Aligned<[u8; BUFFER_SIZE]>, DefaultScratch>>;
 
     #[cfg(feature = "std")]
     pub fn make_default_serializer() -> DefaultSerializer {
-        SharedSerializerAdapter::new(BufferSerializer::new(Aligned([0u8; BUFFER_SIZE])))
+        SharedSerializerAdapter::new(BufferSerializer::with_scratch(
+            Aligned([0u8; BUFFER_SIZE]),
+            make_default_scratch(),
+        ))
     }
 
     #[cfg(feature = "std")]
@@ -41,11 +67,11 @@ mod util {
     }
     
     #[cfg(not(feature = "std"))]
-    pub type DefaultSerializer = BufferSerializer<Aligned<[u8; BUFFER_SIZE]>>;
+    pub type DefaultSerializer = BufferSerializer<Aligned<[u8; BUFFER_SIZE]>, DefaultScratch>;
 
     #[cfg(not(feature = "std"))]
     pub fn make_default_serializer() -> DefaultSerializer {
-        BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]))
+        BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch())
     }
 
     #[cfg(not(feature = "std"))]
@@ -131,7 +157,7 @@ mod no_std_tests {
         test_archive(&123u8);
         test_archive(&123456u32);
         test_archive(&1234567890u128);
-        #[cfg(not(feature = "strict"))]
+        #[cfg(not(any(feature = "strict", feature = "archive_le", feature = "archive_be")))]
         test_archive(&(24, true, 16f32));
         test_archive(&[1, 2, 3, 4, 5, 6]);
 
@@ -144,21 +170,211 @@ mod no_std_tests {
         #[cfg(not(feature = "strict"))]
         test_archive_ref::<[i32; 4]>(&[1, 2, 3, 4]);
         test_archive_ref::<str>("hello world");
+        // Without alloc, only slices of archive copy types can be serialized
+        #[cfg(any(
+            feature = "alloc",
+            not(any(feature = "archive_le", feature = "archive_be"))
+        ))]
         test_archive_ref::<[i32]>([1, 2, 3, 4].as_ref());
     }
 
     #[test]
     fn archive_slices() {
         test_archive_ref::<str>("hello world");
+        // Without alloc, only slices of archive copy types can be serialized
+        #[cfg(any(
+            feature = "alloc",
+            not(any(feature = "archive_le", feature = "archive_be"))
+        ))]
         test_archive_ref::<[i32]>([1, 2, 3, 4].as_ref());
     }
 
     #[test]
     fn archive_empty_slice() {
         test_archive_ref::<[i32; 0]>(&[]);
+        // Without alloc, only slices of archive copy types can be serialized
+        #[cfg(any(
+            feature = "alloc",
+            not(any(feature = "archive_le", feature = "archive_be"))
+        ))]
         test_archive_ref::<[i32]>([].as_ref());
         test_archive_ref::<str>("");
     }
+
+    #[test]
+    fn archive_nonzero_niche() {
+        use core::{mem::size_of, num::NonZeroU32};
+        use rkyv::{
+            core_impl::niche::ArchivedOptionNonZeroU32, from_archived, to_archived, Archived,
+        };
+
+        assert_eq!(size_of::<ArchivedOptionNonZeroU32>(), 4);
+
+        let some = ArchivedOptionNonZeroU32::resolve_from_option(NonZeroU32::new(42));
+        assert!(some.is_some());
+        assert_eq!(some.get(), NonZeroU32::new(42));
+        assert_eq!(some, NonZeroU32::new(42));
+        assert_eq!(from_archived!(*some.as_ref().unwrap()).get(), 42);
+
+        let mut none = ArchivedOptionNonZeroU32::resolve_from_option(None);
+        assert!(none.is_none());
+        assert!(none.as_mut().is_none());
+        assert_eq!(none, None::<NonZeroU32>);
+        assert!(none != some);
+
+        let as_archived: &Archived<NonZeroU32> = some.as_ref().unwrap();
+        assert_eq!(*as_archived, to_archived!(NonZeroU32::new(42).unwrap()));
+    }
+
+    #[cfg(any(feature = "archive_le", feature = "archive_be"))]
+    #[test]
+    fn archive_fixed_endian() {
+        use rkyv::{
+            archived_value,
+            ser::{serializers::BufferSerializer, Serializer},
+            Aligned,
+        };
+
+        let mut serializer = BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]));
+        let pos = serializer
+            .serialize_value(&0x01020304u32)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+
+        #[cfg(feature = "archive_le")]
+        assert_eq!(&buf.as_ref()[pos..pos + 4], &[0x04, 0x03, 0x02, 0x01]);
+        #[cfg(feature = "archive_be")]
+        assert_eq!(&buf.as_ref()[pos..pos + 4], &[0x01, 0x02, 0x03, 0x04]);
+
+        let archived = unsafe { archived_value::<u32>(buf.as_ref(), pos) };
+        assert_eq!(archived.value(), 0x01020304);
+        assert_eq!(*archived, 0x01020304);
+
+        test_archive(&0x0102030405060708u64);
+        test_archive(&-1234i16);
+        test_archive(&1.5f64);
+        test_archive(&'🦀');
+        test_archive(&core::num::NonZeroU32::new(0x01020304).unwrap());
+    }
+
+    #[test]
+    fn archive_hash_map_without_alloc() {
+        use rkyv::{
+            archived_value,
+            core_impl::chd::{ArchivedHashMap, ArchivedHashMapResolver},
+            ser::{
+                adapters::ScratchSpaceAdapter,
+                serializers::{BufferScratch, BufferSerializer},
+                ScratchSpace, Serializer,
+            },
+            to_archived, Aligned, Archive, Archived, OffsetError, Serialize,
+        };
+
+        struct Pairs<'a>(&'a [(u32, u32)]);
+
+        impl Archive for Pairs<'_> {
+            type Archived = ArchivedHashMap<Archived<u32>, Archived<u32>>;
+            type Resolver = ArchivedHashMapResolver;
+
+            fn resolve(
+                &self,
+                pos: usize,
+                resolver: Self::Resolver,
+            ) -> Result<Self::Archived, OffsetError> {
+                resolver.resolve_from_len(pos, self.0.len())
+            }
+        }
+
+        impl<S: ScratchSpace + Serializer + ?Sized> Serialize<S> for Pairs<'_> {
+            fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+                ArchivedHashMap::serialize_from_iter(
+                    self.0.iter().map(|(key, value)| (key, value)),
+                    self.0.len(),
+                    serializer,
+                )
+            }
+        }
+
+        let pairs = [(1, 10), (2, 20), (3, 30), (4, 40), (5, 50)];
+        let mut serializer = ScratchSpaceAdapter::new(
+            BufferSerializer::new(Aligned([0u8; BUFFER_SIZE])),
+            BufferScratch::new(Aligned([0u8; 1024])),
+        );
+        let pos = serializer
+            .serialize_value(&Pairs(&pairs))
+            .expect("failed to archive value");
+        let buf = serializer.into_inner().into_inner();
+        let archived = unsafe { archived_value::<Pairs>(buf.as_ref(), pos) };
+
+        assert_eq!(archived.len(), pairs.len());
+        for (key, value) in pairs.iter() {
+            assert_eq!(
+                archived.get(&to_archived!(*key)),
+                Some(&to_archived!(*value))
+            );
+        }
+        assert!(archived.get(&to_archived!(6u32)).is_none());
+        assert_eq!(archived.iter().count(), pairs.len());
+
+        let mut serializer = ScratchSpaceAdapter::new(
+            BufferSerializer::new(Aligned([0u8; BUFFER_SIZE])),
+            BufferScratch::new(Aligned([0u8; 1024])),
+        );
+        let pos = serializer
+            .serialize_value(&Pairs(&[]))
+            .expect("failed to archive value");
+        let buf = serializer.into_inner().into_inner();
+        let archived = unsafe { archived_value::<Pairs>(buf.as_ref(), pos) };
+
+        assert!(archived.is_empty());
+        assert!(archived.get(&to_archived!(1u32)).is_none());
+        assert!(!archived.contains_key(&to_archived!(1u32)));
+        assert_eq!(archived.iter().count(), 0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn archive_alloc_containers() {
+        use alloc::{
+            boxed::Box,
+            string::{String, ToString},
+            vec,
+            vec::Vec,
+        };
+        use rkyv::{
+            archived_value, de::deserializers::AllocDeserializer, ser::Serializer, Archive,
+            Deserialize, Serialize,
+        };
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        struct Test {
+            name: String,
+            tags: Vec<String>,
+            boxed: Box<[i32]>,
+            text: Box<str>,
+        }
+
+        let value = Test {
+            name: "hello world".to_string(),
+            tags: vec!["a".to_string(), "b".to_string()],
+            boxed: vec![1, 2, 3, 4].into_boxed_slice(),
+            text: "boxed".to_string().into_boxed_str(),
+        };
+
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
+        assert_eq!(archived.name, value.name);
+        assert_eq!(archived.tags, value.tags);
+        assert_eq!(archived.boxed.len(), value.boxed.len());
+        assert_eq!(&*archived.text, &*value.text);
+
+        let deserialized: Test = archived.deserialize(&mut AllocDeserializer).unwrap();
+        assert_eq!(deserialized, value);
+    }
 }
 
 #[cfg(feature = "std")]
@@ -177,12 +393,15 @@ mod tests {
         SerializeUnsized,
         archived_value,
         archived_value_mut,
+        from_archived,
+        to_archived,
         de::{
             adapters::SharedDeserializerAdapter,
             deserializers::AllocDeserializer,
             Deserializer,
         },
         ser::{
+            ScratchSpace,
             SeekSerializer,
             Serializer,
             adapters::SharedSerializerAdapter,
@@ -267,12 +486,25 @@ mod tests {
 
         test_archive(&hash_map);
 
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch());
+        let pos = serializer
+            .serialize_value(&hash_map)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let archived_map = unsafe { archived_value::<HashMap<i32, i32>>(buf.as_ref(), pos) };
+
+        assert_eq!(archived_map.get(&1), Some(&to_archived!(2)));
+        assert_eq!(archived_map[&7], 8);
+        assert!(!archived_map.contains_key(&2));
+
         let mut hash_map = HashMap::new();
         hash_map.insert("hello".to_string(), "world".to_string());
         hash_map.insert("foo".to_string(), "bar".to_string());
         hash_map.insert("baz".to_string(), "bat".to_string());
 
-        let mut serializer = BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]));
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch());
         let pos = serializer
             .serialize_value(&hash_map)
             .expect("failed to archive value");
@@ -293,6 +525,270 @@ mod tests {
         }
     }
 
+    #[test]
+    fn archive_btree_map() {
+        use std::{
+            collections::{BTreeMap, BTreeSet},
+            ops::Bound,
+        };
+
+        test_archive(&BTreeMap::<i32, i32>::new());
+
+        let mut btree_map = BTreeMap::new();
+        btree_map.insert(-3, 4);
+        btree_map.insert(1, 2);
+        btree_map.insert(7, 8);
+        btree_map.insert(5, 6);
+
+        test_archive(&btree_map);
+
+        let mut btree_map = BTreeMap::new();
+        btree_map.insert("hello".to_string(), 1);
+        btree_map.insert("foo".to_string(), 2);
+        btree_map.insert("baz".to_string(), 3);
+        btree_map.insert("bar".to_string(), 4);
+
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&btree_map)
+            .expect("failed to archive value");
+        let mut buf = unwrap_default_serializer(serializer);
+        let archived_map = unsafe { archived_value::<BTreeMap<String, i32>>(buf.as_ref(), pos) };
+
+        assert_eq!(archived_map.len(), btree_map.len());
+        assert!(archived_map.contains_key("foo"));
+        assert!(!archived_map.contains_key("qux"));
+        assert_eq!(from_archived!(archived_map["hello"]), 1);
+        assert!(archived_map.get("qux").is_none());
+
+        let keys = archived_map.keys().map(|k| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, ["bar", "baz", "foo", "hello"]);
+        assert!(archived_map.iter().rev().map(|(k, _)| k.as_str()).eq([
+            "hello", "foo", "baz", "bar"
+        ]
+        .iter()
+        .copied()));
+
+        let range = archived_map
+            .range::<str, _>((Bound::Included("baz"), Bound::Included("foo")))
+            .map(|(k, v)| (k.as_str(), from_archived!(*v)))
+            .collect::<Vec<_>>();
+        assert_eq!(range, [("baz", 3), ("foo", 2)]);
+        assert_eq!(
+            archived_map
+                .range::<str, _>((Bound::Included("c"), Bound::Excluded("f")))
+                .count(),
+            0
+        );
+        assert_eq!(
+            archived_map
+                .range::<str, _>((Bound::Included("g"), Bound::Excluded("a")))
+                .count(),
+            0
+        );
+        assert_eq!(archived_map.range::<str, _>(..).len(), 4);
+
+        let (first, _) = archived_map.first_key_value().unwrap();
+        assert_eq!(first.as_str(), "bar");
+        let (last, _) = archived_map.last_key_value().unwrap();
+        assert_eq!(last.as_str(), "hello");
+
+        let mut deserializer = make_default_deserializer();
+        let deserialized: BTreeMap<String, i32> =
+            archived_map.deserialize(&mut deserializer).unwrap();
+        assert_eq!(deserialized, btree_map);
+
+        let mut archived_map =
+            unsafe { archived_value_mut::<BTreeMap<String, i32>>(Pin::new(buf.as_mut()), pos) };
+        *archived_map.as_mut().get_pin("foo").unwrap() = to_archived!(20);
+        for mut value in archived_map.as_mut().values_pin() {
+            *value = to_archived!(from_archived!(*value) + 100);
+        }
+        assert_eq!(from_archived!(archived_map["foo"]), 120);
+        assert_eq!(from_archived!(archived_map["hello"]), 101);
+
+        let btree_set = ["hello", "foo", "baz", "bar"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<BTreeSet<_>>();
+
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&btree_set)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived_set = unsafe { archived_value::<BTreeSet<String>>(buf.as_ref(), pos) };
+
+        assert!(*archived_set == btree_set);
+        assert!(archived_set.contains("baz"));
+        assert!(!archived_set.contains("qux"));
+        assert_eq!(archived_set.first().unwrap().as_str(), "bar");
+        assert_eq!(archived_set.last().unwrap().as_str(), "hello");
+        assert!(archived_set
+            .range::<str, _>((Bound::Excluded("c"), Bound::Unbounded))
+            .eq(["foo", "hello"].iter()));
+
+        let mut deserializer = make_default_deserializer();
+        let deserialized: BTreeSet<String> = archived_set.deserialize(&mut deserializer).unwrap();
+        assert_eq!(deserialized, btree_set);
+    }
+
+    #[cfg(any(feature = "archive_le", feature = "archive_be"))]
+    #[test]
+    fn archive_btree_map_native_keys() {
+        use std::{
+            collections::{BTreeMap, BTreeSet},
+            ops::Bound,
+        };
+
+        let btree_map = [(-300, 1), (-2, 2), (5, 3), (256, 4), (70000, 5)]
+            .iter()
+            .copied()
+            .collect::<BTreeMap<i32, i32>>();
+
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&btree_map)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived_map = unsafe { archived_value::<BTreeMap<i32, i32>>(buf.as_ref(), pos) };
+
+        for (key, value) in btree_map.iter() {
+            assert!(archived_map.contains_key(key));
+            assert_eq!(archived_map.get(key).unwrap().value(), *value);
+            assert_eq!(archived_map[key].value(), *value);
+        }
+        assert!(!archived_map.contains_key(&0));
+        assert!(archived_map.get(&256).is_some());
+        assert!(archived_map
+            .range((Bound::Included(-2), Bound::Excluded(256)))
+            .map(|(k, _)| k.value())
+            .eq([-2, 5].iter().copied()));
+
+        let btree_set = btree_map.keys().copied().collect::<BTreeSet<i32>>();
+
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&btree_set)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived_set = unsafe { archived_value::<BTreeSet<i32>>(buf.as_ref(), pos) };
+
+        assert!(archived_set.contains(&-300));
+        assert!(!archived_set.contains(&300));
+        assert_eq!(archived_set.get(&70000).unwrap().value(), 70000);
+        assert!(archived_set
+            .range(0..)
+            .map(|k| k.value())
+            .eq([5, 256, 70000].iter().copied()));
+    }
+
+    #[test]
+    fn archive_collections() {
+        use std::collections::{BinaryHeap, LinkedList, VecDeque};
+
+        test_archive(&VecDeque::<i32>::new());
+
+        let mut vec_deque = VecDeque::with_capacity(4);
+        vec_deque.push_back(3);
+        vec_deque.push_back(4);
+        vec_deque.push_front(2);
+        vec_deque.push_front(1);
+
+        test_archive(&vec_deque);
+
+        let mut linked_list = LinkedList::new();
+        linked_list.push_back("hello".to_string());
+        linked_list.push_back("world".to_string());
+
+        test_archive(&linked_list);
+
+        let mut serializer = make_default_serializer();
+        let deque_pos = serializer
+            .serialize_value(&vec_deque)
+            .expect("failed to archive value");
+        let heap = vec![3, 1, 4, 1, 5, 9, 2, 6]
+            .into_iter()
+            .collect::<BinaryHeap<i32>>();
+        let heap_pos = serializer
+            .serialize_value(&heap)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+
+        let archived_deque = unsafe { archived_value::<VecDeque<i32>>(buf.as_ref(), deque_pos) };
+        assert_eq!(archived_deque.len(), 4);
+        assert_eq!(from_archived!(*archived_deque.front().unwrap()), 1);
+        assert_eq!(from_archived!(*archived_deque.back().unwrap()), 4);
+        assert_eq!(from_archived!(archived_deque[1]), 2);
+        assert!(*archived_deque == vec_deque);
+
+        let archived_heap = unsafe { archived_value::<BinaryHeap<i32>>(buf.as_ref(), heap_pos) };
+        assert_eq!(archived_heap.len(), heap.len());
+        assert_eq!(from_archived!(*archived_heap.peek().unwrap()), 9);
+
+        let mut deserializer = make_default_deserializer();
+        let deserialized: BinaryHeap<i32> = archived_heap.deserialize(&mut deserializer).unwrap();
+        assert_eq!(deserialized.into_sorted_vec(), heap.into_sorted_vec());
+    }
+    #[test]
+    fn archive_borrowed() {
+        use std::borrow::Cow;
+
+        #[derive(Archive, Serialize)]
+        struct Test<'a> {
+            name: &'a str,
+            values: &'a [i32],
+            value: &'a i32,
+            note: Cow<'a, str>,
+            tags: Cow<'a, [String]>,
+            count: Cow<'a, u32>,
+        }
+
+        let input = "hello world".to_string();
+        let values = [1, 2, 3];
+        let tags = vec!["foo".to_string(), "bar".to_string()];
+        let value = Test {
+            name: &input[..5],
+            values: &values,
+            value: &42,
+            note: Cow::Borrowed(&input[6..]),
+            tags: Cow::Borrowed(&tags),
+            count: Cow::Owned(7),
+        };
+
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived_value = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
+
+        assert_eq!(archived_value.name, "hello");
+        assert_eq!(archived_value.values.len(), 3);
+        assert_eq!(from_archived!(archived_value.values[2]), 3);
+        assert_eq!(from_archived!(*archived_value.value), 42);
+        assert_eq!(archived_value.note, "world");
+        assert!(archived_value.tags == tags);
+        assert_eq!(from_archived!(*archived_value.count), 7);
+
+        let mut deserializer = make_default_deserializer();
+        let name: String = archived_value.name.deserialize(&mut deserializer).unwrap();
+        assert_eq!(name, "hello");
+        let values: Vec<i32> = archived_value
+            .values
+            .deserialize(&mut deserializer)
+            .unwrap();
+        assert_eq!(values, [1, 2, 3]);
+        let value: Box<i32> = archived_value.value.deserialize(&mut deserializer).unwrap();
+        assert_eq!(*value, 42);
+        let note: Cow<str> = archived_value.note.deserialize(&mut deserializer).unwrap();
+        assert!(matches!(note, Cow::Owned(ref note) if note == "world"));
+        let tags: Cow<[String]> = archived_value.tags.deserialize(&mut deserializer).unwrap();
+        assert_eq!(*tags, ["foo".to_string(), "bar".to_string()]);
+        let count: Cow<u32> = archived_value.count.deserialize(&mut deserializer).unwrap();
+        assert_eq!(*count, 7);
+    }
+
     #[test]
     fn archive_unit_struct() {
         #[derive(Archive, Serialize, Deserialize, PartialEq)]
@@ -531,6 +1027,8 @@ mod tests {
         ]);
     }
 
+    // Multibyte primitives aren't ArchiveCopy with a fixed endianness
+    #[cfg(not(any(feature = "archive_le", feature = "archive_be")))]
     #[test]
     fn archive_copy() {
         #[derive(Archive, Serialize, Deserialize, Clone, Copy, PartialEq)]
@@ -599,7 +1097,7 @@ mod tests {
 
     #[test]
     fn manual_archive_dyn() {
-        use rkyv::{ArchivePointee, ArchivedMetadata};
+        use rkyv::{ArchivePointee, ArchivedMetadata, OffsetError};
         use rkyv_dyn::{
             register_impl, ArchivedDynMetadata, DeserializeDyn, DynDeserializer, DynError,
             RegisteredImpl, SerializeDyn,
@@ -637,8 +1135,8 @@ mod tests {
                 &self,
                 _: usize,
                 _: Self::MetadataResolver,
-            ) -> ArchivedMetadata<Self> {
-                ArchivedDynMetadata::new(self.archived_type_id())
+            ) -> Result<ArchivedMetadata<Self>, OffsetError> {
+                Ok(ArchivedDynMetadata::new(self.archived_type_id()))
             }
         }
 
@@ -652,7 +1150,7 @@ mod tests {
             }
         }
 
-        impl<S: Serializer + ?Sized> SerializeUnsized<S> for dyn SerializeTestTrait {
+        impl<S: ScratchSpace + Serializer + ?Sized> SerializeUnsized<S> for dyn SerializeTestTrait {
             fn serialize_unsized(&self, mut serializer: &mut S) -> Result<usize, S::Error> {
                 self.serialize_dyn(&mut serializer)
                     .map_err(|e| *e.downcast::<S::Error>().unwrap())
@@ -728,13 +1226,14 @@ mod tests {
 
         impl TestTrait for Archived<Test> {
             fn get_id(&self) -> i32 {
-                self.id
+                from_archived!(self.id)
             }
         }
 
         let value: Box<dyn SerializeTestTrait> = Box::new(Test { id: 42 });
 
-        let mut serializer = BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]));
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch());
         let pos = serializer
             .serialize_value(&value)
             .expect("failed to archive value");
@@ -777,13 +1276,14 @@ mod tests {
 
         impl TestTrait for Archived<Test> {
             fn get_id(&self) -> i32 {
-                self.id
+                from_archived!(self.id)
             }
         }
 
         let value: Box<dyn STestTrait> = Box::new(Test { id: 42 });
 
-        let mut serializer = BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]));
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch());
         let pos = serializer
             .serialize_value(&value)
             .expect("failed to archive value");
@@ -829,7 +1329,7 @@ mod tests {
 
         impl TestTrait<i32> for ArchivedTest<i32> {
             fn get_value(&self) -> i32 {
-                self.value
+                from_archived!(self.value)
             }
         }
 
@@ -889,7 +1389,8 @@ mod tests {
             value: "hello world".to_string(),
         });
 
-        let mut serializer = BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]));
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch());
         let i32_pos = serializer
             .serialize_value(&i32_value)
             .expect("failed to archive value");
@@ -954,13 +1455,17 @@ mod tests {
         };
 
         TestTuple(42);
-        ArchivedTestTuple(42);
+        ArchivedTestTuple(to_archived!(42));
         TestStruct { value: 42 };
-        ArchivedTestStruct { value: 42 };
+        ArchivedTestStruct {
+            value: to_archived!(42),
+        };
         TestEnum::B(42);
         TestEnum::C { value: 42 };
-        ArchivedTestEnum::B(42);
-        ArchivedTestEnum::C { value: 42 };
+        ArchivedTestEnum::B(to_archived!(42));
+        ArchivedTestEnum::C {
+            value: to_archived!(42),
+        };
     }
 
     #[test]
@@ -970,7 +1475,7 @@ mod tests {
         let mut buf = serializer.into_inner();
         let mut value = unsafe { archived_value_mut::<i32>(Pin::new(buf.as_mut()), pos) };
         assert_eq!(*value, 42);
-        *value = 11;
+        *value = to_archived!(11);
         assert_eq!(*value, 11);
     }
 
@@ -985,20 +1490,6 @@ mod tests {
             c: HashMap<i32, [i32; 2]>,
         }
 
-        impl ArchivedTest {
-            fn a(self: Pin<&mut Self>) -> Pin<&mut Archived<Box<i32>>> {
-                unsafe { self.map_unchecked_mut(|s| &mut s.a) }
-            }
-
-            fn b(self: Pin<&mut Self>) -> Pin<&mut Archived<Vec<String>>> {
-                unsafe { self.map_unchecked_mut(|s| &mut s.b) }
-            }
-
-            fn c(self: Pin<&mut Self>) -> Pin<&mut Archived<HashMap<i32, [i32; 2]>>> {
-                unsafe { self.map_unchecked_mut(|s| &mut s.c) }
-            }
-        }
-
         let mut value = Test {
             a: Box::new(10),
             b: vec!["hello".to_string(), "world".to_string()],
@@ -1008,7 +1499,8 @@ mod tests {
         value.c.insert(1, [4, 2]);
         value.c.insert(5, [17, 24]);
 
-        let mut serializer = BufferSerializer::new(Aligned([0u8; 256]));
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; 256]), make_default_scratch());
         let pos = serializer.serialize_value(&value).unwrap();
         let mut buf = serializer.into_inner();
         let mut value = unsafe { archived_value_mut::<Test>(Pin::new(buf.as_mut()), pos) };
@@ -1018,10 +1510,10 @@ mod tests {
         assert_eq!(value.b[0], "hello");
         assert_eq!(value.b[1], "world");
         assert_eq!(value.c.len(), 2);
-        assert_eq!(value.c.get(&1).unwrap(), &[4, 2]);
-        assert_eq!(value.c.get(&5).unwrap(), &[17, 24]);
+        assert_eq!(value.c.get(&to_archived!(1)).unwrap(), &[4, 2]);
+        assert_eq!(value.c.get(&to_archived!(5)).unwrap(), &[17, 24]);
 
-        *value.as_mut().a().get_pin() = 50;
+        *value.as_mut().a().get_pin() = to_archived!(50);
         assert_eq!(*value.a, 50);
 
         value
@@ -1039,14 +1531,14 @@ mod tests {
         assert_eq!(value.b[0], "HELLO");
         assert_eq!(value.b[1], "WORLD");
 
-        let mut c1 = value.as_mut().c().get_pin(&1).unwrap();
-        c1[0] = 7;
-        c1[1] = 18;
-        assert_eq!(value.c.get(&1).unwrap(), &[7, 18]);
-        let mut c5 = value.as_mut().c().get_pin(&5).unwrap();
-        c5[0] = 6;
-        c5[1] = 99;
-        assert_eq!(value.c.get(&5).unwrap(), &[6, 99]);
+        let mut c1 = value.as_mut().c().get_pin(&to_archived!(1)).unwrap();
+        c1[0] = to_archived!(7);
+        c1[1] = to_archived!(18);
+        assert_eq!(value.c.get(&to_archived!(1)).unwrap(), &[7, 18]);
+        let mut c5 = value.as_mut().c().get_pin(&to_archived!(5)).unwrap();
+        c5[0] = to_archived!(6);
+        c5[1] = to_archived!(99);
+        assert_eq!(value.c.get(&to_archived!(5)).unwrap(), &[6, 99]);
     }
 
     #[test]
@@ -1072,7 +1564,7 @@ mod tests {
             panic!("incorrect enum after archiving");
         }
 
-        *value = Archived::<Test>::C(42);
+        *value = Archived::<Test>::C(to_archived!(42));
 
         if let Archived::<Test>::C(i) = *value {
             assert_eq!(i, 42);
@@ -1081,6 +1573,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn pin_projections() {
+        #[allow(dead_code)]
+        #[derive(Archive, Serialize)]
+        struct Pair(Box<i32>, #[archive(skip)] u32, String);
+
+        let value = Pair(Box::new(10), 20, "hello".to_string());
+
+        let mut serializer = BufferSerializer::new(Aligned([0u8; 256]));
+        let pos = serializer.serialize_value(&value).unwrap();
+        let mut buf = serializer.into_inner();
+        let mut value = unsafe { archived_value_mut::<Pair>(Pin::new(buf.as_mut()), pos) };
+
+        *value.as_mut().field_0().get_pin() = to_archived!(50);
+        value.as_mut().field_2().str_pin().make_ascii_uppercase();
+        assert_eq!(*value.0, 50);
+        assert_eq!(value.1, "HELLO");
+
+        #[allow(dead_code)]
+        #[derive(Archive, Serialize)]
+        enum Test {
+            A,
+            B(String, #[archive(skip)] u32, Box<i32>),
+            C { values: Vec<String> },
+        }
+
+        let value = Test::B("hello".to_string(), 20, Box::new(10));
+
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; 256]), make_default_scratch());
+        let pos = serializer.serialize_value(&value).unwrap();
+        let mut buf = serializer.into_inner();
+        let mut value = unsafe { archived_value_mut::<Test>(Pin::new(buf.as_mut()), pos) };
+
+        match value.as_mut().project() {
+            ArchivedTestProjection::B(string, boxed) => {
+                string.str_pin().make_ascii_uppercase();
+                *boxed.get_pin() = to_archived!(42);
+            }
+            _ => panic!("incorrect projection variant"),
+        }
+
+        if let Archived::<Test>::B(string, boxed) = &*value {
+            assert_eq!(string.as_str(), "HELLO");
+            assert_eq!(**boxed, 42);
+        } else {
+            panic!("incorrect enum after mutation");
+        }
+
+        let value = Test::C {
+            values: vec!["hello".to_string(), "world".to_string()],
+        };
+
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; 256]), make_default_scratch());
+        let pos = serializer.serialize_value(&value).unwrap();
+        let mut buf = serializer.into_inner();
+        let mut value = unsafe { archived_value_mut::<Test>(Pin::new(buf.as_mut()), pos) };
+
+        if let ArchivedTestProjection::C { values } = value.as_mut().project() {
+            values.index_pin(1).str_pin().make_ascii_uppercase();
+        } else {
+            panic!("incorrect projection variant");
+        }
+
+        if let Archived::<Test>::C { values } = &*value {
+            assert_eq!(values[0], "hello");
+            assert_eq!(values[1], "WORLD");
+        } else {
+            panic!("incorrect enum after mutation");
+        }
+    }
+
     #[test]
     fn mutable_dyn_ref() {
         use rkyv_dyn::archive_dyn;
@@ -1111,19 +1676,20 @@ mod tests {
 
         impl TestTrait for Archived<Test> {
             fn value(&self) -> i32 {
-                self.0
+                from_archived!(self.0)
             }
             fn set_value(self: Pin<&mut Self>, value: i32) {
                 unsafe {
                     let s = self.get_unchecked_mut();
-                    s.0 = value;
+                    s.0 = to_archived!(value);
                 }
             }
         }
 
         let value = Box::new(Test(10)) as Box<dyn SerializeTestTrait>;
 
-        let mut serializer = BufferSerializer::new(Aligned([0u8; 256]));
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; 256]), make_default_scratch());
         let pos = serializer.serialize_value(&value).unwrap();
         let mut buf = serializer.into_inner();
         let mut value = unsafe {
@@ -1137,49 +1703,477 @@ mod tests {
 
     #[test]
     fn recursive_structures() {
-        #[derive(Archive, PartialEq)]
+        #[derive(Archive, Serialize, Deserialize, PartialEq)]
+        #[archive(
+            bound(serialize = "__S: Serializer", deserialize = "__D: Deserializer"),
+            compare(PartialEq)
+        )]
         enum Node {
             Nil,
-            Cons(#[recursive] Box<Node>),
+            Cons(#[omit_bounds] Box<Node>),
         }
 
-        // Right now the derive macros don't apply the right bounds from Box so
-        // we have to implement it manually. Luckily it's not too bad, but
-        // hopefully in the future there's a better way to do this.
-        impl<S: Serializer + ?Sized> Serialize<S> for Node {
-            fn serialize(&self, serializer: &mut S) -> Result<NodeResolver, S::Error> {
-                Ok(match self {
-                    Node::Nil => NodeResolver::Nil,
-                    Node::Cons(inner) => NodeResolver::Cons(inner.serialize(serializer)?),
-                })
-            }
-        }
+        test_archive(&Node::Cons(Box::new(Node::Cons(Box::new(Node::Nil)))));
+    }
 
-        impl<D: Deserializer + ?Sized> Deserialize<Node, D> for ArchivedNode {
-            fn deserialize(&self, deserializer: &mut D) -> Result<Node, D::Error> {
-                Ok(match self {
-                    ArchivedNode::Nil => Node::Nil,
-                    ArchivedNode::Cons(inner) => Node::Cons(inner.deserialize(deserializer)?),
-                })
-            }
+    #[test]
+    fn custom_bounds() {
+        use rkyv::ser::serializers::WriteSerializer;
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(
+            bound(
+                serialize = "__S: ScratchSpace + Serializer",
+                deserialize = "__D: Deserializer"
+            ),
+            compare(PartialEq)
+        )]
+        struct Tree<T> {
+            value: T,
+            #[omit_bounds]
+            children: Vec<Tree<T>>,
         }
 
-        impl PartialEq<Node> for Archived<Node> {
-            fn eq(&self, other: &Node) -> bool {
-                match self {
-                    Archived::<Node>::Nil => match other {
-                        Node::Nil => true,
-                        _ => false,
-                    },
-                    Archived::<Node>::Cons(ar_inner) => match other {
-                        Node::Cons(inner) => ar_inner == inner,
-                        _ => false,
-                    },
-                }
-            }
-        }
+        let value = Tree {
+            value: 1u32,
+            children: vec![
+                Tree {
+                    value: 2u32,
+                    children: Vec::new(),
+                },
+                Tree {
+                    value: 3u32,
+                    children: vec![Tree {
+                        value: 4u32,
+                        children: Vec::new(),
+                    }],
+                },
+            ],
+        };
 
-        test_archive(&Node::Cons(Box::new(Node::Cons(Box::new(Node::Nil)))));
+        test_archive(&value);
+
+        let mut serializer = WriteSerializer::new(Vec::new());
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let archived = unsafe { archived_value::<Tree<u32>>(buf.as_ref(), pos) };
+
+        let mut deserializer = AllocDeserializer;
+        let deserialized = archived.deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, deserialized);
+    }
+
+    #[test]
+    fn archive_enum_repr() {
+        use core::mem::size_of;
+        use rkyv::std_impl::ArchivedString;
+
+        #[derive(Archive, Serialize, Deserialize, PartialEq)]
+        #[archive(repr(u16), compare(PartialEq))]
+        enum Small {
+            A = 5,
+            B,
+            C = 300,
+        }
+
+        assert_eq!(size_of::<ArchivedSmall>(), 2);
+        // Multi-byte tags are stored in the archive's byte order
+        let tag_bytes = |tag: ArchivedSmall| (tag as u16).to_ne_bytes();
+        let expected_bytes = |value: u16| {
+            if cfg!(feature = "archive_le") {
+                value.to_le_bytes()
+            } else if cfg!(feature = "archive_be") {
+                value.to_be_bytes()
+            } else {
+                value.to_ne_bytes()
+            }
+        };
+        assert_eq!(tag_bytes(ArchivedSmall::A), expected_bytes(5));
+        assert_eq!(tag_bytes(ArchivedSmall::B), expected_bytes(6));
+        assert_eq!(tag_bytes(ArchivedSmall::C), expected_bytes(300));
+        test_archive(&Small::A);
+        test_archive(&Small::B);
+        test_archive(&Small::C);
+
+        #[derive(Archive, Serialize, Deserialize, PartialEq)]
+        #[archive(repr(i32), compare(PartialEq))]
+        #[repr(i32)]
+        enum Signed {
+            Negative = -1,
+            Value(String) = 7,
+        }
+
+        #[allow(dead_code)]
+        #[repr(i32)]
+        enum NativeSigned {
+            Negative = -1,
+            Value(ArchivedString) = 7,
+        }
+
+        assert_eq!(size_of::<Archived<Signed>>(), size_of::<NativeSigned>());
+        test_archive(&Signed::Negative);
+        test_archive(&Signed::Value("hello world".to_string()));
+
+        // Without a repr, discriminants that don't fit in the archived repr
+        // aren't kept
+        #[derive(Archive, Serialize, Deserialize, PartialEq)]
+        #[archive(compare(PartialEq))]
+        enum Big {
+            A = 1000,
+            B,
+        }
+
+        assert_eq!(size_of::<ArchivedBig>(), 1);
+        test_archive(&Big::A);
+        test_archive(&Big::B);
+
+        // The repr of the labeled enum is used for the archived enum
+        #[derive(Archive, Serialize, Deserialize, PartialEq)]
+        #[archive(compare(PartialEq))]
+        #[repr(i8)]
+        enum Neg {
+            A = -1,
+            B,
+        }
+
+        assert_eq!(size_of::<ArchivedNeg>(), 1);
+        assert_eq!(ArchivedNeg::A as i8, -1);
+        assert_eq!(ArchivedNeg::B as i8, 0);
+        test_archive(&Neg::A);
+        test_archive(&Neg::B);
+
+        #[derive(Archive, Serialize, Deserialize, PartialEq)]
+        #[archive(repr(C), compare(PartialEq))]
+        enum Mixed {
+            A(u8, String),
+            B { value: u64, name: String },
+            C,
+        }
+
+        #[allow(dead_code)]
+        #[repr(C)]
+        enum Native {
+            A(u8, ArchivedString),
+            B { value: u64, name: ArchivedString },
+            C,
+        }
+
+        assert_eq!(size_of::<ArchivedMixed>(), size_of::<Native>());
+        test_archive(&Mixed::A(1, "hello world".to_string()));
+        test_archive(&Mixed::B {
+            value: 42,
+            name: "hello world".to_string(),
+        });
+        test_archive(&Mixed::C);
+    }
+
+    #[test]
+    fn archive_compare() {
+        use core::cmp::Ordering;
+        use rkyv::ser::serializers::WriteSerializer;
+        use std::collections::HashMap;
+
+        #[allow(dead_code)]
+        #[derive(Archive, Serialize)]
+        #[archive(compare(PartialEq, PartialOrd))]
+        struct Named {
+            a: u32,
+            b: String,
+            c: Option<Vec<u32>>,
+            #[archive(skip)]
+            d: u32,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Archive, Serialize)]
+        #[archive(compare(PartialEq, PartialOrd))]
+        struct Unnamed(#[archive(skip)] u32, String, i32);
+
+        #[derive(Archive, Serialize)]
+        #[archive(compare(PartialEq))]
+        struct Map {
+            values: HashMap<u8, String>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(Archive, Serialize)]
+        #[archive(compare(PartialEq, PartialOrd))]
+        enum Enum<T> {
+            A,
+            B(#[archive(skip)] u32, T),
+            C { value: T, name: String },
+        }
+
+        let value = Named {
+            a: 42,
+            b: "hello world".to_string(),
+            c: Some(vec![1, 2, 3]),
+            d: 10,
+        };
+        let mut serializer = WriteSerializer::new(Vec::new());
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let archived = unsafe { archived_value::<Named>(buf.as_ref(), pos) };
+
+        assert!(*archived == value);
+        assert!(value == *archived);
+        let mut other = Named {
+            a: 42,
+            b: "hello world".to_string(),
+            c: Some(vec![1, 2, 3]),
+            d: 20,
+        };
+        assert!(*archived == other);
+        other.b = "hello there".to_string();
+        assert!(*archived != other);
+        assert_eq!(archived.partial_cmp(&other), Some(Ordering::Greater));
+        assert_eq!(other.partial_cmp(archived), Some(Ordering::Less));
+        other.a = 43;
+        assert_eq!(archived.partial_cmp(&other), Some(Ordering::Less));
+        assert_eq!(other.partial_cmp(archived), Some(Ordering::Greater));
+
+        let value = Unnamed(10, "hello world".to_string(), -1);
+        let mut serializer = WriteSerializer::new(Vec::new());
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let archived = unsafe { archived_value::<Unnamed>(buf.as_ref(), pos) };
+
+        assert!(*archived == Unnamed(20, "hello world".to_string(), -1));
+        assert!(*archived < Unnamed(10, "hello world".to_string(), 0));
+        assert!(Unnamed(10, "hello".to_string(), 0) < *archived);
+
+        let mut values = HashMap::new();
+        values.insert(1, "a".to_string());
+        values.insert(2, "b".to_string());
+        let value = Map { values };
+        let mut serializer = WriteSerializer::new(Vec::new());
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let archived = unsafe { archived_value::<Map>(buf.as_ref(), pos) };
+
+        assert!(*archived == value);
+        assert!(value == *archived);
+
+        let value = Enum::C {
+            value: 1u32,
+            name: "hello world".to_string(),
+        };
+        let mut serializer = WriteSerializer::new(Vec::new());
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let archived = unsafe { archived_value::<Enum<u32>>(buf.as_ref(), pos) };
+
+        assert!(*archived == value);
+        assert!(*archived != Enum::A);
+        assert!(*archived > Enum::A);
+        assert!(*archived > Enum::B(0, 2));
+        assert!(Enum::B(0, 2) < *archived);
+        assert!(
+            *archived
+                < Enum::C {
+                    value: 2,
+                    name: "hello world".to_string(),
+                }
+        );
+    }
+
+    #[test]
+    fn archive_niche_options() {
+        use core::mem::size_of;
+        use rkyv::std_impl::{
+            niche::{ArchivedOptionBox, ArchivedOptionString, ArchivedOptionVec},
+            ArchivedBox, BoxResolver, StringResolver, VecResolver,
+        };
+        use rkyv::OffsetError;
+
+        struct Test {
+            a: Option<Box<i32>>,
+            b: Option<String>,
+            c: Option<Vec<u32>>,
+        }
+
+        struct ArchivedTest {
+            a: ArchivedOptionBox<Archived<i32>>,
+            b: ArchivedOptionString,
+            c: ArchivedOptionVec<Archived<u32>>,
+        }
+
+        struct TestResolver {
+            a: Option<BoxResolver<()>>,
+            b: Option<StringResolver>,
+            c: Option<VecResolver<()>>,
+        }
+
+        impl Archive for Test {
+            type Archived = ArchivedTest;
+            type Resolver = TestResolver;
+
+            fn resolve(
+                &self,
+                pos: usize,
+                resolver: TestResolver,
+            ) -> Result<ArchivedTest, OffsetError> {
+                Ok(ArchivedTest {
+                    a: ArchivedOptionBox::resolve_from_option(
+                        self.a.as_deref(),
+                        pos + rkyv::offset_of!(ArchivedTest, a),
+                        resolver.a,
+                    )?,
+                    b: ArchivedOptionString::resolve_from_option(
+                        self.b.as_deref(),
+                        pos + rkyv::offset_of!(ArchivedTest, b),
+                        resolver.b,
+                    )?,
+                    c: ArchivedOptionVec::resolve_from_option(
+                        self.c.as_deref(),
+                        pos + rkyv::offset_of!(ArchivedTest, c),
+                        resolver.c,
+                    )?,
+                })
+            }
+        }
+
+        impl<S: ScratchSpace + Serializer + ?Sized> Serialize<S> for Test {
+            fn serialize(&self, serializer: &mut S) -> Result<TestResolver, S::Error> {
+                Ok(TestResolver {
+                    a: ArchivedOptionBox::serialize_from_option(self.a.as_deref(), serializer)?,
+                    b: ArchivedOptionString::serialize_from_option(self.b.as_deref(), serializer)?,
+                    c: ArchivedOptionVec::serialize_from_option(self.c.as_deref(), serializer)?,
+                })
+            }
+        }
+
+        assert_eq!(
+            size_of::<ArchivedOptionBox<Archived<i32>>>(),
+            size_of::<ArchivedBox<Archived<i32>>>()
+        );
+        assert!(size_of::<ArchivedOptionString>() < size_of::<Archived<Option<String>>>());
+
+        let values = [
+            Test {
+                a: Some(Box::new(42)),
+                b: Some("hello world".to_string()),
+                c: Some(vec![1, 2, 3]),
+            },
+            Test {
+                a: None,
+                b: None,
+                c: None,
+            },
+            // Empty values must not be mistaken for `None`
+            Test {
+                a: None,
+                b: Some(String::new()),
+                c: Some(Vec::new()),
+            },
+        ];
+
+        for value in values.iter() {
+            let mut serializer =
+                BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch());
+            let pos = serializer
+                .serialize_value(value)
+                .expect("failed to archive value");
+            let buf = serializer.into_inner();
+            let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
+
+            assert_eq!(archived.a, value.a);
+            assert_eq!(archived.b, value.b);
+            assert_eq!(archived.c, value.c);
+            assert_eq!(archived.b.is_some(), value.b.is_some());
+
+            let mut deserializer = AllocDeserializer;
+            let a = archived.a.deserialize_to_option(&mut deserializer).unwrap();
+            let b = archived.b.deserialize_to_option(&mut deserializer).unwrap();
+            let c = archived.c.deserialize_to_option(&mut deserializer).unwrap();
+            assert_eq!(a, value.a);
+            assert_eq!(b, value.b);
+            assert_eq!(c, value.c);
+        }
+    }
+
+    #[test]
+    fn archive_with_niche() {
+        use core::{mem::size_of, num::NonZeroU32};
+        use rkyv::with::{Niche, With};
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        struct Test {
+            #[with(Niche)]
+            a: Option<Box<i32>>,
+            #[with(Niche)]
+            b: Option<String>,
+            #[with(Niche)]
+            c: Option<Vec<u32>>,
+            #[with(Niche)]
+            d: Option<NonZeroU32>,
+        }
+
+        assert_eq!(
+            size_of::<Archived<With<Option<Box<i32>>, Niche>>>(),
+            size_of::<Archived<Box<i32>>>()
+        );
+        assert_eq!(
+            size_of::<Archived<With<Option<String>, Niche>>>(),
+            size_of::<Archived<String>>()
+        );
+        assert_eq!(
+            size_of::<Archived<With<Option<Vec<u32>>, Niche>>>(),
+            size_of::<Archived<Vec<u32>>>()
+        );
+        assert_eq!(
+            size_of::<Archived<With<Option<NonZeroU32>, Niche>>>(),
+            size_of::<Archived<u32>>()
+        );
+
+        let values = [
+            Test {
+                a: Some(Box::new(42)),
+                b: Some("hello world".to_string()),
+                c: Some(vec![1, 2, 3]),
+                d: NonZeroU32::new(7),
+            },
+            Test {
+                a: None,
+                b: None,
+                c: None,
+                d: None,
+            },
+            Test {
+                a: None,
+                b: Some(String::new()),
+                c: Some(Vec::new()),
+                d: None,
+            },
+        ];
+
+        for value in values.iter() {
+            let mut serializer =
+                BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch());
+            let pos = serializer
+                .serialize_value(value)
+                .expect("failed to archive value");
+            let buf = serializer.into_inner();
+            let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
+
+            assert_eq!(archived.a, value.a);
+            assert_eq!(archived.b, value.b);
+            assert_eq!(archived.c, value.c);
+            assert_eq!(archived.d, value.d);
+
+            let deserialized: Test = archived.deserialize(&mut AllocDeserializer).unwrap();
+            assert_eq!(&deserialized, value);
+        }
     }
 
     #[test]
@@ -1215,6 +2209,233 @@ mod tests {
         assert!(*archived_value == value);
     }
 
+    #[test]
+    fn archive_root_at_end() {
+        use rkyv::{archived_root, archived_root_mut};
+
+        #[derive(Archive, Serialize)]
+        struct Test {
+            a: i32,
+            b: String,
+            c: Vec<u8>,
+        }
+
+        let value = Test {
+            a: 42,
+            b: "hello world".to_string(),
+            c: vec![1, 2, 3],
+        };
+
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch());
+        let pos = serializer
+            .serialize_root(&value)
+            .expect("failed to archive value");
+        let len = serializer.pos();
+        assert_eq!(pos + core::mem::size_of::<Archived<Test>>(), len);
+        let mut buf = serializer.into_inner();
+
+        let archived = unsafe { archived_root::<Test>(&buf.as_ref()[..len]) };
+        assert_eq!(archived.a, 42);
+        assert_eq!(archived.b, "hello world");
+        assert_eq!(archived.c.as_slice(), &[1, 2, 3]);
+
+        let archived = unsafe { archived_root_mut::<Test>(Pin::new(&mut buf.as_mut()[..len])) };
+        unsafe {
+            archived.get_unchecked_mut().a = to_archived!(11);
+        }
+        let archived = unsafe { archived_root::<Test>(&buf.as_ref()[..len]) };
+        assert_eq!(archived.a, 11);
+    }
+
+    #[test]
+    fn archive_with_footer() {
+        use rkyv::{
+            archived_value_with_footer,
+            ser::{ArchiveFooter, FooterError},
+        };
+
+        let value = vec!["hello".to_string(), "world".to_string()];
+
+        let mut serializer =
+            BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), make_default_scratch());
+        let pos = serializer
+            .serialize_with_footer(&value)
+            .expect("failed to archive value");
+        let len = serializer.pos();
+        let mut buf = serializer.into_inner();
+
+        let footer = ArchiveFooter::read(&buf.as_ref()[..len]).unwrap();
+        assert_eq!(footer.root(), pos);
+        assert_eq!(footer.version(), ArchiveFooter::VERSION);
+        assert_eq!(footer.flags(), ArchiveFooter::native_flags());
+
+        let archived = unsafe { archived_value_with_footer::<Vec<String>>(&buf.as_ref()[..len]) };
+        assert_eq!(archived.unwrap(), &value);
+
+        let result = unsafe { archived_value_with_footer::<Vec<String>>(&buf.as_ref()[..8]) };
+        assert!(matches!(result, Err(FooterError::MissingFooter)));
+
+        buf.as_mut()[len - ArchiveFooter::SIZE + 6] ^= ArchiveFooter::STRICT as u8;
+        let result = unsafe { archived_value_with_footer::<Vec<String>>(&buf.as_ref()[..len]) };
+        assert!(matches!(result, Err(FooterError::MismatchedFlags { .. })));
+
+        buf.as_mut()[len - ArchiveFooter::SIZE] = b'x';
+        let result = unsafe { archived_value_with_footer::<Vec<String>>(&buf.as_ref()[..len]) };
+        assert!(matches!(result, Err(FooterError::InvalidMagic(_))));
+    }
+
+    #[test]
+    fn archive_aligned_vec() {
+        use rkyv::{archived_root, ser::serializers::AlignedSerializer, AlignedVec};
+
+        let mut bytes = AlignedVec::with_align(64);
+        assert!(bytes.is_empty());
+        bytes.extend_from_slice(&[1, 2, 3]);
+        bytes.push(4);
+        bytes.reserve(1000);
+        assert_eq!(bytes.as_ptr() as usize % 64, 0);
+        assert!(bytes.capacity() >= 1004);
+        assert_eq!(&bytes[..], &[1, 2, 3, 4]);
+        bytes.clear();
+        assert!(bytes.is_empty());
+
+        #[derive(Archive, Serialize)]
+        struct Test {
+            a: u64,
+            b: String,
+            c: Vec<u32>,
+        }
+
+        let value = Test {
+            a: 42,
+            b: "hello world".to_string(),
+            c: (0..1000).collect(),
+        };
+
+        let mut serializer = AlignedSerializer::new(AlignedVec::new());
+        serializer
+            .serialize_root(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        assert_eq!(buf.as_ptr() as usize % 16, 0);
+
+        let archived = unsafe { archived_root::<Test>(&buf) };
+        assert_eq!(archived.a, 42);
+        assert_eq!(archived.b, "hello world");
+        assert_eq!(archived.c.len(), 1000);
+        assert!(archived.c.iter().enumerate().all(|(i, x)| *x == i as u32));
+    }
+
+    #[test]
+    fn offset_out_of_range() {
+        use rkyv::{
+            ser::serializers::{BufferSerializerError, WriteSerializer},
+            FixedIsize, OffsetError, RawRelPtr,
+        };
+        use std::io;
+
+        let max = FixedIsize::MAX as usize;
+        assert!(RawRelPtr::try_new(0, max).is_ok());
+        assert!(RawRelPtr::try_new(max + 1, 0).is_ok());
+        assert_eq!(
+            RawRelPtr::try_new(0, max + 1).unwrap_err(),
+            OffsetError::OutOfRange {
+                from: 0,
+                to: max + 1
+            }
+        );
+
+        // Values without relative pointers can be written anywhere
+        let mut serializer = WriteSerializer::with_pos(Vec::new(), max - 1);
+        serializer
+            .serialize_value(&[1u8, 2, 3, 4])
+            .expect("failed to archive value");
+        assert_eq!(serializer.into_inner(), vec![1, 2, 3, 4]);
+
+        #[derive(Archive, Serialize)]
+        struct Test {
+            a: i32,
+            b: String,
+        }
+
+        let value = Test {
+            a: 42,
+            b: "hello world".to_string(),
+        };
+
+        // Pretend that the archive grew past the limit after the string was
+        // written
+        let mut serializer = WriteSerializer::with_pos(Vec::new(), 0);
+        let resolver = value.serialize(&mut serializer).unwrap();
+        let mut serializer = WriteSerializer::with_pos(serializer.into_inner(), max + 9);
+        let result = unsafe { serializer.resolve_aligned(&value, resolver) };
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Forward pointers are checked as well
+        let mut serializer = BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]));
+        let result = unsafe { serializer.resolve_unsized_aligned("hello", max + 1, ()) };
+        assert!(matches!(
+            result,
+            Err(BufferSerializerError::OffsetOutOfRange(_))
+        ));
+    }
+
+    #[cfg(feature = "size_16")]
+    #[test]
+    fn archive_size_16() {
+        use core::mem::size_of;
+        use rkyv::{
+            archived_root,
+            ser::serializers::{AlignedSerializer, AlignedSerializerError},
+            std_impl::ArchivedString,
+            AlignedVec, OffsetError,
+        };
+
+        assert_eq!(size_of::<ArchivedString>(), 4);
+        assert_eq!(size_of::<Archived<Vec<u8>>>(), 4);
+        assert_eq!(size_of::<Archived<usize>>(), 2);
+
+        let value = vec!["hello".to_string(), "world".to_string()];
+        let mut serializer = AlignedSerializer::new(AlignedVec::new());
+        serializer
+            .serialize_root(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let archived = unsafe { archived_root::<Vec<String>>(&buf) };
+        assert_eq!(archived, &value);
+
+        // Offsets past 32 KiB don't fit in a 16-bit relative pointer
+        let value = vec![0u8; 40_000];
+        let mut serializer = AlignedSerializer::new(AlignedVec::new());
+        assert!(serializer.serialize_root(&value).is_err());
+
+        // Lengths and values past 65535 don't fit in a 16-bit usize
+        let mut serializer = AlignedSerializer::new(AlignedVec::new());
+        assert!(matches!(
+            serializer.serialize_value(&70_000usize),
+            Err(AlignedSerializerError::OffsetOutOfRange(
+                OffsetError::UsizeOutOfRange(70_000)
+            ))
+        ));
+        assert!(matches!(
+            serializer.serialize_value(&-40_000isize),
+            Err(AlignedSerializerError::OffsetOutOfRange(
+                OffsetError::IsizeOutOfRange(-40_000)
+            ))
+        ));
+        let value = vec![(); 70_000];
+        assert!(matches!(
+            serializer.serialize_value(&value),
+            Err(AlignedSerializerError::OffsetOutOfRange(
+                OffsetError::UsizeOutOfRange(70_000)
+            ))
+        ));
+        assert!(serializer.serialize_value(&vec![(); 65_535]).is_ok());
+    }
+
+    // Multibyte atomics are archived as plain primitives with a fixed endianness
+    #[cfg(not(any(feature = "archive_le", feature = "archive_be")))]
     #[test]
     fn archive_more_std() {
         use core::{
@@ -1265,16 +2486,6 @@ mod tests {
             b: Rc<u32>,
         }
 
-        impl ArchivedTest {
-            fn a(self: Pin<&mut Self>) -> Pin<&mut Archived<Rc<u32>>> {
-                unsafe { self.map_unchecked_mut(|s| &mut s.a) }
-            }
-
-            fn b(self: Pin<&mut Self>) -> Pin<&mut Archived<Rc<u32>>> {
-                unsafe { self.map_unchecked_mut(|s| &mut s.b) }
-            }
-        }
-
         impl PartialEq<Test> for Archived<Test> {
             fn eq(&self, other: &Test) -> bool {
                 *self.a == *other.a && *self.b == *other.b
@@ -1300,7 +2511,7 @@ mod tests {
         let mut mutable_archived =
             unsafe { archived_value_mut::<Test>(Pin::new_unchecked(buf.as_mut()), pos) };
         unsafe {
-            *mutable_archived.as_mut().a().get_pin_unchecked() = 42;
+            *mutable_archived.as_mut().a().get_pin_unchecked() = to_archived!(42);
         }
 
         let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
@@ -1310,7 +2521,7 @@ mod tests {
         let mut mutable_archived =
             unsafe { archived_value_mut::<Test>(Pin::new_unchecked(buf.as_mut()), pos) };
         unsafe {
-            *mutable_archived.as_mut().b().get_pin_unchecked() = 17;
+            *mutable_archived.as_mut().b().get_pin_unchecked() = to_archived!(17);
         }
 
         let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
@@ -1381,16 +2592,6 @@ mod tests {
             b: Weak<u32>,
         }
 
-        impl ArchivedTest {
-            fn a(self: Pin<&mut Self>) -> Pin<&mut Archived<Rc<u32>>> {
-                unsafe { self.map_unchecked_mut(|s| &mut s.a) }
-            }
-
-            fn b(self: Pin<&mut Self>) -> Pin<&mut Archived<Weak<u32>>> {
-                unsafe { self.map_unchecked_mut(|s| &mut s.b) }
-            }
-        }
-
         let shared = Rc::new(10);
         let value = Test {
             a: shared.clone(),
@@ -1412,7 +2613,7 @@ mod tests {
         let mut mutable_archived =
             unsafe { archived_value_mut::<Test>(Pin::new_unchecked(buf.as_mut()), pos) };
         unsafe {
-            *mutable_archived.as_mut().a().get_pin_unchecked() = 42;
+            *mutable_archived.as_mut().a().get_pin_unchecked() = to_archived!(42);
         }
 
         let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
@@ -1428,7 +2629,7 @@ mod tests {
                 .b()
                 .upgrade_pin()
                 .unwrap()
-                .get_pin_unchecked() = 17;
+                .get_pin_unchecked() = to_archived!(17);
         }
 
         let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
@@ -1528,4 +2729,982 @@ mod tests {
 
         test_archive(&value);
     }
+
+    #[test]
+    fn archive_with() {
+        use rkyv::with::{AsCopy, CopySafe, Inline, Raw, Skip, Unshare};
+        use std::{rc::Rc, sync::Arc};
+
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(C)]
+        struct Color {
+            r: u8,
+            g: u8,
+            b: u8,
+        }
+
+        unsafe impl CopySafe for Color {}
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        struct Test {
+            #[with(Skip)]
+            a: Option<u32>,
+            #[with(Unshare)]
+            b: Rc<String>,
+            #[with(Unshare)]
+            c: Arc<[u32]>,
+            #[with(Raw)]
+            d: Vec<u8>,
+            #[with(AsCopy)]
+            e: Color,
+        }
+
+        let value = Test {
+            a: Some(42),
+            b: Rc::new("hello world".to_string()),
+            c: Arc::from(vec![1, 2, 3, 4]),
+            d: vec![5, 6, 7, 8],
+            e: Color { r: 1, g: 2, b: 3 },
+        };
+
+        // Unshared pointers don't need a shared serializer
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
+
+        assert_eq!(archived.a, ());
+        assert_eq!(archived.b.as_str(), "hello world");
+        assert_eq!(&*archived.c, &[1, 2, 3, 4]);
+        assert_eq!(archived.d.as_slice(), &[5, 6, 7, 8]);
+        assert_eq!(archived.e, value.e);
+
+        let deserialized = archived
+            .deserialize(&mut make_default_deserializer())
+            .unwrap();
+        assert_eq!(deserialized.a, None);
+        assert_eq!(deserialized.b, value.b);
+        assert_eq!(deserialized.c, value.c);
+        assert_eq!(deserialized.d, value.d);
+        assert_eq!(deserialized.e, value.e);
+
+        #[derive(Archive, Serialize)]
+        enum Borrowed<'a> {
+            A(#[with(Inline)] &'a String),
+            B {
+                #[with(Inline)]
+                value: &'a u32,
+                #[with(Raw)]
+                bytes: Vec<u8>,
+            },
+        }
+
+        let string = "hello world".to_string();
+        let value = Borrowed::A(&string);
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<Borrowed>(buf.as_ref(), pos) };
+        if let ArchivedBorrowed::A(archived) = archived {
+            assert_eq!(archived.as_str(), "hello world");
+        } else {
+            panic!("expected variant A");
+        }
+
+        let number = 42;
+        let value = Borrowed::B {
+            value: &number,
+            bytes: vec![1, 2, 3],
+        };
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<Borrowed>(buf.as_ref(), pos) };
+        if let ArchivedBorrowed::B { value, bytes } = archived {
+            assert_eq!(*value, 42);
+            assert_eq!(bytes.as_slice(), &[1, 2, 3]);
+        } else {
+            panic!("expected variant B");
+        }
+    }
+
+    #[test]
+    fn archive_remote() {
+        use rkyv::with::Unshare;
+        use std::rc::Rc;
+
+        mod remote {
+            use std::rc::Rc;
+
+            #[derive(Debug, PartialEq)]
+            pub struct Point {
+                pub x: i32,
+                y: i32,
+            }
+
+            impl Point {
+                pub fn new(x: i32, y: i32) -> Self {
+                    Self { x, y }
+                }
+
+                pub fn y(&self) -> i32 {
+                    self.y
+                }
+            }
+
+            #[derive(Debug, PartialEq)]
+            pub struct Label(pub Rc<String>);
+
+            #[derive(Debug, PartialEq)]
+            pub enum Shape<T> {
+                Empty,
+                Circle(T),
+                Rect { width: T, height: T },
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(remote = "remote::Point")]
+        struct PointDef {
+            x: i32,
+            #[archive(getter = "remote::Point::y")]
+            y: i32,
+        }
+
+        impl From<PointDef> for remote::Point {
+            fn from(value: PointDef) -> Self {
+                remote::Point::new(value.x, value.y)
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(remote = "remote::Label")]
+        struct LabelDef(#[with(Unshare)] Rc<String>);
+
+        impl From<LabelDef> for remote::Label {
+            fn from(value: LabelDef) -> Self {
+                remote::Label(value.0)
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(remote = "remote::Shape<T>")]
+        enum ShapeDef<T> {
+            Empty,
+            Circle(T),
+            Rect { width: T, height: T },
+        }
+
+        impl<T> From<ShapeDef<T>> for remote::Shape<T> {
+            fn from(value: ShapeDef<T>) -> Self {
+                match value {
+                    ShapeDef::Empty => remote::Shape::Empty,
+                    ShapeDef::Circle(r) => remote::Shape::Circle(r),
+                    ShapeDef::Rect { width, height } => remote::Shape::Rect { width, height },
+                }
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        struct Test {
+            #[with(PointDef)]
+            point: remote::Point,
+            #[with(LabelDef)]
+            label: remote::Label,
+            #[with(ShapeDef<u32>)]
+            shapes: remote::Shape<u32>,
+        }
+
+        let value = Test {
+            point: remote::Point::new(1, 2),
+            label: remote::Label(Rc::new("hello world".to_string())),
+            shapes: remote::Shape::Rect {
+                width: 3,
+                height: 4,
+            },
+        };
+
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
+
+        assert_eq!(archived.point.x, 1);
+        assert_eq!(archived.point.y, 2);
+        assert_eq!(archived.label.0.as_str(), "hello world");
+        if let ArchivedShapeDef::Rect { width, height } = archived.shapes {
+            assert_eq!(width, 3);
+            assert_eq!(height, 4);
+        } else {
+            panic!("expected rect");
+        }
+
+        let deserialized = archived
+            .deserialize(&mut make_default_deserializer())
+            .unwrap();
+        assert_eq!(deserialized, value);
+    }
+
+    #[test]
+    fn archive_skip() {
+        use core::mem::size_of;
+        use std::sync::Mutex;
+
+        fn default_handle() -> u32 {
+            42
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug)]
+        struct Named {
+            a: u32,
+            #[archive(skip)]
+            cache: Mutex<Vec<u32>>,
+            #[archive(skip, default = "default_handle")]
+            handle: u32,
+            b: String,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        struct Tuple(
+            #[archive(skip)] Option<u64>,
+            u32,
+            #[archive(skip)] String,
+            u32,
+        );
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        enum Enum {
+            A(#[archive(skip)] u64, u32),
+            B {
+                #[archive(skip, default = "default_handle")]
+                handle: u32,
+                value: String,
+            },
+        }
+
+        assert_eq!(size_of::<ArchivedTuple>(), 2 * size_of::<Archived<u32>>());
+
+        let value = Named {
+            a: 1,
+            cache: Mutex::new(vec![1, 2, 3]),
+            handle: 7,
+            b: "hello world".to_string(),
+        };
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<Named>(buf.as_ref(), pos) };
+        assert_eq!(archived.a, 1);
+        assert_eq!(archived.b, "hello world");
+        let deserialized = archived
+            .deserialize(&mut make_default_deserializer())
+            .unwrap();
+        assert_eq!(deserialized.a, 1);
+        assert!(deserialized.cache.lock().unwrap().is_empty());
+        assert_eq!(deserialized.handle, 42);
+        assert_eq!(deserialized.b, "hello world");
+
+        let value = Tuple(Some(10), 1, "skipped".to_string(), 2);
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<Tuple>(buf.as_ref(), pos) };
+        assert_eq!(archived.0, 1);
+        assert_eq!(archived.1, 2);
+        let deserialized = archived
+            .deserialize(&mut make_default_deserializer())
+            .unwrap();
+        assert_eq!(deserialized, Tuple(None, 1, String::new(), 2));
+
+        for (value, expected) in [
+            (Enum::A(10, 1), Enum::A(0, 1)),
+            (
+                Enum::B {
+                    handle: 7,
+                    value: "hello world".to_string(),
+                },
+                Enum::B {
+                    handle: 42,
+                    value: "hello world".to_string(),
+                },
+            ),
+        ] {
+            let mut serializer = make_default_serializer();
+            let pos = serializer
+                .serialize_value(&value)
+                .expect("failed to archive value");
+            let buf = unwrap_default_serializer(serializer);
+            let archived = unsafe { archived_value::<Enum>(buf.as_ref(), pos) };
+            let deserialized = archived
+                .deserialize(&mut make_default_deserializer())
+                .unwrap();
+            assert_eq!(deserialized, expected);
+        }
+    }
+
+    #[test]
+    fn archive_evolvable() {
+        mod v0 {
+            use rkyv::{Archive, Deserialize, Serialize};
+
+            #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+            #[archive(evolvable)]
+            pub struct Config {
+                pub name: String,
+            }
+        }
+
+        mod v1 {
+            use rkyv::{Archive, Deserialize, Serialize};
+
+            #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+            #[archive(evolvable)]
+            pub struct Config {
+                pub name: String,
+                #[archive(since = 1)]
+                pub retries: u32,
+                #[archive(since = 1)]
+                pub hosts: Vec<String>,
+            }
+        }
+
+        mod v2 {
+            use rkyv::{Archive, Deserialize, Serialize};
+
+            fn default_retries() -> u32 {
+                5
+            }
+
+            #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+            #[archive(evolvable)]
+            pub struct Config {
+                pub name: String,
+                #[archive(since = 1, default = "default_retries")]
+                pub retries: u32,
+                #[archive(since = 1)]
+                pub hosts: Vec<String>,
+                #[archive(skip)]
+                pub cache: Option<u32>,
+                #[archive(since = 2)]
+                pub timeout: Option<u64>,
+            }
+        }
+
+        // Older writers leave out newer fields
+        let value = v0::Config {
+            name: "server".to_string(),
+        };
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<v2::Config>(buf.as_ref(), pos) };
+        assert_eq!(archived.name, "server");
+        assert!(archived.retries().is_none());
+        assert!(archived.hosts().is_none());
+        assert!(archived.timeout().is_none());
+        let deserialized = archived
+            .deserialize(&mut make_default_deserializer())
+            .unwrap();
+        assert_eq!(
+            deserialized,
+            v2::Config {
+                name: "server".to_string(),
+                retries: 5,
+                hosts: Vec::new(),
+                cache: None,
+                timeout: None,
+            }
+        );
+
+        let value = v1::Config {
+            name: "server".to_string(),
+            retries: 3,
+            hosts: vec!["a".to_string(), "b".to_string()],
+        };
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<v2::Config>(buf.as_ref(), pos) };
+        assert_eq!(from_archived!(*archived.retries().unwrap()), 3);
+        assert!(*archived.hosts().unwrap() == value.hosts);
+        assert!(archived.timeout().is_none());
+
+        // Older readers ignore newer fields
+        let value = v2::Config {
+            name: "server".to_string(),
+            retries: 3,
+            hosts: vec!["a".to_string(), "b".to_string()],
+            cache: Some(1),
+            timeout: Some(30),
+        };
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let mut buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<v0::Config>(buf.as_ref(), pos) };
+        let deserialized = archived
+            .deserialize(&mut make_default_deserializer())
+            .unwrap();
+        assert_eq!(
+            deserialized,
+            v0::Config {
+                name: "server".to_string(),
+            }
+        );
+        let archived = unsafe { archived_value::<v1::Config>(buf.as_ref(), pos) };
+        assert_eq!(from_archived!(*archived.retries().unwrap()), 3);
+
+        let mut archived = unsafe { archived_value_mut::<v2::Config>(Pin::new(buf.as_mut()), pos) };
+        *archived.as_mut().retries_pin().unwrap() = to_archived!(4);
+        archived.as_mut().name().str_pin().make_ascii_uppercase();
+        assert_eq!(from_archived!(*archived.retries().unwrap()), 4);
+        assert_eq!(*archived.timeout().unwrap(), Some(30));
+        let deserialized = archived
+            .deserialize(&mut make_default_deserializer())
+            .unwrap();
+        assert_eq!(
+            deserialized,
+            v2::Config {
+                name: "SERVER".to_string(),
+                retries: 4,
+                hosts: vec!["a".to_string(), "b".to_string()],
+                cache: None,
+                timeout: Some(30),
+            }
+        );
+    }
+
+    // The archived types are only used for their schemas
+    #[cfg(feature = "schema")]
+    #[allow(dead_code)]
+    #[test]
+    fn archive_schema() {
+        use rkyv::{
+            offset_of,
+            schema::{Endian, SchemaGraph, TypeKind},
+            Archived,
+        };
+        use std::collections::HashMap;
+
+        #[derive(Archive)]
+        struct Test {
+            a: u32,
+            b: String,
+            #[archive(skip)]
+            _c: u64,
+            d: Vec<Option<u8>>,
+            e: HashMap<u16, Tuple>,
+        }
+
+        #[derive(Archive)]
+        struct Tuple(u8, bool);
+
+        #[derive(Archive)]
+        #[allow(dead_code)]
+        enum Node {
+            Nil,
+            Cons(u32, #[omit_bounds] Box<Node>),
+        }
+
+        #[derive(Archive, Clone, Copy)]
+        #[archive(copy)]
+        #[repr(u16)]
+        #[allow(dead_code)]
+        enum Color {
+            Red = 1,
+            Green = 2,
+        }
+
+        let graph = SchemaGraph::of::<Archived<Test>>();
+        let root = graph.get(graph.root()).unwrap();
+        assert_eq!(root.size, Some(core::mem::size_of::<Archived<Test>>()));
+        let fields = match root.kind {
+            TypeKind::Struct(ref fields) => fields,
+            _ => panic!("expected a struct"),
+        };
+        let names = fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "d", "e"]);
+        assert_eq!(fields[1].offset, offset_of!(Archived<Test>, b));
+        assert_eq!(fields[2].offset, offset_of!(Archived<Test>, d));
+        match graph.get(&fields[0].ty).unwrap().kind {
+            TypeKind::Primitive { name, endian } => {
+                assert_eq!(name, "u32");
+                if cfg!(feature = "archive_be") {
+                    assert_eq!(endian, Endian::Big);
+                } else if cfg!(feature = "archive_le") {
+                    assert_eq!(endian, Endian::Little);
+                }
+            }
+            _ => panic!("expected a primitive"),
+        }
+        assert!(graph
+            .iter()
+            .any(|(_, schema)| schema.kind == TypeKind::Str && schema.size.is_none()));
+        assert!(graph
+            .iter()
+            .any(|(_, schema)| matches!(schema.kind, TypeKind::HashMap { .. })));
+
+        let graph = SchemaGraph::of::<Archived<Node>>();
+        let root = graph.get(graph.root()).unwrap();
+        let variants = match root.kind {
+            TypeKind::Enum {
+                ref tag,
+                ref variants,
+            } => {
+                assert_eq!(tag, "u8");
+                variants
+            }
+            _ => panic!("expected an enum"),
+        };
+        assert_eq!(variants[0].tag, 0);
+        assert!(variants[0].fields.is_empty());
+        assert_eq!(variants[1].tag, 1);
+        assert_eq!(variants[1].fields[0].offset, 4);
+        assert_eq!(variants[1].fields[1].offset, 8);
+        // The box points back to the root
+        match graph.get(&variants[1].fields[1].ty).unwrap().kind {
+            TypeKind::Struct(ref fields) => match graph.get(&fields[0].ty).unwrap().kind {
+                TypeKind::RelPtr { ref pointee, .. } => {
+                    assert_eq!(pointee.as_deref(), Some(graph.root()))
+                }
+                _ => panic!("expected a relative pointer"),
+            },
+            _ => panic!("expected a struct"),
+        }
+
+        let graph = SchemaGraph::of::<Color>();
+        let json = graph.to_json();
+        assert!(json.contains("\"kind\":\"enum\",\"tag\":\"u16\""));
+        assert!(json.contains("{\"name\":\"Green\",\"tag\":2,\"fields\":[]}"));
+    }
+
+    // The archived types are only used for their schemas. Pointer sizes and
+    // fixed byte orders change the header, so it's only compared with the
+    // default layout.
+    #[cfg(all(
+        feature = "schema",
+        not(any(
+            feature = "size_16",
+            feature = "size_64",
+            feature = "archive_le",
+            feature = "archive_be"
+        ))
+    ))]
+    #[allow(dead_code)]
+    #[test]
+    fn archive_c_header() {
+        use rkyv::{offset_of, schema::SchemaGraph, Archived};
+
+        #[derive(Archive)]
+        struct Test {
+            id: u32,
+            name: String,
+            node: Node,
+        }
+
+        #[derive(Archive)]
+        #[allow(dead_code)]
+        enum Node {
+            Nil,
+            Cons(u32, #[omit_bounds] Box<Node>),
+        }
+
+        // The archived struct may reorder its fields, so they're listed in the
+        // order of their offsets
+        let mut fields = [
+            (offset_of!(Archived<Test>, id), "uint32_t", "id"),
+            (offset_of!(Archived<Test>, name), "ArchivedString", "name"),
+            (offset_of!(Archived<Test>, node), "ArchivedNode", "node"),
+        ];
+        fields.sort_by_key(|&(offset, _, _)| offset);
+        let mut test_fields = String::new();
+        let mut test_asserts = String::new();
+        for (offset, ty, name) in fields.iter() {
+            test_fields += &format!("    {} {};\n", ty, name);
+            test_asserts += &format!(
+                "RKYV_STATIC_ASSERT(offsetof(ArchivedTest, {}) == {});\n",
+                name, offset
+            );
+        }
+
+        let expected = format!(
+            r#"/* Archived layouts for rkyv_test::tests::archive_c_header::ArchivedTest */
+#pragma once
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifndef RKYV_STATIC_ASSERT
+#ifdef __cplusplus
+#define RKYV_STATIC_ASSERT(cond) static_assert(cond, #cond)
+#else
+#define RKYV_STATIC_ASSERT(cond) _Static_assert(cond, #cond)
+#endif
+#endif
+
+#ifndef RKYV_ALIGNOF
+#ifdef __cplusplus
+#define RKYV_ALIGNOF(type) alignof(type)
+#else
+#define RKYV_ALIGNOF(type) _Alignof(type)
+#endif
+#endif
+
+/* Relative pointers point to the address of their offset plus its value.
+ * These helpers assume the offset is in native byte order. */
+#ifndef RKYV_REL_PTR
+#define RKYV_REL_PTR(type, ptr) \
+    ((const type *)((const char *)&(ptr)->offset + (ptr)->offset))
+#endif
+
+#ifndef RKYV_REL_PTR_IS_NULL
+#define RKYV_REL_PTR_IS_NULL(ptr) ((ptr)->offset == 0)
+#endif
+
+/* rkyv::RelPtr<rkyv_test::tests::archive_c_header::ArchivedNode>
+ * Points to ArchivedNode */
+typedef struct RelPtr_ArchivedNode {{
+    int32_t offset;
+}} RelPtr_ArchivedNode;
+RKYV_STATIC_ASSERT(offsetof(RelPtr_ArchivedNode, offset) == 0);
+RKYV_STATIC_ASSERT(sizeof(RelPtr_ArchivedNode) == 4);
+RKYV_STATIC_ASSERT(RKYV_ALIGNOF(RelPtr_ArchivedNode) == 4);
+
+/* rkyv::RelPtr<str>
+ * Points to metadata bytes of UTF-8 */
+typedef struct RelPtr_str {{
+    int32_t offset;
+    uint32_t metadata;
+}} RelPtr_str;
+RKYV_STATIC_ASSERT(offsetof(RelPtr_str, offset) == 0);
+RKYV_STATIC_ASSERT(offsetof(RelPtr_str, metadata) == 4);
+RKYV_STATIC_ASSERT(sizeof(RelPtr_str) == 8);
+RKYV_STATIC_ASSERT(RKYV_ALIGNOF(RelPtr_str) == 4);
+
+/* rkyv::std_impl::ArchivedBox<rkyv_test::tests::archive_c_header::ArchivedNode> */
+typedef struct ArchivedBox_ArchivedNode {{
+    RelPtr_ArchivedNode _0;
+}} ArchivedBox_ArchivedNode;
+RKYV_STATIC_ASSERT(offsetof(ArchivedBox_ArchivedNode, _0) == 0);
+RKYV_STATIC_ASSERT(sizeof(ArchivedBox_ArchivedNode) == 4);
+RKYV_STATIC_ASSERT(RKYV_ALIGNOF(ArchivedBox_ArchivedNode) == 4);
+
+/* rkyv::std_impl::ArchivedString */
+typedef struct ArchivedString {{
+    RelPtr_str _0;
+}} ArchivedString;
+RKYV_STATIC_ASSERT(offsetof(ArchivedString, _0) == 0);
+RKYV_STATIC_ASSERT(sizeof(ArchivedString) == 8);
+RKYV_STATIC_ASSERT(RKYV_ALIGNOF(ArchivedString) == 4);
+
+/* rkyv_test::tests::archive_c_header::ArchivedNode */
+#define ArchivedNode_TAG_Nil 0
+#define ArchivedNode_TAG_Cons 1
+typedef struct ArchivedNode_Cons {{
+    uint8_t rkyv_tag;
+    uint8_t rkyv_pad0[3];
+    uint32_t _0;
+    ArchivedBox_ArchivedNode _1;
+}} ArchivedNode_Cons;
+RKYV_STATIC_ASSERT(offsetof(ArchivedNode_Cons, rkyv_tag) == 0);
+RKYV_STATIC_ASSERT(offsetof(ArchivedNode_Cons, _0) == 4);
+RKYV_STATIC_ASSERT(offsetof(ArchivedNode_Cons, _1) == 8);
+typedef union ArchivedNode {{
+    uint8_t rkyv_tag;
+    ArchivedNode_Cons Cons;
+}} ArchivedNode;
+RKYV_STATIC_ASSERT(sizeof(ArchivedNode) == 12);
+RKYV_STATIC_ASSERT(RKYV_ALIGNOF(ArchivedNode) == 4);
+
+/* rkyv_test::tests::archive_c_header::ArchivedTest */
+typedef struct ArchivedTest {{
+{}}} ArchivedTest;
+{}RKYV_STATIC_ASSERT(sizeof(ArchivedTest) == 24);
+RKYV_STATIC_ASSERT(RKYV_ALIGNOF(ArchivedTest) == 4);
+"#,
+            test_fields, test_asserts
+        );
+        assert_eq!(SchemaGraph::of::<Archived<Test>>().to_c_header(), expected);
+    }
+
+    #[test]
+    fn archive_diff() {
+        use rkyv::{
+            diff::{diff, PathSegment},
+            ser::serializers::AlignedSerializer,
+            AlignedVec,
+        };
+        use std::collections::HashMap;
+
+        #[derive(Archive, Serialize)]
+        #[archive(diff)]
+        struct Test {
+            name: String,
+            ports: Vec<u16>,
+            limits: HashMap<String, u32>,
+            owner: Option<String>,
+            shape: Shape,
+            #[archive(skip)]
+            #[allow(dead_code)]
+            cache: u32,
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(diff)]
+        enum Shape {
+            Circle { radius: u32 },
+            Square(u32),
+        }
+
+        let mut old_limits = HashMap::new();
+        old_limits.insert("cpu".to_string(), 2);
+        old_limits.insert("memory".to_string(), 512);
+        let mut new_limits = HashMap::new();
+        new_limits.insert("cpu".to_string(), 4);
+        new_limits.insert("disk".to_string(), 10);
+
+        let mut serializer = AlignedSerializer::new(AlignedVec::new());
+        let old_pos = serializer
+            .serialize_value(&Test {
+                name: "server".to_string(),
+                ports: vec![80, 443],
+                limits: old_limits,
+                owner: None,
+                shape: Shape::Circle { radius: 1 },
+                cache: 1,
+            })
+            .expect("failed to archive value");
+        let new_pos = serializer
+            .serialize_value(&Test {
+                name: "server".to_string(),
+                ports: vec![80],
+                limits: new_limits,
+                owner: Some("admin".to_string()),
+                shape: Shape::Circle { radius: 2 },
+                cache: 2,
+            })
+            .expect("failed to archive value");
+        let square_pos = serializer
+            .serialize_value(&Shape::Square(1))
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let old = unsafe { archived_value::<Test>(buf.as_ref(), old_pos) };
+        let new = unsafe { archived_value::<Test>(buf.as_ref(), new_pos) };
+        let square = unsafe { archived_value::<Shape>(buf.as_ref(), square_pos) };
+
+        assert!(diff(old, old).is_empty());
+
+        let changes = diff(old, new);
+        let mut descriptions = changes.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        // Hashmap entries are visited in hash order
+        descriptions[1..4].sort();
+        assert_eq!(
+            descriptions,
+            [
+                "ports[1]: 443 -> (none)",
+                "limits[\"cpu\"]: 2 -> 4",
+                "limits[\"disk\"]: (none) -> 10",
+                "limits[\"memory\"]: 512 -> (none)",
+                "owner: None -> Some(\"admin\")",
+                "shape.radius: 1 -> 2",
+            ]
+        );
+        assert!(matches!(
+            changes[0].path[..],
+            [PathSegment::Field("ports"), PathSegment::Index(1)]
+        ));
+        assert!(changes[0].new.is_none());
+
+        let changes = diff(&old.shape, square);
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].path.is_empty());
+        assert_eq!(changes[0].to_string(), "(root): \"Circle\" -> \"Square\"");
+    }
+
+    #[test]
+    fn archived_debug() {
+        use std::{collections::HashMap, rc::Rc};
+
+        #[derive(Archive, Serialize)]
+        struct Test {
+            name: String,
+            ports: Vec<u16>,
+            limits: HashMap<String, u32>,
+            parent: Option<Box<u32>>,
+            shared: Rc<Point>,
+            shape: Shape,
+            #[archive(skip)]
+            #[allow(dead_code)]
+            cache: u32,
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Point(i32, #[archive(skip)] (), i32);
+
+        #[derive(Archive, Serialize)]
+        #[allow(dead_code)]
+        enum Shape {
+            Circle { radius: u32 },
+            Square(u32),
+            Empty,
+        }
+
+        let mut limits = HashMap::new();
+        limits.insert("cpu".to_string(), 2);
+        let value = Test {
+            name: "server".to_string(),
+            ports: vec![80, 443],
+            limits,
+            parent: Some(Box::new(7)),
+            shared: Rc::new(Point(1, (), -1)),
+            shape: Shape::Circle { radius: 3 },
+            cache: 1,
+        };
+
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
+        assert_eq!(
+            format!("{:?}", archived),
+            "Test { name: \"server\", ports: [80, 443], limits: {\"cpu\": 2}, parent: Some(7), shared: Point(1, -1), shape: Circle { radius: 3 } }"
+        );
+
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&Shape::Empty)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<Shape>(buf.as_ref(), pos) };
+        assert_eq!(format!("{:?}", archived), "Empty");
+
+        #[derive(Archive, Serialize)]
+        #[archive(bound(serialize = "__S: Serializer"))]
+        enum List<T> {
+            Nil,
+            Cons(T, #[omit_bounds] Box<List<T>>),
+        }
+
+        let list = List::Cons(1u32, Box::new(List::Cons(2, Box::new(List::Nil))));
+        let mut serializer = make_default_serializer();
+        let pos = serializer
+            .serialize_value(&list)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_value::<List<u32>>(buf.as_ref(), pos) };
+        assert_eq!(format!("{:?}", archived), "Cons(1, Cons(2, Nil))");
+    }
+
+    #[test]
+    fn archive_scratch_space() {
+        use core::alloc::Layout;
+        use rkyv::ser::{
+            adapters::ScratchSpaceAdapter,
+            serializers::{
+                AllocScratch, BufferScratch, BufferSerializerError, FallbackScratch,
+                ScratchSpaceError,
+            },
+        };
+        use std::collections::HashMap;
+
+        let mut value = HashMap::new();
+        for i in 0..16 {
+            value.insert(i.to_string(), "x".repeat(i));
+        }
+
+        let mut serializer = ScratchSpaceAdapter::new(
+            BufferSerializer::new(Aligned([0u8; 4096])),
+            BufferScratch::new(Aligned([0u8; 4096])),
+        );
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner().into_inner();
+        let archived = unsafe { archived_value::<HashMap<String, String>>(buf.as_ref(), pos) };
+        assert_eq!(archived.len(), value.len());
+        for (key, value) in value.iter() {
+            assert_eq!(archived.get(key.as_str()).unwrap(), value);
+        }
+
+        let mut serializer = ScratchSpaceAdapter::new(
+            BufferSerializer::new(Aligned([0u8; 4096])),
+            BufferScratch::new([0u8; 16]),
+        );
+        match serializer.serialize_value(&value) {
+            Err(BufferSerializerError::ScratchSpace(ScratchSpaceError::ExceededLimit {
+                ..
+            })) => (),
+            result => panic!("expected scratch space to run out, got {:?}", result),
+        }
+
+        let mut serializer = ScratchSpaceAdapter::new(
+            BufferSerializer::new(Aligned([0u8; 4096])),
+            FallbackScratch::new(BufferScratch::new([0u8; 16]), AllocScratch::new()),
+        );
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner().into_inner();
+        let archived = unsafe { archived_value::<HashMap<String, String>>(buf.as_ref(), pos) };
+        assert_eq!(archived.len(), value.len());
+
+        let mut serializer = BufferSerializer::with_scratch(
+            Aligned([0u8; 4096]),
+            BufferScratch::new(Aligned([0u8; 4096])),
+        );
+        let pos = serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let archived = unsafe { archived_value::<HashMap<String, String>>(buf.as_ref(), pos) };
+        assert_eq!(archived.len(), value.len());
+
+        let mut scratch = BufferScratch::new(Aligned([0u8; 256]));
+        unsafe {
+            let first_layout = Layout::new::<[u32; 4]>();
+            let first = scratch.push_scratch(first_layout).unwrap();
+            let second_layout = Layout::new::<[u64; 2]>();
+            let second = scratch.push_scratch(second_layout).unwrap();
+
+            assert!(matches!(
+                scratch.pop_scratch(first, first_layout),
+                Err(ScratchSpaceError::NotPoppedInReverseOrder)
+            ));
+            assert!(matches!(
+                scratch.pop_scratch(second, Layout::new::<[u64; 1]>()),
+                Err(ScratchSpaceError::NotPoppedInReverseOrder)
+            ));
+            assert!(matches!(
+                scratch.pop_scratch(second, Layout::new::<[u16; 8]>()),
+                Err(ScratchSpaceError::NotPoppedInReverseOrder)
+            ));
+
+            scratch.pop_scratch(second, second_layout).unwrap();
+            scratch.pop_scratch(first, first_layout).unwrap();
+            assert!(matches!(
+                scratch.pop_scratch(first, first_layout),
+                Err(ScratchSpaceError::NoAllocationsToPop)
+            ));
+        }
+    }
 }
@@ -1,23 +1,28 @@
 use bytecheck::CheckBytes;
-use core::fmt;
 use rkyv::{
     check_archive,
-    ser::{adapters::SharedSerializerAdapter, serializers::BufferSerializer, Serializer},
+    ser::{
+        adapters::SharedSerializerAdapter,
+        serializers::{AllocScratch, BufferSerializer},
+        Serializer,
+    },
     validation::DefaultArchiveValidator,
     Aligned, Archive, Serialize,
 };
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
     error::Error,
 };
 
 const BUFFER_SIZE: usize = 512;
 
-fn serialize_and_check<T: Serialize<BufferSerializer<Aligned<[u8; BUFFER_SIZE]>>>>(value: &T)
-where
+fn serialize_and_check<T: Serialize<BufferSerializer<Aligned<[u8; BUFFER_SIZE]>, AllocScratch>>>(
+    value: &T,
+) where
     T::Archived: CheckBytes<DefaultArchiveValidator>,
 {
-    let mut serializer = BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]));
+    let mut serializer =
+        BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), AllocScratch::new());
     let pos = serializer
         .serialize_value(value)
         .expect("failed to archive value");
@@ -30,7 +35,8 @@ fn basic_functionality() {
     // Regular archiving
     let value = Some("Hello world".to_string());
 
-    let mut serializer = BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]));
+    let mut serializer =
+        BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), AllocScratch::new());
     let pos = serializer
         .serialize_value(&value)
         .expect("failed to archive value");
@@ -39,58 +45,133 @@ fn basic_functionality() {
     let result = check_archive::<Option<String>>(buf.as_ref(), pos);
     result.unwrap();
 
-    #[cfg(not(feature = "size_64"))]
+    // Various buffer errors:
+    // Out of bounds
+    check_archive::<u32>(Aligned([0u8, 1, 2, 3, 4]).as_ref(), 5).unwrap_err();
+    // Overrun
+    check_archive::<u32>(Aligned([0u8, 1, 2, 3, 4]).as_ref(), 4).unwrap_err();
+    // Unaligned
+    check_archive::<u32>(Aligned([0u8, 1, 2, 3, 4]).as_ref(), 1).unwrap_err();
+}
+
+// The synthetic archive is written in little-endian byte order
+#[cfg(not(feature = "archive_be"))]
+#[test]
+fn synthetic_archive() {
+    #[cfg(feature = "size_16")]
     // Synthetic archive (correct)
-    let synthetic_buf = [
+    let synthetic_buf = Aligned([
+        1u8, 0u8, // Some + padding
+        4u8, 0u8, // points 4 bytes forward
+        11u8, 0u8, // string is 11 characters long
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    #[cfg(not(any(feature = "size_16", feature = "size_64")))]
+    // Synthetic archive (correct)
+    let synthetic_buf = Aligned([
         1u8, 0u8, 0u8, 0u8, // Some + padding
         8u8, 0u8, 0u8, 0u8, // points 8 bytes forward
         11u8, 0u8, 0u8, 0u8, // string is 11 characters long
         // "Hello world"
         0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
-    ];
+    ]);
 
     #[cfg(feature = "size_64")]
     // Synthetic archive (correct)
-    let synthetic_buf = [
+    let synthetic_buf = Aligned([
         1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // Some + padding
         16u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 16 bytes forward
         11u8, 0u8, 0u8, 0u8, // string is 11 characters long
         0u8, 0u8, 0u8, 0u8, // padding
         // "Hello world"
         0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
-    ];
+    ]);
 
-    let result = check_archive::<Option<String>>(&synthetic_buf, 0);
+    let result = check_archive::<Option<String>>(synthetic_buf.as_ref(), 0);
     result.unwrap();
+}
 
-    // Various buffer errors:
-    // Out of bounds
-    check_archive::<u32>(&[0, 1, 2, 3, 4], 5).unwrap_err();
-    // Overrun
-    check_archive::<u32>(&[0, 1, 2, 3, 4], 4).unwrap_err();
-    // Unaligned
-    check_archive::<u32>(&[0, 1, 2, 3, 4], 1).unwrap_err();
+#[test]
+fn root_at_end() {
+    use rkyv::check_archived_root;
+
+    let value = Some("Hello world".to_string());
+
+    let mut serializer =
+        BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), AllocScratch::new());
+    serializer
+        .serialize_root(&value)
+        .expect("failed to archive value");
+    let len = serializer.pos();
+    let buf = serializer.into_inner();
+
+    check_archived_root::<Option<String>>(&buf.as_ref()[..len]).unwrap();
+    // Too small to hold the root
+    check_archived_root::<u32>(&[0, 1, 2]).unwrap_err();
+}
+
+#[test]
+fn footer() {
+    use rkyv::{check_archive_with_footer, validation::CheckArchiveError};
+
+    let value = Some("Hello world".to_string());
+
+    let mut serializer =
+        BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), AllocScratch::new());
+    serializer
+        .serialize_with_footer(&value)
+        .expect("failed to archive value");
+    let len = serializer.pos();
+    let mut buf = serializer.into_inner();
+
+    check_archive_with_footer::<Option<String>>(&buf.as_ref()[..len]).unwrap();
+
+    // Missing footer
+    match check_archive_with_footer::<Option<String>>(&buf.as_ref()[..len - 1]) {
+        Err(CheckArchiveError::FooterError(_)) => (),
+        _ => panic!("expected a footer error"),
+    }
+
+    // Root points past the end of the data
+    buf.as_mut()[len - 8] = 0xff;
+    check_archive_with_footer::<Option<String>>(&buf.as_ref()[..len]).unwrap_err();
 }
 
 #[test]
 fn invalid_tags() {
     // Invalid archive (invalid tag)
-    let synthetic_buf = [
+    let synthetic_buf = Aligned([
         2u8, 0u8, 0u8, 0u8, // invalid tag + padding
         8u8, 0u8, 0u8, 0u8, // points 8 bytes forward
         11u8, 0u8, 0u8, 0u8, // string is 11 characters long
         // "Hello world"
         0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
-    ];
+    ]);
 
-    let result = check_archive::<Option<String>>(&synthetic_buf, 0);
+    let result = check_archive::<Option<String>>(synthetic_buf.as_ref(), 0);
     result.unwrap_err();
 }
 
 #[test]
 fn overlapping_claims() {
+    #[cfg(feature = "size_16")]
+    // Invalid archive (overlapping claims)
+    let synthetic_buf = Aligned([
+        // First string
+        8u8, 0u8, // points 8 bytes forward
+        11u8, 0u8, // string is 11 characters long
+        // Second string
+        4u8, 0u8, // points 4 bytes forward
+        11u8, 0u8, // string is 11 characters long
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    #[cfg(not(any(feature = "size_16", feature = "size_64")))]
     // Invalid archive (overlapping claims)
-    let synthetic_buf = [
+    let synthetic_buf = Aligned([
         // First string
         16u8, 0u8, 0u8, 0u8, // points 16 bytes forward
         11u8, 0u8, 0u8, 0u8, // string is 11 characters long
@@ -99,16 +180,31 @@ fn overlapping_claims() {
         11u8, 0u8, 0u8, 0u8, // string is 11 characters long
         // "Hello world"
         0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
-    ];
+    ]);
 
-    check_archive::<[String; 2]>(&synthetic_buf, 0).unwrap_err();
+    #[cfg(feature = "size_64")]
+    // Invalid archive (overlapping claims)
+    let synthetic_buf = Aligned([
+        // First string
+        32u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 32 bytes forward
+        11u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        0u8, 0u8, 0u8, 0u8, // padding
+        // Second string
+        16u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 16 bytes forward
+        11u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        0u8, 0u8, 0u8, 0u8, // padding
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    check_archive::<[String; 2]>(synthetic_buf.as_ref(), 0).unwrap_err();
 }
 
 #[test]
 fn cycle_detection() {
     use rkyv::{
         validation::{ArchiveBoundsContext, ArchiveMemoryContext},
-        Archived,
+        Fallible,
     };
 
     #[derive(Archive)]
@@ -116,75 +212,30 @@ fn cycle_detection() {
     struct NodePtr(Box<Node>);
 
     #[allow(dead_code)]
-    #[derive(Archive)]
-    #[archive(derive(Debug))]
+    #[derive(Archive, Serialize)]
+    #[archive(
+        derive(CheckBytes, Debug),
+        bound(
+            serialize = "__S: Serializer",
+            check_bytes = "__C: ArchiveBoundsContext + ArchiveMemoryContext, <__C as Fallible>::Error: Error"
+        )
+    )]
     enum Node {
         Nil,
-        Cons(#[recursive] Box<Node>),
-    }
-
-    impl<S: Serializer + ?Sized> Serialize<S> for Node {
-        fn serialize(&self, serializer: &mut S) -> Result<NodeResolver, S::Error> {
-            Ok(match self {
-                Node::Nil => NodeResolver::Nil,
-                Node::Cons(inner) => NodeResolver::Cons(inner.serialize(serializer)?),
-            })
-        }
-    }
-
-    #[derive(Debug)]
-    struct NodeError(Box<dyn Error>);
-
-    impl fmt::Display for NodeError {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "node error: {}", self.0)
-        }
-    }
-
-    impl Error for NodeError {
-        fn source(&self) -> Option<&(dyn Error + 'static)> {
-            Some(&*self.0)
-        }
-    }
-
-    impl<C: ArchiveBoundsContext + ArchiveMemoryContext + ?Sized> CheckBytes<C> for ArchivedNode
-    where
-        C::Error: Error,
-    {
-        type Error = NodeError;
-
-        unsafe fn check_bytes<'a>(
-            value: *const Self,
-            context: &mut C,
-        ) -> Result<&'a Self, Self::Error> {
-            let bytes = value.cast::<u8>();
-            let tag = *bytes;
-            match tag {
-                0 => (),
-                1 => {
-                    <Archived<Box<Node>> as CheckBytes<C>>::check_bytes(
-                        bytes.add(4).cast(),
-                        context,
-                    )
-                    .map_err(|e| NodeError(e.into()))?;
-                }
-                _ => panic!(),
-            }
-            Ok(&*bytes.cast())
-        }
+        Cons(#[omit_bounds] Box<Node>),
     }
 
     // Invalid archive (cyclic claims)
-    let synthetic_buf = [
+    let synthetic_buf = Aligned([
         // First node
         1u8, 0u8, 0u8, 0u8, // Cons
         4u8, 0u8, 0u8, 0u8, // Node is 4 bytes forward
         // Second string
         1u8, 0u8, 0u8, 0u8, // Cons
         244u8, 255u8, 255u8, 255u8, // Node is 12 bytes back
-    ];
+    ]);
 
-    check_archive::<Node>(&synthetic_buf, 0).unwrap_err();
+    check_archive::<Node>(synthetic_buf.as_ref(), 0).unwrap_err();
 }
 
 #[test]
@@ -263,9 +314,44 @@ fn hashmap() {
     serialize_and_check(&set);
 }
 
+#[test]
+fn btree_map() {
+    let mut map = BTreeMap::new();
+    map.insert("Hello".to_string(), 12);
+    map.insert("world".to_string(), 34);
+    map.insert("foo".to_string(), 56);
+    map.insert("bar".to_string(), 78);
+    map.insert("baz".to_string(), 90);
+    serialize_and_check(&map);
+
+    let mut set = BTreeSet::new();
+    set.insert("Hello".to_string());
+    set.insert("world".to_string());
+    set.insert("foo".to_string());
+    set.insert("bar".to_string());
+    set.insert("baz".to_string());
+    serialize_and_check(&set);
+}
+
+#[test]
+fn collections() {
+    let mut vec_deque = VecDeque::new();
+    vec_deque.push_back("world".to_string());
+    vec_deque.push_front("hello".to_string());
+    serialize_and_check(&vec_deque);
+
+    let heap = vec![3, 1, 4, 1, 5].into_iter().collect::<BinaryHeap<i32>>();
+    serialize_and_check(&heap);
+
+    let mut linked_list = LinkedList::new();
+    linked_list.push_back("hello".to_string());
+    linked_list.push_back("world".to_string());
+    serialize_and_check(&linked_list);
+}
+
 #[test]
 fn check_dyn() {
-    use rkyv::Archived;
+    use rkyv::{from_archived, Archived};
     use rkyv_dyn::archive_dyn;
     use rkyv_typename::TypeName;
 
@@ -289,7 +375,7 @@ fn check_dyn() {
 
     impl TestTrait for Archived<Test> {
         fn get_id(&self) -> i32 {
-            self.id
+            from_archived!(self.id)
         }
     }
 
@@ -312,13 +398,14 @@ fn check_dyn() {
 
     impl TestTrait for Archived<TestUnchecked> {
         fn get_id(&self) -> i32 {
-            self.id
+            from_archived!(self.id)
         }
     }
 
     let value: Box<dyn SerializeTestTrait> = Box::new(TestUnchecked { id: 42 });
 
-    let mut serializer = BufferSerializer::new(Aligned([0u8; BUFFER_SIZE]));
+    let mut serializer =
+        BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), AllocScratch::new());
     let pos = serializer
         .serialize_value(&value)
         .expect("failed to archive value");
@@ -345,8 +432,10 @@ fn check_shared_ptr() {
         b: shared.clone(),
     };
 
-    let mut serializer =
-        SharedSerializerAdapter::new(BufferSerializer::new(Aligned([0u8; BUFFER_SIZE])));
+    let mut serializer = SharedSerializerAdapter::new(BufferSerializer::with_scratch(
+        Aligned([0u8; BUFFER_SIZE]),
+        AllocScratch::new(),
+    ));
     let pos = serializer
         .serialize_value(&value)
         .expect("failed to archive value");
@@ -354,3 +443,53 @@ fn check_shared_ptr() {
 
     check_archive::<Test>(buf.as_ref(), pos).unwrap();
 }
+
+#[test]
+fn check_evolvable() {
+    mod v0 {
+        use bytecheck::CheckBytes;
+        use rkyv::{Archive, Serialize};
+
+        #[derive(Archive, Serialize)]
+        #[archive(evolvable, derive(CheckBytes))]
+        pub struct Test {
+            pub a: u32,
+        }
+    }
+
+    mod v1 {
+        use bytecheck::CheckBytes;
+        use rkyv::{Archive, Serialize};
+
+        #[derive(Archive, Serialize)]
+        #[archive(evolvable, derive(CheckBytes))]
+        pub struct Test {
+            pub a: u32,
+            #[archive(since = 1)]
+            pub b: String,
+        }
+    }
+
+    let mut serializer =
+        BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), AllocScratch::new());
+    let pos = serializer
+        .serialize_value(&v0::Test { a: 42 })
+        .expect("failed to archive value");
+    let buf = serializer.into_inner();
+    check_archive::<v0::Test>(buf.as_ref(), pos).unwrap();
+    let archived = check_archive::<v1::Test>(buf.as_ref(), pos).unwrap();
+    assert!(archived.b().is_none());
+
+    let mut serializer =
+        BufferSerializer::with_scratch(Aligned([0u8; BUFFER_SIZE]), AllocScratch::new());
+    let pos = serializer
+        .serialize_value(&v1::Test {
+            a: 42,
+            b: "hello world".to_string(),
+        })
+        .expect("failed to archive value");
+    let buf = serializer.into_inner();
+    check_archive::<v0::Test>(buf.as_ref(), pos).unwrap();
+    let archived = check_archive::<v1::Test>(buf.as_ref(), pos).unwrap();
+    assert_eq!(archived.b().unwrap().as_str(), "hello world");
+}